@@ -0,0 +1,72 @@
+//! Benchmark of the synthetic chunk-like packet: heightmaps in NBT, block states as longs and light as bytes.
+//!
+//! Run with `cargo bench -p bird-protocol --features fastnbt --bench chunk`.
+//!
+//! Baseline, median of `--warm-up-time 1 --measurement-time 2` on x86_64 linux: write 31 µs, read 21 µs.
+use std::collections::HashMap;
+use criterion::{black_box, Criterion, criterion_group, criterion_main};
+use fastnbt::{LongArray, Value};
+use bird_protocol::*;
+
+struct ChunkLike {
+    x: i32,
+    z: i32,
+    heightmaps: Value,
+    sections: Vec<Vec<i64>>,
+    light: Vec<Vec<u8>>,
+}
+
+impl<'a> PacketReadable<'a> for ChunkLike {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(Self {
+            x: i32::read(read)?,
+            z: i32::read(read)?,
+            heightmaps: Value::read(read)?,
+            sections: Vec::read(read)?,
+            light: LengthProvidedSlice::<VarInt, LengthProvidedBytesSlice<VarInt, i32>, i32, Vec<u8>>::read_variant(read)?,
+        })
+    }
+}
+
+impl PacketWritable for ChunkLike {
+    fn write<W>(&self, write: &mut W) -> Result<(), anyhow::Error> where W: PacketWrite {
+        self.x.write(write)?;
+        self.z.write(write)?;
+        self.heightmaps.write(write)?;
+        self.sections.write(write)?;
+        LengthProvidedSlice::<VarInt, LengthProvidedBytesSlice<VarInt, i32>, i32, Vec<u8>>::write_variant(self.light.as_slice(), write)
+    }
+}
+
+fn chunk() -> ChunkLike {
+    let heightmap = || Value::LongArray(LongArray::new((0..37).map(|index| index * 0x0102030405).collect()));
+    ChunkLike {
+        x: 3,
+        z: -7,
+        heightmaps: Value::Compound(HashMap::from([
+            ("MOTION_BLOCKING".to_string(), heightmap()),
+            ("WORLD_SURFACE".to_string(), heightmap()),
+        ])),
+        sections: (0..24).map(|section| (0..256).map(|index| index * section).collect()).collect(),
+        light: (0..26).map(|section| vec![section as u8; 2048]).collect(),
+    }
+}
+
+fn chunk_like(c: &mut Criterion) {
+    let chunk = chunk();
+    let mut bytes = Vec::new();
+    chunk.write(&mut bytes).unwrap();
+    c.bench_function("chunk_like/write", |b| {
+        let mut write = Vec::with_capacity(bytes.len());
+        b.iter(|| {
+            write.clear();
+            black_box(&chunk).write(&mut write).unwrap();
+        })
+    });
+    c.bench_function("chunk_like/read", |b| {
+        b.iter(|| black_box(ChunkLike::read(&mut SlicePacketRead::new(black_box(&bytes))).unwrap()))
+    });
+}
+
+criterion_group!(benches, chunk_like);
+criterion_main!(benches);
@@ -0,0 +1,133 @@
+//! Benchmarks of the codec hot paths. Values are read by [SlicePacketRead] and written to [Vec],
+//! so numbers don't include executor overhead.
+//!
+//! Run with `cargo bench -p bird-protocol --bench codec`. To compare changes, save the baseline before them
+//! with `--save-baseline before` and run after them with `--baseline before`.
+//!
+//! Baseline, median of `--warm-up-time 1 --measurement-time 2` on x86_64 linux:
+//!
+//! | Benchmark                              | Time     |
+//! |----------------------------------------|----------|
+//! | var_int/encode/1                       | 5.2 ns   |
+//! | var_int/decode/1                       | 2.4 ns   |
+//! | var_int/encode/2147483647              | 8.2 ns   |
+//! | var_int/decode/2147483647              | 5.6 ns   |
+//! | var_long/encode/9223372036854775807    | 9.8 ns   |
+//! | var_long/decode/9223372036854775807    | 6.8 ns   |
+//! | string/round_trip/16                   | 39 ns    |
+//! | string/round_trip/32767                | 1.6 µs   |
+//! | component/round_trip                   | 2.2 µs   |
+//! | bytes/per_element/1024                 | 3.2 µs   |
+//! | bytes/bulk/1024                        | 80 ns    |
+//! | bytes/per_element/65536                | 208 µs   |
+//! | bytes/bulk/65536                       | 3.7 µs   |
+//! | bytes/per_element/1048576              | 4.0 ms   |
+//! | bytes/bulk/1048576                     | 109 µs   |
+//! | block_position/round_trip              | 18 ns    |
+use criterion::{BenchmarkId, black_box, Criterion, criterion_group, criterion_main, Throughput};
+use bird_chat::component::Component;
+use bird_protocol::*;
+
+fn var_numbers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("var_int");
+    for value in [1, 300, i32::MAX, -1] {
+        group.bench_with_input(BenchmarkId::new("encode", value), &value, |b, value| {
+            let mut write = Vec::with_capacity(VarInt::MAX_SIZE);
+            b.iter(|| {
+                write.clear();
+                VarInt::write_variant(black_box(value), &mut write).unwrap();
+            })
+        });
+        let mut bytes = Vec::new();
+        VarInt::write_variant(&value, &mut bytes).unwrap();
+        group.bench_with_input(BenchmarkId::new("decode", value), &bytes, |b, bytes| {
+            b.iter(|| -> i32 { VarInt::read_variant(&mut SlicePacketRead::new(black_box(bytes))).unwrap() })
+        });
+    }
+    group.finish();
+    let mut group = c.benchmark_group("var_long");
+    for value in [1, i32::MAX as i64 + 1, i64::MAX, -1] {
+        group.bench_with_input(BenchmarkId::new("encode", value), &value, |b, value| {
+            let mut write = Vec::with_capacity(VarLong::MAX_SIZE);
+            b.iter(|| {
+                write.clear();
+                VarLong::write_variant(black_box(value), &mut write).unwrap();
+            })
+        });
+        let mut bytes = Vec::new();
+        VarLong::write_variant(&value, &mut bytes).unwrap();
+        group.bench_with_input(BenchmarkId::new("decode", value), &bytes, |b, bytes| {
+            b.iter(|| -> i64 { VarLong::read_variant(&mut SlicePacketRead::new(black_box(bytes))).unwrap() })
+        });
+    }
+    group.finish();
+}
+
+fn strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("string");
+    for length in [16, 256, DEFAULT_LIMIT as usize] {
+        let string = "a".repeat(length);
+        group.throughput(Throughput::Bytes(length as u64));
+        group.bench_with_input(BenchmarkId::new("round_trip", length), string.as_str(), |b, string| {
+            let mut write = Vec::with_capacity(length + VarInt::MAX_SIZE);
+            b.iter(|| {
+                write.clear();
+                black_box(string).write(&mut write).unwrap();
+                black_box(<&str>::read(&mut SlicePacketRead::new(&write)).unwrap());
+            })
+        });
+    }
+    group.finish();
+    let mut bytes = Vec::new();
+    r#"{"text":"Hello, ","bold":true,"extra":[{"text":"world","color":"gold","extra":[]},{"text":"!","extra":[]}]}"#.write(&mut bytes).unwrap();
+    c.bench_function("component/round_trip", |b| {
+        let mut write = Vec::with_capacity(bytes.len());
+        b.iter(|| {
+            let component = Component::read(&mut SlicePacketRead::new(black_box(&bytes))).unwrap();
+            write.clear();
+            component.write(&mut write).unwrap();
+        })
+    });
+}
+
+fn byte_arrays(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bytes");
+    for length in [1 << 10, 1 << 16, 1 << 20] {
+        let data = vec![0x55u8; length];
+        group.throughput(Throughput::Bytes(length as u64));
+        group.bench_with_input(BenchmarkId::new("per_element", length), &data, |b, data| {
+            let mut write = Vec::with_capacity(length + VarInt::MAX_SIZE);
+            b.iter(|| {
+                write.clear();
+                LengthProvidedSlice::<VarInt, u8, i32>::write_variant(black_box(data), &mut write).unwrap();
+                let read: Vec<u8> = LengthProvidedSlice::<VarInt, u8, i32>::read_variant(&mut SlicePacketRead::new(&write)).unwrap();
+                black_box(read);
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("bulk", length), &data, |b, data| {
+            let mut write = Vec::with_capacity(length + VarInt::MAX_SIZE);
+            b.iter(|| {
+                write.clear();
+                LengthProvidedBytesSlice::<VarInt, i32>::write_variant(black_box(data.as_slice()), &mut write).unwrap();
+                let read: Vec<u8> = LengthProvidedBytesSlice::<VarInt, i32>::read_variant(&mut SlicePacketRead::new(&write)).unwrap();
+                black_box(read);
+            })
+        });
+    }
+    group.finish();
+}
+
+fn block_positions(c: &mut Criterion) {
+    let position = BlockPosition { x: -33554432, y: 2047, z: 12345 };
+    c.bench_function("block_position/round_trip", |b| {
+        let mut write = Vec::with_capacity(8);
+        b.iter(|| {
+            write.clear();
+            black_box(&position).write(&mut write).unwrap();
+            black_box(BlockPosition::read(&mut SlicePacketRead::new(&write)).unwrap());
+        })
+    });
+}
+
+criterion_group!(benches, var_numbers, strings, byte_arrays, block_positions);
+criterion_main!(benches);
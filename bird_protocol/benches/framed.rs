@@ -0,0 +1,72 @@
+//! Benchmarks of writing the frame and of reading a fully received frame from the async stream.
+//! Only receiving of the frame awaits, its body is read by [SlicePacketRead] synchronously, so the difference with `slice` is the executor overhead.
+//!
+//! Run with `cargo bench -p bird-protocol --features tokio-bytes --bench framed`.
+//!
+//! Baseline, median of `--warm-up-time 1 --measurement-time 2` on x86_64 linux:
+//!
+//! | Benchmark                              | Time     |
+//! |----------------------------------------|----------|
+//! | framed/slice                           | 91 ns    |
+//! | framed/read_framed                     | 375 ns   |
+//! | framed/stream_reader                   | 443 ns   |
+//! | framed_write/two_buffers               | 220 ns   |
+//! | framed_write/write_framed_into         | 111 ns   |
+//!
+//! `write_framed_into` doesn't allocate per packet, see `tests/framed_allocations.rs`.
+use criterion::{black_box, Criterion, criterion_group, criterion_main};
+use bird_protocol::*;
+use bytes::BytesMut;
+use bird_protocol::packet_bytes::{PacketStreamReader, read_framed, write_framed_into};
+
+type Body = (u8, String, uuid::Uuid, BlockPosition, Option<i64>, Vec<u16>);
+
+fn framed(c: &mut Criterion) {
+    let body: Body = (7, "bird".into(), uuid::Uuid::from_u128(0x1234), BlockPosition { x: 5, y: -3, z: 100 }, Some(-1), vec![1, 2, 3]);
+    let mut framed = Vec::new();
+    PacketFramer::new().write_frame(&body, &mut framed).unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let mut group = c.benchmark_group("framed");
+    group.bench_function("slice", |b| {
+        b.iter(|| -> Body { read_frame(&mut SlicePacketRead::new(black_box(&framed))).and_then(|mut read| Body::read(&mut read)).unwrap() })
+    });
+    group.bench_function("read_framed", |b| {
+        b.iter(|| -> Body {
+            let mut stream = black_box(framed.as_slice());
+            runtime.block_on(read_framed(&mut stream, DEFAULT_MAX_FRAME_LENGTH)).unwrap()
+        })
+    });
+    group.bench_function("stream_reader", |b| {
+        b.iter(|| -> Body {
+            let mut reader = PacketStreamReader::new(black_box(framed.as_slice()));
+            runtime.block_on(reader.read_next()).unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn framed_write(c: &mut Criterion) {
+    let body: Body = (7, "bird".into(), uuid::Uuid::from_u128(0x1234), BlockPosition { x: 5, y: -3, z: 100 }, Some(-1), vec![1, 2, 3]);
+    let mut group = c.benchmark_group("framed_write");
+    group.bench_function("two_buffers", |b| {
+        b.iter(|| -> Vec<u8> {
+            let mut buffer = Vec::new();
+            VarInt::write_variant(&0x21, &mut buffer).unwrap();
+            black_box(&body).write(&mut buffer).unwrap();
+            let mut frame = Vec::new();
+            write_frame_bytes(&buffer, &mut frame).unwrap();
+            frame
+        })
+    });
+    let mut buf = BytesMut::with_capacity(1024);
+    group.bench_function("write_framed_into", |b| {
+        b.iter(|| {
+            buf.clear();
+            write_framed_into(&mut buf, 0x21, black_box(&body)).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, framed, framed_write);
+criterion_main!(benches);
@@ -0,0 +1,230 @@
+//! Golden tests: packets of the vanilla protocol are read from `tests/golden/*.txt`, written back
+//! and compared with the original bytes, so they check compatibility and not only consistency of the round trip.
+//!
+//! Each file has one packet. Lines starting with `#` are comments, header lines are `key: value`
+//! and all lines after `body:` are hex bytes of the body after the packet id:
+//!
+//! ```text
+//! # Keep alive of the server
+//! id: 0x1e
+//! protocol: 759
+//! direction: clientbound
+//! state: play
+//! body:
+//! 00 00 01 81 8f 0b 3a 98
+//! ```
+//!
+//! To add a dump drop the file in the directory. Packets which are not in [decoders] yet fail with a report.
+use std::path::Path;
+use crate::packet::*;
+use crate::packet_debug::hexdump;
+use crate::packet_default::*;
+use crate::packet_play::*;
+use crate::packet_version::{ProtocolVersion, SUPPORTED_VERSIONS};
+
+const GOLDEN_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+#[derive(Debug, Clone, PartialEq)]
+struct GoldenDump {
+    id: i32,
+    protocol: i32,
+    bound: PacketBound,
+    state: PacketState,
+    body: Vec<u8>,
+}
+
+/// Reads the packet from the body, checks that the body is read fully and writes the packet back.
+type Reencode = fn(&[u8]) -> Result<Vec<u8>, PacketReadableError>;
+
+struct GoldenDecoder {
+    name: &'static str,
+    bound: PacketBound,
+    state: PacketState,
+    id: i32,
+    reencode: Reencode,
+}
+
+macro_rules! golden_decoder {
+    ($packet: ident) => {
+        GoldenDecoder {
+            name: stringify!($packet),
+            bound: <$packet as PacketScope>::bound(),
+            state: <$packet as PacketScope>::state(),
+            id: <$packet as Packet>::id(),
+            reencode: |body: &[u8]| {
+                let mut read = SlicePacketRead::new(body);
+                let packet = $packet::read(&mut read)?;
+                read.finish_strict()?;
+                let mut write = Vec::new();
+                packet.write(&mut write)?;
+                Ok(write)
+            },
+        }
+    };
+}
+
+/// Packets of [SUPPORTED_VERSIONS] which the dumps can have.
+fn decoders() -> Vec<GoldenDecoder> {
+    #[cfg_attr(not(feature = "fastnbt"), allow(unused_mut))]
+    let mut decoders = vec![
+        golden_decoder!(HandshakePacket),
+        golden_decoder!(StatusRequest),
+        golden_decoder!(StatusResponse),
+        golden_decoder!(StatusPingRequest),
+        golden_decoder!(StatusPingResponse),
+        golden_decoder!(LoginStart),
+        golden_decoder!(LoginSetCompression),
+        golden_decoder!(LoginSuccess),
+        golden_decoder!(PlayKeepAlive),
+        golden_decoder!(PlayKeepAliveResponse),
+        golden_decoder!(PlaySynchronizePlayerPosition),
+        golden_decoder!(PlaySetDefaultSpawnPosition),
+        golden_decoder!(PlayUpdateLight),
+        golden_decoder!(PlayUpdateTime),
+        golden_decoder!(PlaySetHealth),
+    ];
+    #[cfg(feature = "fastnbt")]
+    decoders.extend([golden_decoder!(PlayJoinGame), golden_decoder!(PlayChunkDataAndUpdateLight)]);
+    decoders
+}
+
+fn parse_number(value: &str) -> Result<i32, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => i32::from_str_radix(hex, 16),
+        None => value.parse(),
+    }.map_err(|_| format!("Bad number {:?}", value))
+}
+
+fn parse_dump(text: &str) -> Result<GoldenDump, String> {
+    let (mut id, mut protocol, mut bound, mut state) = (None, None, None, None);
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+    for line in lines.by_ref() {
+        if line == "body:" {
+            break;
+        }
+        let (key, value) = line.split_once(':').ok_or_else(|| format!("Bad header line {:?}", line))?;
+        let value = value.trim();
+        match key.trim() {
+            "id" => id = Some(parse_number(value)?),
+            "protocol" => protocol = Some(parse_number(value)?),
+            "direction" => bound = Some(match value {
+                "clientbound" => PacketBound::Client,
+                "serverbound" => PacketBound::Server,
+                _ => return Err(format!("Bad direction {:?}", value)),
+            }),
+            "state" => state = Some(match value {
+                "handshake" => PacketState::Handshake,
+                "status" => PacketState::Status,
+                "login" => PacketState::Login,
+                "play" => PacketState::Play,
+                _ => return Err(format!("Bad state {:?}", value)),
+            }),
+            key => return Err(format!("Unknown header {:?}", key)),
+        }
+    }
+    let mut body = Vec::new();
+    for token in lines.flat_map(str::split_whitespace) {
+        body.push(u8::from_str_radix(token, 16).map_err(|_| format!("Bad hex byte {:?}", token))?);
+    }
+    Ok(GoldenDump {
+        id: id.ok_or("Missing id")?,
+        protocol: protocol.ok_or("Missing protocol")?,
+        bound: bound.ok_or("Missing direction")?,
+        state: state.ok_or("Missing state")?,
+        body,
+    })
+}
+
+/// Hexdumps of both byte arrays, where differing lines are marked by `-` for expected and `+` for actual.
+fn hexdump_diff(expected: &[u8], actual: &[u8]) -> String {
+    let mut diff = match expected.iter().zip(actual).position(|(expected, actual)| expected != actual) {
+        Some(offset) => format!("First difference at {:#x}\n", offset),
+        None => format!("Expected {} bytes, written {}\n", expected.len(), actual.len()),
+    };
+    let expected = hexdump(expected);
+    let actual = hexdump(actual);
+    let mut expected = expected.lines();
+    let mut actual = actual.lines();
+    loop {
+        match (expected.next(), actual.next()) {
+            (None, None) => return diff,
+            (Some(expected), Some(actual)) if expected == actual => diff.push_str(&format!("  {}\n", expected)),
+            (expected, actual) => {
+                if let Some(expected) = expected {
+                    diff.push_str(&format!("- {}\n", expected));
+                }
+                if let Some(actual) = actual {
+                    diff.push_str(&format!("+ {}\n", actual));
+                }
+            }
+        }
+    }
+}
+
+fn check_dump(dump: &GoldenDump, decoders: &[GoldenDecoder]) -> Result<(), String> {
+    if !ProtocolVersion::from_number(dump.protocol).is_some_and(|version| SUPPORTED_VERSIONS.contains(&version)) {
+        return Err(format!("Protocol {} is not supported", dump.protocol));
+    }
+    let decoder = decoders.iter()
+        .find(|decoder| (decoder.bound, decoder.state, decoder.id) == (dump.bound, dump.state, dump.id))
+        .ok_or_else(|| format!("No decoder of {:?} bound {:?} packet {:#04x}", dump.bound, dump.state, dump.id))?;
+    let written = (decoder.reencode)(&dump.body)
+        .map_err(|error| format!("{} is not read: {}\n{}", decoder.name, error, hexdump(&dump.body)))?;
+    match written == dump.body {
+        true => Ok(()),
+        false => Err(format!("{} is written differently\n{}", decoder.name, hexdump_diff(&dump.body, &written))),
+    }
+}
+
+fn check_file(path: &Path, decoders: &[GoldenDecoder]) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    check_dump(&parse_dump(&text)?, decoders)
+}
+
+#[test]
+fn golden_dump_tests() {
+    let decoders = decoders();
+    let mut paths: Vec<_> = std::fs::read_dir(GOLDEN_DIRECTORY).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    paths.sort();
+    assert!(paths.len() >= 10, "Only {} golden dumps are found", paths.len());
+    let failures: Vec<_> = paths.iter()
+        // Join game needs the nbt of the registry codec
+        .filter(|path| cfg!(feature = "fastnbt") || !path.ends_with("join_game.txt"))
+        .filter_map(|path| check_file(path, &decoders).err().map(|error| format!("{}: {}", path.display(), error)))
+        .collect();
+    assert!(failures.is_empty(), "{} of {} golden dumps failed\n\n{}", failures.len(), paths.len(), failures.join("\n"));
+}
+
+#[test]
+fn golden_harness_tests() {
+    let dump = parse_dump("# Comment\nid: 0x1E\nprotocol: 759\ndirection: clientbound\nstate: play\nbody:\n00 00 00 00\n00 00 30 39\n").unwrap();
+    assert_eq!(dump, GoldenDump {
+        id: 0x1E, protocol: 759, bound: PacketBound::Client, state: PacketState::Play,
+        body: vec![0, 0, 0, 0, 0, 0, 0x30, 0x39],
+    });
+    assert_eq!(parse_dump("id: 0x00\nprotocol: 759\ndirection: up\n"), Err("Bad direction \"up\"".into()));
+    assert_eq!(parse_dump("id: 0x00\nprotocol: 759\nstate: play\nbody:\n"), Err("Missing direction".into()));
+    assert_eq!(parse_dump("id: 0x00\nbody:\nzz").unwrap_err(), "Bad hex byte \"zz\"");
+
+    let decoders = decoders();
+    check_dump(&dump, &decoders).unwrap();
+    let error = check_dump(&GoldenDump { protocol: 47, ..dump.clone() }, &decoders).unwrap_err();
+    assert_eq!(error, "Protocol 47 is not supported");
+    let error = check_dump(&GoldenDump { id: 0x7F, ..dump.clone() }, &decoders).unwrap_err();
+    assert_eq!(error, "No decoder of Client bound Play packet 0x7f");
+    let error = check_dump(&GoldenDump { body: vec![0; 9], ..dump.clone() }, &decoders).unwrap_err();
+    assert!(error.starts_with("PlayKeepAlive is not read: 1 bytes are left after the packet\n00000000 "), "{}", error);
+    // Any non-zero byte is read as true, but true is written as 1
+    let mut body = vec![0; 34];
+    body.push(0x02);
+    let position = GoldenDump { id: 0x36, body, ..dump };
+    let error = check_dump(&position, &decoders).unwrap_err();
+    assert!(error.starts_with("PlaySynchronizePlayerPosition is written differently\nFirst difference at 0x22\n  00000000 "), "{}", error);
+    assert!(error.ends_with(concat!(
+        "- 00000020  00 00 02                                          |...|\n",
+        "+ 00000020  00 00 01                                          |...|\n",
+    )), "{}", error);
+}
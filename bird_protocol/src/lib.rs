@@ -1,10 +1,19 @@
 #![feature(const_trait_impl)]
 #![feature(associated_type_bounds)]
+#![feature(array_try_from_fn)]
 
 extern crate anyhow;
 
 pub mod packet;
 pub mod packet_types;
+pub mod packet_frame;
+pub mod packet_version;
+pub mod packet_io;
+pub mod packet_debug;
+#[cfg(feature = "replay")]
+pub mod packet_replay;
+#[macro_use]
+pub mod packet_state;
 #[cfg(feature = "euclid")]
 pub mod packet_euclid;
 #[cfg(feature = "tokio-bytes")]
@@ -13,11 +22,34 @@ pub mod packet_bytes;
 pub mod packet_fastnbt;
 #[cfg(feature = "packet_default")]
 pub mod packet_default;
+#[cfg(feature = "packet_default")]
+pub mod packet_metadata;
+#[cfg(feature = "packet_default")]
+pub mod packet_chunk;
+#[cfg(feature = "packet_default")]
+pub mod packet_play;
+#[cfg(feature = "packet_default")]
+pub mod packet_commands;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "compression")]
+pub mod packet_compression;
+#[cfg(feature = "encryption")]
+pub mod packet_encryption;
+#[cfg(feature = "auth")]
+pub mod server_auth;
+#[cfg(feature = "fuzz")]
+pub mod packet_fuzz;
 #[cfg(test)]
 mod tests;
+#[cfg(all(test, feature = "packet_default"))]
+mod golden_tests;
 
 pub use crate::packet::*;
 pub use crate::packet_types::*;
+pub use crate::packet_frame::*;
+#[cfg(feature = "fastnbt")]
+pub use crate::packet_fastnbt::*;
 #[cfg(feature = "derive")]
 pub mod derive {
     pub use bird_protocol_derive::*;
@@ -4,10 +4,106 @@ use anyhow::Error;
 pub enum PacketReadableError {
     #[error("Bytes exceeded")]
     BytesExceeded,
+    #[error("Bad nbt: {0}")]
+    BadNbt(Error),
+    #[error("Bad var number: {0}")]
+    BadVarNumber(&'static str),
+    #[error("Bad enum value")]
+    BadEnumValue,
+    #[error("Bad packet id: {0}")]
+    BadPacketId(i32),
+    #[error("Bad entity data type: {0}")]
+    BadEntityDataType(i32),
+    #[error("Bad entity data index: {0}")]
+    BadEntityDataIndex(u8),
+    #[error("Duplicate map key")]
+    DuplicateKey,
+    #[error("Bad length")]
+    BadLength,
+    #[error("Identifier of {length} bytes exceeds limit of {limit}")]
+    BadIdentifierLength { limit: i32, length: i32 },
+    #[error("Bad identifier: {0}")]
+    BadIdentifier(&'static str),
+    #[error("{value} is out of range {min}..={max}")]
+    OutOfRange { value: i64, min: i64, max: i64 },
+    #[error("{remaining} bytes are left after the packet")]
+    TrailingBytes { remaining: usize },
+    #[error("Number is NaN or infinite")]
+    NonFinite,
     #[error("{0}")]
     Any(#[from] Error),
 }
 
+/// Error of [PacketReadable::read_with_path].
+#[derive(Debug, thiserror::Error)]
+#[error("{error} at {offset:#x}{}", field_path_display(.field_path))]
+pub struct FieldReadError {
+    /// Offset where the failed field starts.
+    pub offset: usize,
+    /// Names of the fields from the outermost to the failed one, separated by dots.
+    pub field_path: Option<String>,
+    pub error: PacketReadableError,
+}
+
+impl FieldReadError {
+    pub fn new(error: PacketReadableError, offset: usize) -> Self {
+        Self { offset, field_path: None, error }
+    }
+
+    /// Adds the field to the start of the path. Offset is taken if the error is not inside of its inner field yet.
+    pub fn in_field(mut self, name: &str, offset: usize) -> Self {
+        self.field_path = Some(match self.field_path {
+            Some(path) => format!("{name}.{path}"),
+            None => {
+                self.offset = offset;
+                name.to_string()
+            }
+        });
+        self
+    }
+}
+
+/// Error of reading the packet with its id and position of the failure.
+#[derive(Debug, thiserror::Error)]
+#[error("Packet {id:#x}: {source} at {offset:#x}{}", field_path_display(.field_path))]
+pub struct PacketDecodeError {
+    pub id: i32,
+    pub offset: usize,
+    pub field_path: Option<String>,
+    pub source: PacketReadableError,
+}
+
+impl PacketDecodeError {
+    pub fn new(id: i32, error: FieldReadError) -> Self {
+        Self { id, offset: error.offset, field_path: error.field_path, source: error.error }
+    }
+}
+
+fn field_path_display(field_path: &Option<String>) -> String {
+    field_path.as_ref().map(|path| format!(" in field {path}")).unwrap_or_default()
+}
+
+/// Errors of writing which can be recognized by downcasting [Error].
+#[derive(Debug, thiserror::Error)]
+pub enum PacketWritableError {
+    #[error("String of {length} UTF-16 units exceeds limit of {limit}")]
+    StringLimit { limit: i32, length: usize },
+    #[error("{0} is out of range")]
+    OutOfRange(&'static str),
+    #[error("{value} is out of range {min}..={max}")]
+    OutOfBounds { value: i64, min: i64, max: i64 },
+    #[error("{value} is out of range {min}..={max}")]
+    FloatOutOfBounds { value: f64, min: f64, max: f64 },
+    #[error("Length {length} doesn't fit in the length prefix")]
+    LengthOverflow { length: usize },
+    #[error("Number is NaN or infinite")]
+    NonFinite,
+    #[error("Writing {required} bytes exceeds capacity of {capacity}")]
+    Full { capacity: usize, required: usize },
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PacketBound {
     Client,
@@ -22,16 +118,74 @@ pub enum PacketState {
     Play,
 }
 
-pub trait Packet {
+/// Bound and state of the packet. Enums of [packet_state](crate::packet_state) have ones of their packets.
+pub trait PacketScope {
     fn bound() -> PacketBound;
 
     fn state() -> PacketState;
+}
 
+pub trait Packet: PacketScope {
     fn id() -> i32;
 }
 
+/// Tells whether bytes which are left in the frame after the packet body are an error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Left bytes are [PacketReadableError::TrailingBytes].
+    #[default]
+    Strict,
+    /// Left bytes are ignored, so packets of a newer version with appended fields are still read.
+    Lenient,
+}
+
+impl DecodeMode {
+    /// Server bound packets are read strictly, client bound ones are usually passed through by proxies, so leniently.
+    pub const fn for_bound(bound: PacketBound) -> Self {
+        match bound {
+            PacketBound::Server => Self::Strict,
+            PacketBound::Client => Self::Lenient,
+        }
+    }
+
+    /// Checks the reader after the packet body is read.
+    pub fn finish<'a, R>(self, read: &R) -> Result<(), PacketReadableError> where R: PacketRead<'a> {
+        match self {
+            Self::Strict => read.finish_strict(),
+            Self::Lenient => Ok(()),
+        }
+    }
+}
+
+/// Enum without fields which is written as its value. Implemented by `PacketEnum` derive.
+pub trait PacketEnum: Sized {
+    /// Type of the value from `enum_type` attribute.
+    type Value: Copy;
+
+    fn value(&self) -> Self::Value;
+
+    fn from_value(value: Self::Value) -> Option<Self>;
+
+    /// Reads the value with the variant from `enum_variant` attribute.
+    fn read_value<'a, R>(read: &mut R) -> Result<Self::Value, PacketReadableError> where R: PacketRead<'a>;
+
+    fn write_value<W>(value: &Self::Value, write: &mut W) -> Result<(), Error> where W: PacketWrite;
+}
+
 pub trait PacketReadable<'a>: Sized {
     fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a>;
+
+    /// Reads like [PacketReadable::read], but the error also tells which field failed and where.
+    /// It is implemented by the derive macro, other types report only the offset of their start.
+    fn read_with_path<R>(read: &mut R) -> Result<Self, FieldReadError> where R: PacketRead<'a> {
+        let offset = read.offset();
+        Self::read(read).map_err(|error| FieldReadError::new(error, offset))
+    }
+}
+
+/// Enum of packets whose body is read by the already read id. Implemented by [packet_state](crate::packet_state).
+pub trait PacketReadableById<'a>: Sized {
+    fn read_by_id<R>(id: i32, read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a>;
 }
 
 pub trait PacketVariantReadable<'a, T: Sized> {
@@ -40,10 +194,24 @@ pub trait PacketVariantReadable<'a, T: Sized> {
 
 pub trait PacketWritable {
     fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite;
+
+    /// Returns count of bytes which will be written. By default object is written to [CountingPacketWrite].
+    fn size(&self) -> Result<usize, Error> {
+        let mut write = CountingPacketWrite::new();
+        self.write(&mut write)?;
+        Ok(write.length())
+    }
 }
 
 pub trait PacketVariantWritable<T: ?Sized> {
     fn write_variant<W>(object: &T, write: &mut W) -> Result<(), Error> where W: PacketWrite;
+
+    /// Returns count of bytes which will be written. By default object is written to [CountingPacketWrite].
+    fn size_variant(object: &T) -> Result<usize, Error> {
+        let mut write = CountingPacketWrite::new();
+        Self::write_variant(object, &mut write)?;
+        Ok(write.length())
+    }
 }
 
 impl<'a, T: PacketReadable<'a>> PacketVariantReadable<'a, T> for T {
@@ -56,12 +224,20 @@ impl<T: PacketWritable> PacketVariantWritable<T> for T {
     fn write_variant<W>(object: &T, write: &mut W) -> Result<(), Error> where W: PacketWrite {
         object.write(write)
     }
+
+    fn size_variant(object: &T) -> Result<usize, Error> {
+        object.size()
+    }
 }
 
 impl<'a, V, T: PacketVariantWritable<[V]>> PacketVariantWritable<&'a [V]> for T {
     fn write_variant<W>(object: &&'a [V], write: &mut W) -> Result<(), Error> where W: PacketWrite {
         T::write_variant(*object, write)
     }
+
+    fn size_variant(object: &&'a [V]) -> Result<usize, Error> {
+        T::size_variant(*object)
+    }
 }
 
 pub trait PacketWrite {
@@ -72,6 +248,17 @@ pub trait PacketWrite {
     fn write_bytes_owned(&mut self, bytes: Vec<u8>) -> Result<(), Error>;
 
     fn write_bytes_fixed<const SIZE: usize>(&mut self, bytes: [u8; SIZE]) -> Result<(), Error>;
+
+    /// Hints that at least `additional` bytes will be written, so the writer can allocate them at once.
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Writes all slices one after another.
+    fn write_slices<'b, I>(&mut self, slices: I) -> Result<(), Error> where I: IntoIterator<Item=&'b [u8]> {
+        for slice in slices {
+            self.write_bytes(slice)?;
+        }
+        Ok(())
+    }
 }
 
 pub trait PacketRead<'a> {
@@ -84,8 +271,72 @@ pub trait PacketRead<'a> {
     fn available(&self) -> usize;
 
     fn is_available(&self, bytes: usize) -> bool;
+
+    /// Count of bytes consumed from the start of the reader. Readers which don't track it return 0.
+    ///
+    /// Reader from [PacketRead::take_bounded] starts from 0 again.
+    fn offset(&self) -> usize {
+        0
+    }
+
+    /// Takes next `length` bytes without copying, if the reader is backed by [bytes::Bytes].
+    ///
+    /// Returns none and consumes nothing if the reader can't share or `length` bytes are not available.
+    #[cfg(feature = "tokio-bytes")]
+    fn take_shared(&mut self, _length: usize) -> Option<bytes::Bytes> {
+        None
+    }
+
+    /// Takes next `length` bytes and returns reader which can not read outside of them.
+    ///
+    /// This reader is advanced by `length` bytes regardless of how many bytes will be read from the returned one.
+    fn take_bounded(&mut self, length: usize) -> Result<SlicePacketRead<'a>, PacketReadableError> {
+        self.take_slice(length).map(SlicePacketRead::new)
+    }
+
+    /// Returns [PacketReadableError::TrailingBytes] if any bytes are available, so the frame was not read fully.
+    fn finish_strict(&self) -> Result<(), PacketReadableError> {
+        match self.available() {
+            0 => Ok(()),
+            remaining => Err(PacketReadableError::TrailingBytes { remaining }),
+        }
+    }
+
+    /// Returns next byte without consuming it.
+    fn peek_byte(&mut self) -> Result<u8, PacketReadableError> {
+        let byte = self.take_byte()?;
+        self.rollback(1)?;
+        Ok(byte)
+    }
+
+    /// Returns next `length` bytes without consuming them.
+    fn peek_slice(&mut self, length: usize) -> Result<&'a [u8], PacketReadableError> {
+        let slice = self.take_slice(length)?;
+        self.rollback(length)?;
+        Ok(slice)
+    }
+
+    /// Remembers current position, so reading can be restarted from it by [PacketRead::reset].
+    fn mark(&self) -> ReadMark {
+        ReadMark { available: self.available() }
+    }
+
+    /// Returns to the marked position. Mark must be taken from this reader.
+    fn reset(&mut self, mark: ReadMark) -> Result<(), Error> {
+        match mark.available.checked_sub(self.available()) {
+            Some(length) => self.rollback(length),
+            None => Err(Error::msg("Mark is ahead of the reader")),
+        }
+    }
 }
 
+/// Position of [PacketRead] which is stored as count of the available bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadMark {
+    available: usize,
+}
+
+#[derive(Debug)]
 pub struct SlicePacketRead<'a> {
     pub bytes: &'a [u8],
     offset: usize,
@@ -140,6 +391,10 @@ impl<'a> PacketRead<'a> for SlicePacketRead<'a> {
     fn is_available(&self, bytes: usize) -> bool {
         self.available() >= bytes
     }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
 }
 
 impl PacketWrite for Vec<u8> {
@@ -158,6 +413,112 @@ impl PacketWrite for Vec<u8> {
     fn write_bytes_fixed<const SIZE: usize>(&mut self, bytes: [u8; SIZE]) -> Result<(), Error> {
         self.write_bytes(bytes.as_slice())
     }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
+}
+
+/// Writer which discards bytes and only counts them.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CountingPacketWrite {
+    length: usize,
+}
+
+impl CountingPacketWrite {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+}
+
+impl PacketWrite for CountingPacketWrite {
+    fn write_byte(&mut self, _: u8) -> Result<(), Error> {
+        self.length += 1;
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.length += bytes.len();
+        Ok(())
+    }
+
+    fn write_bytes_owned(&mut self, bytes: Vec<u8>) -> Result<(), Error> {
+        self.write_bytes(bytes.as_slice())
+    }
+
+    fn write_bytes_fixed<const SIZE: usize>(&mut self, _: [u8; SIZE]) -> Result<(), Error> {
+        self.length += SIZE;
+        Ok(())
+    }
+}
+
+/// Writer into the buffer of the fixed capacity, which fails with [PacketWritableError::Full] instead of growing.
+///
+/// Bytes of the failed write are not written, so the buffer has only the complete writes.
+#[derive(Debug, Clone)]
+pub struct FixedCapacityPacketWrite {
+    bytes: Vec<u8>,
+    capacity: usize,
+}
+
+impl FixedCapacityPacketWrite {
+    pub fn new(capacity: usize) -> Self {
+        Self { bytes: Vec::with_capacity(capacity), capacity }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.capacity - self.bytes.len()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear()
+    }
+
+    fn check(&self, length: usize) -> Result<(), Error> {
+        match length <= self.remaining() {
+            true => Ok(()),
+            false => Err(PacketWritableError::Full {
+                capacity: self.capacity,
+                required: self.bytes.len() + length,
+            }.into()),
+        }
+    }
+}
+
+impl PacketWrite for FixedCapacityPacketWrite {
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+        self.check(1)?;
+        self.bytes.write_byte(byte)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.check(bytes.len())?;
+        self.bytes.write_bytes(bytes)
+    }
+
+    fn write_bytes_owned(&mut self, bytes: Vec<u8>) -> Result<(), Error> {
+        self.write_bytes(bytes.as_slice())
+    }
+
+    fn write_bytes_fixed<const SIZE: usize>(&mut self, bytes: [u8; SIZE]) -> Result<(), Error> {
+        self.write_bytes(bytes.as_slice())
+    }
 }
 
 #[cfg(test)]
@@ -180,4 +541,113 @@ mod tests {
             _ => false
         }, true);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn bounded_packet_read() {
+        let mut packet_read = SlicePacketRead::new(&[1, 2, 3, 4, 5]);
+        {
+            let mut bounded = packet_read.take_bounded(3).unwrap();
+            assert_eq!(bounded.take_byte().unwrap(), 1);
+            assert_eq!(bounded.available(), 2);
+        }
+        assert_eq!(packet_read.take_byte().unwrap(), 4);
+        {
+            let mut bounded = packet_read.take_bounded(1).unwrap();
+            assert!(matches!(bounded.take_slice(2).unwrap_err(), PacketReadableError::BytesExceeded));
+            assert_eq!(bounded.take_byte().unwrap(), 5);
+            assert!(matches!(bounded.take_byte().unwrap_err(), PacketReadableError::BytesExceeded));
+        }
+        assert!(matches!(packet_read.take_bounded(1).unwrap_err(), PacketReadableError::BytesExceeded));
+    }
+
+    #[test]
+    pub fn peek_packet_read() {
+        let mut packet_read = SlicePacketRead::new(&[1, 2, 3, 4]);
+        assert_eq!(packet_read.peek_byte().unwrap(), 1);
+        assert_eq!(packet_read.take_byte().unwrap(), 1);
+        assert_eq!(packet_read.peek_slice(2).unwrap(), &[2, 3]);
+        assert!(matches!(packet_read.peek_slice(4).unwrap_err(), PacketReadableError::BytesExceeded));
+        let mark = packet_read.mark();
+        assert_eq!(packet_read.take_slice(2).unwrap(), &[2, 3]);
+        let end = packet_read.mark();
+        packet_read.reset(mark).unwrap();
+        assert_eq!(packet_read.take_slice(3).unwrap(), &[2, 3, 4]);
+        assert!(matches!(packet_read.peek_byte().unwrap_err(), PacketReadableError::BytesExceeded));
+        packet_read.reset(end).unwrap();
+        assert_eq!(packet_read.take_byte().unwrap(), 4);
+        let mut packet_read = SlicePacketRead::new(&[1, 2]);
+        let mark = packet_read.mark();
+        packet_read.take_byte().unwrap();
+        let mut other = SlicePacketRead::new(&[1, 2, 3]);
+        assert!(other.reset(packet_read.mark()).is_err());
+        packet_read.reset(mark).unwrap();
+        assert_eq!(packet_read.available(), 2);
+    }
+
+    #[test]
+    pub fn finish_packet_read() {
+        let mut packet_read = SlicePacketRead::new(&[1, 2, 3]);
+        packet_read.take_byte().unwrap();
+        assert!(matches!(packet_read.finish_strict(), Err(PacketReadableError::TrailingBytes { remaining: 2 })));
+        assert!(matches!(DecodeMode::Strict.finish(&packet_read), Err(PacketReadableError::TrailingBytes { remaining: 2 })));
+        DecodeMode::Lenient.finish(&packet_read).unwrap();
+        packet_read.take_slice(2).unwrap();
+        packet_read.finish_strict().unwrap();
+        DecodeMode::Strict.finish(&packet_read).unwrap();
+        DecodeMode::Lenient.finish(&packet_read).unwrap();
+        // Bounded reader is finished by itself
+        let mut packet_read = SlicePacketRead::new(&[1, 2, 3]);
+        let bounded = packet_read.take_bounded(2).unwrap();
+        assert!(matches!(bounded.finish_strict(), Err(PacketReadableError::TrailingBytes { remaining: 2 })));
+        assert_eq!(DecodeMode::for_bound(PacketBound::Server), DecodeMode::Strict);
+        assert_eq!(DecodeMode::for_bound(PacketBound::Client), DecodeMode::Lenient);
+    }
+
+    #[test]
+    pub fn reserve_packet_write() {
+        let mut write = Vec::new();
+        PacketWrite::reserve(&mut write, 100);
+        assert!(write.capacity() >= 100);
+        write.write_slices([&[1u8, 2][..], &[], &[3]]).unwrap();
+        assert_eq!(write, &[1, 2, 3]);
+        let mut counting = CountingPacketWrite::new();
+        counting.reserve(100);
+        counting.write_slices([&[1u8, 2][..], &[3]]).unwrap();
+        assert_eq!(counting.length(), 3);
+        // Arrays reserve the whole size before writing elements
+        let mut write = Vec::new();
+        vec![0x12345678i32; 100].write(&mut write).unwrap();
+        assert_eq!(write.len(), 401);
+        assert!(write.capacity() >= 401);
+    }
+
+    #[test]
+    pub fn fixed_capacity_packet_write() {
+        let mut write = FixedCapacityPacketWrite::new(4);
+        write.write_bytes_fixed([1, 2, 3]).unwrap();
+        assert_eq!(write.remaining(), 1);
+        let error = write.write_bytes(&[4, 5]).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketWritableError::Full { capacity: 4, required: 5 })));
+        // Failed write is not written partially
+        assert_eq!(write.as_slice(), &[1, 2, 3]);
+        write.write_byte(4).unwrap();
+        assert!(write.write_byte(5).is_err());
+        assert_eq!(write.into_inner(), vec![1, 2, 3, 4]);
+        // Error is surfaced from the nested writable
+        let strings = vec!["first".to_string(), "second".to_string(), "third".to_string()];
+        let size = strings.size().unwrap();
+        let mut write = FixedCapacityPacketWrite::new(size - 1);
+        let error = strings.write(&mut write).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(PacketWritableError::Full { capacity, required }) if *capacity == size - 1 && *required == size,
+        ));
+        // Length of the last string is written before its bytes
+        assert_eq!(write.as_slice().len(), size - 5);
+        let mut write = FixedCapacityPacketWrite::new(size);
+        strings.write(&mut write).unwrap();
+        assert_eq!(write.remaining(), 0);
+        write.clear();
+        assert_eq!(write.remaining(), size);
+    }
+}
@@ -1,6 +1,16 @@
+#[cfg(feature = "compression")]
+use std::borrow::Cow;
 use anyhow::Error;
-use bytes::{BufMut, BytesMut};
-use crate::packet::PacketWrite;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::packet::{Packet, PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite, SlicePacketRead};
+#[cfg(feature = "compression")]
+use crate::packet_compression::PacketCompression;
+#[cfg(feature = "encryption")]
+use crate::packet_encryption::{PacketDecryptor, PacketEncryptor};
+use crate::packet_frame::write_frame_bytes;
+pub use crate::packet_frame::DEFAULT_MAX_FRAME_LENGTH;
+use crate::packet_types::{DEFAULT_LIMIT, LengthProvidedBytesSlice, PacketVariant, PREALLOCATION_LIMIT, str_fits_limit, VarInt, write_str_with_limit};
 
 impl PacketWrite for BytesMut {
     fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
@@ -18,4 +28,1308 @@ impl PacketWrite for BytesMut {
     fn write_bytes_fixed<const SIZE: usize>(&mut self, bytes: [u8; SIZE]) -> Result<(), Error> {
         self.write_bytes(bytes.as_slice())
     }
-}
\ No newline at end of file
+
+    fn reserve(&mut self, additional: usize) {
+        BytesMut::reserve(self, additional)
+    }
+}
+
+/// Reader of [Bytes], which shares them with [PacketRead::take_shared] instead of copying.
+#[derive(Debug)]
+pub struct BytesPacketRead<'a> {
+    bytes: &'a Bytes,
+    offset: usize,
+}
+
+impl<'a> BytesPacketRead<'a> {
+    pub fn new(bytes: &'a Bytes) -> Self {
+        Self { bytes, offset: 0 }
+    }
+}
+
+impl<'a> PacketRead<'a> for BytesPacketRead<'a> {
+    fn take_byte(&mut self) -> Result<u8, PacketReadableError> {
+        match self.offset == self.bytes.len() {
+            true => Err(PacketReadableError::BytesExceeded),
+            false => {
+                let byte = self.bytes[self.offset];
+                self.offset += 1;
+                Ok(byte)
+            }
+        }
+    }
+
+    fn take_slice(&mut self, length: usize) -> Result<&'a [u8], PacketReadableError> {
+        match self.is_available(length) {
+            true => {
+                let bytes: &'a [u8] = self.bytes;
+                self.offset += length;
+                Ok(&bytes[self.offset - length..self.offset])
+            }
+            false => Err(PacketReadableError::BytesExceeded),
+        }
+    }
+
+    fn rollback(&mut self, length: usize) -> Result<(), Error> {
+        match self.offset < length {
+            true => Err(Error::msg("Can not rollback")),
+            false => {
+                self.offset -= length;
+                Ok(())
+            }
+        }
+    }
+
+    fn available(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    fn is_available(&self, bytes: usize) -> bool {
+        self.available() >= bytes
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn take_shared(&mut self, length: usize) -> Option<Bytes> {
+        match self.is_available(length) {
+            true => {
+                self.offset += length;
+                Some(self.bytes.slice(self.offset - length..self.offset))
+            }
+            false => None,
+        }
+    }
+}
+
+fn take_bytes<'a, R>(read: &mut R, length: usize) -> Result<Bytes, PacketReadableError> where R: PacketRead<'a> {
+    match read.take_shared(length) {
+        Some(bytes) => Ok(bytes),
+        None => read.take_slice(length).map(Bytes::copy_from_slice),
+    }
+}
+
+/// Bytes prefixed by [VarInt] length. They are not copied if the reader is [BytesPacketRead].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ProtocolBytes(pub Bytes);
+
+impl<'a> PacketReadable<'a> for ProtocolBytes {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let length = VarInt::read_variant(read)?;
+        match length < 0 {
+            true => Err(PacketReadableError::BadLength),
+            false => take_bytes(read, length as usize).map(Self),
+        }
+    }
+}
+
+impl PacketWritable for ProtocolBytes {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        LengthProvidedBytesSlice::<VarInt, i32>::write_variant(self.0.as_ref(), write)
+    }
+}
+
+/// String like `&str`, which owns [Bytes]. They are not copied if the reader is [BytesPacketRead].
+///
+/// UTF-8 is validated on construction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ProtocolStr(Bytes);
+
+impl ProtocolStr {
+    pub fn new(bytes: Bytes) -> Result<Self, std::str::Utf8Error> {
+        std::str::from_utf8(&bytes)?;
+        Ok(Self(bytes))
+    }
+
+    pub const fn from_static(str: &'static str) -> Self {
+        Self(Bytes::from_static(str.as_bytes()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Safety. bytes are validated on construction
+        unsafe { std::str::from_utf8_unchecked(&self.0) }
+    }
+
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+impl From<String> for ProtocolStr {
+    fn from(str: String) -> Self {
+        Self(Bytes::from(str))
+    }
+}
+
+impl From<&'static str> for ProtocolStr {
+    fn from(str: &'static str) -> Self {
+        Self::from_static(str)
+    }
+}
+
+impl std::fmt::Display for ProtocolStr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'a> PacketReadable<'a> for ProtocolStr {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let length = VarInt::read_variant(read)?;
+        if length < 0 || length as i64 > DEFAULT_LIMIT as i64 * 3 {
+            return Err(PacketReadableError::Any(Error::msg("Too big string")));
+        }
+        let str = Self::new(take_bytes(read, length as usize)?)
+            .map_err(|err| PacketReadableError::Any(err.into()))?;
+        match str_fits_limit(str.as_str(), DEFAULT_LIMIT) {
+            true => Ok(str),
+            false => Err(PacketReadableError::Any(Error::msg("Too big string"))),
+        }
+    }
+}
+
+impl PacketWritable for ProtocolStr {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_str_with_limit(self.as_str(), DEFAULT_LIMIT, write)
+    }
+}
+
+fn unexpected_eof() -> PacketReadableError {
+    Error::from(std::io::Error::from(std::io::ErrorKind::UnexpectedEof)).into()
+}
+
+/// Reader of the frames from the async stream.
+///
+/// Bytes are buffered until the whole frame is received, so [PacketStreamReader::next_packet] is cancellation safe.
+pub struct PacketStreamReader<S> {
+    stream: S,
+    buffer: BytesMut,
+    max_frame_length: usize,
+    /// Frames which are returned since [PacketStreamReader::mark] was called.
+    marked: Option<BytesMut>,
+    #[cfg(feature = "compression")]
+    compression: Option<PacketCompression>,
+    #[cfg(feature = "encryption")]
+    decryptor: Option<PacketDecryptor>,
+}
+
+impl<S: AsyncRead + Unpin> PacketStreamReader<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            buffer: BytesMut::new(),
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+            marked: None,
+            #[cfg(feature = "compression")]
+            compression: None,
+            #[cfg(feature = "encryption")]
+            decryptor: None,
+        }
+    }
+
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Returns the stream and bytes which are received, but not returned as a frame yet.
+    ///
+    /// Frames which are returned after the mark are not included.
+    pub fn into_inner(self) -> (S, BytesMut) {
+        (self.stream, self.buffer)
+    }
+
+    /// Sets compression of the next frames, so [PacketStreamReader::next_packet] returns uncompressed bodies.
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, compression: Option<PacketCompression>) {
+        self.compression = compression;
+    }
+
+    /// Decrypts all bytes which are not returned yet and all bytes which are received later.
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), Error> {
+        let mut decryptor = PacketDecryptor::new(shared_secret)?;
+        decryptor.decrypt(&mut self.buffer);
+        self.decryptor = Some(decryptor);
+        Ok(())
+    }
+
+    /// Starts keeping returned frames, so they can be returned again after [PacketStreamReader::reset].
+    ///
+    /// Previous mark is replaced.
+    pub fn mark(&mut self) {
+        self.marked = Some(BytesMut::new());
+    }
+
+    /// Stops keeping returned frames without returning them again.
+    pub fn unmark(&mut self) {
+        self.marked = None;
+    }
+
+    /// Returns to the marked position, so frames which are returned after the mark are returned again.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        match self.marked.take() {
+            Some(mut marked) => {
+                marked.unsplit(std::mem::take(&mut self.buffer));
+                self.buffer = marked;
+                Ok(())
+            }
+            None => Err(Error::msg("Reader is not marked")),
+        }
+    }
+
+    /// Reads more bytes into the buffer. Returns false if the stream is closed.
+    async fn receive(&mut self) -> Result<bool, PacketReadableError> {
+        let start = self.buffer.len();
+        if self.stream.read_buf(&mut self.buffer).await.map_err(Error::from)? == 0 {
+            return Ok(false);
+        }
+        #[cfg(feature = "encryption")]
+        if let Some(decryptor) = &mut self.decryptor {
+            decryptor.decrypt(&mut self.buffer[start..]);
+        }
+        #[cfg(not(feature = "encryption"))]
+        let _ = start;
+        Ok(true)
+    }
+
+    /// Returns received bytes which are not consumed yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Returns next `length` received bytes without consuming them, waiting until they arrive.
+    ///
+    /// Bytes stay buffered, so the future is cancellation safe as well.
+    pub async fn peek(&mut self, length: usize) -> Result<&[u8], PacketReadableError> {
+        while self.buffer.len() < length {
+            if !self.receive().await? {
+                return Err(unexpected_eof());
+            }
+        }
+        Ok(&self.buffer[..length])
+    }
+
+    /// Returns body of the next frame.
+    ///
+    /// If the stream is closed, [std::io::ErrorKind::UnexpectedEof] error is returned.
+    /// Dropping the future does not lose any received bytes, so it can be used in `tokio::select!`.
+    pub async fn next_packet(&mut self) -> Result<BytesMut, PacketReadableError> {
+        self.try_next_packet().await?.ok_or_else(unexpected_eof)
+    }
+
+    /// Reads value from the body of the next frame with [SlicePacketRead], because the whole frame is received.
+    ///
+    /// Frame is dropped after reading, so the value can't borrow it. To borrow use [PacketStreamReader::next_packet].
+    pub async fn read_next<T>(&mut self) -> Result<T, PacketReadableError> where T: for<'a> PacketReadable<'a> {
+        let frame = self.next_packet().await?;
+        T::read(&mut SlicePacketRead::new(&frame))
+    }
+
+    /// Returns body of the next frame or none if the stream is closed right before the frame.
+    ///
+    /// If the stream is closed in the middle of the frame, [std::io::ErrorKind::UnexpectedEof] error is returned.
+    pub async fn try_next_packet(&mut self) -> Result<Option<BytesMut>, PacketReadableError> {
+        loop {
+            if let Some(frame) = self.split_frame()? {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(length = frame.len(), "frame is read");
+                return self.uncompress(frame).map(Some);
+            }
+            if !self.receive().await? {
+                return match self.buffer.is_empty() {
+                    true => Ok(None),
+                    false => Err(unexpected_eof()),
+                };
+            }
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    fn uncompress(&self, mut frame: BytesMut) -> Result<BytesMut, PacketReadableError> {
+        let Some(compression) = &self.compression else { return Ok(frame); };
+        let body = match compression.decompress(&frame)? {
+            Cow::Borrowed(body) => frame.len() - body.len(),
+            Cow::Owned(body) => return Ok(BytesMut::from(body.as_slice())),
+        };
+        frame.advance(body);
+        Ok(frame)
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn uncompress(&self, frame: BytesMut) -> Result<BytesMut, PacketReadableError> {
+        Ok(frame)
+    }
+
+    fn split_frame(&mut self) -> Result<Option<BytesMut>, PacketReadableError> {
+        let mut length = 0usize;
+        for (position, byte) in self.buffer.iter().take(5).enumerate() {
+            length |= ((byte & 0x7F) as usize) << (position * 7);
+            if (byte & 0x80) != 0 {
+                continue;
+            }
+            if length > self.max_frame_length {
+                return Err(PacketReadableError::Any(Error::msg("Too big frame")));
+            }
+            return Ok(match self.buffer.len() - position > length {
+                true => {
+                    if let Some(marked) = &mut self.marked {
+                        marked.extend_from_slice(&self.buffer[..position + 1 + length]);
+                    }
+                    self.buffer.advance(position + 1);
+                    Some(self.buffer.split_to(length))
+                }
+                false => {
+                    // Declared length is not trusted, so the buffer grows with received bytes
+                    self.buffer.reserve((length + position + 1 - self.buffer.len()).min(PREALLOCATION_LIMIT));
+                    None
+                }
+            });
+        }
+        match self.buffer.len() >= 5 {
+            true => Err(PacketReadableError::BadVarNumber("Too long frame length")),
+            false => Ok(None),
+        }
+    }
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for PacketStreamReader<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketStreamReader")
+            .field("stream", &self.stream)
+            .field("buffered", &self.buffer.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Waits for the whole next frame of the stream once and then reads the value from its body synchronously.
+///
+/// Unlike [PacketStreamReader] nothing is buffered after the frame, so the stream can be used for other things
+/// right after, but a dropped future loses bytes which are already received.
+pub async fn read_framed<T, S>(stream: &mut S, max_frame_length: usize) -> Result<T, PacketReadableError>
+    where T: for<'a> PacketReadable<'a>, S: AsyncRead + Unpin {
+    let length = read_frame_length(stream, max_frame_length).await?;
+    // Declared length is not trusted, so the body grows with received bytes
+    let mut body = Vec::with_capacity(length.min(PREALLOCATION_LIMIT));
+    stream.take(length as u64).read_to_end(&mut body).await.map_err(Error::from)?;
+    match body.len() == length {
+        true => T::read(&mut SlicePacketRead::new(&body)),
+        false => Err(unexpected_eof()),
+    }
+}
+
+/// Reads the length prefix of the frame byte by byte, so nothing after it is consumed.
+async fn read_frame_length<S>(stream: &mut S, max_frame_length: usize) -> Result<usize, PacketReadableError>
+    where S: AsyncRead + Unpin {
+    let mut length = 0usize;
+    let mut position = 0;
+    loop {
+        let byte = stream.read_u8().await.map_err(Error::from)?;
+        length |= ((byte & 0x7F) as usize) << (position * 7);
+        if (byte & 0x80) == 0 {
+            break;
+        }
+        position += 1;
+        if position == VarInt::MAX_SIZE {
+            return Err(PacketReadableError::BadVarNumber("Too long frame length"));
+        }
+    }
+    match length > max_frame_length {
+        true => Err(PacketReadableError::Any(Error::msg("Too big frame"))),
+        false => Ok(length),
+    }
+}
+
+/// Default count of bytes which [IncrementalFrameReader] requests from the stream at once.
+pub const DEFAULT_READ_AHEAD: usize = 8192;
+
+/// Reader of one frame body, which receives it from the async stream while values of the body are read.
+///
+/// Values are read synchronously from the received bytes. If they are not enough, more bytes are received
+/// and the value is read again, so large packets like chunk data can be read value by value while they arrive.
+/// At most the length of the frame is consumed from the stream, so the next frame stays in it.
+pub struct IncrementalFrameReader<S> {
+    stream: S,
+    buffer: BytesMut,
+    /// Bytes of the frame which are not received yet.
+    unreceived: usize,
+    read_ahead: usize,
+}
+
+impl<S: AsyncRead + Unpin> IncrementalFrameReader<S> {
+    /// Reader of the body with the given length, which starts at the current position of the stream.
+    pub fn new(stream: S, length: usize) -> Self {
+        Self { stream, buffer: BytesMut::new(), unreceived: length, read_ahead: DEFAULT_READ_AHEAD }
+    }
+
+    /// Reads the length prefix of the next frame and returns reader of its body.
+    pub async fn from_frame(mut stream: S, max_frame_length: usize) -> Result<Self, PacketReadableError> {
+        let length = read_frame_length(&mut stream, max_frame_length).await?;
+        Ok(Self::new(stream, length))
+    }
+
+    /// Sets count of bytes which are requested from the stream at once. It can't be 0.
+    pub fn with_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead.max(1);
+        self
+    }
+
+    /// Count of bytes of the body which are not read yet, received or not.
+    pub fn remaining(&self) -> usize {
+        self.buffer.len() + self.unreceived
+    }
+
+    /// Tells whether the body has `length` more bytes, even if they are not received yet.
+    pub fn has_bytes(&self, length: usize) -> bool {
+        self.remaining() >= length
+    }
+
+    /// Bytes which are received, but not read yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Receives at most `length` more bytes of the body. Closed stream is [std::io::ErrorKind::UnexpectedEof].
+    async fn receive(&mut self, length: usize) -> Result<(), PacketReadableError> {
+        let limit = length.min(self.unreceived);
+        self.buffer.reserve(limit.min(PREALLOCATION_LIMIT));
+        let received = (&mut self.stream).take(limit as u64).read_buf(&mut self.buffer).await.map_err(Error::from)?;
+        if received == 0 {
+            return Err(unexpected_eof());
+        }
+        self.unreceived -= received;
+        Ok(())
+    }
+
+    /// Waits until at least `length` bytes are received. Bytes are requested by the read ahead, but not after the body.
+    pub async fn ensure(&mut self, length: usize) -> Result<(), PacketReadableError> {
+        if !self.has_bytes(length) {
+            return Err(PacketReadableError::BytesExceeded);
+        }
+        while self.buffer.len() < length {
+            self.receive((length - self.buffer.len()).max(self.read_ahead)).await?;
+        }
+        Ok(())
+    }
+
+    /// Reads the next value of the body, receiving bytes until it is read.
+    ///
+    /// Value is read again after each receive, so the requested count doubles to keep the reading of large values linear.
+    pub async fn read<T>(&mut self) -> Result<T, PacketReadableError> where T: for<'a> PacketReadable<'a> {
+        let mut read_ahead = self.read_ahead;
+        loop {
+            let mut read = SlicePacketRead::new(&self.buffer);
+            match T::read(&mut read) {
+                Ok(value) => {
+                    let consumed = self.buffer.len() - read.available();
+                    self.buffer.advance(consumed);
+                    return Ok(value);
+                }
+                Err(PacketReadableError::BytesExceeded) if self.unreceived > 0 => {
+                    self.receive(read_ahead).await?;
+                    read_ahead = read_ahead.saturating_mul(2);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Receives and drops the rest of the body, so the stream is at the next frame.
+    pub async fn skip_remaining(&mut self) -> Result<(), PacketReadableError> {
+        self.buffer.clear();
+        while self.unreceived > 0 {
+            self.receive(self.read_ahead).await?;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    /// Returns the stream, which is positioned after the received bytes of the body.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+/// Writer of the frames to the async stream.
+///
+/// Frames are written to the stream right away, but the stream is flushed only by [PacketStreamWriter::flush].
+pub struct PacketStreamWriter<S> {
+    stream: S,
+    body: Vec<u8>,
+    frame: Vec<u8>,
+    #[cfg(feature = "compression")]
+    compressed: Vec<u8>,
+    #[cfg(feature = "compression")]
+    compression: Option<PacketCompression>,
+    #[cfg(feature = "encryption")]
+    encryptor: Option<PacketEncryptor>,
+}
+
+impl<S: AsyncWrite + Unpin> PacketStreamWriter<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            body: Vec::new(),
+            frame: Vec::new(),
+            #[cfg(feature = "compression")]
+            compressed: Vec::new(),
+            #[cfg(feature = "compression")]
+            compression: None,
+            #[cfg(feature = "encryption")]
+            encryptor: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Sets compression of the next frames.
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, compression: Option<PacketCompression>) {
+        self.compression = compression;
+    }
+
+    /// Encrypts all next frames.
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), Error> {
+        self.encryptor = Some(PacketEncryptor::new(shared_secret)?);
+        Ok(())
+    }
+
+    /// Writes packet with its id as the frame.
+    pub async fn write_packet<T>(&mut self, packet: &T) -> Result<(), Error> where T: PacketWritable + Packet {
+        let mut body = std::mem::take(&mut self.body);
+        body.clear();
+        let result = match PacketVariant::write_variant(packet, &mut body) {
+            Ok(()) => self.write_frame(&body).await,
+            Err(err) => Err(err),
+        };
+        self.body = body;
+        result
+    }
+
+    /// Writes already serialized body as the frame.
+    pub async fn write_frame(&mut self, body: &[u8]) -> Result<(), Error> {
+        #[cfg(feature = "compression")]
+        let body = match &self.compression {
+            Some(compression) => {
+                self.compressed.clear();
+                compression.compress(body, &mut self.compressed)?;
+                self.compressed.as_slice()
+            }
+            None => body,
+        };
+        self.frame.clear();
+        write_frame_bytes(body, &mut self.frame)?;
+        #[cfg(feature = "encryption")]
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.encrypt(&mut self.frame);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(length = body.len(), frame_length = self.frame.len(), "frame is written");
+        Ok(self.stream.write_all(&self.frame).await?)
+    }
+
+    /// Writes all frames of the batch with one call and clears it. Frames are encrypted if the encryption is enabled.
+    pub async fn write_batch(&mut self, batch: &mut PacketBatch) -> Result<(), Error> {
+        #[cfg(feature = "encryption")]
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.encrypt(&mut batch.buffer);
+        }
+        let result = self.stream.write_all(&batch.buffer).await;
+        batch.clear();
+        Ok(result?)
+    }
+
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.stream.flush().await?)
+    }
+}
+
+/// Frames which are written to the stream at once, for example packets of one tick.
+///
+/// Buffer is cleared after the write but keeps its capacity, so steady batches don't allocate.
+#[derive(Debug, Default)]
+pub struct PacketBatch {
+    buffer: BytesMut,
+    body: Vec<u8>,
+    packet_count: usize,
+    #[cfg(feature = "compression")]
+    compressed: Vec<u8>,
+    #[cfg(feature = "compression")]
+    compression: Option<PacketCompression>,
+}
+
+impl PacketBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { buffer: BytesMut::with_capacity(capacity), ..Self::default() }
+    }
+
+    /// Sets compression of the next frames.
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, compression: Option<PacketCompression>) {
+        self.compression = compression;
+    }
+
+    /// Appends packet with its id as the frame.
+    pub fn push<T>(&mut self, packet: &T) -> Result<(), Error> where T: PacketWritable + Packet {
+        let mut body = std::mem::take(&mut self.body);
+        body.clear();
+        let result = match PacketVariant::write_variant(packet, &mut body) {
+            Ok(()) => self.push_frame(&body),
+            Err(err) => Err(err),
+        };
+        self.body = body;
+        result
+    }
+
+    /// Appends already serialized body as the frame.
+    pub fn push_frame(&mut self, body: &[u8]) -> Result<(), Error> {
+        #[cfg(feature = "compression")]
+        let body = match &self.compression {
+            Some(compression) => {
+                self.compressed.clear();
+                compression.compress(body, &mut self.compressed)?;
+                self.compressed.as_slice()
+            }
+            None => body,
+        };
+        write_frame_bytes(body, &mut self.buffer)?;
+        self.packet_count += 1;
+        Ok(())
+    }
+
+    /// Count of bytes in the batch.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn packet_count(&self) -> usize {
+        self.packet_count
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.packet_count = 0;
+    }
+
+    /// Writes all frames with one call and clears the batch. The stream is not flushed.
+    ///
+    /// Frames are written as they are, so encrypted streams should use [PacketStreamWriter::write_batch].
+    pub async fn flush_to<W>(&mut self, write: &mut W) -> Result<(), Error> where W: AsyncWrite + Unpin {
+        write.write_all(&self.buffer).await?;
+        self.clear();
+        Ok(())
+    }
+}
+
+/// How [write_framed_into] wrote the length of the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramingStrategy {
+    /// Size of the body was computed by [PacketWritable::size], so the length is written before the body.
+    Sized,
+    /// Size was not known, so the body was written after space of the longest [VarInt] and then shifted to the length.
+    Shifted,
+}
+
+/// Max bytes of [VarInt], which the shifted frame reserves for the length.
+const MAX_LENGTH_SIZE: usize = 5;
+
+/// Writes object with the [VarInt] id as the frame right into the buffer, without the intermediate body buffer.
+///
+/// Nothing is allocated if the buffer has enough capacity. If the object fails, the buffer is truncated back.
+pub fn write_framed_into<T>(buf: &mut BytesMut, id: i32, object: &T) -> Result<FramingStrategy, Error>
+    where T: PacketWritable + ?Sized {
+    let start = buf.len();
+    let result = match object.size() {
+        Ok(size) => write_sized(buf, id, object, size),
+        Err(_) => write_shifted(buf, start, id, object),
+    };
+    if result.is_err() {
+        buf.truncate(start);
+    }
+    result
+}
+
+fn write_sized<T>(buf: &mut BytesMut, id: i32, object: &T, size: usize) -> Result<FramingStrategy, Error>
+    where T: PacketWritable + ?Sized {
+    let length = frame_length(VarInt::size_variant(&id)? + size)?;
+    let frame_start = buf.len();
+    // Size is only a hint of the object, so it is not trusted
+    buf.reserve((VarInt::size_variant(&length)? + length as usize).min(PREALLOCATION_LIMIT));
+    VarInt::write_variant(&length, buf)?;
+    let body_start = buf.len();
+    VarInt::write_variant(&id, buf)?;
+    object.write(buf)?;
+    match buf.len() - body_start == length as usize {
+        true => Ok(FramingStrategy::Sized),
+        // Size differs from the written body, so the length is patched
+        false => patch_length(buf, frame_start, body_start).map(|_| FramingStrategy::Shifted),
+    }
+}
+
+fn write_shifted<T>(buf: &mut BytesMut, frame_start: usize, id: i32, object: &T) -> Result<FramingStrategy, Error>
+    where T: PacketWritable + ?Sized {
+    buf.put_bytes(0, MAX_LENGTH_SIZE);
+    let body_start = buf.len();
+    VarInt::write_variant(&id, buf)?;
+    object.write(buf)?;
+    patch_length(buf, frame_start, body_start).map(|_| FramingStrategy::Shifted)
+}
+
+fn frame_length(length: usize) -> Result<i32, Error> {
+    i32::try_from(length).map_err(|_| Error::msg("Too big frame"))
+}
+
+/// Writes length of the body, which starts at `body_start`, to `frame_start` and moves the body right after it.
+fn patch_length(buf: &mut BytesMut, frame_start: usize, body_start: usize) -> Result<(), Error> {
+    let mut value = frame_length(buf.len() - body_start)? as u32;
+    let mut prefix = [0; MAX_LENGTH_SIZE];
+    let mut prefix_length = 0;
+    loop {
+        let byte = value as u8 & 0x7F;
+        value >>= 7;
+        prefix[prefix_length] = match value {
+            0 => byte,
+            _ => byte | 0x80,
+        };
+        prefix_length += 1;
+        if value == 0 {
+            break;
+        }
+    }
+    let new_body_start = frame_start + prefix_length;
+    if new_body_start > body_start {
+        buf.put_bytes(0, new_body_start - body_start);
+    }
+    let body_end = buf.len() - new_body_start.saturating_sub(body_start);
+    buf.copy_within(body_start..body_end, new_body_start);
+    buf.truncate(body_end - body_start + new_body_start);
+    buf[frame_start..new_body_start].copy_from_slice(&prefix[..prefix_length]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn bytes_mut_write_tests() {
+        let body = vec![0x5A; 256 * 1024];
+        let mut bytes = BytesMut::new();
+        crate::packet_types::LengthProvidedBytesSlice::<crate::packet_types::VarInt, i32>::write_variant(&body, &mut bytes).unwrap();
+        assert!(bytes.capacity() >= body.len() + 3);
+        let mut vec = Vec::new();
+        crate::packet_types::LengthProvidedBytesSlice::<crate::packet_types::VarInt, i32>::write_variant(&body, &mut vec).unwrap();
+        assert_eq!(&bytes[..], vec.as_slice());
+        assert_eq!(&bytes[..4], &[0x80, 0x80, 0x10, 0x5A]);
+    }
+
+    #[tokio::test]
+    async fn byte_by_byte_tests() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        let bytes = [0x03, 1, 2, 3, 0x00, 0x80, 0x01];
+        let mut frame = vec![0xAB; 128];
+        frame.extend_from_slice(&[0x01, 0x07]);
+        let written = tokio::spawn(async move {
+            for byte in bytes.iter().chain(&frame[..128]).chain(&frame[128..]) {
+                client.write_all(&[*byte]).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        });
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[1, 2, 3]);
+        assert!(reader.next_packet().await.unwrap().is_empty());
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0xAB; 128]);
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x07]);
+        written.await.unwrap();
+        assert!(reader.next_packet().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_next_tests() {
+        type Body = (u8, String, uuid::Uuid, crate::packet_types::BlockPosition, Option<i64>, Vec<u16>);
+        let body: Body = (
+            7, "bird".into(), uuid::Uuid::from_u128(0x1234), crate::packet_types::BlockPosition { x: 5, y: -3, z: 100 },
+            Some(-1), vec![1, 2, 3],
+        );
+        let mut bytes = Vec::new();
+        body.write(&mut bytes).unwrap();
+        let mut framed = Vec::new();
+        write_frame_bytes(&bytes, &mut framed).unwrap();
+        write_frame_bytes(&[0x01], &mut framed).unwrap();
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        tokio::spawn(async move { client.write_all(&framed).await.unwrap() });
+        // Stream and slice give the same values for the same bytes
+        let streamed: Body = reader.read_next().await.unwrap();
+        assert_eq!(streamed, Body::read(&mut SlicePacketRead::new(&bytes)).unwrap());
+        assert_eq!(streamed, body);
+        assert!(reader.read_next::<String>().await.is_err());
+        assert!(reader.read_next::<u8>().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_framed_tests() {
+        let mut framed = Vec::new();
+        write_frame_bytes(&[0x04, b'b', b'i', b'r', b'd'], &mut framed).unwrap();
+        write_frame_bytes(&[0x01, 0x2C], &mut framed).unwrap();
+        framed.extend_from_slice(&[0x03, 0x01]);
+        let (mut client, mut server) = tokio::io::duplex(64);
+        tokio::spawn(async move {
+            for byte in framed {
+                client.write_all(&[byte]).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        });
+        assert_eq!(read_framed::<String, _>(&mut server, DEFAULT_MAX_FRAME_LENGTH).await.unwrap(), "bird");
+        assert_eq!(read_framed::<u16, _>(&mut server, DEFAULT_MAX_FRAME_LENGTH).await.unwrap(), 300);
+        assert!(read_framed::<u8, _>(&mut server, DEFAULT_MAX_FRAME_LENGTH).await.is_err());
+
+        let mut stream: &[u8] = &[0x05, 1, 2, 3, 4, 5];
+        assert!(read_framed::<u8, _>(&mut stream, 4).await.is_err());
+        let mut stream: &[u8] = &[0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+        assert!(matches!(read_framed::<u8, _>(&mut stream, DEFAULT_MAX_FRAME_LENGTH).await, Err(PacketReadableError::BadVarNumber(_))));
+    }
+
+    #[tokio::test]
+    async fn cancellation_tests() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        let frames: Vec<Vec<u8>> = (1..=5u8).map(|length| (0..length).collect()).collect();
+        let sent = frames.clone();
+        tokio::spawn(async move {
+            for frame in sent {
+                let mut bytes = vec![frame.len() as u8];
+                bytes.extend_from_slice(&frame);
+                for byte in bytes {
+                    client.write_all(&[byte]).await.unwrap();
+                    tokio::time::sleep(Duration::from_millis(2)).await;
+                }
+            }
+        });
+        let mut received = Vec::new();
+        let mut cancelled = 0;
+        while received.len() < frames.len() {
+            tokio::select! {
+                frame = reader.next_packet() => received.push(frame.unwrap().to_vec()),
+                _ = tokio::time::sleep(Duration::from_millis(1)) => cancelled += 1,
+            }
+        }
+        assert!(cancelled > 0);
+        assert_eq!(received, frames);
+    }
+
+    #[tokio::test]
+    async fn peek_tests() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        client.write_all(&[0xFE]).await.unwrap();
+        assert_eq!(reader.peek(1).await.unwrap(), &[0xFE]);
+        let written = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            client.write_all(&[0x01, 0x02, 0x05]).await.unwrap();
+            client
+        });
+        // Peeked bytes are kept while more bytes arrive
+        assert_eq!(reader.peek(3).await.unwrap(), &[0xFE, 0x01, 0x02]);
+        let _client = written.await.unwrap();
+        assert_eq!(reader.peek(4).await.unwrap(), &[0xFE, 0x01, 0x02, 0x05]);
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        client.write_all(&[0x02, 0x0A, 0x0B]).await.unwrap();
+        assert_eq!(reader.peek(2).await.unwrap(), &[0x02, 0x0A]);
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x0A, 0x0B]);
+        drop(client);
+        assert!(reader.peek(1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mark_tests() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        assert!(reader.reset().is_err());
+        client.write_all(&[0x01, 0x0A, 0x03, 0x0B]).await.unwrap();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x0A]);
+        reader.mark();
+        let written = tokio::spawn(async move {
+            client.write_all(&[0x0C]).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            client.write_all(&[0x0D, 0x02, 0x0E]).await.unwrap();
+            client
+        });
+        // Mark is held while the rest of the frame arrives
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x0B, 0x0C, 0x0D]);
+        let mut client = written.await.unwrap();
+        client.write_all(&[0x0F]).await.unwrap();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x0E, 0x0F]);
+        reader.reset().unwrap();
+        assert!(reader.reset().is_err());
+        reader.mark();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x0B, 0x0C, 0x0D]);
+        reader.unmark();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x0E, 0x0F]);
+        assert!(reader.reset().is_err());
+        drop(client);
+        assert!(reader.next_packet().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn writer_tests() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut writer = PacketStreamWriter::new(client);
+        let mut reader = PacketStreamReader::new(server);
+        writer.write_frame(&[1, 2, 3]).await.unwrap();
+        writer.write_frame(&[]).await.unwrap();
+        writer.flush().await.unwrap();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[1, 2, 3]);
+        assert!(reader.next_packet().await.unwrap().is_empty());
+        drop(writer);
+        assert!(reader.try_next_packet().await.unwrap().is_none());
+        let (mut client, server) = tokio::io::duplex(1024);
+        let mut reader = PacketStreamReader::new(server);
+        client.write_all(&[0x02, 0x01]).await.unwrap();
+        drop(client);
+        assert!(reader.try_next_packet().await.is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn compression_tests() {
+        let (client, server) = tokio::io::duplex(1 << 16);
+        let mut writer = PacketStreamWriter::new(client);
+        let mut reader = PacketStreamReader::new(server);
+        let long = vec![7; 1000];
+        writer.write_frame(&[1, 2]).await.unwrap();
+        writer.set_compression(Some(PacketCompression::new(256)));
+        writer.write_frame(&[3, 4]).await.unwrap();
+        writer.write_frame(&long).await.unwrap();
+        writer.set_compression(None);
+        writer.write_frame(&[5]).await.unwrap();
+        writer.flush().await.unwrap();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[1, 2]);
+        reader.set_compression(Some(PacketCompression::new(256)));
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[3, 4]);
+        assert_eq!(&reader.next_packet().await.unwrap()[..], long.as_slice());
+        reader.set_compression(None);
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[5]);
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        reader.set_compression(Some(PacketCompression::new(256)));
+        // Declared uncompressed length is below the threshold
+        client.write_all(&[0x03, 0x0A, 0x00, 0x00]).await.unwrap();
+        assert!(matches!(reader.next_packet().await, Err(PacketReadableError::Any(_))));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn encryption_tests() {
+        let shared_secret = crate::packet_encryption::generate_shared_secret();
+        let (client, server) = tokio::io::duplex(1 << 16);
+        let mut writer = PacketStreamWriter::new(client);
+        let mut reader = PacketStreamReader::new(server);
+        writer.write_frame(&[1, 2, 3]).await.unwrap();
+        writer.enable_encryption(&shared_secret).unwrap();
+        writer.write_frame(&[4, 5]).await.unwrap();
+        writer.write_frame(&[6; 300]).await.unwrap();
+        writer.flush().await.unwrap();
+        // Encrypted frames are already buffered when the encryption is enabled
+        assert_eq!(reader.peek(6).await.unwrap().len(), 6);
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[1, 2, 3]);
+        reader.enable_encryption(&shared_secret).unwrap();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[4, 5]);
+        writer.write_frame(&[7]).await.unwrap();
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[6; 300]);
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[7]);
+        assert!(reader.enable_encryption(&[0; 4]).is_err());
+    }
+
+    /// Stream which returns at most `chunk` bytes at once and is pending before each of them.
+    struct ChunkedRead {
+        bytes: Vec<u8>,
+        position: usize,
+        chunk: usize,
+        pending: bool,
+    }
+
+    impl AsyncRead for ChunkedRead {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>, buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            self.pending = !self.pending;
+            if self.pending {
+                cx.waker().wake_by_ref();
+                return std::task::Poll::Pending;
+            }
+            let end = self.bytes.len().min(self.position + self.chunk.min(buf.remaining()));
+            buf.put_slice(&self.bytes[self.position..end]);
+            self.position = end;
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn incremental_frame_tests() {
+        type Body = (String, Vec<i64>, uuid::Uuid, Option<Vec<u8>>);
+        let body: Body = ("bird".into(), (0..1000).collect(), uuid::Uuid::from_u128(7), Some(vec![0xAB; 300]));
+        let mut bytes = Vec::new();
+        body.write(&mut bytes).unwrap();
+        let mut framed = Vec::new();
+        write_frame_bytes(&bytes, &mut framed).unwrap();
+        let frame_end = framed.len();
+        framed.extend_from_slice(&[0x01, 0x2A]);
+        let expected = Body::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        for (chunk, read_ahead) in [(1, 1), (3, 16), (64, 100), (4096, DEFAULT_READ_AHEAD)] {
+            let stream = ChunkedRead { bytes: framed.clone(), position: 0, chunk, pending: false };
+            let mut reader = IncrementalFrameReader::from_frame(stream, DEFAULT_MAX_FRAME_LENGTH).await.unwrap()
+                .with_read_ahead(read_ahead);
+            assert_eq!(reader.remaining(), bytes.len());
+            assert!(reader.has_bytes(bytes.len()) && !reader.has_bytes(bytes.len() + 1));
+            assert!(reader.buffered().is_empty());
+            let streamed = (
+                reader.read::<String>().await.unwrap(),
+                reader.read::<Vec<i64>>().await.unwrap(),
+                reader.read::<uuid::Uuid>().await.unwrap(),
+                reader.read::<Option<Vec<u8>>>().await.unwrap(),
+            );
+            assert_eq!(streamed, expected);
+            assert_eq!(reader.remaining(), 0);
+            assert!(matches!(reader.read::<u8>().await, Err(PacketReadableError::BytesExceeded)));
+            // Next frame is not consumed
+            assert_eq!(reader.into_inner().position, frame_end);
+        }
+
+        let stream = ChunkedRead { bytes: framed.clone(), position: 0, chunk: 5, pending: false };
+        let mut reader = IncrementalFrameReader::from_frame(stream, DEFAULT_MAX_FRAME_LENGTH).await.unwrap()
+            .with_read_ahead(1 << 20);
+        reader.ensure(10).await.unwrap();
+        assert!(reader.buffered().len() >= 10);
+        assert!(matches!(reader.ensure(bytes.len() + 1).await, Err(PacketReadableError::BytesExceeded)));
+        reader.ensure(bytes.len()).await.unwrap();
+        assert_eq!(reader.buffered(), bytes.as_slice());
+        assert_eq!(reader.into_inner().position, frame_end);
+        let stream = ChunkedRead { bytes: framed.clone(), position: 0, chunk: 7, pending: false };
+        let mut reader = IncrementalFrameReader::from_frame(stream, DEFAULT_MAX_FRAME_LENGTH).await.unwrap();
+        assert_eq!(reader.read::<String>().await.unwrap(), "bird");
+        reader.skip_remaining().await.unwrap();
+        let mut stream = reader.into_inner();
+        assert_eq!(read_framed::<u8, _>(&mut stream, DEFAULT_MAX_FRAME_LENGTH).await.unwrap(), 0x2A);
+    }
+
+    #[tokio::test]
+    async fn incremental_frame_eof_tests() {
+        // Stream is closed after 3 of 6 declared bytes
+        let stream = ChunkedRead { bytes: vec![0x06, 0x00, 0x01, 0x02], position: 0, chunk: 1, pending: false };
+        let mut reader = IncrementalFrameReader::from_frame(stream, DEFAULT_MAX_FRAME_LENGTH).await.unwrap();
+        assert_eq!(reader.read::<u16>().await.unwrap(), 0x0001);
+        let error = reader.read::<u32>().await.unwrap_err();
+        match error {
+            PacketReadableError::Any(error) => assert_eq!(error.downcast_ref::<std::io::Error>().unwrap().kind(), std::io::ErrorKind::UnexpectedEof),
+            error => panic!("Unexpected error {:?}", error),
+        }
+        let stream = ChunkedRead { bytes: vec![0x06, 0x00], position: 0, chunk: 1, pending: false };
+        let mut reader = IncrementalFrameReader::new(stream, 6);
+        assert!(reader.skip_remaining().await.is_err());
+        let stream = ChunkedRead { bytes: vec![0x05, 0x00], position: 0, chunk: 1, pending: false };
+        assert!(IncrementalFrameReader::from_frame(stream, 4).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn max_frame_length_tests() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server).with_max_frame_length(16);
+        client.write_all(&[0x10]).await.unwrap();
+        client.write_all(&[0; 16]).await.unwrap();
+        client.write_all(&[0x11]).await.unwrap();
+        assert_eq!(reader.next_packet().await.unwrap().len(), 16);
+        assert!(matches!(reader.next_packet().await, Err(PacketReadableError::Any(_))));
+        let (mut client, server) = tokio::io::duplex(64);
+        let mut reader = PacketStreamReader::new(server);
+        client.write_all(&[0x80, 0x80, 0x80, 0x80, 0x80]).await.unwrap();
+        assert!(matches!(reader.next_packet().await, Err(PacketReadableError::BadVarNumber(_))));
+    }
+
+    #[test]
+    fn protocol_str_tests() {
+        let mut write = Vec::new();
+        ProtocolStr::from("bird").write(&mut write).unwrap();
+        ProtocolBytes(Bytes::from_static(&[1, 2])).write(&mut write).unwrap();
+        assert_eq!(write, &[0x04, b'b', b'i', b'r', b'd', 0x02, 1, 2]);
+        let source = Bytes::from(write);
+        let mut read = BytesPacketRead::new(&source);
+        let str = ProtocolStr::read(&mut read).unwrap();
+        let bytes = ProtocolBytes::read(&mut read).unwrap();
+        assert_eq!(read.available(), 0);
+        assert_eq!(str.as_str(), "bird");
+        assert_eq!(bytes.0.as_ref(), &[1, 2]);
+        // Both are slices of the source
+        assert_eq!(str.as_bytes().as_ptr(), source[1..].as_ptr());
+        assert_eq!(bytes.0.as_ptr(), source[6..].as_ptr());
+        // Copied from the reader, which can't share
+        let mut read = SlicePacketRead::new(&source);
+        assert_eq!(ProtocolStr::read(&mut read).unwrap(), str);
+        assert_eq!(ProtocolBytes::read(&mut read).unwrap(), bytes);
+        assert_eq!(read.available(), 0);
+
+        let bad = Bytes::from_static(&[0x02, 0xC3, 0x28]);
+        assert!(ProtocolStr::read(&mut BytesPacketRead::new(&bad)).is_err());
+        assert!(ProtocolStr::new(bad.slice(1..)).is_err());
+        let short = Bytes::from_static(&[0x05, b'a']);
+        let mut read = BytesPacketRead::new(&short);
+        assert!(matches!(ProtocolStr::read(&mut read), Err(PacketReadableError::BytesExceeded)));
+    }
+
+    #[cfg(feature = "packet_default")]
+    #[tokio::test]
+    async fn batch_tests() {
+        use crate::packet_default::{StatusPingRequest, StatusPingResponse, StatusRequest};
+        let mut batch = PacketBatch::new();
+        let mut expected = Vec::new();
+        let mut framer = crate::packet_frame::PacketFramer::new();
+        for tick in 0..4 {
+            batch.push(&StatusRequest).unwrap();
+            batch.push(&StatusPingRequest { payload: tick }).unwrap();
+            batch.push_frame(&[0x7F; 100]).unwrap();
+            framer.write_packet(&StatusRequest, &mut expected).unwrap();
+            framer.write_packet(&StatusPingRequest { payload: tick }, &mut expected).unwrap();
+            write_frame_bytes(&[0x7F; 100], &mut expected).unwrap();
+            assert_eq!((batch.len(), batch.packet_count()), ((tick as usize + 1) * (2 + 10 + 101), tick as usize * 3 + 3));
+        }
+        assert_eq!(batch.as_bytes(), expected.as_slice());
+        let (client, server) = tokio::io::duplex(1 << 16);
+        let mut client = client;
+        batch.flush_to(&mut client).await.unwrap();
+        assert!(batch.is_empty() && batch.packet_count() == 0);
+        let capacity = batch.capacity();
+        for tick in 0..16 {
+            batch.push(&StatusPingResponse { payload: tick }).unwrap();
+            batch.push_frame(&[0x7F; 100]).unwrap();
+            batch.flush_to(&mut client).await.unwrap();
+            assert_eq!(batch.capacity(), capacity);
+        }
+        drop(client);
+        let mut reader = PacketStreamReader::new(server);
+        for _ in 0..12 {
+            reader.next_packet().await.unwrap();
+        }
+        let frame = reader.next_packet().await.unwrap();
+        assert_eq!(&frame[..], &[0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[cfg(all(feature = "compression", feature = "encryption"))]
+    #[tokio::test]
+    async fn batch_compression_tests() {
+        let shared_secret = crate::packet_encryption::generate_shared_secret();
+        let mut batch = PacketBatch::new();
+        batch.set_compression(Some(PacketCompression::new(256)));
+        batch.push_frame(&[1, 2]).unwrap();
+        batch.push_frame(&[3; 1000]).unwrap();
+        assert!(batch.len() < 100);
+        let (client, server) = tokio::io::duplex(1 << 16);
+        let mut writer = PacketStreamWriter::new(client);
+        let mut reader = PacketStreamReader::new(server);
+        writer.enable_encryption(&shared_secret).unwrap();
+        writer.write_batch(&mut batch).await.unwrap();
+        assert!(batch.is_empty());
+        reader.enable_encryption(&shared_secret).unwrap();
+        reader.set_compression(Some(PacketCompression::new(256)));
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[1, 2]);
+        assert_eq!(&reader.next_packet().await.unwrap()[..], &[3; 1000]);
+    }
+
+    /// Writes the bytes, but its size is wrong or unknown.
+    struct BadSize(Vec<u8>, Option<usize>);
+
+    impl PacketWritable for BadSize {
+        fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+            write.write_bytes(&self.0)
+        }
+
+        fn size(&self) -> Result<usize, Error> {
+            self.1.ok_or_else(|| Error::msg("Unknown size"))
+        }
+    }
+
+    #[test]
+    fn write_framed_into_tests() {
+        fn naive<T: PacketWritable + ?Sized>(id: i32, object: &T) -> Vec<u8> {
+            let mut body = Vec::new();
+            VarInt::write_variant(&id, &mut body).unwrap();
+            object.write(&mut body).unwrap();
+            let mut frame = Vec::new();
+            write_frame_bytes(&body, &mut frame).unwrap();
+            frame
+        }
+        // Lengths of 1, 2, 3 and 4 bytes of the prefix
+        for length in [0, 10, 200, 20000, 3000000] {
+            let body = vec![7u8; length];
+            let expected = naive(0x21, &body);
+            let mut buf = BytesMut::from(&b"previous"[..]);
+            assert_eq!(write_framed_into(&mut buf, 0x21, &body).unwrap(), FramingStrategy::Sized);
+            assert_eq!(&buf[8..], expected.as_slice());
+            // Unknown size, bigger size and smaller size, which needs longer prefix
+            for size in [None, Some(length + 200)].into_iter().chain((length > 0).then_some(Some(0))) {
+                let mut buf = BytesMut::from(&b"previous"[..]);
+                let strategy = write_framed_into(&mut buf, 0x21, &BadSize(body.clone(), size)).unwrap();
+                assert_eq!(strategy, FramingStrategy::Shifted);
+                assert_eq!(&buf[..8], b"previous");
+                assert_eq!(&buf[8..], &naive(0x21, &BadSize(body.clone(), None))[..]);
+            }
+        }
+        // Frame is read back
+        let mut buf = BytesMut::new();
+        write_framed_into(&mut buf, 0x1E, &0x0102030405060708i64).unwrap();
+        write_framed_into(&mut buf, 0x00, &BadSize(vec![1, 2], None)).unwrap();
+        let mut read = SlicePacketRead::new(&buf);
+        let mut frame = crate::packet_frame::read_frame(&mut read).unwrap();
+        assert_eq!((VarInt::read_variant(&mut frame).unwrap(), i64::read(&mut frame).unwrap()), (0x1E, 0x0102030405060708));
+        assert_eq!(crate::packet_frame::read_frame(&mut read).unwrap().take_slice(3).unwrap(), &[0x00, 1, 2]);
+        // Huge size is not reserved
+        let mut buf = BytesMut::new();
+        write_framed_into(&mut buf, 0x00, &BadSize(vec![1, 2], Some(i32::MAX as usize - 8))).unwrap();
+        assert!(buf.capacity() <= PREALLOCATION_LIMIT);
+        // Failed object is not left in the buffer
+        let mut buf = BytesMut::from(&b"previous"[..]);
+        assert!(write_framed_into(&mut buf, 0, &crate::packet_types::OptVarInt(Some(-1))).is_err());
+        assert_eq!(&buf[..], b"previous");
+    }
+}
@@ -0,0 +1,1052 @@
+use anyhow::Error;
+use crate::packet::{PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite};
+use crate::packet_types::{BitSet, LengthProvidedBytesSlice, LengthProvidedSlice, preallocate, VarInt};
+use crate::derive::*;
+
+type PaletteArray = LengthProvidedSlice<VarInt, VarInt, i32, i32>;
+
+/// Sizes and bits per entry of the paletted container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PaletteKind {
+    /// Count of entries along each axis.
+    pub side: usize,
+    /// Bits per entry of the indirect palette, which are used if fewer bits are sent.
+    pub min_indirect_bits: u8,
+    pub max_indirect_bits: u8,
+    /// Bits per entry of the direct palette, which depend on the size of the registry.
+    pub direct_bits: u8,
+}
+
+impl PaletteKind {
+    pub const BLOCK_STATES: Self = Self { side: 16, min_indirect_bits: 4, max_indirect_bits: 8, direct_bits: 15 };
+    pub const BIOMES: Self = Self { side: 4, min_indirect_bits: 1, max_indirect_bits: 3, direct_bits: 6 };
+
+    pub const fn entries(&self) -> usize {
+        self.side * self.side * self.side
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Palette {
+    /// All entries have the same value. Data array is empty.
+    Single(i32),
+    /// Entries are indices in the palette.
+    Indirect(Vec<i32>),
+    /// Entries are registry ids.
+    Direct,
+}
+
+/// Count of longs which store the entries. Entries do not span longs.
+pub const fn packed_longs(bits: u8, entries: usize) -> usize {
+    match bits {
+        0 => 0,
+        bits => entries.div_ceil(64 / bits as usize),
+    }
+}
+
+/// Array of unsigned entries of the same bits, which are packed to longs like `SimpleBitStorage` of vanilla.
+///
+/// Each long has `64 / bits` entries from the low bits, entries do not span longs and unused high bits are zero.
+/// It is written as longs prefixed by [VarInt] length and is read by [PackedDataArray::read_with].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PackedDataArray {
+    bits: u8,
+    capacity: usize,
+    data: Vec<u64>,
+}
+
+impl PackedDataArray {
+    /// Maximum bits per entry.
+    pub const MAX_BITS: u8 = 32;
+
+    /// Creates array of zero entries. With zero bits all entries are zero and there are no longs.
+    pub fn new(bits: u8, capacity: usize) -> Self {
+        assert!(bits <= Self::MAX_BITS, "Bits per entry {} exceed {}", bits, Self::MAX_BITS);
+        Self { bits, capacity, data: vec![0; packed_longs(bits, capacity)] }
+    }
+
+    /// Creates array from the longs, which count has to match [packed_longs] of the bits and the capacity.
+    pub fn from_longs(bits: u8, data: Vec<u64>, capacity: usize) -> Result<Self, Error> {
+        if bits > Self::MAX_BITS {
+            return Err(Error::msg("Too many bits per entry of the packed data array"));
+        }
+        match data.len() == packed_longs(bits, capacity) {
+            true => Ok(Self { bits, capacity, data }),
+            false => Err(Error::msg("Bad length of the packed data array")),
+        }
+    }
+
+    /// Reads [VarInt] length and longs, which count has to match the bits and the capacity.
+    pub fn read_with<'a, R: PacketRead<'a>>(bits: u8, capacity: usize, read: &mut R) -> Result<Self, PacketReadableError> {
+        let length = VarInt::read_variant(read)?;
+        if length < 0 || length as usize != packed_longs(bits, capacity) {
+            return Err(PacketReadableError::Any(Error::msg("Bad length of the packed data array")));
+        }
+        let data = (0..length).map(|_| u64::read(read)).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_longs(bits, data, capacity)?)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Count of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn as_longs(&self) -> &[u64] {
+        &self.data
+    }
+
+    pub fn into_longs(self) -> Vec<u64> {
+        self.data
+    }
+
+    fn mask(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    fn position(&self, index: usize) -> (usize, usize) {
+        assert!(index < self.capacity, "Packed data index {} is out of {}", index, self.capacity);
+        let per_long = 64 / self.bits as usize;
+        (index / per_long, (index % per_long) * self.bits as usize)
+    }
+
+    pub fn get(&self, index: usize) -> u64 {
+        if self.bits == 0 {
+            assert!(index < self.capacity, "Packed data index {} is out of {}", index, self.capacity);
+            return 0;
+        }
+        let (long, offset) = self.position(index);
+        (self.data[long] >> offset) & self.mask()
+    }
+
+    /// Sets entry to the low bits of the value. Nothing is stored with zero bits.
+    pub fn set(&mut self, index: usize, value: u64) {
+        if self.bits == 0 {
+            assert!(index < self.capacity, "Packed data index {} is out of {}", index, self.capacity);
+            return;
+        }
+        let (long, offset) = self.position(index);
+        let mask = self.mask();
+        let long = &mut self.data[long];
+        *long = (*long & !(mask << offset)) | ((value & mask) << offset);
+    }
+
+    /// Repacks entries with other bits per entry. Entries are cut to the low bits if there are fewer of them.
+    pub fn resize_bits(&mut self, bits: u8) {
+        if bits == self.bits {
+            return;
+        }
+        let mut resized = Self::new(bits, self.capacity);
+        for index in 0..self.capacity {
+            resized.set(index, self.get(index));
+        }
+        *self = resized;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=u64> + '_ {
+        (0..self.capacity).map(|index| self.get(index))
+    }
+}
+
+impl PacketWritable for PackedDataArray {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.data.write(write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        self.data.size()
+    }
+}
+
+/// Block states or biomes of the chunk section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalettedContainer {
+    kind: PaletteKind,
+    palette: Palette,
+    data: PackedDataArray,
+}
+
+/// Variant of [PalettedContainer] with [PaletteKind::BLOCK_STATES].
+pub struct BlockStatesContainer;
+
+/// Variant of [PalettedContainer] with [PaletteKind::BIOMES].
+pub struct BiomesContainer;
+
+impl PalettedContainer {
+    /// Creates container where all entries are the value.
+    pub fn new(kind: PaletteKind, value: i32) -> Self {
+        Self { kind, palette: Palette::Single(value), data: PackedDataArray::new(0, kind.entries()) }
+    }
+
+    pub fn kind(&self) -> PaletteKind {
+        self.kind
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.data.bits()
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn data(&self) -> &[u64] {
+        self.data.as_longs()
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        let side = self.kind.side;
+        assert!(x < side && y < side && z < side, "Coordinates are out of the container");
+        (y * side + z) * side + x
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> i32 {
+        let raw = self.data.get(self.index(x, y, z));
+        match &self.palette {
+            Palette::Single(value) => *value,
+            Palette::Indirect(palette) => palette[raw as usize],
+            Palette::Direct => raw as i32,
+        }
+    }
+
+    /// Sets the entry and upgrades the palette if it has no place for the value.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, value: i32) {
+        assert!(
+            value >= 0 && value < 1 << self.kind.direct_bits,
+            "Value does not fit the direct palette"
+        );
+        let index = self.index(x, y, z);
+        let raw = match &mut self.palette {
+            Palette::Single(single) if *single == value => return,
+            Palette::Single(single) => {
+                self.palette = Palette::Indirect(vec![*single, value]);
+                self.data.resize_bits(self.kind.min_indirect_bits.max(1));
+                1
+            }
+            Palette::Indirect(palette) => match palette.iter().position(|entry| *entry == value) {
+                Some(raw) => raw as u64,
+                None => {
+                    palette.push(value);
+                    let raw = palette.len() as u64 - 1;
+                    let bits = self.data.bits();
+                    if palette.len() > 1 << bits {
+                        match bits < self.kind.max_indirect_bits {
+                            true => self.data.resize_bits(bits + 1),
+                            false => {
+                                let palette = std::mem::replace(&mut self.palette, Palette::Direct);
+                                let Palette::Indirect(palette) = palette else { unreachable!() };
+                                let mut data = PackedDataArray::new(self.kind.direct_bits, self.kind.entries());
+                                for (index, raw) in self.data.iter().enumerate() {
+                                    data.set(index, palette[raw as usize] as u64);
+                                }
+                                self.data = data;
+                                return self.data.set(index, value as u64);
+                            }
+                        }
+                    }
+                    raw
+                }
+            },
+            Palette::Direct => value as u64,
+        };
+        self.data.set(index, raw)
+    }
+
+    /// Reads container with bits per entry chosen the same way as vanilla does.
+    pub fn read_with_kind<'a, R: PacketRead<'a>>(kind: PaletteKind, read: &mut R) -> Result<Self, PacketReadableError> {
+        let (bits, palette) = match u8::read(read)? {
+            0 => (0, Palette::Single(VarInt::read_variant(read)?)),
+            bits if bits <= kind.max_indirect_bits =>
+                (bits.max(kind.min_indirect_bits), Palette::Indirect(PaletteArray::read_variant(read)?)),
+            _ => (kind.direct_bits, Palette::Direct),
+        };
+        let container = Self { kind, palette, data: PackedDataArray::read_with(bits, kind.entries(), read)? };
+        if let Palette::Indirect(palette) = &container.palette {
+            if palette.is_empty() || palette.len() > 1 << bits ||
+                container.data.iter().any(|raw| raw as usize >= palette.len()) {
+                return Err(PacketReadableError::Any(Error::msg("Bad palette of the paletted container")));
+            }
+        }
+        Ok(container)
+    }
+}
+
+impl PacketWritable for PalettedContainer {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.data.bits().write(write)?;
+        match &self.palette {
+            Palette::Single(value) => VarInt::write_variant(value, write)?,
+            Palette::Indirect(palette) => PaletteArray::write_variant(palette, write)?,
+            Palette::Direct => {}
+        }
+        self.data.write(write)
+    }
+}
+
+impl<'a> PacketVariantReadable<'a, PalettedContainer> for BlockStatesContainer {
+    fn read_variant<R>(read: &mut R) -> Result<PalettedContainer, PacketReadableError> where R: PacketRead<'a> {
+        PalettedContainer::read_with_kind(PaletteKind::BLOCK_STATES, read)
+    }
+}
+
+impl PacketVariantWritable<PalettedContainer> for BlockStatesContainer {
+    fn write_variant<W>(object: &PalettedContainer, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        object.write(write)
+    }
+}
+
+impl<'a> PacketVariantReadable<'a, PalettedContainer> for BiomesContainer {
+    fn read_variant<R>(read: &mut R) -> Result<PalettedContainer, PacketReadableError> where R: PacketRead<'a> {
+        PalettedContainer::read_with_kind(PaletteKind::BIOMES, read)
+    }
+}
+
+impl PacketVariantWritable<PalettedContainer> for BiomesContainer {
+    fn write_variant<W>(object: &PalettedContainer, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        object.write(write)
+    }
+}
+
+/// Chunk section of 16x16x16 blocks, which is a part of the chunk data.
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSection {
+    /// Count of non-air blocks.
+    pub block_count: i16,
+    #[variant(BlockStatesContainer)]
+    pub block_states: PalettedContainer,
+    #[variant(BiomesContainer)]
+    pub biomes: PalettedContainer,
+}
+
+impl ChunkSection {
+    /// Creates section with air and the single biome.
+    pub fn empty(air: i32, biome: i32) -> Self {
+        Self {
+            block_count: 0,
+            block_states: PalettedContainer::new(PaletteKind::BLOCK_STATES, air),
+            biomes: PalettedContainer::new(PaletteKind::BIOMES, biome),
+        }
+    }
+}
+
+/// Heightmap of the chunk column, which stores heights relative to the world minimum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap {
+    min_y: i32,
+    height: u32,
+    data: PackedDataArray,
+}
+
+impl Heightmap {
+    /// Minimum y of the overworld.
+    pub const OVERWORLD_MIN_Y: i32 = -64;
+    /// Height of the overworld.
+    pub const OVERWORLD_HEIGHT: u32 = 384;
+
+    /// Creates heightmap of the overworld where all heights are the world minimum.
+    pub fn new() -> Self {
+        Self::with_world(Self::OVERWORLD_MIN_Y, Self::OVERWORLD_HEIGHT)
+    }
+
+    pub fn with_world(min_y: i32, height: u32) -> Self {
+        Self { min_y, height, data: PackedDataArray::new(Self::bits(height), 256) }
+    }
+
+    /// Creates heightmap from the packed longs, which length depends on the world height.
+    pub fn from_longs(min_y: i32, height: u32, data: Vec<u64>) -> Result<Self, Error> {
+        match PackedDataArray::from_longs(Self::bits(height), data, 256) {
+            Ok(data) => Ok(Self { min_y, height, data }),
+            Err(_) => Err(Error::msg("Bad length of the heightmap")),
+        }
+    }
+
+    fn bits(height: u32) -> u8 {
+        (u32::BITS - height.leading_zeros()) as u8
+    }
+
+    pub fn as_longs(&self) -> &[u64] {
+        self.data.as_longs()
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn index(&self, x: usize, z: usize) -> Result<usize, Error> {
+        match x < 16 && z < 16 {
+            true => Ok(z * 16 + x),
+            false => Err(Error::msg("Coordinates are out of the chunk")),
+        }
+    }
+
+    pub fn get_height(&self, x: usize, z: usize) -> Result<i32, Error> {
+        Ok(self.data.get(self.index(x, z)?) as i32 + self.min_y)
+    }
+
+    /// Sets y of the highest block, which can be from the world minimum to the world maximum inclusive.
+    pub fn set_height(&mut self, x: usize, z: usize, y: i32) -> Result<(), Error> {
+        let relative = i64::from(y) - i64::from(self.min_y);
+        if relative < 0 || relative > i64::from(self.height) {
+            return Err(Error::msg("Height is out of the world"));
+        }
+        let index = self.index(x, z)?;
+        self.data.set(index, relative as u64);
+        Ok(())
+    }
+}
+
+impl Default for Heightmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Heightmaps which are sent with the chunk data as nbt compound.
+///
+/// Bits per entry depend on the world height, which is not sent with heightmaps.
+/// [PacketReadable] reads them as overworld ones, other worlds should use [Heightmaps::read_with_world].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Heightmaps {
+    pub motion_blocking: Heightmap,
+    pub world_surface: Heightmap,
+}
+
+impl Heightmaps {
+    pub fn with_world(min_y: i32, height: u32) -> Self {
+        Self {
+            motion_blocking: Heightmap::with_world(min_y, height),
+            world_surface: Heightmap::with_world(min_y, height),
+        }
+    }
+
+    #[cfg(feature = "fastnbt")]
+    pub fn read_with_world<'a, R: PacketRead<'a>>(min_y: i32, height: u32, read: &mut R) -> Result<Self, PacketReadableError> {
+        let nbt: HeightmapsNbt = crate::packet_types::ProtocolNbt::read_variant(read)?;
+        Ok(Self {
+            motion_blocking: heightmap_from_nbt(min_y, height, nbt.motion_blocking)?,
+            world_surface: heightmap_from_nbt(min_y, height, nbt.world_surface)?,
+        })
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct HeightmapsNbt {
+    #[serde(rename = "MOTION_BLOCKING")]
+    motion_blocking: fastnbt::LongArray,
+    #[serde(rename = "WORLD_SURFACE")]
+    world_surface: fastnbt::LongArray,
+}
+
+#[cfg(feature = "fastnbt")]
+fn heightmap_from_nbt(min_y: i32, height: u32, longs: fastnbt::LongArray) -> Result<Heightmap, PacketReadableError> {
+    let longs = longs.into_inner().into_iter().map(|long| long as u64).collect();
+    Ok(Heightmap::from_longs(min_y, height, longs)?)
+}
+
+#[cfg(feature = "fastnbt")]
+fn heightmap_to_nbt(heightmap: &Heightmap) -> fastnbt::LongArray {
+    fastnbt::LongArray::new(heightmap.as_longs().iter().map(|long| *long as i64).collect())
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> PacketReadable<'a> for Heightmaps {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Self::read_with_world(Heightmap::OVERWORLD_MIN_Y, Heightmap::OVERWORLD_HEIGHT, read)
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketWritable for Heightmaps {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        crate::packet_types::ProtocolNbt::write_variant(&HeightmapsNbt {
+            motion_blocking: heightmap_to_nbt(&self.motion_blocking),
+            world_surface: heightmap_to_nbt(&self.world_surface),
+        }, write)
+    }
+}
+
+/// Array of 4 bit entries, two of them are packed to one byte. Even index is the low nibble like in vanilla.
+///
+/// It is written as bytes prefixed by [VarInt] length, so odd length is read back with one more zero entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NibbleArray {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl NibbleArray {
+    /// Count of entries in the light section of 16x16x16 blocks.
+    pub const SECTION_LEN: usize = 4096;
+
+    /// Creates array of zero entries.
+    pub fn new(len: usize) -> Self {
+        Self { bytes: vec![0; len.div_ceil(2)], len }
+    }
+
+    /// Creates array of 2048 bytes, which is sent for every light section.
+    pub fn section() -> Self {
+        Self::new(Self::SECTION_LEN)
+    }
+
+    /// Creates array from packed bytes, which count has to be enough for `len` entries and not more.
+    pub fn from_bytes(bytes: Vec<u8>, len: usize) -> Result<Self, Error> {
+        match bytes.len() == len.div_ceil(2) {
+            true => Ok(Self { bytes, len }),
+            false => Err(Error::msg("Bad length of the nibble array")),
+        }
+    }
+
+    /// Count of entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        assert!(index < self.len, "Nibble index {} is out of {}", index, self.len);
+        match index % 2 {
+            0 => self.bytes[index / 2] & 0x0F,
+            _ => self.bytes[index / 2] >> 4,
+        }
+    }
+
+    /// Sets entry to the low 4 bits of the value.
+    pub fn set(&mut self, index: usize, value: u8) {
+        assert!(index < self.len, "Nibble index {} is out of {}", index, self.len);
+        let byte = &mut self.bytes[index / 2];
+        *byte = match index % 2 {
+            0 => (*byte & 0xF0) | (value & 0x0F),
+            _ => (*byte & 0x0F) | (value << 4),
+        };
+    }
+}
+
+impl<'a> PacketReadable<'a> for NibbleArray {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let bytes: Vec<u8> = LengthProvidedBytesSlice::<VarInt, i32>::read_variant(read)?;
+        let len = bytes.len() * 2;
+        Ok(Self { bytes, len })
+    }
+}
+
+impl PacketWritable for NibbleArray {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        LengthProvidedBytesSlice::<VarInt, i32>::write_variant(self.bytes.as_slice(), write)
+    }
+}
+
+/// Light of one kind, which has arrays only for sections marked by the mask.
+///
+/// Bit of the section is its index from the bottom, where 0 is the section below the world.
+/// Sections marked by the empty mask have zero light, other sections are unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LightSections {
+    mask: BitSet,
+    empty_mask: BitSet,
+    sections: Vec<NibbleArray>,
+}
+
+impl LightSections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mask(&self) -> &BitSet {
+        &self.mask
+    }
+
+    pub fn empty_mask(&self) -> &BitSet {
+        &self.empty_mask
+    }
+
+    /// Arrays in the order of the mask bits.
+    pub fn sections(&self) -> &[NibbleArray] {
+        &self.sections
+    }
+
+    fn position(&self, index: usize) -> usize {
+        self.mask.iter().take_while(|bit| *bit < index).count()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&NibbleArray> {
+        match self.mask.get(index) {
+            true => self.sections.get(self.position(index)),
+            false => None,
+        }
+    }
+
+    /// Sets light array of the section, which is no more marked as empty.
+    pub fn set(&mut self, index: usize, light: [u8; 2048]) {
+        let light = NibbleArray { bytes: light.to_vec(), len: NibbleArray::SECTION_LEN };
+        let position = self.position(index);
+        match self.mask.get(index) {
+            true => self.sections[position] = light,
+            false => {
+                self.mask.set(index);
+                self.sections.insert(position, light);
+            }
+        }
+        self.empty_mask.clear(index);
+    }
+
+    /// Marks the section as empty and removes its array.
+    pub fn set_empty(&mut self, index: usize) {
+        self.remove(index);
+        self.empty_mask.set(index);
+    }
+
+    /// Removes the section from both masks, so the client keeps its light.
+    pub fn remove(&mut self, index: usize) -> Option<NibbleArray> {
+        self.empty_mask.clear(index);
+        match self.mask.get(index) {
+            true => {
+                let position = self.position(index);
+                self.mask.clear(index);
+                Some(self.sections.remove(position))
+            }
+            false => None,
+        }
+    }
+
+    fn read_sections<'a, R: PacketRead<'a>>(mask: BitSet, empty_mask: BitSet, read: &mut R) -> Result<Self, PacketReadableError> {
+        let count = VarInt::read_variant(read)?;
+        if usize::try_from(count).ok() != Some(mask.iter().count()) {
+            return Err(PacketReadableError::BadLength);
+        }
+        // Every array takes at least its bytes, so the count is checked before anything is allocated.
+        if !read.is_available((count as usize).saturating_mul(NibbleArray::SECTION_LEN / 2)) {
+            return Err(PacketReadableError::BytesExceeded);
+        }
+        let mut sections = preallocate::<NibbleArray>(count as usize);
+        for _ in 0..count {
+            let section = NibbleArray::read(read)?;
+            if section.len() != NibbleArray::SECTION_LEN {
+                return Err(PacketReadableError::BadLength);
+            }
+            sections.push(section);
+        }
+        Ok(Self { mask, empty_mask, sections })
+    }
+}
+
+/// Light of the chunk column, which is sent by the update light and the chunk data packets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LightData {
+    /// Tells the client to not recalculate light on the edges of the chunk.
+    pub trust_edges: bool,
+    pub sky_light: LightSections,
+    pub block_light: LightSections,
+}
+
+impl LightData {
+    pub fn new(trust_edges: bool) -> Self {
+        Self { trust_edges, ..Self::default() }
+    }
+
+    pub fn set_sky_section(&mut self, index: usize, light: [u8; 2048]) {
+        self.sky_light.set(index, light)
+    }
+
+    pub fn set_block_section(&mut self, index: usize, light: [u8; 2048]) {
+        self.block_light.set(index, light)
+    }
+}
+
+impl<'a> PacketReadable<'a> for LightData {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let trust_edges = bool::read(read)?;
+        let sky_mask = BitSet::read(read)?;
+        let block_mask = BitSet::read(read)?;
+        let empty_sky_mask = BitSet::read(read)?;
+        let empty_block_mask = BitSet::read(read)?;
+        Ok(Self {
+            trust_edges,
+            sky_light: LightSections::read_sections(sky_mask, empty_sky_mask, read)?,
+            block_light: LightSections::read_sections(block_mask, empty_block_mask, read)?,
+        })
+    }
+}
+
+impl PacketWritable for LightData {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.trust_edges.write(write)?;
+        self.sky_light.mask.write(write)?;
+        self.block_light.mask.write(write)?;
+        self.sky_light.empty_mask.write(write)?;
+        self.block_light.empty_mask.write(write)?;
+        self.sky_light.sections.write(write)?;
+        self.block_light.sections.write(write)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::SlicePacketRead;
+    use super::*;
+
+    fn round_trip(section: &ChunkSection) -> Vec<u8> {
+        let mut write = Vec::new();
+        section.write(&mut write).unwrap();
+        assert_eq!(write.len(), section.size().unwrap());
+        let mut read = SlicePacketRead::new(&write);
+        assert_eq!(&ChunkSection::read(&mut read).unwrap(), section);
+        write
+    }
+
+    #[test]
+    fn palette_upgrade_tests() {
+        let mut container = PalettedContainer::new(PaletteKind::BLOCK_STATES, 0);
+        container.set(0, 0, 0, 0);
+        assert_eq!(container.bits(), 0);
+        container.set(1, 0, 0, 1);
+        assert_eq!((container.bits(), container.palette()), (4, &Palette::Indirect(vec![0, 1])));
+        for value in 2..=16 {
+            container.set(value as usize % 16, 15, value as usize / 16, value);
+        }
+        assert_eq!(container.bits(), 5);
+        assert_eq!(container.data().len(), packed_longs(5, 4096));
+        for value in 17..256 {
+            container.set(value as usize % 16, 8, value as usize / 16, value);
+        }
+        assert_eq!(container.bits(), 8);
+        container.set(15, 15, 15, 1000);
+        assert_eq!((container.bits(), container.palette()), (15, &Palette::Direct));
+        assert_eq!(container.data().len(), 1024);
+        assert_eq!(container.get(0, 0, 0), 0);
+        assert_eq!(container.get(1, 0, 0), 1);
+        assert_eq!(container.get(2, 15, 0), 2);
+        assert_eq!(container.get(0, 15, 1), 16);
+        assert_eq!(container.get(15, 8, 15), 255);
+        assert_eq!(container.get(15, 15, 15), 1000);
+        let mut biomes = PalettedContainer::new(PaletteKind::BIOMES, 3);
+        biomes.set(3, 3, 3, 4);
+        assert_eq!((biomes.bits(), biomes.data().len()), (1, 1));
+        assert_eq!((biomes.get(3, 3, 3), biomes.get(0, 0, 0)), (4, 3));
+    }
+
+    #[test]
+    fn chunk_section_tests() {
+        // Empty section as vanilla sends for the sky
+        let section = ChunkSection::empty(0, 39);
+        assert_eq!(round_trip(&section), &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x27, 0x00]);
+        let mut section = ChunkSection::empty(0, 1);
+        for x in 0..16 {
+            for z in 0..16 {
+                section.block_states.set(x, 0, z, 1);
+            }
+        }
+        section.block_states.set(1, 1, 0, 9);
+        section.block_count = 257;
+        section.biomes.set(0, 2, 0, 2);
+        let write = round_trip(&section);
+        assert_eq!(&write[..8], &[0x01, 0x01, 0x04, 0x03, 0x00, 0x01, 0x09, 0x80]);
+        // First 16 blocks are stone, each of them takes 4 bits
+        assert_eq!(&write[8..17], &[0x02, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11]);
+        // Block at x = 1 of the second layer is the third entry of the palette
+        assert_eq!(&write[9 + 16 * 8..9 + 17 * 8], &[0, 0, 0, 0, 0, 0, 0, 0x20]);
+        // Biomes are 1 bit per entry, biome at y = 2 has index 32
+        assert_eq!(&write[write.len() - 13..], &[0x01, 0x02, 0x01, 0x02, 0x01, 0, 0, 0, 0x01, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn vanilla_section_tests() {
+        // Bottom section of the default superflat world in the form which vanilla 1.19 server sends:
+        // palette is air, bedrock (74), dirt (10), grass block (9) in the order blocks were generated
+        let mut bytes = vec![0x04, 0x00, 0x04, 0x04, 0x00, 0x4A, 0x0A, 0x09, 0x80, 0x02];
+        for layer in [[0x11; 8], [0x22; 8], [0x22; 8], [0x33; 8]] {
+            for _ in 0..16 {
+                bytes.extend_from_slice(&layer);
+            }
+        }
+        bytes.resize(bytes.len() + 192 * 8, 0);
+        // Biomes are plains only
+        bytes.extend_from_slice(&[0x00, 0x01, 0x00]);
+        let mut read = SlicePacketRead::new(&bytes);
+        let section = ChunkSection::read(&mut read).unwrap();
+        assert_eq!(read.available(), 0);
+        assert_eq!(section.block_count, 1024);
+        assert_eq!(section.block_states.palette(), &Palette::Indirect(vec![0, 74, 10, 9]));
+        assert_eq!(section.block_states.bits(), 4);
+        for (y, block) in [74, 10, 10, 9, 0, 0].into_iter().enumerate() {
+            assert_eq!(section.block_states.get(0, y, 0), block);
+            assert_eq!(section.block_states.get(15, y, 9), block);
+        }
+        assert_eq!(section.block_states.get(7, 15, 7), 0);
+        assert_eq!(section.biomes.palette(), &Palette::Single(1));
+        assert_eq!(round_trip(&section), bytes);
+        // The same section built with set, which upgrades palette the same way as vanilla
+        let mut built = ChunkSection::empty(0, 1);
+        for (y, block) in [74, 10, 10, 9].into_iter().enumerate() {
+            for z in 0..16 {
+                for x in 0..16 {
+                    built.block_states.set(x, y, z, block);
+                }
+            }
+        }
+        built.block_count = 1024;
+        assert_eq!(built, section);
+    }
+
+    #[test]
+    fn bad_paletted_container_tests() {
+        let read_blocks = |bytes: &[u8]| BlockStatesContainer::read_variant(&mut SlicePacketRead::new(bytes));
+        assert!(read_blocks(&[0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]).is_err());
+        // Indirect palette with 1 bit per entry is read with 4 bits, so 256 longs must follow
+        assert!(read_blocks(&[0x01, 0x01, 0x00, 0x40]).is_err());
+        let mut bytes = vec![0x04, 0x01, 0x00, 0x80, 0x02, 0, 0, 0, 0, 0, 0, 0, 0x01];
+        bytes.resize(bytes.len() + 255 * 8, 0);
+        assert!(read_blocks(&bytes).is_err());
+        bytes[3..].fill(0);
+        bytes[3..5].copy_from_slice(&[0x80, 0x02]);
+        assert_eq!(read_blocks(&bytes).unwrap().get(0, 0, 0), 0);
+        // Direct palette always uses bits of the registry
+        let mut bytes = vec![0x09, 0x80, 0x08];
+        bytes.resize(bytes.len() + 1024 * 8, 0);
+        assert_eq!(read_blocks(&bytes).unwrap().bits(), 15);
+    }
+
+    #[test]
+    fn packed_data_array_tests() {
+        for bits in [1u8, 4, 5, 9, 15] {
+            let per_long = 64 / bits as usize;
+            let mask = (1u64 << bits) - 1;
+            for capacity in [1, per_long - 1, per_long, per_long + 1, 100, 256, 4096] {
+                let mut array = PackedDataArray::new(bits, capacity);
+                assert_eq!(array.as_longs().len(), capacity.div_ceil(per_long));
+                let value = |index: usize| (index as u64).wrapping_mul(0x9E3779B97F4A7C15) >> 7 & mask;
+                for index in 0..capacity {
+                    array.set(index, value(index));
+                }
+                for index in 0..capacity {
+                    assert_eq!(array.get(index), value(index), "bits {} capacity {} index {}", bits, capacity, index);
+                }
+                // Bits above the entries of each long stay zero
+                let unused = !((1u128 << (per_long * bits as usize)) - 1) as u64;
+                assert!(array.as_longs().iter().all(|long| long & unused == 0));
+                let last = array.as_longs().last().unwrap();
+                let last_entries = capacity - (array.as_longs().len() - 1) * per_long;
+                assert_eq!(last >> (last_entries * bits as usize - 1) >> 1, 0);
+
+                let mut write = Vec::new();
+                array.write(&mut write).unwrap();
+                assert_eq!(write.len(), array.size().unwrap());
+                let read = PackedDataArray::read_with(bits, capacity, &mut SlicePacketRead::new(&write)).unwrap();
+                assert_eq!(read, array);
+                assert_eq!(PackedDataArray::from_longs(bits, array.clone().into_longs(), capacity).unwrap(), array);
+                assert!(PackedDataArray::from_longs(bits, vec![0; array.as_longs().len() + 1], capacity).is_err());
+                assert!(PackedDataArray::read_with(bits, capacity + per_long, &mut SlicePacketRead::new(&write)).is_err());
+
+                let mut resized = array.clone();
+                resized.resize_bits(bits + 3);
+                assert_eq!(resized.bits(), bits + 3);
+                assert_eq!(resized.iter().collect::<Vec<_>>(), array.iter().collect::<Vec<_>>());
+                resized.resize_bits(bits);
+                assert_eq!(resized, array);
+            }
+        }
+
+        let mut array = PackedDataArray::new(4, 20);
+        // Value is cut to the low bits
+        array.set(16, 0x1F);
+        assert_eq!(array.get(16), 0x0F);
+        assert_eq!(array.as_longs(), &[0, 0x0F]);
+        array.resize_bits(2);
+        assert_eq!(array.get(16), 0x03);
+        let mut empty = PackedDataArray::new(0, 4096);
+        empty.set(100, 5);
+        assert_eq!((empty.get(100), empty.as_longs().len()), (0, 0));
+        // Vanilla long of 5 bit entries, where 4 high bits are unused
+        let array = PackedDataArray::from_longs(5, vec![0x0020863148418841], 12).unwrap();
+        assert_eq!(array.iter().collect::<Vec<_>>(), [1, 2, 2, 3, 4, 4, 5, 6, 6, 4, 8, 0]);
+    }
+
+    #[test]
+    fn heightmap_tests() {
+        let mut heightmap = Heightmap::new();
+        assert_eq!(heightmap.as_longs().len(), 37);
+        assert_eq!(heightmap.get_height(5, 5).unwrap(), -64);
+        heightmap.set_height(0, 0, 0).unwrap();
+        heightmap.set_height(6, 0, 320).unwrap();
+        heightmap.set_height(7, 0, -1).unwrap();
+        heightmap.set_height(15, 15, -64).unwrap();
+        heightmap.set_height(14, 15, 100).unwrap();
+        assert_eq!(heightmap.as_longs()[0], 64 | 384 << 54);
+        assert_eq!(heightmap.as_longs()[1], 63);
+        assert_eq!(
+            (heightmap.get_height(0, 0).unwrap(), heightmap.get_height(6, 0).unwrap(), heightmap.get_height(7, 0).unwrap()),
+            (0, 320, -1)
+        );
+        assert_eq!((heightmap.get_height(15, 15).unwrap(), heightmap.get_height(14, 15).unwrap()), (-64, 100));
+        assert!(Heightmap::from_longs(-64, 384, vec![0; 36]).is_err());
+        assert!(heightmap.set_height(0, 0, 321).is_err());
+        assert!(heightmap.set_height(0, 0, -65).is_err());
+        assert!(heightmap.set_height(0, 0, i32::MIN).is_err());
+        assert!(heightmap.set_height(16, 0, 0).is_err());
+        assert!(heightmap.get_height(0, 16).is_err());
+        assert_eq!(heightmap.get_height(0, 0).unwrap(), 0);
+        // World of 128 blocks uses 8 bits per entry
+        let mut heightmap = Heightmap::with_world(0, 128);
+        assert_eq!(heightmap.as_longs().len(), 32);
+        heightmap.set_height(1, 0, 128).unwrap();
+        assert_eq!(heightmap.as_longs()[0], 128 << 8);
+        assert!(heightmap.set_height(1, 0, 129).is_err());
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn heightmaps_nbt_tests() {
+        let mut heightmaps = Heightmaps::default();
+        for x in 0..16 {
+            for z in 0..16 {
+                heightmaps.motion_blocking.set_height(x, z, -60).unwrap();
+                heightmaps.world_surface.set_height(x, z, (x * 16 + z) as i32 - 64).unwrap();
+            }
+        }
+        let mut write = Vec::new();
+        heightmaps.write(&mut write).unwrap();
+        let mut read = SlicePacketRead::new(&write);
+        assert_eq!(Heightmaps::read(&mut read).unwrap(), heightmaps);
+        assert_eq!(read.available(), 0);
+        // Motion blocking heightmap of the default superflat world, where blocks end at y = -61
+        let mut expected = vec![0x0A, 0x00, 0x00, 0x0C, 0x00, 0x0F];
+        expected.extend_from_slice(b"MOTION_BLOCKING");
+        expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x25]);
+        for _ in 0..36 {
+            expected.extend_from_slice(&[0x01, 0x00, 0x80, 0x40, 0x20, 0x10, 0x08, 0x04]);
+        }
+        // Last long has only 4 entries
+        expected.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x20, 0x10, 0x08, 0x04]);
+        assert_eq!(&write[..expected.len()], &expected);
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn custom_height_heightmaps_tests() {
+        // Nether is 256 blocks high, custom world is 2032 blocks high
+        for (min_y, height, longs) in [(0, 256, 37), (-1024, 2032, 52)] {
+            let mut heightmaps = Heightmaps::with_world(min_y, height);
+            heightmaps.motion_blocking.set_height(3, 4, min_y + height as i32).unwrap();
+            heightmaps.world_surface.set_height(15, 15, min_y + 1).unwrap();
+            assert_eq!(heightmaps.motion_blocking.as_longs().len(), longs);
+            let mut write = Vec::new();
+            heightmaps.write(&mut write).unwrap();
+            let read = Heightmaps::read_with_world(min_y, height, &mut SlicePacketRead::new(&write)).unwrap();
+            assert_eq!(read, heightmaps);
+            assert_eq!(read.motion_blocking.get_height(3, 4).unwrap(), min_y + height as i32);
+        }
+        let mut write = Vec::new();
+        Heightmaps::with_world(-1024, 2032).write(&mut write).unwrap();
+        assert!(Heightmaps::read(&mut SlicePacketRead::new(&write)).is_err());
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn bad_heightmaps_tests() {
+        let nbt = HeightmapsNbt {
+            motion_blocking: fastnbt::LongArray::new(vec![0; 37]),
+            world_surface: fastnbt::LongArray::new(vec![0; 36]),
+        };
+        let mut write = Vec::new();
+        crate::packet_types::ProtocolNbt::write_variant(&nbt, &mut write).unwrap();
+        assert!(Heightmaps::read(&mut SlicePacketRead::new(&write)).is_err());
+    }
+
+    #[test]
+    fn nibble_array_tests() {
+        let mut nibbles = NibbleArray::new(5);
+        assert_eq!((nibbles.len(), nibbles.as_bytes()), (5, &[0u8, 0, 0][..]));
+        nibbles.set(0, 0x1);
+        nibbles.set(1, 0x2);
+        nibbles.set(4, 0xFF);
+        assert_eq!(nibbles.as_bytes(), &[0x21, 0x00, 0x0F]);
+        nibbles.set(1, 0x0);
+        assert_eq!((nibbles.get(0), nibbles.get(1), nibbles.get(3), nibbles.get(4)), (0x1, 0x0, 0x0, 0xF));
+        assert!(std::panic::catch_unwind(|| NibbleArray::new(5).get(5)).is_err());
+        assert!(NibbleArray::from_bytes(vec![0; 2], 5).is_err());
+        assert_eq!(NibbleArray::from_bytes(vec![0xBA; 3], 5).unwrap().get(3), 0xB);
+        let mut light = NibbleArray::section();
+        assert_eq!(light.as_bytes().len(), 2048);
+        for index in 0..light.len() {
+            light.set(index, (index * 7 % 16) as u8);
+        }
+        let mut write = Vec::new();
+        light.write(&mut write).unwrap();
+        assert_eq!(&write[..3], &[0x80, 0x10, 0x70]);
+        let read = NibbleArray::read(&mut SlicePacketRead::new(&write)).unwrap();
+        assert!((0..light.len()).all(|index| read.get(index) == (index * 7 % 16) as u8));
+        assert_eq!(read, light);
+        // Odd entry is padded by the zero high nibble
+        let mut write = Vec::new();
+        nibbles.write(&mut write).unwrap();
+        let read = NibbleArray::read(&mut SlicePacketRead::new(&write)).unwrap();
+        assert_eq!((read.len(), read.get(4), read.get(5)), (6, 0xF, 0x0));
+    }
+
+    #[test]
+    fn light_data_tests() {
+        let mut write = Vec::new();
+        LightData::new(true).write(&mut write).unwrap();
+        assert_eq!(write, &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(LightData::read(&mut SlicePacketRead::new(&write)).unwrap(), LightData::new(true));
+        let mut light = LightData::new(false);
+        light.set_sky_section(5, [0xFF; 2048]);
+        light.set_sky_section(1, [0x11; 2048]);
+        light.set_block_section(0, [0x0F; 2048]);
+        light.sky_light.set_empty(2);
+        assert_eq!(light.sky_light.mask().iter().collect::<Vec<_>>(), vec![1, 5]);
+        assert_eq!(light.sky_light.get(1).unwrap().get(0), 0x1);
+        assert_eq!(light.sky_light.get(5).unwrap().get(4095), 0xF);
+        assert!(light.sky_light.get(2).is_none());
+        light.set_sky_section(1, [0x22; 2048]);
+        assert_eq!(light.sky_light.sections().len(), 2);
+        let mut write = Vec::new();
+        light.write(&mut write).unwrap();
+        // Longs of the masks are big endian
+        assert_eq!(&write[..9], &[0x00, 0x01, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&write[9..31], &[0x22, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x04, 0x00, 0x02, 0x80]);
+        let read = LightData::read(&mut SlicePacketRead::new(&write)).unwrap();
+        assert_eq!(read, light);
+        assert_eq!(read.sky_light.get(1).unwrap().get(1), 0x2);
+        assert_eq!(light.sky_light.remove(5).unwrap().get(0), 0xF);
+        light.set_sky_section(2, [0; 2048]);
+        assert!(light.sky_light.empty_mask().is_empty());
+        assert_eq!(light.sky_light.mask().iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn light_data_error_tests() {
+        // Sky mask has two sections, but only one is sent
+        let mut bytes = vec![0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x03, 0x00, 0x00, 0x00, 0x01, 0x80, 0x10];
+        bytes.extend_from_slice(&[0; 2048]);
+        bytes.push(0x00);
+        assert!(matches!(LightData::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BadLength)));
+        // Section is shorter than 2048 bytes
+        let mut bytes = vec![0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00];
+        bytes.extend_from_slice(&[0; 2048]);
+        assert!(matches!(LightData::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BadLength)));
+        // Mask of 4096 sections is rejected before arrays are allocated, because their bytes are not sent
+        let mut bytes = vec![0x00, 0x40];
+        bytes.extend_from_slice(&[0xFF; 64 * 8]);
+        bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x80, 0x20]);
+        assert!(matches!(LightData::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BytesExceeded)));
+    }
+}
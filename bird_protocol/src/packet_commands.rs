@@ -0,0 +1,475 @@
+use std::borrow::Cow;
+use anyhow::Error;
+use bird_chat::identifier::Identifier;
+use crate::*;
+use crate::derive::*;
+
+const NODE_TYPE_MASK: u8 = 0x03;
+const NODE_EXECUTABLE: u8 = 0x04;
+const NODE_REDIRECT: u8 = 0x08;
+const NODE_SUGGESTIONS: u8 = 0x10;
+
+const BOUND_MIN: u8 = 0x01;
+const BOUND_MAX: u8 = 0x02;
+
+const ENTITY_SINGLE: u8 = 0x01;
+const ENTITY_PLAYERS_ONLY: u8 = 0x02;
+
+const SCORE_HOLDER_MULTIPLE: u8 = 0x01;
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum StringMode {
+    SingleWord,
+    /// Word or string in quotes.
+    QuotablePhrase,
+    /// Rest of the command.
+    GreedyPhrase,
+}
+
+/// Parser of the argument node. Ids are from the `command_argument_type` registry of 1.19.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandParser<'a> {
+    Bool,
+    Float { min: Option<f32>, max: Option<f32> },
+    Double { min: Option<f64>, max: Option<f64> },
+    Integer { min: Option<i32>, max: Option<i32> },
+    Long { min: Option<i64>, max: Option<i64> },
+    String(StringMode),
+    Entity { single: bool, players_only: bool },
+    GameProfile,
+    BlockPos,
+    ColumnPos,
+    Vec3,
+    Vec2,
+    BlockState,
+    BlockPredicate,
+    ItemStack,
+    ItemPredicate,
+    Color,
+    Component,
+    Message,
+    Nbt,
+    NbtTag,
+    NbtPath,
+    Objective,
+    ObjectiveCriteria,
+    Operation,
+    Particle,
+    Angle,
+    Rotation,
+    ScoreboardSlot,
+    ScoreHolder { multiple: bool },
+    Swizzle,
+    Team,
+    ItemSlot,
+    ResourceLocation,
+    MobEffect,
+    Function,
+    EntityAnchor,
+    IntRange,
+    FloatRange,
+    ItemEnchantment,
+    EntitySummon,
+    Dimension,
+    Time,
+    ResourceOrTag { registry: Identifier<'a> },
+    Resource { registry: Identifier<'a> },
+    TemplateMirror,
+    TemplateRotation,
+    Uuid,
+}
+
+impl CommandParser<'_> {
+    pub fn id(&self) -> i32 {
+        match self {
+            Self::Bool => 0,
+            Self::Float { .. } => 1,
+            Self::Double { .. } => 2,
+            Self::Integer { .. } => 3,
+            Self::Long { .. } => 4,
+            Self::String(_) => 5,
+            Self::Entity { .. } => 6,
+            Self::GameProfile => 7,
+            Self::BlockPos => 8,
+            Self::ColumnPos => 9,
+            Self::Vec3 => 10,
+            Self::Vec2 => 11,
+            Self::BlockState => 12,
+            Self::BlockPredicate => 13,
+            Self::ItemStack => 14,
+            Self::ItemPredicate => 15,
+            Self::Color => 16,
+            Self::Component => 17,
+            Self::Message => 18,
+            Self::Nbt => 19,
+            Self::NbtTag => 20,
+            Self::NbtPath => 21,
+            Self::Objective => 22,
+            Self::ObjectiveCriteria => 23,
+            Self::Operation => 24,
+            Self::Particle => 25,
+            Self::Angle => 26,
+            Self::Rotation => 27,
+            Self::ScoreboardSlot => 28,
+            Self::ScoreHolder { .. } => 29,
+            Self::Swizzle => 30,
+            Self::Team => 31,
+            Self::ItemSlot => 32,
+            Self::ResourceLocation => 33,
+            Self::MobEffect => 34,
+            Self::Function => 35,
+            Self::EntityAnchor => 36,
+            Self::IntRange => 37,
+            Self::FloatRange => 38,
+            Self::ItemEnchantment => 39,
+            Self::EntitySummon => 40,
+            Self::Dimension => 41,
+            Self::Time => 42,
+            Self::ResourceOrTag { .. } => 43,
+            Self::Resource { .. } => 44,
+            Self::TemplateMirror => 45,
+            Self::TemplateRotation => 46,
+            Self::Uuid => 47,
+        }
+    }
+}
+
+/// Number bounds are prefixed by the flags byte, which tells which of them are present.
+fn read_bounds<'a, T, R>(read: &mut R) -> Result<(Option<T>, Option<T>), PacketReadableError>
+    where T: PacketReadable<'a>, R: PacketRead<'a> {
+    let flags = u8::read(read)?;
+    let min = match flags & BOUND_MIN != 0 {
+        true => Some(T::read(read)?),
+        false => None,
+    };
+    let max = match flags & BOUND_MAX != 0 {
+        true => Some(T::read(read)?),
+        false => None,
+    };
+    Ok((min, max))
+}
+
+fn write_bounds<T, W>(min: &Option<T>, max: &Option<T>, write: &mut W) -> Result<(), Error>
+    where T: PacketWritable, W: PacketWrite {
+    let mut flags = 0u8;
+    if min.is_some() {
+        flags |= BOUND_MIN;
+    }
+    if max.is_some() {
+        flags |= BOUND_MAX;
+    }
+    flags.write(write)?;
+    if let Some(min) = min {
+        min.write(write)?;
+    }
+    if let Some(max) = max {
+        max.write(write)?;
+    }
+    Ok(())
+}
+
+impl<'a> PacketReadable<'a> for CommandParser<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(match VarInt::read_variant(read)? {
+            0 => Self::Bool,
+            1 => read_bounds(read).map(|(min, max)| Self::Float { min, max })?,
+            2 => read_bounds(read).map(|(min, max)| Self::Double { min, max })?,
+            3 => read_bounds(read).map(|(min, max)| Self::Integer { min, max })?,
+            4 => read_bounds(read).map(|(min, max)| Self::Long { min, max })?,
+            5 => Self::String(StringMode::read(read)?),
+            6 => {
+                let flags = u8::read(read)?;
+                Self::Entity { single: flags & ENTITY_SINGLE != 0, players_only: flags & ENTITY_PLAYERS_ONLY != 0 }
+            }
+            7 => Self::GameProfile,
+            8 => Self::BlockPos,
+            9 => Self::ColumnPos,
+            10 => Self::Vec3,
+            11 => Self::Vec2,
+            12 => Self::BlockState,
+            13 => Self::BlockPredicate,
+            14 => Self::ItemStack,
+            15 => Self::ItemPredicate,
+            16 => Self::Color,
+            17 => Self::Component,
+            18 => Self::Message,
+            19 => Self::Nbt,
+            20 => Self::NbtTag,
+            21 => Self::NbtPath,
+            22 => Self::Objective,
+            23 => Self::ObjectiveCriteria,
+            24 => Self::Operation,
+            25 => Self::Particle,
+            26 => Self::Angle,
+            27 => Self::Rotation,
+            28 => Self::ScoreboardSlot,
+            29 => Self::ScoreHolder { multiple: u8::read(read)? & SCORE_HOLDER_MULTIPLE != 0 },
+            30 => Self::Swizzle,
+            31 => Self::Team,
+            32 => Self::ItemSlot,
+            33 => Self::ResourceLocation,
+            34 => Self::MobEffect,
+            35 => Self::Function,
+            36 => Self::EntityAnchor,
+            37 => Self::IntRange,
+            38 => Self::FloatRange,
+            39 => Self::ItemEnchantment,
+            40 => Self::EntitySummon,
+            41 => Self::Dimension,
+            42 => Self::Time,
+            43 => Self::ResourceOrTag { registry: Identifier::read(read)? },
+            44 => Self::Resource { registry: Identifier::read(read)? },
+            45 => Self::TemplateMirror,
+            46 => Self::TemplateRotation,
+            47 => Self::Uuid,
+            // Properties of the unknown parser can't be skipped
+            _ => return Err(PacketReadableError::BadEnumValue),
+        })
+    }
+}
+
+impl PacketWritable for CommandParser<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        VarInt::write_variant(&self.id(), write)?;
+        match self {
+            Self::Float { min, max } => write_bounds(min, max, write),
+            Self::Double { min, max } => write_bounds(min, max, write),
+            Self::Integer { min, max } => write_bounds(min, max, write),
+            Self::Long { min, max } => write_bounds(min, max, write),
+            Self::String(mode) => mode.write(write),
+            Self::Entity { single, players_only } => {
+                let mut flags = 0u8;
+                if *single {
+                    flags |= ENTITY_SINGLE;
+                }
+                if *players_only {
+                    flags |= ENTITY_PLAYERS_ONLY;
+                }
+                flags.write(write)
+            }
+            Self::ScoreHolder { multiple } => match multiple {
+                true => SCORE_HOLDER_MULTIPLE.write(write),
+                false => 0u8.write(write),
+            },
+            Self::ResourceOrTag { registry } | Self::Resource { registry } => registry.write(write),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandNodeType<'a> {
+    Root,
+    Literal {
+        name: Cow<'a, str>,
+    },
+    Argument {
+        name: Cow<'a, str>,
+        parser: CommandParser<'a>,
+        /// Suggestions type, for example `minecraft:ask_server`.
+        suggestions: Option<Identifier<'a>>,
+    },
+}
+
+/// Node of the command graph. Children and redirect are indices of nodes in [PlayCommands::nodes].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandNode<'a> {
+    pub node_type: CommandNodeType<'a>,
+    pub executable: bool,
+    pub children: Vec<i32>,
+    pub redirect: Option<i32>,
+}
+
+impl<'a> CommandNode<'a> {
+    pub fn root(children: Vec<i32>) -> Self {
+        Self { node_type: CommandNodeType::Root, executable: false, children, redirect: None }
+    }
+
+    pub fn literal(name: impl Into<Cow<'a, str>>, executable: bool, children: Vec<i32>) -> Self {
+        Self { node_type: CommandNodeType::Literal { name: name.into() }, executable, children, redirect: None }
+    }
+
+    pub fn argument(name: impl Into<Cow<'a, str>>, parser: CommandParser<'a>, executable: bool, children: Vec<i32>) -> Self {
+        Self {
+            node_type: CommandNodeType::Argument { name: name.into(), parser, suggestions: None },
+            executable,
+            children,
+            redirect: None,
+        }
+    }
+
+    pub fn with_redirect(mut self, redirect: i32) -> Self {
+        self.redirect = Some(redirect);
+        self
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = match &self.node_type {
+            CommandNodeType::Root => 0,
+            CommandNodeType::Literal { .. } => 1,
+            CommandNodeType::Argument { suggestions: Some(_), .. } => 2 | NODE_SUGGESTIONS,
+            CommandNodeType::Argument { .. } => 2,
+        };
+        if self.executable {
+            flags |= NODE_EXECUTABLE;
+        }
+        if self.redirect.is_some() {
+            flags |= NODE_REDIRECT;
+        }
+        flags
+    }
+}
+
+impl<'a> PacketReadable<'a> for CommandNode<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let flags = u8::read(read)?;
+        let children = LengthProvidedSlice::<VarInt, VarInt, i32, i32>::read_variant(read)?;
+        let redirect = match flags & NODE_REDIRECT != 0 {
+            true => Some(VarInt::read_variant(read)?),
+            false => None,
+        };
+        let node_type = match flags & NODE_TYPE_MASK {
+            0 => CommandNodeType::Root,
+            1 => CommandNodeType::Literal { name: Cow::read(read)? },
+            2 => CommandNodeType::Argument {
+                name: Cow::read(read)?,
+                parser: CommandParser::read(read)?,
+                suggestions: match flags & NODE_SUGGESTIONS != 0 {
+                    true => Some(Identifier::read(read)?),
+                    false => None,
+                },
+            },
+            _ => return Err(PacketReadableError::BadEnumValue),
+        };
+        Ok(Self { node_type, executable: flags & NODE_EXECUTABLE != 0, children, redirect })
+    }
+}
+
+impl PacketWritable for CommandNode<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.flags().write(write)?;
+        LengthProvidedSlice::<VarInt, VarInt, i32, i32>::write_variant(&self.children, write)?;
+        if let Some(redirect) = self.redirect {
+            VarInt::write_variant(&redirect, write)?;
+        }
+        match &self.node_type {
+            CommandNodeType::Root => Ok(()),
+            CommandNodeType::Literal { name } => name.write(write),
+            CommandNodeType::Argument { name, parser, suggestions } => {
+                name.write(write)?;
+                parser.write(write)?;
+                match suggestions {
+                    Some(suggestions) => suggestions.write(write),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+/// Flattened command graph, which is used by the client for completion and highlighting.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x0F)]
+pub struct PlayCommands<'a> {
+    pub nodes: Vec<CommandNode<'a>>,
+    #[variant(VarInt)]
+    pub root_index: i32,
+}
+
+impl PlayCommands<'_> {
+    /// Checks that the root, children and redirects point to existing nodes.
+    pub fn validate(&self) -> Result<(), Error> {
+        let exists = |index: i32| index >= 0 && (index as usize) < self.nodes.len();
+        if !exists(self.root_index) {
+            return Err(Error::msg("Root node doesn't exist"));
+        }
+        for node in &self.nodes {
+            if !node.children.iter().copied().chain(node.redirect).all(exists) {
+                return Err(Error::msg("Node points to the node which doesn't exist"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_tests() {
+        // Root, /give <count>, /g redirected to /give
+        let packet = PlayCommands {
+            nodes: vec![
+                CommandNode::root(vec![1, 3]),
+                CommandNode::literal("give", false, vec![2]),
+                CommandNode::argument("count", CommandParser::Integer { min: Some(1), max: Some(64) }, true, vec![]),
+                CommandNode::literal("g", false, vec![]).with_redirect(1),
+            ],
+            root_index: 0,
+        };
+        packet.validate().unwrap();
+        let mut write = Vec::new();
+        PacketVariant::write_variant(&packet, &mut write).unwrap();
+        let mut expected = vec![0x0F, 0x04];
+        expected.extend_from_slice(&[0x00, 0x02, 0x01, 0x03]);
+        expected.extend_from_slice(&[0x01, 0x01, 0x02, 0x04, b'g', b'i', b'v', b'e']);
+        expected.extend_from_slice(&[0x06, 0x00, 0x05, b'c', b'o', b'u', b'n', b't', 0x03, 0x03, 0, 0, 0, 1, 0, 0, 0, 64]);
+        expected.extend_from_slice(&[0x09, 0x00, 0x01, 0x01, b'g']);
+        expected.push(0x00);
+        assert_eq!(write, expected);
+        let mut read = SlicePacketRead::new(&write);
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), PlayCommands::ID);
+        assert_eq!(PlayCommands::read(&mut read).unwrap(), packet);
+        assert_eq!(read.available(), 0);
+        let broken = PlayCommands { root_index: 4, ..packet.clone() };
+        assert!(broken.validate().is_err());
+        let mut broken = packet;
+        broken.nodes[3].redirect = Some(-1);
+        assert!(broken.validate().is_err());
+    }
+
+    #[test]
+    fn command_parser_tests() {
+        let registry = Identifier::new_fulled("minecraft:worldgen/biome").unwrap();
+        let parsers = [
+            CommandParser::Bool,
+            CommandParser::Float { min: None, max: Some(1.5) },
+            CommandParser::Double { min: Some(-2.0), max: None },
+            CommandParser::Long { min: None, max: None },
+            CommandParser::String(StringMode::GreedyPhrase),
+            CommandParser::Entity { single: true, players_only: true },
+            CommandParser::ScoreHolder { multiple: true },
+            CommandParser::ResourceOrTag { registry: registry.clone() },
+            CommandParser::Resource { registry },
+            CommandParser::Time,
+            CommandParser::Uuid,
+        ];
+        for parser in parsers {
+            let mut write = Vec::new();
+            parser.write(&mut write).unwrap();
+            assert_eq!(write[0] as i32, parser.id());
+            let mut read = SlicePacketRead::new(&write);
+            assert_eq!(CommandParser::read(&mut read).unwrap(), parser);
+            assert_eq!(read.available(), 0);
+        }
+        let mut write = Vec::new();
+        CommandParser::Float { min: None, max: Some(1.5) }.write(&mut write).unwrap();
+        assert_eq!(write, &[0x01, 0x02, 0x3F, 0xC0, 0x00, 0x00]);
+        let mut write = Vec::new();
+        CommandParser::Entity { single: false, players_only: true }.write(&mut write).unwrap();
+        assert_eq!(write, &[0x06, 0x02]);
+        let mut node = CommandNode::argument("biome", CommandParser::Message, false, vec![]);
+        if let CommandNodeType::Argument { suggestions, .. } = &mut node.node_type {
+            *suggestions = Some(Identifier::new_fulled("minecraft:ask_server").unwrap());
+        }
+        let mut write = Vec::new();
+        node.write(&mut write).unwrap();
+        assert_eq!(write[0], 0x12);
+        assert_eq!(CommandNode::read(&mut SlicePacketRead::new(&write)).unwrap(), node);
+        assert!(matches!(CommandParser::read(&mut SlicePacketRead::new(&[48])), Err(PacketReadableError::BadEnumValue)));
+        assert!(matches!(CommandNode::read(&mut SlicePacketRead::new(&[0x03, 0x00])), Err(PacketReadableError::BadEnumValue)));
+    }
+}
@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use anyhow::Error;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use crate::packet::{PacketRead, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite, SlicePacketRead};
+use crate::packet_frame::{read_frame, write_frame_bytes};
+use crate::packet_types::{preallocate, VarInt};
+
+/// Maximum uncompressed length of the packet which vanilla accepts.
+pub const MAX_UNCOMPRESSED_LENGTH: usize = 8388608;
+
+/// Compressed packet format which is used after Set Compression packet.
+///
+/// Frame is [VarInt] length, [VarInt] uncompressed length (zero if body is not compressed) and body.
+/// Bodies which length is not less than threshold are compressed with zlib.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacketCompression {
+    pub threshold: usize,
+    pub max_length: usize,
+}
+
+impl PacketCompression {
+    pub fn new(threshold: usize) -> Self {
+        Self { threshold, max_length: MAX_UNCOMPRESSED_LENGTH }
+    }
+
+    pub fn write_object<T, W>(&self, object: &T, write: &mut W) -> Result<(), Error>
+        where T: PacketWritable, W: PacketWrite {
+        let mut body = Vec::new();
+        object.write(&mut body)?;
+        self.write_packet(&body, write)
+    }
+
+    pub fn write_packet<W>(&self, body: &[u8], write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        let mut data = Vec::new();
+        self.compress(body, &mut data)?;
+        write_frame_bytes(&data, write)
+    }
+
+    /// Writes [VarInt] uncompressed length and the body, which is compressed if it is long enough.
+    ///
+    /// Output is the frame body, so it is not prefixed with the frame length.
+    pub fn compress(&self, body: &[u8], data: &mut Vec<u8>) -> Result<(), Error> {
+        match body.len() >= self.threshold {
+            true => {
+                let length = i32::try_from(body.len()).map_err(|_| Error::msg("Too big packet"))?;
+                VarInt::write_variant(&length, data)?;
+                let mut encoder = ZlibEncoder::new(data, Compression::default());
+                encoder.write_all(body)?;
+                #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                let data = encoder.finish()?;
+                #[cfg(feature = "tracing")]
+                tracing::trace!(length = body.len(), compressed_length = data.len(), "body is compressed");
+            }
+            false => {
+                VarInt::write_variant(&0, data)?;
+                data.extend_from_slice(body);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads frame and returns uncompressed body of it.
+    ///
+    /// Uncompressed body is borrowed from the reader.
+    pub fn read_packet<'a, R>(&self, read: &mut R) -> Result<Cow<'a, [u8]>, PacketReadableError>
+        where R: PacketRead<'a> {
+        let mut frame = read_frame(read)?;
+        self.decompress(frame.take_slice(frame.available())?)
+    }
+
+    /// Returns uncompressed body of the frame body, which starts with [VarInt] uncompressed length.
+    pub fn decompress<'a>(&self, frame: &'a [u8]) -> Result<Cow<'a, [u8]>, PacketReadableError> {
+        let mut frame = SlicePacketRead::new(frame);
+        let length = VarInt::read_variant(&mut frame)?;
+        let data = frame.take_slice(frame.available())?;
+        if length == 0 {
+            return Ok(Cow::Borrowed(data));
+        }
+        let length = match usize::try_from(length) {
+            Ok(length) if length < self.threshold => Err(Error::msg("Compressed packet is below threshold")),
+            Ok(length) if length > self.max_length => Err(Error::msg("Compressed packet is too big")),
+            Ok(length) => Ok(length),
+            Err(_) => Err(Error::msg("Negative uncompressed length")),
+        }?;
+        let mut body = preallocate(length);
+        ZlibDecoder::new(data)
+            .take(length as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|err| PacketReadableError::Any(err.into()))?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(length, compressed_length = data.len(), "body is decompressed");
+        match body.len() == length {
+            true => Ok(Cow::Owned(body)),
+            false => Err(PacketReadableError::Any(Error::msg("Uncompressed length does not match"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compressed_frame(length: i32, body: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        VarInt::write_variant(&length, &mut data).unwrap();
+        let mut encoder = ZlibEncoder::new(data, Compression::default());
+        encoder.write_all(body).unwrap();
+        let mut frame = Vec::new();
+        write_frame_bytes(&encoder.finish().unwrap(), &mut frame).unwrap();
+        frame
+    }
+
+    #[test]
+    fn compression_tests() {
+        let compression = PacketCompression::new(256);
+        let mut write = Vec::new();
+        compression.write_packet(&[1; 255], &mut write).unwrap();
+        assert_eq!(&write[..4], &[0x80, 0x02, 0x00, 0x01]);
+        let uncompressed_length = write.len();
+        compression.write_packet(&[2; 256], &mut write).unwrap();
+        assert_eq!(&write[uncompressed_length + 1..uncompressed_length + 3], &[0x80, 0x02]);
+        compression.write_packet(&[3; 4096], &mut write).unwrap();
+        compression.write_object(&"compressed", &mut write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert!(matches!(compression.read_packet(&mut read).unwrap(), Cow::Borrowed(body) if body == [1; 255]));
+        assert_eq!(compression.read_packet(&mut read).unwrap().as_ref(), &[2; 256]);
+        assert_eq!(compression.read_packet(&mut read).unwrap().as_ref(), &[3; 4096]);
+        assert_eq!(
+            <&str>::read_variant(&mut SlicePacketRead::new(&compression.read_packet(&mut read).unwrap())).unwrap(),
+            "compressed"
+        );
+        assert_eq!(read.available(), 0);
+    }
+
+    #[test]
+    fn bad_compression_tests() {
+        let compression = PacketCompression { threshold: 256, max_length: 4096 };
+        let bad_frames = [
+            compressed_frame(300, &[0; 299]),
+            compressed_frame(300, &[0; 301]),
+            compressed_frame(4097, &[0; 4097]),
+            compressed_frame(100, &[0; 100]),
+            compressed_frame(300, &[0; 1 << 20]),
+            compressed_frame(-1, &[0; 300]),
+            compressed_frame(4096, &[]),
+        ];
+        for frame in bad_frames {
+            let mut read = SlicePacketRead::new(&frame);
+            assert!(matches!(compression.read_packet(&mut read).unwrap_err(), PacketReadableError::Any(_)));
+        }
+        let frame = compressed_frame(300, &[0; 300]);
+        let mut read = SlicePacketRead::new(&frame[..frame.len() - 1]);
+        assert!(matches!(compression.read_packet(&mut read).unwrap_err(), PacketReadableError::BytesExceeded));
+    }
+}
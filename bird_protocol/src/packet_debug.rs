@@ -0,0 +1,236 @@
+use std::fmt::Write as _;
+use anyhow::Error;
+use crate::packet::{PacketRead, PacketReadableError, PacketWrite};
+
+/// Formats bytes like `hexdump -C`: offset, 16 bytes in hex and the same bytes in ascii.
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        let _ = write!(dump, "{:08x} ", line * 16);
+        for position in 0..16 {
+            if position == 8 {
+                dump.push(' ');
+            }
+            let _ = match chunk.get(position) {
+                Some(byte) => write!(dump, " {:02x}", byte),
+                None => write!(dump, "   "),
+            };
+        }
+        dump.push_str("  |");
+        dump.extend(chunk.iter().map(|byte| match byte.is_ascii_graphic() || *byte == b' ' {
+            true => *byte as char,
+            false => '.',
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+/// Call of the traced reader or writer. Offset is counted from the position where tracing has started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// Bytes which are returned by [PacketRead::take_byte] or [PacketRead::take_slice].
+    Read { offset: usize, bytes: Vec<u8> },
+    /// Take of `length` bytes which has failed.
+    Failed { offset: usize, length: usize },
+    /// Successful [PacketRead::rollback]. Offset is the position before the rollback.
+    Rollback { offset: usize, length: usize },
+    /// Bytes which are given to the writer.
+    Write { offset: usize, bytes: Vec<u8> },
+}
+
+impl std::fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceEvent::Read { offset, bytes } => write!(f, "{:#06x} read {:02x?}", offset, bytes),
+            TraceEvent::Failed { offset, length } => write!(f, "{:#06x} failed to read {} bytes", offset, length),
+            TraceEvent::Rollback { offset, length } => write!(f, "{:#06x} rollback {} bytes", offset, length),
+            TraceEvent::Write { offset, bytes } => write!(f, "{:#06x} write {:02x?}", offset, bytes),
+        }
+    }
+}
+
+/// Formats events one per line, so a failing test can print them.
+pub fn format_trace(trace: &[TraceEvent]) -> String {
+    trace.iter().map(|event| format!("{}\n", event)).collect()
+}
+
+/// Reader which records every call of the inner reader.
+///
+/// Readers returned by [PacketRead::take_bounded] are not traced, only the bounded slice is recorded.
+#[derive(Debug)]
+pub struct TracingPacketRead<R> {
+    inner: R,
+    offset: usize,
+    trace: Vec<TraceEvent>,
+}
+
+impl<R> TracingPacketRead<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, offset: 0, trace: Vec::new() }
+    }
+
+    pub fn trace(&self) -> &[TraceEvent] {
+        &self.trace
+    }
+
+    /// Count of bytes which are consumed since tracing has started.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn record<T: AsRef<[u8]>>(&mut self, length: usize, result: Result<T, PacketReadableError>)
+                              -> Result<T, PacketReadableError> {
+        match &result {
+            Ok(bytes) => {
+                self.trace.push(TraceEvent::Read { offset: self.offset, bytes: bytes.as_ref().to_vec() });
+                self.offset += length;
+            }
+            Err(_) => self.trace.push(TraceEvent::Failed { offset: self.offset, length }),
+        }
+        result
+    }
+}
+
+impl<'a, R: PacketRead<'a>> PacketRead<'a> for TracingPacketRead<R> {
+    fn take_byte(&mut self) -> Result<u8, PacketReadableError> {
+        let result = self.inner.take_byte().map(|byte| [byte]);
+        self.record(1, result).map(|[byte]| byte)
+    }
+
+    fn take_slice(&mut self, length: usize) -> Result<&'a [u8], PacketReadableError> {
+        let result = self.inner.take_slice(length);
+        self.record(length, result)
+    }
+
+    fn rollback(&mut self, length: usize) -> Result<(), Error> {
+        self.inner.rollback(length)?;
+        self.trace.push(TraceEvent::Rollback { offset: self.offset, length });
+        self.offset = self.offset.saturating_sub(length);
+        Ok(())
+    }
+
+    fn available(&self) -> usize {
+        self.inner.available()
+    }
+
+    fn is_available(&self, bytes: usize) -> bool {
+        self.inner.is_available(bytes)
+    }
+
+    fn offset(&self) -> usize {
+        self.inner.offset()
+    }
+}
+
+/// Writer which records every write to the inner writer.
+#[derive(Debug)]
+pub struct TracingPacketWrite<W> {
+    inner: W,
+    offset: usize,
+    trace: Vec<TraceEvent>,
+}
+
+impl<W> TracingPacketWrite<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, offset: 0, trace: Vec::new() }
+    }
+
+    pub fn trace(&self) -> &[TraceEvent] {
+        &self.trace
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn record(&mut self, bytes: &[u8], result: Result<(), Error>) -> Result<(), Error> {
+        if result.is_ok() {
+            self.trace.push(TraceEvent::Write { offset: self.offset, bytes: bytes.to_vec() });
+            self.offset += bytes.len();
+        }
+        result
+    }
+}
+
+impl<W: PacketWrite> PacketWrite for TracingPacketWrite<W> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+        let result = self.inner.write_byte(byte);
+        self.record(&[byte], result)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let result = self.inner.write_bytes(bytes);
+        self.record(bytes, result)
+    }
+
+    fn write_bytes_owned(&mut self, bytes: Vec<u8>) -> Result<(), Error> {
+        self.write_bytes(bytes.as_slice())
+    }
+
+    fn write_bytes_fixed<const SIZE: usize>(&mut self, bytes: [u8; SIZE]) -> Result<(), Error> {
+        let result = self.inner.write_bytes_fixed(bytes);
+        self.record(&bytes, result)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::{PacketReadable, PacketVariantReadable, PacketVariantWritable, PacketWritable, SlicePacketRead};
+    use crate::packet_types::VarInt;
+    use super::*;
+
+    #[test]
+    fn hexdump_tests() {
+        assert_eq!(hexdump(&[]), "");
+        assert_eq!(
+            hexdump(b"bird protocol\x00\x01\xFFab"),
+            "00000000  62 69 72 64 20 70 72 6f  74 6f 63 6f 6c 00 01 ff  |bird protocol...|\n\
+             00000010  61 62                                             |ab|\n"
+        );
+    }
+
+    #[test]
+    fn trace_tests() {
+        let mut write = TracingPacketWrite::new(Vec::new());
+        7u8.write(&mut write).unwrap();
+        "bird".write(&mut write).unwrap();
+        VarInt::write_variant(&300, &mut write).unwrap();
+        assert_eq!(write.trace(), &[
+            TraceEvent::Write { offset: 0, bytes: vec![7] },
+            TraceEvent::Write { offset: 1, bytes: vec![4] },
+            TraceEvent::Write { offset: 2, bytes: b"bird".to_vec() },
+            TraceEvent::Write { offset: 6, bytes: vec![0xAC, 0x02] },
+        ]);
+        let bytes = write.into_inner();
+        // Reader expects a long after the length, so it diverges right at the string
+        let mut read = TracingPacketRead::new(SlicePacketRead::new(&bytes));
+        assert_eq!(u8::read(&mut read).unwrap(), 7);
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), 4);
+        let error = u64::read(&mut read).unwrap_err();
+        assert!(matches!(error, PacketReadableError::BytesExceeded), "{}{}", hexdump(&bytes), format_trace(read.trace()));
+        assert_eq!(read.trace(), &[
+            TraceEvent::Read { offset: 0, bytes: vec![7] },
+            TraceEvent::Read { offset: 1, bytes: vec![4] },
+            TraceEvent::Failed { offset: 2, length: 8 },
+        ]);
+        assert_eq!(read.offset(), 2);
+        assert_eq!(read.trace()[2].to_string(), "0x0002 failed to read 8 bytes");
+        let mut read = TracingPacketRead::new(SlicePacketRead::new(&bytes));
+        assert_eq!(read.peek_byte().unwrap(), 7);
+        assert_eq!(read.trace()[1], TraceEvent::Rollback { offset: 1, length: 1 });
+        assert_eq!(read.offset(), 0);
+    }
+}
@@ -4,9 +4,10 @@ use bird_chat::identifier::Identifier;
 use uuid::Uuid;
 use crate::*;
 use crate::derive::*;
+use crate::packet_version::ProtocolVersion;
 use serde::{Serialize, Deserialize};
 
-#[derive(PacketWritable, PacketReadable, Debug, Clone, Copy, PartialEq)]
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq)]
 #[enum_type(i32)]
 #[enum_variant(VarInt)]
 pub enum HandshakeNextState {
@@ -24,9 +25,17 @@ pub struct HandshakePacket<'a> {
     pub next_state: HandshakeNextState,
 }
 
+impl HandshakePacket<'_> {
+    /// Returns version of the client or none if the number is unknown.
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        ProtocolVersion::from_number(self.protocol_version)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct StatusResponseVersion<'a> {
-    pub name: &'a str,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
     pub protocol: i32,
 }
 
@@ -34,26 +43,149 @@ pub struct StatusResponseVersion<'a> {
 pub struct StatusResponsePlayers<'a> {
     pub max: i32,
     pub online: i32,
-    #[serde(borrow = "'a", skip_serializing_if = "is_cow_empty")]
+    #[serde(borrow = "'a", default, skip_serializing_if = "is_cow_empty")]
     pub sample: Cow<'a, [StatusResponseSample<'a>]>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct StatusResponseSample<'a> {
-    pub name: &'a str,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
     pub id: Uuid,
 }
 
+/// Fields are serialized in the order of the vanilla server.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct StatusResponseObject<'a> {
-    pub version: StatusResponseVersion<'a>,
+    #[serde(with = "status_description")]
+    pub description: either::Either<Cow<'a, str>, Component<'a>>,
+    #[serde(borrow = "'a")]
     pub players: StatusResponsePlayers<'a>,
     #[serde(borrow = "'a")]
-    pub description: either::Either<&'a str, Component<'a>>,
-    #[serde(skip_serializing_if = "str::is_empty")]
-    pub favicon: &'a str,
-    #[serde(rename = "previewsChat")]
-    pub previews_chat: bool,
+    pub version: StatusResponseVersion<'a>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<Cow<'a, str>>,
+    #[serde(rename = "previewsChat", default, skip_serializing_if = "Option::is_none")]
+    pub previews_chat: Option<bool>,
+    #[serde(rename = "enforcesSecureChat", default, skip_serializing_if = "Option::is_none")]
+    pub enforces_secure_chat: Option<bool>,
+    #[serde(rename = "forgeData", default, skip_serializing_if = "Option::is_none")]
+    pub forge_data: Option<serde_json::Value>,
+}
+
+/// Vanilla omits empty `extra` of the components, but [Component] requires it to be present.
+mod status_description {
+    use std::borrow::Cow;
+    use bird_chat::component::Component;
+    use either::Either;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+    use serde_json::Value;
+
+    pub fn serialize<S>(description: &Either<Cow<str>, Component>, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer {
+        either::serde_untagged::serialize(description, serializer)
+    }
+
+    pub fn deserialize<'de, 'a, D>(deserializer: D) -> Result<Either<Cow<'a, str>, Component<'a>>, D::Error>
+        where D: Deserializer<'de> {
+        match Value::deserialize(deserializer)? {
+            Value::String(text) => Ok(Either::Left(Cow::Owned(text))),
+            mut value => {
+                insert_extra(&mut value);
+                Component::deserialize(value).map(Either::Right).map_err(D::Error::custom)
+            }
+        }
+    }
+
+    fn insert_extra(value: &mut Value) {
+        if let Value::Object(object) = value {
+            object.entry("extra").or_insert_with(|| Value::Array(Vec::new()));
+            for key in ["extra", "with"] {
+                if let Some(Value::Array(components)) = object.get_mut(key) {
+                    components.iter_mut().for_each(insert_extra);
+                }
+            }
+        }
+    }
+}
+
+/// Maximum count of players in the sample which vanilla server sends.
+pub const STATUS_SAMPLE_LIMIT: usize = 12;
+
+impl<'a> StatusResponseObject<'a> {
+    pub fn new(
+        version: StatusResponseVersion<'a>,
+        max_players: i32,
+        online_players: i32,
+        description: either::Either<Cow<'a, str>, Component<'a>>,
+    ) -> Self {
+        Self {
+            version,
+            players: StatusResponsePlayers { max: max_players, online: online_players, sample: Cow::Borrowed(&[]) },
+            description,
+            favicon: None,
+            previews_chat: None,
+            enforces_secure_chat: None,
+            forge_data: None,
+        }
+    }
+
+    /// Sets players sample. Only first [STATUS_SAMPLE_LIMIT] players are kept.
+    pub fn with_sample(mut self, sample: impl Into<Cow<'a, [StatusResponseSample<'a>]>>) -> Self {
+        let sample = sample.into();
+        self.players.sample = match sample.len() > STATUS_SAMPLE_LIMIT {
+            true => match sample {
+                Cow::Borrowed(borrowed) => Cow::Borrowed(&borrowed[..STATUS_SAMPLE_LIMIT]),
+                Cow::Owned(mut owned) => {
+                    owned.truncate(STATUS_SAMPLE_LIMIT);
+                    Cow::Owned(owned)
+                }
+            },
+            false => sample,
+        };
+        self
+    }
+
+    /// Sets favicon which is already encoded as data url.
+    pub fn with_favicon(mut self, favicon: impl Into<Cow<'a, str>>) -> Self {
+        self.favicon = Some(favicon.into());
+        self
+    }
+
+    /// Sets favicon from raw png image bytes.
+    pub fn with_favicon_png(self, png: &[u8]) -> Self {
+        let mut favicon = String::from("data:image/png;base64,");
+        encode_base64(png, &mut favicon);
+        self.with_favicon(favicon)
+    }
+
+    pub fn with_previews_chat(mut self, previews_chat: bool) -> Self {
+        self.previews_chat = Some(previews_chat);
+        self
+    }
+
+    pub fn with_enforces_secure_chat(mut self, enforces_secure_chat: bool) -> Self {
+        self.enforces_secure_chat = Some(enforces_secure_chat);
+        self
+    }
+
+    pub fn with_forge_data(mut self, forge_data: serde_json::Value) -> Self {
+        self.forge_data = Some(forge_data);
+        self
+    }
+}
+
+impl<'a> PacketReadable<'a> for StatusResponseObject<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        <ProtocolJson>::read_variant(read)
+    }
+}
+
+impl PacketWritable for StatusResponseObject<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), anyhow::Error> where W: PacketWrite {
+        <ProtocolJson>::write_variant(self, write)
+    }
 }
 
 #[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
@@ -79,6 +211,20 @@ pub struct StatusPingRequest {
     pub payload: i64,
 }
 
+packet_state! {
+    pub enum ClientStatusPacket<'a>: Client, Status {
+        Response(StatusResponse<'a>),
+        PingResponse(StatusPingResponse),
+    }
+}
+
+packet_state! {
+    pub enum ServerStatusPacket: Server, Status {
+        Request(StatusRequest),
+        PingRequest(StatusPingRequest),
+    }
+}
+
 #[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
 #[packet(bound = Client, state = Login, id = 0x00)]
 pub struct LoginDisconnect<'a> {
@@ -87,9 +233,18 @@ pub struct LoginDisconnect<'a> {
 
 type LengthProvidedBytesSliceVI = LengthProvidedBytesSlice<VarInt, i32>;
 
+/// Max length of the player name.
+pub const PLAYER_NAME_LIMIT: i32 = 16;
+/// Max length of the server id in the encryption request.
+pub const SERVER_ID_LIMIT: i32 = 20;
+
+pub type PlayerName = LimitedString<PLAYER_NAME_LIMIT>;
+pub type ServerId = LimitedString<SERVER_ID_LIMIT>;
+
 #[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
 #[packet(bound = Client, state = Login, id = 0x01)]
 pub struct LoginEncryptionRequest<'a> {
+    #[variant(ServerId)]
     pub server_id: &'a str,
     #[variant(LengthProvidedBytesSliceVI)]
     pub public_key: &'a [u8],
@@ -114,6 +269,7 @@ type LoginSuccessPropertyArray<'a> = LengthProvidedSlice<
 #[packet(bound = Client, state = Login, id = 0x02)]
 pub struct LoginSuccess<'a> {
     pub uuid: Uuid,
+    #[variant(PlayerName)]
     pub name: &'a str,
     #[variant(LoginSuccessPropertyArray)]
     pub properties: Cow<'a, [LoginSuccessProperty<'a>]>,
@@ -136,8 +292,10 @@ pub struct LoginPluginRequest<'a> {
     pub data: &'a [u8],
 }
 
+/// Public key of the player profile, which is signed by Mojang. Chat messages of the player are signed by its private key.
 #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
-pub struct LoginStartSignatureData<'a> {
+pub struct ProfilePublicKey<'a> {
+    /// Expiration time in milliseconds since the epoch.
     pub timestamp: i64,
     #[variant(LengthProvidedBytesSliceVI)]
     pub public_key: &'a [u8],
@@ -148,21 +306,26 @@ pub struct LoginStartSignatureData<'a> {
 #[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
 #[packet(bound = Server, state = Login, id = 0x00)]
 pub struct LoginStart<'a> {
+    #[variant(PlayerName)]
     pub name: &'a str,
-    pub signature_data: Option<LoginStartSignatureData<'a>>,
+    #[protocol(since = ProtocolVersion::V1_19, until = ProtocolVersion::V1_19_2)]
+    pub signature_data: Option<ProfilePublicKey<'a>>,
 }
 
+/// Verify token or, if the client has a profile key, salt and signature of the verify token.
+///
+/// Written with a boolean which is true for the verify token.
 #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
 #[enum_type(u8)]
 pub enum LoginEncryptionResponseData<'a> {
     MessageSignature {
+        salt: i64,
         #[variant(LengthProvidedBytesSliceVI)]
-        message_signature: &'a [u8]
+        message_signature: &'a [u8],
     },
     VerifyToken {
         #[variant(LengthProvidedBytesSliceVI)]
         verify_token: &'a [u8],
-        salt: i64,
     },
 }
 
@@ -174,16 +337,355 @@ pub struct LoginEncryptionResponse<'a> {
     pub data: LoginEncryptionResponseData<'a>,
 }
 
-#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+/// Response to [LoginPluginRequest]. Data is absent if the client does not understand the request.
+///
+/// Data is written as a boolean and the remaining bytes, so it must be the last field.
+#[derive(Packet, Debug, Clone, PartialEq)]
 #[packet(bound = Server, state = Login, id = 0x02)]
 pub struct LoginPluginResponse<'a> {
-    #[variant(VarInt)]
     pub message_id: i32,
-    pub successful: bool,
-    #[variant(RemainingBytesSlice)]
-    pub data: &'a [u8],
+    pub data: Option<&'a [u8]>,
+}
+
+impl<'a> PacketReadable<'a> for LoginPluginResponse<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let message_id = VarInt::read_variant(read)?;
+        let data = match bool::read(read)? {
+            true => Some(RemainingBytesSlice::read_variant(read)?),
+            false => None,
+        };
+        Ok(Self { message_id, data })
+    }
+}
+
+impl PacketWritable for LoginPluginResponse<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), anyhow::Error> where W: PacketWrite {
+        VarInt::write_variant(&self.message_id, write)?;
+        self.data.is_some().write(write)?;
+        match self.data {
+            Some(data) => RemainingBytesSlice::write_variant(data, write),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
 }
 
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum Pose {
+    Standing,
+    FallFlying,
+    Sleeping,
+    Swimming,
+    SpinAttack,
+    Crouching,
+    LongJumping,
+    Dying,
+    Croaking,
+    UsingTongue,
+    Roaring,
+    Sniffing,
+    Emerging,
+    Digging,
+}
+
+// Signature is required by `skip_serializing_if`
+#[allow(clippy::ptr_arg)]
 fn is_cow_empty<T: Clone>(cow: &Cow<[T]>) -> bool {
     cow.is_empty()
+}
+
+pub(crate) fn encode_base64(bytes: &[u8], output: &mut String) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in bytes.chunks(3) {
+        let value = (chunk[0] as u32) << 16 |
+            (chunk.get(1).copied().unwrap_or(0) as u32) << 8 |
+            chunk.get(2).copied().unwrap_or(0) as u32;
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => output.push(ALPHABET[(value >> (18 - index * 6)) as usize & 0x3F] as char),
+                false => output.push('='),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enum_tests() {
+        let directions = [Direction::Down, Direction::Up, Direction::North, Direction::South, Direction::West, Direction::East];
+        let poses = [
+            Pose::Standing, Pose::FallFlying, Pose::Sleeping, Pose::Swimming, Pose::SpinAttack, Pose::Crouching,
+            Pose::LongJumping, Pose::Dying, Pose::Croaking, Pose::UsingTongue, Pose::Roaring, Pose::Sniffing,
+            Pose::Emerging, Pose::Digging,
+        ];
+        let mut write = Vec::new();
+        for direction in directions {
+            direction.write(&mut write).unwrap();
+        }
+        for pose in poses {
+            pose.write(&mut write).unwrap();
+        }
+        let expected: Vec<u8> = (0..6).chain(0..14).collect();
+        assert_eq!(write, expected);
+        let mut read = SlicePacketRead::new(write.as_slice());
+        for direction in directions {
+            assert_eq!(Direction::read(&mut read).unwrap(), direction);
+        }
+        for pose in poses {
+            assert_eq!(Pose::read(&mut read).unwrap(), pose);
+        }
+        assert_eq!(read.available(), 0);
+        let mut read = SlicePacketRead::new(&[6, 14, 0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert!(matches!(Direction::read(&mut read), Err(PacketReadableError::BadEnumValue)));
+        assert!(matches!(Pose::read(&mut read), Err(PacketReadableError::BadEnumValue)));
+        assert!(matches!(Direction::read(&mut read), Err(PacketReadableError::BadEnumValue)));
+        assert!(matches!(Pose::read(&mut read), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    fn login_round_trip<'a, T>(packet: &T, write: &'a mut Vec<u8>) -> T
+        where T: PacketReadable<'a> + PacketWritable + Packet + std::fmt::Debug + PartialEq {
+        PacketVariant::write_variant(packet, write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), T::id());
+        let result = T::read(&mut read).unwrap();
+        assert_eq!(read.available(), 0);
+        assert_eq!(&result, packet);
+        result
+    }
+
+    #[test]
+    fn handshake_version_tests() {
+        let mut handshake = HandshakePacket {
+            protocol_version: 759, server_address: "localhost", server_port: 25565, next_state: HandshakeNextState::Status,
+        };
+        assert_eq!(handshake.version(), Some(ProtocolVersion::V1_19));
+        assert!(handshake.version().unwrap().is_supported());
+        handshake.protocol_version = 758;
+        assert!(!handshake.version().unwrap().is_supported());
+        handshake.protocol_version = 1;
+        assert_eq!(handshake.version(), None);
+    }
+
+    #[test]
+    fn status_packet_tests() {
+        let mut write = Vec::new();
+        StatusRequest.write(&mut write).unwrap();
+        assert!(write.is_empty());
+        let mut read = SlicePacketRead::new(&[0x01]);
+        assert_eq!(StatusRequest::read(&mut read).unwrap(), StatusRequest);
+        assert_eq!(read.available(), 1);
+        ServerStatusPacket::Request(StatusRequest).write(&mut write).unwrap();
+        ServerStatusPacket::PingRequest(StatusPingRequest { payload: -1 }).write(&mut write).unwrap();
+        assert_eq!(write, &[0x00, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(ServerStatusPacket::read(&mut read).unwrap(), ServerStatusPacket::Request(StatusRequest));
+        assert_eq!(
+            ServerStatusPacket::read(&mut read).unwrap(),
+            ServerStatusPacket::PingRequest(StatusPingRequest { payload: -1 })
+        );
+        let status = StatusResponseObject::new(
+            StatusResponseVersion { name: "1.19".into(), protocol: 759 }, 20, 0, either::Either::Left("motd".into()),
+        );
+        let packets = [
+            ClientStatusPacket::Response(StatusResponse(status)),
+            ClientStatusPacket::PingResponse(StatusPingResponse { payload: 0x0102030405060708 }),
+        ];
+        let mut write = Vec::new();
+        for packet in &packets {
+            packet.write(&mut write).unwrap();
+        }
+        assert_eq!(&write[write.len() - 9..], &[0x01, 1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut read = SlicePacketRead::new(write.as_slice());
+        for packet in &packets {
+            assert_eq!(&ClientStatusPacket::read(&mut read).unwrap(), packet);
+        }
+        assert_eq!(read.available(), 0);
+        assert!(matches!(
+            ServerStatusPacket::read_by_id(0x02, &mut SlicePacketRead::new(&[])),
+            Err(PacketReadableError::BadPacketId(0x02))
+        ));
+    }
+
+    #[test]
+    fn login_tests() {
+        let mut write = Vec::new();
+        login_round_trip(&LoginStart { name: "Notch", signature_data: None }, &mut write);
+        assert_eq!(write, &[0x00, 0x05, b'N', b'o', b't', b'c', b'h', 0x00]);
+        let mut write = Vec::new();
+        login_round_trip(&LoginStart {
+            name: "Notch",
+            signature_data: Some(ProfilePublicKey { timestamp: 1, public_key: &[1, 2], signature: &[3] }),
+        }, &mut write);
+        assert_eq!(&write[7..], &[0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0x02, 1, 2, 0x01, 3]);
+        login_round_trip(&LoginEncryptionRequest { server_id: "", public_key: &[1, 2, 3], verify_token: &[4; 4] }, &mut Vec::new());
+        let mut write = Vec::new();
+        login_round_trip(&LoginEncryptionResponse {
+            shared_secret: &[5; 16],
+            data: LoginEncryptionResponseData::VerifyToken { verify_token: &[4; 4] },
+        }, &mut write);
+        assert_eq!(&write[18..], &[0x01, 0x04, 4, 4, 4, 4]);
+        let mut write = Vec::new();
+        login_round_trip(&LoginEncryptionResponse {
+            shared_secret: &[5; 16],
+            data: LoginEncryptionResponseData::MessageSignature { salt: 7, message_signature: &[8, 9] },
+        }, &mut write);
+        assert_eq!(&write[18..], &[0x00, 0, 0, 0, 0, 0, 0, 0, 7, 0x02, 8, 9]);
+        // Component is written without empty extra, which bird-chat requires on read
+        let mut write = Vec::new();
+        r#"{"text":"Bye","extra":[]}"#.write(&mut write).unwrap();
+        let disconnect = LoginDisconnect::read(&mut SlicePacketRead::new(write.as_slice())).unwrap();
+        assert!(matches!(&disconnect.reason, Component::Text(text) if text.text == "Bye"));
+        let mut write = Vec::new();
+        PacketVariant::write_variant(&disconnect, &mut write).unwrap();
+        assert_eq!(write[0], 0x00);
+        assert_eq!(<&str>::read(&mut SlicePacketRead::new(&write[1..])).unwrap(), r#"{"text":"Bye"}"#);
+        login_round_trip(&LoginSetCompression { threshold: 256 }, &mut Vec::new());
+        login_round_trip(&LoginPluginRequest {
+            message_id: 3,
+            channel: Identifier::new_fulled("velocity:player_info").unwrap(),
+            data: &[1, 2],
+        }, &mut Vec::new());
+        let mut write = Vec::new();
+        login_round_trip(&LoginPluginResponse { message_id: 3, data: Some(&[]) }, &mut write);
+        assert_eq!(write, &[0x02, 0x03, 0x01]);
+        let mut write = Vec::new();
+        login_round_trip(&LoginPluginResponse { message_id: 3, data: None }, &mut write);
+        assert_eq!(write, &[0x02, 0x03, 0x00]);
+        login_round_trip(&LoginPluginResponse { message_id: 4, data: Some(&[1, 2, 3]) }, &mut Vec::new());
+        assert!(LoginStart { name: "ThisNameIsTooLong", signature_data: None }.write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn versioned_login_start_tests() {
+        use crate::packet_version::{DEFAULT_VERSION, VersionedReadable, VersionedWritable};
+        let signed = LoginStart {
+            name: "Notch",
+            signature_data: Some(ProfilePublicKey { timestamp: 1, public_key: &[1, 2], signature: &[3] }),
+        };
+        let mut write_1_19 = Vec::new();
+        signed.write_versioned(ProtocolVersion::V1_19, &mut write_1_19).unwrap();
+        let mut write_default = Vec::new();
+        signed.write(&mut write_default).unwrap();
+        assert_eq!(DEFAULT_VERSION, ProtocolVersion::V1_19);
+        assert_eq!(write_default, write_1_19);
+        // 1.18.2 has no signature, so it is skipped
+        let mut write_1_18_2 = Vec::new();
+        signed.write_versioned(ProtocolVersion::V1_18_2, &mut write_1_18_2).unwrap();
+        assert_eq!(write_1_18_2, &[0x05, b'N', b'o', b't', b'c', b'h']);
+        let unsigned = LoginStart { name: "Notch", signature_data: None };
+        let read = LoginStart::read_versioned(ProtocolVersion::V1_18_2, &mut SlicePacketRead::new(&write_1_18_2)).unwrap();
+        assert_eq!(read, unsigned);
+        // Same logical packet from bytes of both versions
+        let mut read = SlicePacketRead::new(&write_1_19);
+        assert_eq!(LoginStart::read_versioned(ProtocolVersion::V1_19_2, &mut read).unwrap(), signed);
+        assert_eq!(read.available(), 0);
+        let mut read = SlicePacketRead::new(&write_1_19);
+        assert_eq!(LoginStart::read_versioned(ProtocolVersion::V1_18_2, &mut read).unwrap(), unsigned);
+        assert_eq!(read.available(), write_1_19.len() - write_1_18_2.len());
+        // Signature is removed in 1.19.3 again
+        let mut write = Vec::new();
+        signed.write_versioned(ProtocolVersion::V1_19_3, &mut write).unwrap();
+        assert_eq!(write, write_1_18_2);
+        // Fields which are read by the default version are traced
+        let error = LoginStart::read_with_path(&mut SlicePacketRead::new(&[0x05, b'N', b'o', b't', b'c', b'h', 0x01])).unwrap_err();
+        assert_eq!((error.field_path.as_deref(), error.offset), (Some("signature_data"), 6));
+        assert_eq!(LoginStart::read_with_path(&mut SlicePacketRead::new(&write_1_19)).unwrap(), signed);
+    }
+
+    #[test]
+    fn login_success_tests() {
+        let uuid = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let mut write = Vec::new();
+        login_round_trip(&LoginSuccess { uuid, name: "Notch", properties: Cow::Borrowed(&[]) }, &mut write);
+        assert_eq!(write, &[
+            0x02, 0x06, 0x9A, 0x79, 0xF4, 0x44, 0xE9, 0x47, 0x26, 0xA5, 0xBE, 0xFC, 0xA9, 0x0E, 0x38, 0xAA, 0xF5,
+            0x05, b'N', b'o', b't', b'c', b'h', 0x00,
+        ]);
+        let properties = [LoginSuccessProperty { name: "textures", value: "e30=", signature: None }];
+        login_round_trip(&LoginSuccess { uuid, name: "Notch", properties: Cow::Borrowed(&properties) }, &mut Vec::new());
+    }
+
+    #[test]
+    fn status_response_tests() {
+        let json = r#"{"version":{"name":"1.19","protocol":759},"players":{"max":100,"online":5,"sample":[{"name":"thinkofdeath","id":"4566e69f-c907-48ee-8d71-d7ba5aa00d20"}]},"description":"Hello world","favicon":"data:image/png;base64,iVBORw==","previewsChat":false}"#;
+        let status: StatusResponseObject = serde_json::from_str(json).unwrap();
+        let built = StatusResponseObject::new(
+            StatusResponseVersion { name: "1.19".into(), protocol: 759 },
+            100, 5,
+            either::Either::Left("Hello world".into()),
+        )
+            .with_sample(vec![StatusResponseSample {
+                name: "thinkofdeath".into(),
+                id: Uuid::parse_str("4566e69f-c907-48ee-8d71-d7ba5aa00d20").unwrap(),
+            }])
+            .with_favicon_png(&[0x89, b'P', b'N', b'G'])
+            .with_previews_chat(false);
+        assert_eq!(status, built);
+        assert_eq!(
+            serde_json::to_value(&built).unwrap(),
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+        let mut write = Vec::new();
+        built.write(&mut write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(StatusResponseObject::read(&mut read).unwrap(), built);
+    }
+
+    #[test]
+    fn status_response_escapes() {
+        // Reply of the vanilla 1.19 server: motd is a text component and gson escapes the apostrophe
+        let json = r#"{"description":{"text":"Bird\u0027s server\n\u00a7aonline"},"players":{"max":20,"online":1,"sample":[{"id":"4566e69f-c907-48ee-8d71-d7ba5aa00d20","name":"\"quoted\""}]},"version":{"name":"1.19","protocol":759},"previewsChat":false}"#;
+        let mut write = Vec::new();
+        json.write(&mut write).unwrap();
+        let status = StatusResponseObject::read(&mut SlicePacketRead::new(write.as_slice())).unwrap();
+        match &status.description {
+            either::Either::Right(Component::Text(text)) => assert_eq!(text.text, "Bird's server\n\u{a7}aonline"),
+            description => panic!("Unexpected description {:?}", description),
+        }
+        assert_eq!(status.players.sample[0].name, "\"quoted\"");
+        assert_eq!(status.version.name, "1.19");
+        let legacy = r#"{"version":{"name":"Bird\u00a71.19","protocol":759},"players":{"max":20,"online":0},"description":"\u00a7cline\nline"}"#;
+        let status: StatusResponseObject = serde_json::from_str(legacy).unwrap();
+        assert_eq!(status.description, either::Either::Left("\u{a7}cline\nline".into()));
+        assert_eq!(status.version.name, "Bird\u{a7}1.19");
+    }
+
+    #[test]
+    fn status_response_optional_fields() {
+        let status = StatusResponseObject::new(
+            StatusResponseVersion { name: "1.19".into(), protocol: 759 }, 20, 0, either::Either::Left("motd".into()),
+        ).with_sample(vec![StatusResponseSample { name: "bird".into(), id: Uuid::nil() }; 20]);
+        assert_eq!(status.players.sample.len(), STATUS_SAMPLE_LIMIT);
+        let json = serde_json::to_value(status.clone().with_sample(vec![])).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "version": {"name": "1.19", "protocol": 759},
+            "players": {"max": 20, "online": 0},
+            "description": "motd",
+        }));
+        let forge = serde_json::json!({"channels": [], "mods": [], "fmlNetworkVersion": 3});
+        let json = serde_json::to_value(status.with_forge_data(forge.clone()).with_enforces_secure_chat(true)).unwrap();
+        assert_eq!(json["forgeData"], forge);
+        assert_eq!(json["enforcesSecureChat"], true);
+        let mut base64 = String::new();
+        encode_base64(b"bird", &mut base64);
+        assert_eq!(base64, "YmlyZA==");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,285 @@
+use aes::Aes128;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::cipher::generic_array::GenericArray;
+use anyhow::Error;
+use rand::RngCore;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+use rsa::pkcs8::EncodePublicKey;
+use crate::packet::PacketWrite;
+
+/// Length of the shared secret which is used as both key and iv of the cipher.
+pub const SHARED_SECRET_LENGTH: usize = 16;
+/// Bit size of the server key which vanilla generates.
+pub const SERVER_KEY_BITS: usize = 1024;
+/// Length of the verify token which vanilla generates.
+pub const VERIFY_TOKEN_LENGTH: usize = 4;
+
+/// AES/CFB8 encryptor of the outgoing byte stream.
+///
+/// Each byte is a separate cipher block, so the stream can be encrypted in chunks of any size.
+pub struct PacketEncryptor(cfb8::Encryptor<Aes128>);
+
+/// AES/CFB8 decryptor of the incoming byte stream.
+///
+/// Readers borrow bytes, so incoming bytes should be decrypted in place before they are read.
+pub struct PacketDecryptor(cfb8::Decryptor<Aes128>);
+
+fn check_shared_secret(shared_secret: &[u8]) -> Result<(), Error> {
+    match shared_secret.len() == SHARED_SECRET_LENGTH {
+        true => Ok(()),
+        false => Err(Error::msg("Bad shared secret length")),
+    }
+}
+
+impl PacketEncryptor {
+    pub fn new(shared_secret: &[u8]) -> Result<Self, Error> {
+        check_shared_secret(shared_secret)?;
+        Ok(Self(cfb8::Encryptor::new_from_slices(shared_secret, shared_secret)?))
+    }
+
+    pub fn encrypt(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            self.0.encrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+        }
+    }
+}
+
+impl PacketDecryptor {
+    pub fn new(shared_secret: &[u8]) -> Result<Self, Error> {
+        check_shared_secret(shared_secret)?;
+        Ok(Self(cfb8::Decryptor::new_from_slices(shared_secret, shared_secret)?))
+    }
+
+    pub fn decrypt(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            self.0.decrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(byte)));
+        }
+    }
+}
+
+/// Count of bytes which are encrypted at once by [EncryptedPacketWrite].
+const ENCRYPTION_CHUNK: usize = 4096;
+
+/// Writer which encrypts all bytes before passing them to the inner writer.
+///
+/// Borrowed bytes are copied to the reused buffer by chunks and are encrypted there.
+pub struct EncryptedPacketWrite<W: PacketWrite> {
+    write: W,
+    encryptor: PacketEncryptor,
+    buffer: Vec<u8>,
+}
+
+impl<W: PacketWrite> EncryptedPacketWrite<W> {
+    pub fn new(write: W, shared_secret: &[u8]) -> Result<Self, Error> {
+        Ok(Self { write, encryptor: PacketEncryptor::new(shared_secret)?, buffer: Vec::new() })
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.write
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.write
+    }
+
+    pub fn into_inner(self) -> W {
+        self.write
+    }
+}
+
+impl<W: PacketWrite> PacketWrite for EncryptedPacketWrite<W> {
+    fn write_byte(&mut self, mut byte: u8) -> Result<(), Error> {
+        self.encryptor.encrypt(std::slice::from_mut(&mut byte));
+        self.write.write_byte(byte)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        for chunk in bytes.chunks(ENCRYPTION_CHUNK) {
+            self.buffer.clear();
+            self.buffer.extend_from_slice(chunk);
+            self.encryptor.encrypt(&mut self.buffer);
+            self.write.write_bytes(&self.buffer)?;
+        }
+        Ok(())
+    }
+
+    fn write_bytes_owned(&mut self, mut bytes: Vec<u8>) -> Result<(), Error> {
+        self.encryptor.encrypt(&mut bytes);
+        self.write.write_bytes_owned(bytes)
+    }
+
+    fn write_bytes_fixed<const SIZE: usize>(&mut self, mut bytes: [u8; SIZE]) -> Result<(), Error> {
+        self.encryptor.encrypt(&mut bytes);
+        self.write.write_bytes_fixed(bytes)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.write.reserve(additional)
+    }
+
+    /// Small slices are encrypted together, so the inner writer gets bytes by chunks.
+    fn write_slices<'b, I>(&mut self, slices: I) -> Result<(), Error> where I: IntoIterator<Item=&'b [u8]> {
+        self.buffer.clear();
+        for slice in slices {
+            if self.buffer.len() + slice.len() > ENCRYPTION_CHUNK {
+                self.encryptor.encrypt(&mut self.buffer);
+                self.write.write_bytes(&self.buffer)?;
+                self.buffer.clear();
+            }
+            match slice.len() > ENCRYPTION_CHUNK {
+                true => {
+                    self.write_bytes(slice)?;
+                    self.buffer.clear();
+                }
+                false => self.buffer.extend_from_slice(slice),
+            }
+        }
+        self.encryptor.encrypt(&mut self.buffer);
+        self.write.write_bytes(&self.buffer)
+    }
+}
+
+/// Rsa key pair of the server which is used for the encryption handshake.
+#[derive(Debug, Clone)]
+pub struct ServerKey {
+    private_key: RsaPrivateKey,
+    public_key_der: Vec<u8>,
+}
+
+impl ServerKey {
+    pub fn generate() -> Result<Self, Error> {
+        Self::from_private_key(RsaPrivateKey::new(&mut rand::thread_rng(), SERVER_KEY_BITS)?)
+    }
+
+    pub fn from_private_key(private_key: RsaPrivateKey) -> Result<Self, Error> {
+        let public_key_der = private_key.to_public_key().to_public_key_der()?.into_vec();
+        Ok(Self { private_key, public_key_der })
+    }
+
+    /// Public key in the X.509 DER format which is sent in the encryption request.
+    pub fn public_key_der(&self) -> &[u8] {
+        &self.public_key_der
+    }
+
+    pub fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(self.private_key.decrypt(Pkcs1v15Encrypt, bytes)?)
+    }
+
+    /// Decrypts shared secret and verify token of the encryption response.
+    ///
+    /// Returns shared secret if the verify token is the same as the sent one.
+    pub fn verify_response(
+        &self, verify_token: &[u8], shared_secret: &[u8], encrypted_verify_token: &[u8],
+    ) -> Result<[u8; SHARED_SECRET_LENGTH], Error> {
+        match self.decrypt(encrypted_verify_token)? == verify_token {
+            true => self.decrypt(shared_secret)?.as_slice().try_into()
+                .map_err(|_| Error::msg("Bad shared secret length")),
+            false => Err(Error::msg("Verify token does not match")),
+        }
+    }
+
+    #[cfg(feature = "packet_default")]
+    pub fn encryption_request<'a>(
+        &'a self, server_id: &'a str, verify_token: &'a [u8],
+    ) -> crate::packet_default::LoginEncryptionRequest<'a> {
+        crate::packet_default::LoginEncryptionRequest {
+            server_id,
+            public_key: self.public_key_der(),
+            verify_token,
+        }
+    }
+}
+
+pub fn generate_verify_token() -> [u8; VERIFY_TOKEN_LENGTH] {
+    let mut verify_token = [0; VERIFY_TOKEN_LENGTH];
+    rand::thread_rng().fill_bytes(&mut verify_token);
+    verify_token
+}
+
+pub fn generate_shared_secret() -> [u8; SHARED_SECRET_LENGTH] {
+    let mut shared_secret = [0; SHARED_SECRET_LENGTH];
+    rand::thread_rng().fill_bytes(&mut shared_secret);
+    shared_secret
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::RsaPublicKey;
+    use rsa::pkcs8::DecodePublicKey;
+    use crate::packet::{PacketRead, PacketVariantReadable, PacketVariantWritable, SlicePacketRead};
+    use crate::packet_types::VarInt;
+    use super::*;
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+    ];
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+    const PLAIN: [u8; 18] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a, 0xae, 0x2d,
+    ];
+    const CIPHER: [u8; 18] = [
+        0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a, 0x4f, 0x32, 0xb9,
+    ];
+
+    #[test]
+    fn cfb8_vector_tests() {
+        let mut encryptor = PacketEncryptor(cfb8::Encryptor::new_from_slices(&KEY, &IV).unwrap());
+        let mut bytes = PLAIN;
+        encryptor.encrypt(&mut bytes[..5]);
+        encryptor.encrypt(&mut bytes[5..6]);
+        encryptor.encrypt(&mut bytes[6..]);
+        assert_eq!(bytes, CIPHER);
+        let mut decryptor = PacketDecryptor(cfb8::Decryptor::new_from_slices(&KEY, &IV).unwrap());
+        decryptor.decrypt(&mut bytes[..1]);
+        decryptor.decrypt(&mut bytes[1..]);
+        assert_eq!(bytes, PLAIN);
+    }
+
+    #[test]
+    fn encrypted_write_tests() {
+        let shared_secret = generate_shared_secret();
+        let mut write = EncryptedPacketWrite::new(Vec::new(), &shared_secret).unwrap();
+        VarInt::write_variant(&25565, &mut write).unwrap();
+        <&str>::write_variant(&"encrypted", &mut write).unwrap();
+        write.write_bytes_fixed([1, 2, 3]).unwrap();
+        let mut bytes = write.into_inner();
+        let mut decryptor = PacketDecryptor::new(&shared_secret).unwrap();
+        for chunk in bytes.chunks_mut(3) {
+            decryptor.decrypt(chunk);
+        }
+        let mut read = SlicePacketRead::new(&bytes);
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), 25565);
+        assert_eq!(<&str>::read_variant(&mut read).unwrap(), "encrypted");
+        assert_eq!(read.take_slice(3).unwrap(), &[1, 2, 3]);
+        assert!(PacketEncryptor::new(&[0; 15]).is_err());
+        let body: Vec<u8> = (0..10000u32).map(|index| index as u8).collect();
+        let mut write = EncryptedPacketWrite::new(Vec::new(), &shared_secret).unwrap();
+        write.write_bytes(&body).unwrap();
+        write.write_slices([&body[..10], &body[10..5000], &[], &body[5000..]]).unwrap();
+        let mut bytes = write.into_inner();
+        let mut decryptor = PacketDecryptor::new(&shared_secret).unwrap();
+        decryptor.decrypt(&mut bytes);
+        assert_eq!(&bytes[..10000], body.as_slice());
+        assert_eq!(&bytes[10000..], body.as_slice());
+    }
+
+    #[test]
+    fn server_key_tests() {
+        let server_key = ServerKey::generate().unwrap();
+        let public_key = RsaPublicKey::from_public_key_der(server_key.public_key_der()).unwrap();
+        let verify_token = generate_verify_token();
+        let shared_secret = generate_shared_secret();
+        let mut rng = rand::thread_rng();
+        let encrypted_secret = public_key.encrypt(&mut rng, Pkcs1v15Encrypt, &shared_secret).unwrap();
+        let encrypted_token = public_key.encrypt(&mut rng, Pkcs1v15Encrypt, &verify_token).unwrap();
+        assert_eq!(
+            server_key.verify_response(&verify_token, &encrypted_secret, &encrypted_token).unwrap(),
+            shared_secret
+        );
+        assert!(server_key.verify_response(&[0; 4], &encrypted_secret, &encrypted_token).is_err());
+        let encrypted_short = public_key.encrypt(&mut rng, Pkcs1v15Encrypt, &[0; 8]).unwrap();
+        assert!(server_key.verify_response(&verify_token, &encrypted_short, &encrypted_token).is_err());
+    }
+}
@@ -1,7 +1,8 @@
 use anyhow::Error;
 use euclid::default::Vector3D;
 use crate::packet::{PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite};
-use crate::packet_types::{Angle, BlockPosition};
+use crate::packet::PacketWritableError;
+use crate::packet_types::{Angle, BlockPosition, PositionDelta, Velocity};
 
 impl<'a> PacketReadable<'a> for euclid::Angle<f32> {
     fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
@@ -66,4 +67,63 @@ macro_rules! angle_variant_impl {
     }
 }
 
-angle_variant_impl!(f32, f64);
\ No newline at end of file
+angle_variant_impl!(f32, f64);
+impl PositionDelta {
+    /// Returns delta of the movement from `prev` to `current`, which must be shorter than 8 blocks on each axis.
+    ///
+    /// Difference is rounded, not the positions, so [PositionDelta::apply] is closer than 1/4096 of a block to `current`.
+    pub fn from_positions(prev: Vector3D<f64>, current: Vector3D<f64>) -> Result<Self, PacketWritableError> {
+        let delta = current - prev;
+        Self::from_blocks(delta.x, delta.y, delta.z)
+    }
+
+    /// Returns position after the movement from `prev`.
+    pub fn apply(&self, prev: Vector3D<f64>) -> Vector3D<f64> {
+        prev + Vector3D::from(self.to_blocks())
+    }
+}
+
+impl Velocity {
+    /// Converts velocity in blocks per tick, which is clamped on each axis to [Velocity::MAX_BLOCKS_PER_TICK].
+    pub fn from_blocks_per_tick(velocity: Vector3D<f64>) -> Self {
+        Self::from_blocks(velocity.x, velocity.y, velocity.z)
+    }
+
+    pub fn to_blocks_per_tick(&self) -> Vector3D<f64> {
+        Vector3D::from(self.to_blocks())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_delta_tests() {
+        let prev = euclid::vec3(100.5, 64.0, -3000.25);
+        for delta in [
+            (0.0, 0.0, 0.0), (7.999, -7.999, 0.001), (-8.0, 7.9997, -0.00012), (1.0 / 3.0, -2.0 / 7.0, 5.123456),
+        ] {
+            let current = prev + Vector3D::from(delta);
+            let position_delta = PositionDelta::from_positions(prev, current).unwrap();
+            assert!((position_delta.apply(prev) - current).to_array().iter().all(|error| error.abs() < 1.0 / 4096.0));
+        }
+        let delta = PositionDelta::from_positions(prev, prev + euclid::vec3(7.999, -7.999, 0.0)).unwrap();
+        assert_eq!(delta, PositionDelta { x: 32764, y: -32764, z: 0 });
+        assert!(PositionDelta::from_positions(prev, prev + euclid::vec3(8.0, 0.0, 0.0)).is_err());
+        assert!(PositionDelta::from_positions(prev, prev + euclid::vec3(0.0, -8.001, 0.0)).is_err());
+        assert!(PositionDelta::from_positions(prev, euclid::vec3(f64::NAN, 0.0, 0.0)).is_err());
+        assert!(PositionDelta::from_positions(prev, prev + euclid::vec3(0.0, 0.0, 1e20)).is_err());
+    }
+
+    #[test]
+    fn velocity_tests() {
+        for velocity in [euclid::vec3(0.0, -0.0784, 0.0), euclid::vec3(1.0 / 3.0, -3.9, 2.5e-5)] {
+            let error = Velocity::from_blocks_per_tick(velocity).to_blocks_per_tick() - velocity;
+            assert!(error.to_array().iter().all(|error| error.abs() <= 0.5 / Velocity::SCALE));
+        }
+        let velocity = Velocity::from_blocks_per_tick(euclid::vec3(100.0, -4.0, f64::NEG_INFINITY));
+        assert_eq!(velocity, Velocity { x: 31200, y: -31200, z: -31200 });
+        assert_eq!(velocity.to_blocks_per_tick(), euclid::vec3(3.9, -3.9, -3.9));
+    }
+}
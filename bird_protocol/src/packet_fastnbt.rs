@@ -1,12 +1,24 @@
 use std::marker::PhantomData;
 use anyhow::Error;
 use serde::Deserialize;
-use crate::packet::{PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWrite};
-use crate::packet_types::{ProtocolNbt, RemainingBytesSlice};
+use crate::packet::{PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite};
+use crate::packet_types::{ProtocolNbt, ProtocolOptionalNbt, RemainingBytesSlice, VarInt};
 
-impl<'a, T: serde::Serialize> PacketVariantWritable<T> for ProtocolNbt {
+/// Item stack of the inventory slot.
+///
+/// Empty slot is represented as `Option::<Slot>::None`, so the "present" boolean is handled by [Option].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slot {
+    pub item_id: i32,
+    pub count: i8,
+    pub nbt: Option<fastnbt::Value>,
+}
+
+impl<T: serde::Serialize> PacketVariantWritable<T> for ProtocolNbt {
     fn write_variant<W>(object: &T, write: &mut W) -> Result<(), anyhow::Error> where W: PacketWrite {
-        RemainingBytesSlice::write_variant(&fastnbt::to_bytes(object)?, write)
+        let bytes = fastnbt::to_bytes(object)?;
+        write.reserve(bytes.len());
+        RemainingBytesSlice::write_variant(&bytes, write)
     }
 }
 
@@ -19,7 +31,6 @@ struct MemorizePacketRead<'b, 'a, R: PacketRead<'a>> {
 impl<'b, 'a, R: PacketRead<'a>> MemorizePacketRead<'b, 'a, R> {
     fn skip_bytes(&mut self, size: usize) -> Result<(), anyhow::Error> {
         self.take_slice(size)?;
-        self.length += size;
         Ok(())
     }
 }
@@ -51,27 +62,51 @@ impl<'b, 'a, R: PacketRead<'a>> PacketRead<'a> for MemorizePacketRead<'b, 'a, R>
     }
 }
 
+/// Max depth of nested lists and compounds, the same as vanilla has.
+pub const MAX_NBT_DEPTH: usize = 512;
+
+/// Returns length of the nbt at the start of the input. Input is rolled back to the start, even if the nbt is bad.
 fn read_nbt_length<'a, R>(input: &mut R) -> Result<usize, PacketReadableError> where R: PacketRead<'a> {
     let mut memorize = MemorizePacketRead { input, length: 0, a_ph: PhantomData };
-    let tag = u8::read(&mut memorize)?;
+    let result = skip_nbt(&mut memorize);
+    memorize.input.rollback(memorize.length)?;
+    result.map(|_| memorize.length)
+}
+
+fn skip_nbt<'a, R: PacketRead<'a>>(input: &mut MemorizePacketRead<'_, 'a, R>) -> Result<(), PacketReadableError> {
+    let tag = u8::read(input)?;
     if tag != 0 {
-        let length = u16::read(&mut memorize)?;
-        memorize.skip_bytes(length as usize)?;
-        skip_tag(tag, 1, &mut memorize)?
+        let length = u16::read(input)?;
+        input.skip_bytes(length as usize)?;
+        skip_tag(tag, 1, 0, input)?
     }
-    memorize.input.rollback(memorize.length)?;
-    Ok(memorize.length)
+    Ok(())
 }
 
 fn skip_tag<'a, R: PacketRead<'a>>(
-    tag: u8, count: usize, input: &mut MemorizePacketRead<'_, 'a, R>,
+    tag: u8, count: usize, depth: usize, input: &mut MemorizePacketRead<'_, 'a, R>,
 ) -> Result<(), PacketReadableError> {
-    Ok(match tag {
+    match tag {
         0 => (),
         1 => input.skip_bytes(count)?,
         2 => input.skip_bytes(count * 2)?,
         3 | 5 => input.skip_bytes(count * 4)?,
         4 | 6 => input.skip_bytes(count * 8)?,
+        7..=12 => for _ in 0..count {
+            skip_sized_tag(tag, depth, input)?
+        },
+        _ => return Err(PacketReadableError::BadNbt(Error::msg("Bad nbt tag value")))
+    }
+    Ok(())
+}
+
+fn skip_sized_tag<'a, R: PacketRead<'a>>(
+    tag: u8, depth: usize, input: &mut MemorizePacketRead<'_, 'a, R>,
+) -> Result<(), PacketReadableError> {
+    if matches!(tag, 9 | 10) && depth >= MAX_NBT_DEPTH {
+        return Err(PacketReadableError::BadNbt(Error::msg("Too deep nbt")));
+    }
+    match tag {
         7 => {
             let length = i32::read(input)?;
             if length <= 0 { return Ok(()); }
@@ -85,14 +120,14 @@ fn skip_tag<'a, R: PacketRead<'a>>(
             let tag = u8::read(input)?;
             let size = i32::read(input)?;
             if size <= 0 { return Ok(()); }
-            skip_tag(tag, size as usize, input)?
+            skip_tag(tag, size as usize, depth + 1, input)?
         }
         10 => loop {
             let tag = u8::read(input)?;
-            if tag == 0 { break (); }
+            if tag == 0 { break; }
             let name_length = u16::read(input)?;
             input.skip_bytes(name_length as usize)?;
-            skip_tag(tag, 1, input)?
+            skip_tag(tag, 1, depth + 1, input)?
         }
         11 => {
             let length = i32::read(input)?;
@@ -104,14 +139,167 @@ fn skip_tag<'a, R: PacketRead<'a>>(
             if length <= 0 { return Ok(()); }
             input.skip_bytes(length as usize * 8)?
         }
-        _ => Err(anyhow::Error::msg("Bad nbt tag value"))?
-    })
+        _ => return Err(PacketReadableError::BadNbt(Error::msg("Bad nbt tag value")))
+    }
+    Ok(())
 }
 
 impl<'a, T: Deserialize<'a>> PacketVariantReadable<'a, T> for ProtocolNbt {
     fn read_variant<R>(read: &mut R) -> Result<T, PacketReadableError> where R: PacketRead<'a> {
         let length = read_nbt_length(read)?;
         fastnbt::from_bytes(read.take_slice(length)?)
-            .map_err(|err| PacketReadableError::Any(err.into()))
+            .map_err(|err| PacketReadableError::BadNbt(err.into()))
+    }
+}
+
+impl<'a, T: Deserialize<'a>> PacketVariantReadable<'a, Option<T>> for ProtocolOptionalNbt {
+    fn read_variant<R>(read: &mut R) -> Result<Option<T>, PacketReadableError> where R: PacketRead<'a> {
+        match u8::read(read)? {
+            0 => Ok(None),
+            _ => {
+                read.rollback(1)?;
+                ProtocolNbt::read_variant(read).map(Some)
+            }
+        }
+    }
+}
+
+impl<T: serde::Serialize> PacketVariantWritable<Option<T>> for ProtocolOptionalNbt {
+    fn write_variant<W>(object: &Option<T>, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        match object {
+            Some(ref value) => ProtocolNbt::write_variant(value, write),
+            None => 0u8.write(write),
+        }
+    }
+}
+
+impl<'a> PacketReadable<'a> for fastnbt::Value {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        ProtocolNbt::read_variant(read)
+    }
+}
+
+impl PacketWritable for fastnbt::Value {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        ProtocolNbt::write_variant(self, write)
+    }
+}
+
+impl<'a> PacketReadable<'a> for Slot {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let item_id = VarInt::read_variant(read)?;
+        let count = i8::read(read)?;
+        let nbt = ProtocolOptionalNbt::read_variant(read)?;
+        Ok(Slot { item_id, count, nbt })
+    }
+}
+
+impl PacketWritable for Slot {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        VarInt::write_variant(&self.item_id, write)?;
+        self.count.write(write)?;
+        ProtocolOptionalNbt::write_variant(&self.nbt, write)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use fastnbt::Value;
+    use crate::packet::SlicePacketRead;
+    use super::*;
+
+    fn slot_round_trip(slot: Option<Slot>) -> Vec<u8> {
+        let mut write = Vec::new();
+        slot.write(&mut write).unwrap();
+        0x55u8.write(&mut write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(Option::<Slot>::read(&mut read).unwrap(), slot);
+        assert_eq!(u8::read(&mut read).unwrap(), 0x55);
+        write.pop();
+        write
+    }
+
+    #[test]
+    fn slot_tests() {
+        assert_eq!(slot_round_trip(None), &[0x00]);
+        assert_eq!(
+            slot_round_trip(Some(Slot { item_id: 200, count: 64, nbt: None })),
+            &[0x01, 0xC8, 0x01, 0x40, 0x00]
+        );
+        let enchantment = Value::Compound(HashMap::from([
+            ("id".to_string(), Value::String("minecraft:sharpness".to_string())),
+            ("lvl".to_string(), Value::Short(5)),
+        ]));
+        let nbt = Value::Compound(HashMap::from([
+            ("Enchantments".to_string(), Value::List(vec![enchantment])),
+        ]));
+        let bytes = slot_round_trip(Some(Slot { item_id: 803, count: 1, nbt: Some(nbt) }));
+        assert_eq!(&bytes[..5], &[0x01, 0xA3, 0x06, 0x01, 0x0A]);
+    }
+
+    #[test]
+    fn nbt_tests() {
+        let compound = Value::Compound(HashMap::from([
+            ("name".to_string(), Value::String("bird".to_string())),
+            ("tags".to_string(), Value::List(vec![
+                Value::String("first".to_string()),
+                Value::String("second".to_string()),
+            ])),
+            ("sections".to_string(), Value::List(vec![
+                Value::Compound(HashMap::from([("y".to_string(), Value::Byte(-4))])),
+                Value::Compound(HashMap::from([("y".to_string(), Value::Byte(3))])),
+            ])),
+            ("heights".to_string(), Value::LongArray(fastnbt::LongArray::new(vec![1, 2, 3]))),
+        ]));
+        {
+            let mut write = Vec::new();
+            compound.write(&mut write).unwrap();
+            0x12345678u32.write(&mut write).unwrap();
+            "after nbt".write(&mut write).unwrap();
+            ProtocolOptionalNbt::write_variant(&Option::<Value>::None, &mut write).unwrap();
+            let mut read = SlicePacketRead::new(write.as_slice());
+            assert_eq!(Value::read(&mut read).unwrap(), compound);
+            assert_eq!(u32::read(&mut read).unwrap(), 0x12345678);
+            assert_eq!(<&str>::read(&mut read).unwrap(), "after nbt");
+            assert_eq!(
+                <ProtocolOptionalNbt as PacketVariantReadable<Option<Value>>>::read_variant(&mut read).unwrap(),
+                None
+            );
+            assert_eq!(read.available(), 0);
+        }
+        {
+            let mut write = Vec::new();
+            compound.write(&mut write).unwrap();
+            assert_eq!(write.capacity(), write.len());
+        }
+        {
+            let mut read = SlicePacketRead::new(&[0x0A, 0x00, 0x00, 0x0D, 0x00, 0x00, 0x00]);
+            assert!(matches!(Value::read(&mut read).unwrap_err(), PacketReadableError::BadNbt(_)));
+            assert_eq!(read.available(), 7);
+            // Truncated string is not skipped partly
+            let mut read = SlicePacketRead::new(&[0x0A, 0x00, 0x00, 0x08, 0x00, 0x01, 0x61, 0x00, 0x05, 0x62]);
+            assert!(read_nbt_length(&mut read).is_err());
+            assert_eq!(read.available(), 10);
+        }
+        {
+            // Lists of lists, each of them has one element
+            let nested = |depth: usize| {
+                let mut bytes = vec![0x09, 0x00, 0x00];
+                for _ in 0..depth {
+                    bytes.extend_from_slice(&[0x09, 0x00, 0x00, 0x00, 0x01]);
+                }
+                bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00]);
+                bytes
+            };
+            let bytes = nested(MAX_NBT_DEPTH - 1);
+            assert_eq!(read_nbt_length(&mut SlicePacketRead::new(&bytes)).unwrap(), bytes.len());
+            let bytes = nested(MAX_NBT_DEPTH);
+            let mut read = SlicePacketRead::new(&bytes);
+            assert!(matches!(read_nbt_length(&mut read).unwrap_err(), PacketReadableError::BadNbt(_)));
+            let bytes = nested(100_000);
+            let mut read = SlicePacketRead::new(&bytes);
+            assert!(matches!(Value::read(&mut read).unwrap_err(), PacketReadableError::BadNbt(_)));
+        }
+    }
+}
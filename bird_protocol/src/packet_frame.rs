@@ -0,0 +1,62 @@
+use anyhow::Error;
+use crate::packet::{Packet, PacketRead, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite, SlicePacketRead};
+use crate::packet_types::{PacketVariant, VarInt};
+
+/// Max length of the frame which vanilla accepts: 3 bytes of [VarInt].
+pub const DEFAULT_MAX_FRAME_LENGTH: usize = 2097151;
+
+/// Writes objects as full frames: [VarInt] length of the body and then the body itself.
+///
+/// The body is serialized into an internal buffer first, which is reused between frames.
+#[derive(Debug, Default)]
+pub struct PacketFramer {
+    buffer: Vec<u8>,
+}
+
+impl PacketFramer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_frame<T, W>(&mut self, object: &T, write: &mut W) -> Result<(), Error>
+        where T: PacketWritable, W: PacketWrite {
+        self.write_frame_variant::<T, T, W>(object, write)
+    }
+
+    /// Writes packet with its [VarInt] id as the frame body.
+    pub fn write_packet<T, W>(&mut self, packet: &T, write: &mut W) -> Result<(), Error>
+        where T: PacketWritable + Packet, W: PacketWrite {
+        self.write_frame_variant::<PacketVariant, T, W>(packet, write)
+    }
+
+    pub fn write_frame_variant<V, T, W>(&mut self, object: &T, write: &mut W) -> Result<(), Error>
+        where V: PacketVariantWritable<T>, T: ?Sized, W: PacketWrite {
+        self.buffer.clear();
+        V::write_variant(object, &mut self.buffer)?;
+        write_frame_bytes(self.buffer.as_slice(), write)
+    }
+}
+
+/// Writes already serialized body as a frame.
+pub fn write_frame_bytes<W>(body: &[u8], write: &mut W) -> Result<(), Error> where W: PacketWrite {
+    match i32::try_from(body.len()) {
+        Ok(length) => {
+            write.reserve(VarInt::size_variant(&length)? + body.len());
+            VarInt::write_variant(&length, write)?;
+            write.write_bytes(body)
+        }
+        Err(_) => Err(Error::msg("Too big frame")),
+    }
+}
+
+/// Reads [VarInt] length of the frame and returns reader which is bounded by this length.
+///
+/// Bytes of the frame are consumed from the given reader, so the next frame can be read right after.
+pub fn read_frame<'a, R>(read: &mut R) -> Result<SlicePacketRead<'a>, PacketReadableError>
+    where R: PacketRead<'a> {
+    let length = VarInt::read_variant(read)?;
+    match length < 0 {
+        true => Err(PacketReadableError::Any(Error::msg("Negative frame length"))),
+        false => read.take_bounded(length as usize),
+    }
+}
@@ -0,0 +1,108 @@
+//! Harness of the fuzz targets in `fuzz/`. Readers are fed with arbitrary bytes through [SlicePacketRead],
+//! and values which are read are written and read back to check that the encoding round trips.
+//!
+//! Failed checks panic, so the fuzzer reports them as crashes. Targets are run with `cargo fuzz run <target>`
+//! from this crate, where the target is `numbers`, `strings`, `collections` or `packets`.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use anyhow::Error;
+use bird_chat::component::Component;
+use bird_chat::identifier::Identifier;
+use uuid::Uuid;
+use crate::packet::{PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, SlicePacketRead};
+use crate::packet_types::{BitSet, BlockPosition, LengthProvidedBytesSlice, LimitedIdentifier, LimitedString, PositionDelta, VarInt, VarLong};
+
+/// Reads value from the data without checking the round trip.
+///
+/// It is meant for types like [Component](bird_chat::component::Component), which are not written as they are read.
+pub fn fuzz_read<'a, T>(data: &'a [u8]) -> Option<T> where T: PacketReadable<'a> {
+    T::read(&mut SlicePacketRead::new(data)).ok()
+}
+
+/// Reads value from the data and, if it is read, writes it to the buffer and reads it again.
+/// Read back value has to consume the whole buffer and be equal to the first one,
+/// unless the value is not equal to itself (NaN floats), then it is only checked to be read back.
+///
+/// Buffer keeps the written bytes, so values which borrow the data can borrow the buffer as well.
+pub fn fuzz_roundtrip<'a, T>(data: &'a [u8], buffer: &'a mut Vec<u8>) -> Option<T>
+    where T: PacketReadable<'a> + PacketWritable + PartialEq + Debug {
+    let value = T::read(&mut SlicePacketRead::new(data)).ok()?;
+    Some(check_roundtrip(value, buffer, |value, write| value.write(write), |read| T::read(read)))
+}
+
+/// Same as [fuzz_roundtrip], but the value is read and written by the variant.
+pub fn fuzz_variant_roundtrip<'a, V, T>(data: &'a [u8], buffer: &'a mut Vec<u8>) -> Option<T>
+    where V: PacketVariantReadable<'a, T> + PacketVariantWritable<T>, T: PartialEq + Debug {
+    let value = V::read_variant(&mut SlicePacketRead::new(data)).ok()?;
+    Some(check_roundtrip(value, buffer, |value, write| V::write_variant(value, write), |read| V::read_variant(read)))
+}
+
+fn check_roundtrip<'a, T, W, R>(value: T, buffer: &'a mut Vec<u8>, write: W, read: R) -> T
+    where T: PartialEq + Debug,
+          W: Fn(&T, &mut Vec<u8>) -> Result<(), Error>,
+          R: FnOnce(&mut SlicePacketRead<'a>) -> Result<T, PacketReadableError> {
+    buffer.clear();
+    if let Err(err) = write(&value, buffer) {
+        panic!("{:?} is read, but it is not written: {}", value, err);
+    }
+    let buffer: &'a Vec<u8> = buffer;
+    let mut buffer_read = SlicePacketRead::new(buffer);
+    let read_back = match read(&mut buffer_read) {
+        Ok(read_back) => read_back,
+        Err(err) => panic!("{:?} is written as {:?}, which is not read: {}", value, buffer, err),
+    };
+    assert_eq!(buffer_read.available(), 0, "{:?} is not read fully", buffer);
+    if is_reflexive(&value) {
+        assert_eq!(read_back, value, "{:?} is read back differently", buffer);
+    }
+    value
+}
+
+#[allow(clippy::eq_op)]
+fn is_reflexive<T: PartialEq>(value: &T) -> bool {
+    value == value
+}
+
+/// Variable and fixed size numbers, positions and uuids.
+pub fn fuzz_numbers(data: &[u8]) {
+    fuzz_variant_roundtrip::<VarInt, i32>(data, &mut Vec::new());
+    fuzz_variant_roundtrip::<VarLong, i64>(data, &mut Vec::new());
+    fuzz_roundtrip::<BlockPosition>(data, &mut Vec::new());
+    fuzz_roundtrip::<PositionDelta>(data, &mut Vec::new());
+    fuzz_roundtrip::<Uuid>(data, &mut Vec::new());
+    fuzz_roundtrip::<(bool, i16, u64)>(data, &mut Vec::new());
+}
+
+/// Strings, identifiers and chat components.
+pub fn fuzz_strings(data: &[u8]) {
+    fuzz_roundtrip::<String>(data, &mut Vec::new());
+    fuzz_roundtrip::<&str>(data, &mut Vec::new());
+    fuzz_variant_roundtrip::<LimitedString<16>, &str>(data, &mut Vec::new());
+    fuzz_roundtrip::<Identifier>(data, &mut Vec::new());
+    fuzz_variant_roundtrip::<LimitedIdentifier<16>, Identifier>(data, &mut Vec::new());
+    fuzz_read::<Component>(data);
+}
+
+/// Optional values, arrays and maps, which are preallocated by the read length.
+pub fn fuzz_collections(data: &[u8]) {
+    fuzz_roundtrip::<Option<Vec<i16>>>(data, &mut Vec::new());
+    fuzz_roundtrip::<Vec<Option<String>>>(data, &mut Vec::new());
+    fuzz_roundtrip::<Vec<Vec<u8>>>(data, &mut Vec::new());
+    fuzz_roundtrip::<HashMap<u8, String>>(data, &mut Vec::new());
+    fuzz_roundtrip::<BTreeMap<i32, bool>>(data, &mut Vec::new());
+    fuzz_variant_roundtrip::<LengthProvidedBytesSlice<VarInt, i32>, &[u8]>(data, &mut Vec::new());
+    fuzz_roundtrip::<BitSet>(data, &mut Vec::new());
+}
+
+/// Packets of the handshake, status and login states and item slots.
+#[cfg(feature = "packet_default")]
+pub fn fuzz_packets(data: &[u8]) {
+    use crate::packet_default::*;
+    fuzz_roundtrip::<HandshakePacket>(data, &mut Vec::new());
+    fuzz_roundtrip::<ServerStatusPacket>(data, &mut Vec::new());
+    fuzz_read::<ClientStatusPacket>(data);
+    fuzz_roundtrip::<LoginStart>(data, &mut Vec::new());
+    fuzz_roundtrip::<LoginEncryptionResponse>(data, &mut Vec::new());
+    #[cfg(feature = "fastnbt")]
+    fuzz_roundtrip::<Option<crate::packet_fastnbt::Slot>>(data, &mut Vec::new());
+}
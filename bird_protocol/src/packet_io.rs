@@ -0,0 +1,326 @@
+use std::io::{Read, Write};
+use anyhow::Error;
+use crate::packet::{PacketReadable, PacketReadableError, PacketWritableError, PacketWrite, SlicePacketRead};
+use crate::packet_frame::DEFAULT_MAX_FRAME_LENGTH;
+use crate::packet_types::PREALLOCATION_LIMIT;
+
+/// Capacity of the buffer of [IoPacketWrite].
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8192;
+
+/// Writer of packets into [std::io::Write], for example into a file, without tokio.
+///
+/// Small writes are collected in the buffer, so the inner writer is not called for each byte.
+/// Io errors are returned as [PacketWritableError::Io] from the write which has failed.
+/// Buffer is flushed on drop, but errors of that flush are ignored, so [IoPacketWrite::flush] should be called.
+pub struct IoPacketWrite<W: Write> {
+    /// Inner writer is taken only by [IoPacketWrite::into_inner].
+    inner: Option<W>,
+    buffer: Vec<u8>,
+}
+
+fn io_error(error: std::io::Error) -> Error {
+    PacketWritableError::Io(error).into()
+}
+
+impl<W: Write> IoPacketWrite<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUFFER_CAPACITY, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self { inner: Some(inner), buffer: Vec::with_capacity(capacity) }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().expect("Inner writer is taken")
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().expect("Inner writer is taken")
+    }
+
+    /// Returns bytes which are not written to the inner writer yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn flush_buffer(&mut self) -> Result<(), Error> {
+        if !self.buffer.is_empty() {
+            let inner = self.inner.as_mut().expect("Inner writer is taken");
+            let result = inner.write_all(&self.buffer);
+            // Bytes are dropped even if writing failed, so they are not written twice
+            self.buffer.clear();
+            result.map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    /// Writes buffered bytes and flushes the inner writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.flush_buffer()?;
+        self.get_mut().flush().map_err(io_error)
+    }
+
+    /// Writes buffered bytes and returns the inner writer.
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        self.flush_buffer()?;
+        Ok(self.inner.take().expect("Inner writer is taken"))
+    }
+}
+
+impl<W: Write> PacketWrite for IoPacketWrite<W> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+        self.write_bytes(&[byte])
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        if self.buffer.len() + bytes.len() > self.buffer.capacity() {
+            self.flush_buffer()?;
+        }
+        match bytes.len() >= self.buffer.capacity() {
+            true => self.get_mut().write_all(bytes).map_err(io_error),
+            false => {
+                self.buffer.extend_from_slice(bytes);
+                Ok(())
+            }
+        }
+    }
+
+    fn write_bytes_owned(&mut self, bytes: Vec<u8>) -> Result<(), Error> {
+        self.write_bytes(bytes.as_slice())
+    }
+
+    fn write_bytes_fixed<const SIZE: usize>(&mut self, bytes: [u8; SIZE]) -> Result<(), Error> {
+        self.write_bytes(bytes.as_slice())
+    }
+}
+
+impl<W: Write> Drop for IoPacketWrite<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.flush_buffer();
+        }
+    }
+}
+
+/// Reader of the frames from [std::io::Read], for example from a capture file, without tokio.
+///
+/// Calls block until the whole frame is read. Length of the frame is read byte by byte,
+/// so the inner reader should be buffered, for example by [std::io::BufReader].
+/// Frame is returned as a slice, so [SlicePacketRead] knows how many bytes are available.
+pub struct IoPacketReader<R: Read> {
+    inner: R,
+    frame: Vec<u8>,
+    max_frame_length: usize,
+}
+
+fn read_io_error(error: std::io::Error) -> PacketReadableError {
+    PacketReadableError::Any(error.into())
+}
+
+impl<R: Read> IoPacketReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, frame: Vec::new(), max_frame_length: DEFAULT_MAX_FRAME_LENGTH }
+    }
+
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns body of the next frame or none if the reader ends right before the frame.
+    ///
+    /// If the reader ends in the middle of the frame, [std::io::ErrorKind::UnexpectedEof] error is returned.
+    pub fn try_next_frame(&mut self) -> Result<Option<&[u8]>, PacketReadableError> {
+        let Some(length) = self.read_length()? else { return Ok(None); };
+        self.frame.clear();
+        // Declared length is not trusted, so the frame grows with read bytes
+        self.frame.reserve(length.min(PREALLOCATION_LIMIT));
+        let read = (&mut self.inner).take(length as u64).read_to_end(&mut self.frame).map_err(read_io_error)?;
+        match read == length {
+            true => Ok(Some(&self.frame)),
+            false => Err(read_io_error(std::io::ErrorKind::UnexpectedEof.into())),
+        }
+    }
+
+    /// Returns body of the next frame. If the reader ends, [std::io::ErrorKind::UnexpectedEof] error is returned.
+    pub fn next_frame(&mut self) -> Result<&[u8], PacketReadableError> {
+        self.try_next_frame()?.ok_or_else(|| read_io_error(std::io::ErrorKind::UnexpectedEof.into()))
+    }
+
+    /// Reads value from the body of the next frame. To borrow the frame use [IoPacketReader::next_frame].
+    pub fn read_next<T>(&mut self) -> Result<T, PacketReadableError> where T: for<'a> PacketReadable<'a> {
+        T::read(&mut SlicePacketRead::new(self.next_frame()?))
+    }
+
+    fn read_length(&mut self) -> Result<Option<usize>, PacketReadableError> {
+        let mut length = 0usize;
+        for position in 0..5 {
+            let mut byte = [0u8];
+            if let Err(error) = self.inner.read_exact(&mut byte) {
+                return match position == 0 && error.kind() == std::io::ErrorKind::UnexpectedEof {
+                    true => Ok(None),
+                    false => Err(read_io_error(error)),
+                };
+            }
+            length |= ((byte[0] & 0x7F) as usize) << (position * 7);
+            if (byte[0] & 0x80) == 0 {
+                return match length > self.max_frame_length {
+                    true => Err(PacketReadableError::Any(Error::msg("Too big frame"))),
+                    false => Ok(Some(length)),
+                };
+            }
+        }
+        Err(PacketReadableError::BadVarNumber("Too long frame length"))
+    }
+}
+
+impl<R: Read + std::fmt::Debug> std::fmt::Debug for IoPacketReader<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IoPacketReader")
+            .field("inner", &self.inner)
+            .field("max_frame_length", &self.max_frame_length)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::{PacketVariantReadable, PacketVariantWritable, PacketWritable};
+    use crate::packet_types::*;
+    use super::*;
+
+    fn write_values<W: PacketWrite>(write: &mut W) {
+        0x12u8.write(write).unwrap();
+        VarInt::write_variant(&25565, write).unwrap();
+        "bird protocol".write(write).unwrap();
+        vec![1i64, -1, 3].write(write).unwrap();
+        uuid::Uuid::from_u128(7).write(write).unwrap();
+        RemainingBytesSlice::write_variant(&[0xAB; 100][..], write).unwrap();
+    }
+
+    #[test]
+    fn io_write_tests() {
+        let mut expected = Vec::new();
+        write_values(&mut expected);
+        // Capacity is smaller than the written bytes, so buffer is flushed in the middle
+        let mut write = IoPacketWrite::with_capacity(16, Vec::new());
+        write_values(&mut write);
+        assert!(!write.get_ref().is_empty());
+        assert_eq!(write.into_inner().unwrap(), expected);
+        let mut write = IoPacketWrite::new(Vec::new());
+        write_values(&mut write);
+        assert!(write.get_ref().is_empty());
+        write.flush().unwrap();
+        assert_eq!(write.get_ref(), &expected);
+        #[cfg(feature = "tokio-bytes")]
+        {
+            let mut bytes = bytes::BytesMut::new();
+            write_values(&mut bytes);
+            assert_eq!(&bytes[..], expected.as_slice());
+        }
+        let mut output = Vec::new();
+        {
+            let mut write = IoPacketWrite::new(&mut output);
+            write_values(&mut write);
+        }
+        assert_eq!(output, expected);
+    }
+
+    /// Writer which accepts `limit` bytes and fails after them.
+    struct FailingWrite {
+        limit: usize,
+    }
+
+    impl Write for FailingWrite {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            match self.limit {
+                0 => Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed")),
+                limit => {
+                    let length = buf.len().min(limit);
+                    self.limit -= length;
+                    Ok(length)
+                }
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn io_error_tests() {
+        let is_io = |error: Error| matches!(
+            error.downcast_ref(),
+            Some(PacketWritableError::Io(error)) if error.kind() == std::io::ErrorKind::BrokenPipe
+        );
+        // Big write goes to the inner writer right away
+        let mut write = IoPacketWrite::with_capacity(8, FailingWrite { limit: 4 });
+        assert!(is_io(RemainingBytesSlice::write_variant(&[0u8; 10][..], &mut write).unwrap_err()));
+        // Small writes fail when the buffer is flushed
+        let mut write = IoPacketWrite::with_capacity(8, FailingWrite { limit: 4 });
+        0u32.write(&mut write).unwrap();
+        0u32.write(&mut write).unwrap();
+        assert!(is_io(0u32.write(&mut write).unwrap_err()));
+        let mut write = IoPacketWrite::with_capacity(8, FailingWrite { limit: 0 });
+        0u8.write(&mut write).unwrap();
+        assert!(is_io(write.flush().unwrap_err()));
+        assert!(write.buffer().is_empty());
+        let mut write = IoPacketWrite::new(FailingWrite { limit: 0 });
+        0u8.write(&mut write).unwrap();
+        assert!(write.into_inner().is_err());
+    }
+
+    #[test]
+    fn io_read_tests() {
+        let mut body = Vec::new();
+        write_values(&mut body);
+        let mut framed = Vec::new();
+        crate::packet_frame::write_frame_bytes(&body, &mut framed).unwrap();
+        crate::packet_frame::write_frame_bytes(&[], &mut framed).unwrap();
+        crate::packet_frame::write_frame_bytes(&[0x80, 0x01], &mut framed).unwrap();
+        let mut reader = IoPacketReader::new(std::io::BufReader::new(framed.as_slice()));
+        assert_eq!(reader.next_frame().unwrap(), body.as_slice());
+        assert!(reader.next_frame().unwrap().is_empty());
+        assert_eq!(VarInt::read_variant(&mut SlicePacketRead::new(reader.next_frame().unwrap())).unwrap(), 128);
+        assert!(reader.try_next_frame().unwrap().is_none());
+        assert!(reader.next_frame().is_err());
+        // Values which borrow the frame are read from the slice
+        let mut reader = IoPacketReader::new(framed.as_slice());
+        let mut read = SlicePacketRead::new(reader.next_frame().unwrap());
+        assert_eq!(u8::read(&mut read).unwrap(), 0x12);
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), 25565);
+        assert_eq!(<&str>::read(&mut read).unwrap(), "bird protocol");
+        assert_eq!(Vec::<i64>::read(&mut read).unwrap(), vec![1, -1, 3]);
+        assert_eq!(reader.read_next::<Vec<u8>>().ok(), None);
+        assert_eq!(reader.read_next::<i32>().ok(), None);
+        assert!(reader.read_next::<u8>().is_err());
+    }
+
+    #[test]
+    fn io_read_error_tests() {
+        // Frame ends before its declared length
+        let mut reader = IoPacketReader::new(&[0x05, 1, 2][..]);
+        assert!(reader.try_next_frame().is_err());
+        // Length ends in the middle
+        let mut reader = IoPacketReader::new(&[0x80][..]);
+        assert!(reader.try_next_frame().is_err());
+        let mut reader = IoPacketReader::new(&[0xFF; 6][..]);
+        assert!(matches!(reader.try_next_frame(), Err(PacketReadableError::BadVarNumber(_))));
+        let mut reader = IoPacketReader::new(&[0x05, 1, 2, 3, 4, 5][..]).with_max_frame_length(4);
+        assert!(reader.try_next_frame().is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::Error;
+use bird_chat::component::Component;
+use crate::packet::{PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite};
+use crate::packet_types::{BlockPosition, GlobalPosition, OptUuid, OptVarInt, VarInt};
+use crate::packet_default::{Direction, Pose};
+#[cfg(feature = "fastnbt")]
+use crate::packet_fastnbt::Slot;
+
+/// Index which terminates entity metadata.
+pub const METADATA_END: u8 = 0xFF;
+
+/// Entity metadata variant, which is index and value pairs terminated by [METADATA_END].
+///
+/// Readable and writable for `Vec<(u8, EntityDataValue)>`. Indices must be unique.
+pub struct EntityMetadata;
+
+/// Value of the entity metadata field with the type id of protocol 759.
+///
+/// Particle (15) is not supported yet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityDataValue<'a> {
+    Byte(i8),
+    VarInt(i32),
+    Float(f32),
+    String(&'a str),
+    Component(Component<'a>),
+    OptComponent(Option<Component<'a>>),
+    #[cfg(feature = "fastnbt")]
+    Slot(Option<Slot>),
+    Boolean(bool),
+    Rotation(f32, f32, f32),
+    BlockPosition(BlockPosition),
+    OptBlockPosition(Option<BlockPosition>),
+    Direction(Direction),
+    OptUuid(OptUuid),
+    /// Block state id, where zero means absence.
+    OptBlockState(Option<i32>),
+    #[cfg(feature = "fastnbt")]
+    Nbt(fastnbt::Value),
+    VillagerData { villager_type: i32, profession: i32, level: i32 },
+    /// Written as [OptVarInt].
+    OptVarInt(Option<i32>),
+    Pose(Pose),
+    CatVariant(i32),
+    FrogVariant(i32),
+    OptGlobalPosition(Option<GlobalPosition<'a>>),
+    PaintingVariant(i32),
+}
+
+impl EntityDataValue<'_> {
+    pub fn type_id(&self) -> i32 {
+        match self {
+            EntityDataValue::Byte(_) => 0,
+            EntityDataValue::VarInt(_) => 1,
+            EntityDataValue::Float(_) => 2,
+            EntityDataValue::String(_) => 3,
+            EntityDataValue::Component(_) => 4,
+            EntityDataValue::OptComponent(_) => 5,
+            #[cfg(feature = "fastnbt")]
+            EntityDataValue::Slot(_) => 6,
+            EntityDataValue::Boolean(_) => 7,
+            EntityDataValue::Rotation(..) => 8,
+            EntityDataValue::BlockPosition(_) => 9,
+            EntityDataValue::OptBlockPosition(_) => 10,
+            EntityDataValue::Direction(_) => 11,
+            EntityDataValue::OptUuid(_) => 12,
+            EntityDataValue::OptBlockState(_) => 13,
+            #[cfg(feature = "fastnbt")]
+            EntityDataValue::Nbt(_) => 14,
+            EntityDataValue::VillagerData { .. } => 16,
+            EntityDataValue::OptVarInt(_) => 17,
+            EntityDataValue::Pose(_) => 18,
+            EntityDataValue::CatVariant(_) => 19,
+            EntityDataValue::FrogVariant(_) => 20,
+            EntityDataValue::OptGlobalPosition(_) => 21,
+            EntityDataValue::PaintingVariant(_) => 22,
+        }
+    }
+}
+
+impl<'a> PacketReadable<'a> for EntityDataValue<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(match VarInt::read_variant(read)? {
+            0 => EntityDataValue::Byte(i8::read(read)?),
+            1 => EntityDataValue::VarInt(VarInt::read_variant(read)?),
+            2 => EntityDataValue::Float(f32::read(read)?),
+            3 => EntityDataValue::String(<&'a str>::read(read)?),
+            4 => EntityDataValue::Component(Component::read(read)?),
+            5 => EntityDataValue::OptComponent(Option::read(read)?),
+            #[cfg(feature = "fastnbt")]
+            6 => EntityDataValue::Slot(Option::read(read)?),
+            7 => EntityDataValue::Boolean(bool::read(read)?),
+            8 => EntityDataValue::Rotation(f32::read(read)?, f32::read(read)?, f32::read(read)?),
+            9 => EntityDataValue::BlockPosition(BlockPosition::read(read)?),
+            10 => EntityDataValue::OptBlockPosition(Option::read(read)?),
+            11 => EntityDataValue::Direction(Direction::read(read)?),
+            12 => EntityDataValue::OptUuid(Option::read(read)?),
+            13 => EntityDataValue::OptBlockState(match VarInt::read_variant(read)? {
+                0 => None,
+                block_state => Some(block_state),
+            }),
+            #[cfg(feature = "fastnbt")]
+            14 => EntityDataValue::Nbt(fastnbt::Value::read(read)?),
+            16 => EntityDataValue::VillagerData {
+                villager_type: VarInt::read_variant(read)?,
+                profession: VarInt::read_variant(read)?,
+                level: VarInt::read_variant(read)?,
+            },
+            17 => EntityDataValue::OptVarInt(OptVarInt::read(read)?.into()),
+            18 => EntityDataValue::Pose(Pose::read(read)?),
+            19 => EntityDataValue::CatVariant(VarInt::read_variant(read)?),
+            20 => EntityDataValue::FrogVariant(VarInt::read_variant(read)?),
+            21 => EntityDataValue::OptGlobalPosition(Option::read(read)?),
+            22 => EntityDataValue::PaintingVariant(VarInt::read_variant(read)?),
+            type_id => return Err(PacketReadableError::BadEntityDataType(type_id)),
+        })
+    }
+}
+
+impl PacketWritable for EntityDataValue<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        VarInt::write_variant(&self.type_id(), write)?;
+        match self {
+            EntityDataValue::Byte(value) => value.write(write),
+            EntityDataValue::VarInt(value) |
+            EntityDataValue::CatVariant(value) |
+            EntityDataValue::FrogVariant(value) |
+            EntityDataValue::PaintingVariant(value) => VarInt::write_variant(value, write),
+            EntityDataValue::Float(value) => value.write(write),
+            EntityDataValue::String(value) => value.write(write),
+            EntityDataValue::Component(value) => value.write(write),
+            EntityDataValue::OptComponent(value) => value.write(write),
+            #[cfg(feature = "fastnbt")]
+            EntityDataValue::Slot(value) => value.write(write),
+            EntityDataValue::Boolean(value) => value.write(write),
+            EntityDataValue::Rotation(x, y, z) => {
+                x.write(write)?;
+                y.write(write)?;
+                z.write(write)
+            }
+            EntityDataValue::BlockPosition(value) => value.write(write),
+            EntityDataValue::Direction(value) => value.write(write),
+            EntityDataValue::Pose(value) => value.write(write),
+            EntityDataValue::OptBlockPosition(value) => value.write(write),
+            EntityDataValue::OptUuid(value) => value.write(write),
+            EntityDataValue::OptGlobalPosition(value) => value.write(write),
+            EntityDataValue::OptBlockState(value) => VarInt::write_variant(&value.unwrap_or(0), write),
+            #[cfg(feature = "fastnbt")]
+            EntityDataValue::Nbt(value) => value.write(write),
+            EntityDataValue::VillagerData { villager_type, profession, level } => {
+                VarInt::write_variant(villager_type, write)?;
+                VarInt::write_variant(profession, write)?;
+                VarInt::write_variant(level, write)
+            }
+            EntityDataValue::OptVarInt(value) => OptVarInt(*value).write(write),
+        }
+    }
+}
+
+impl<'a> PacketVariantReadable<'a, Vec<(u8, EntityDataValue<'a>)>> for EntityMetadata {
+    fn read_variant<R>(read: &mut R) -> Result<Vec<(u8, EntityDataValue<'a>)>, PacketReadableError>
+        where R: PacketRead<'a> {
+        let mut result = Vec::new();
+        loop {
+            let index = u8::read(read)?;
+            if index == METADATA_END {
+                break Ok(result);
+            }
+            if result.iter().any(|(other, _)| *other == index) {
+                break Err(PacketReadableError::BadEntityDataIndex(index));
+            }
+            result.push((index, EntityDataValue::read(read)?));
+        }
+    }
+}
+
+impl PacketVariantWritable<[(u8, EntityDataValue<'_>)]> for EntityMetadata {
+    fn write_variant<W>(object: &[(u8, EntityDataValue<'_>)], write: &mut W) -> Result<(), Error>
+        where W: PacketWrite {
+        for (position, (index, value)) in object.iter().enumerate() {
+            if *index == METADATA_END || object[..position].iter().any(|(other, _)| other == index) {
+                return Err(Error::msg("Bad entity data index"));
+            }
+            index.write(write)?;
+            value.write(write)?;
+        }
+        METADATA_END.write(write)
+    }
+}
+
+impl PacketVariantWritable<Vec<(u8, EntityDataValue<'_>)>> for EntityMetadata {
+    fn write_variant<W>(object: &Vec<(u8, EntityDataValue<'_>)>, write: &mut W) -> Result<(), Error>
+        where W: PacketWrite {
+        Self::write_variant(object.as_slice(), write)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use crate::packet::SlicePacketRead;
+    use super::*;
+
+    #[test]
+    fn metadata_tests() {
+        let metadata = vec![
+            (0, EntityDataValue::Byte(0x20)),
+            (1, EntityDataValue::VarInt(300)),
+            (2, EntityDataValue::OptComponent(None)),
+            (3, EntityDataValue::Boolean(true)),
+            (4, EntityDataValue::Float(1.5)),
+            (5, EntityDataValue::String("name")),
+            (6, EntityDataValue::Rotation(0.0, 90.0, -45.5)),
+            (7, EntityDataValue::BlockPosition(BlockPosition { x: -1, y: 64, z: 1000 })),
+            (8, EntityDataValue::OptBlockPosition(Some(BlockPosition { x: 0, y: -64, z: 0 }))),
+            (9, EntityDataValue::Direction(Direction::East)),
+            (10, EntityDataValue::OptUuid(Some(Uuid::from_u128(1)))),
+            (11, EntityDataValue::OptBlockState(None)),
+            (12, EntityDataValue::VillagerData { villager_type: 1, profession: 2, level: 3 }),
+            (13, EntityDataValue::OptVarInt(Some(0))),
+            (14, EntityDataValue::OptVarInt(None)),
+            (15, EntityDataValue::Pose(Pose::Crouching)),
+            (16, EntityDataValue::PaintingVariant(3)),
+            (18, EntityDataValue::OptGlobalPosition(Some(GlobalPosition {
+                dimension: bird_chat::identifier::Identifier::new_fulled("minecraft:overworld").unwrap(),
+                position: BlockPosition { x: 1, y: 2, z: 3 },
+            }))),
+            #[cfg(feature = "fastnbt")]
+            (17, EntityDataValue::Slot(Some(Slot { item_id: 1, count: 64, nbt: None }))),
+        ];
+        let mut write = Vec::new();
+        EntityMetadata::write_variant(&metadata, &mut write).unwrap();
+        assert_eq!(&write[..4], &[0x00, 0x00, 0x20, 0x01]);
+        assert_eq!(write.last(), Some(&METADATA_END));
+        // Float, string and pose fields
+        assert!(write.windows(6).any(|window| window == [0x04, 0x02, 0x3F, 0xC0, 0x00, 0x00]));
+        assert!(write.windows(4).any(|window| window == [0x05, 0x03, 0x04, b'n']));
+        assert!(write.windows(3).any(|window| window == [0x0F, 0x12, 0x05]));
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(
+            <EntityMetadata as PacketVariantReadable<'_, Vec<(u8, EntityDataValue)>>>::read_variant(&mut read).unwrap(),
+            metadata
+        );
+        assert_eq!(read.available(), 0);
+    }
+
+    #[test]
+    fn metadata_component_tests() {
+        let mut write = vec![0x02, 0x04];
+        r#"{"text":"hi","extra":[]}"#.write(&mut write).unwrap();
+        write.push(METADATA_END);
+        let mut read = SlicePacketRead::new(write.as_slice());
+        let metadata =
+            <EntityMetadata as PacketVariantReadable<'_, Vec<(u8, EntityDataValue)>>>::read_variant(&mut read).unwrap();
+        assert!(matches!(metadata.as_slice(), [(2, EntityDataValue::Component(Component::Text(text)))] if text.text == "hi"));
+    }
+
+    #[test]
+    fn bad_metadata_tests() {
+        // Particle is not supported
+        let mut read = SlicePacketRead::new(&[0x00, 0x0F, 0x00, METADATA_END]);
+        assert!(matches!(
+            <EntityMetadata as PacketVariantReadable<'_, Vec<(u8, EntityDataValue)>>>::read_variant(&mut read),
+            Err(PacketReadableError::BadEntityDataType(15))
+        ));
+        let mut read = SlicePacketRead::new(&[0x00, 0x17, 0x00, METADATA_END]);
+        assert!(matches!(
+            <EntityMetadata as PacketVariantReadable<'_, Vec<(u8, EntityDataValue)>>>::read_variant(&mut read),
+            Err(PacketReadableError::BadEntityDataType(23))
+        ));
+        let mut read = SlicePacketRead::new(&[0x01, 0x07, 0x01, 0x01, 0x07, 0x00, METADATA_END]);
+        assert!(matches!(
+            <EntityMetadata as PacketVariantReadable<'_, Vec<(u8, EntityDataValue)>>>::read_variant(&mut read),
+            Err(PacketReadableError::BadEntityDataIndex(1))
+        ));
+        let mut read = SlicePacketRead::new(&[0x01, 0x07, 0x01]);
+        assert!(matches!(
+            <EntityMetadata as PacketVariantReadable<'_, Vec<(u8, EntityDataValue)>>>::read_variant(&mut read),
+            Err(PacketReadableError::BytesExceeded)
+        ));
+        assert!(EntityMetadata::write_variant(&vec![(METADATA_END, EntityDataValue::Byte(0))], &mut Vec::new()).is_err());
+        assert!(EntityMetadata::write_variant(
+            &vec![(1, EntityDataValue::Byte(0)), (1, EntityDataValue::Byte(1))], &mut Vec::new(),
+        ).is_err());
+    }
+}
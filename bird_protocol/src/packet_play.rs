@@ -0,0 +1,4154 @@
+use anyhow::Error;
+use bird_chat::component::Component;
+use bird_chat::identifier::Identifier;
+use crate::*;
+use crate::derive::*;
+use crate::packet_chunk::LightData;
+#[cfg(feature = "fastnbt")]
+use crate::packet_chunk::{ChunkSection, Heightmap, Heightmaps};
+use crate::packet_default::{ProfilePublicKey, LoginSuccessProperty, PlayerName};
+use uuid::Uuid;
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[enum_type(u8)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+/// Variant of `Option<GameMode>` which is written as a byte, where -1 means absence.
+pub struct PreviousGameMode;
+
+impl<'a> PacketVariantReadable<'a, Option<GameMode>> for PreviousGameMode {
+    fn read_variant<R>(read: &mut R) -> Result<Option<GameMode>, PacketReadableError> where R: PacketRead<'a> {
+        match i8::read(read)? {
+            -1 => Ok(None),
+            _ => {
+                read.rollback(1)?;
+                GameMode::read(read).map(Some)
+            }
+        }
+    }
+}
+
+impl PacketVariantWritable<Option<GameMode>> for PreviousGameMode {
+    fn write_variant<W>(object: &Option<GameMode>, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        match object {
+            Some(game_mode) => game_mode.write(write),
+            None => (-1i8).write(write),
+        }
+    }
+}
+
+/// Variant of [GameMode] which is written as [VarInt].
+pub struct VarIntGameMode;
+
+impl<'a> PacketVariantReadable<'a, GameMode> for VarIntGameMode {
+    fn read_variant<R>(read: &mut R) -> Result<GameMode, PacketReadableError> where R: PacketRead<'a> {
+        match VarInt::read_variant(read)? {
+            0 => Ok(GameMode::Survival),
+            1 => Ok(GameMode::Creative),
+            2 => Ok(GameMode::Adventure),
+            3 => Ok(GameMode::Spectator),
+            _ => Err(PacketReadableError::BadEnumValue),
+        }
+    }
+}
+
+impl PacketVariantWritable<GameMode> for VarIntGameMode {
+    fn write_variant<W>(object: &GameMode, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        VarInt::write_variant(&(*object as i32), write)
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x17)]
+pub struct PlayDisconnect<'a> {
+    pub reason: Component<'a>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x1E)]
+pub struct PlayKeepAlive {
+    pub id: i64,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x11)]
+pub struct PlayKeepAliveResponse {
+    pub id: i64,
+}
+
+/// First packet of the play state, which is sent right after the login success.
+#[cfg(feature = "fastnbt")]
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x23)]
+pub struct PlayJoinGame<'a> {
+    pub entity_id: i32,
+    pub hardcore: bool,
+    pub game_mode: GameMode,
+    #[variant(PreviousGameMode)]
+    pub previous_game_mode: Option<GameMode>,
+    pub dimensions: Vec<Identifier<'a>>,
+    pub registry_codec: fastnbt::Value,
+    pub dimension_type: Identifier<'a>,
+    pub dimension_name: Identifier<'a>,
+    /// First 8 bytes of the SHA-256 hash of the world seed.
+    pub hashed_seed: i64,
+    /// Ignored by the client.
+    #[variant(VarInt)]
+    pub max_players: i32,
+    #[variant(VarInt)]
+    pub view_distance: i32,
+    #[variant(VarInt)]
+    pub simulation_distance: i32,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+    pub debug: bool,
+    pub flat: bool,
+    pub death_location: Option<GlobalPosition<'a>>,
+}
+
+/// Declares flags which are sent as one byte. Named flags and their accessors go to a separate impl of the type.
+macro_rules! byte_flags {
+    ($(#[$meta: meta])* $name: ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+        pub struct $name(u8);
+
+        impl $name {
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Unknown bits are kept, so the flags are written back as they are read.
+            pub const fn from_bits(bits: u8) -> Self {
+                Self(bits)
+            }
+
+            pub const fn bits(self) -> u8 {
+                self.0
+            }
+
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            pub fn set(&mut self, other: Self, value: bool) {
+                match value {
+                    true => self.0 |= other.0,
+                    false => self.0 &= !other.0,
+                }
+            }
+        }
+
+        impl std::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl<'a> PacketReadable<'a> for $name {
+            fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+                u8::read(read).map(Self)
+            }
+        }
+
+        impl PacketWritable for $name {
+            fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+                self.0.write(write)
+            }
+        }
+    };
+}
+
+byte_flags! {
+    /// Flags of [PlaySynchronizePlayerPosition], each of them marks the field as relative to the current one.
+    RelativeFlags
+}
+
+impl RelativeFlags {
+    pub const X: Self = Self(0x01);
+    pub const Y: Self = Self(0x02);
+    pub const Z: Self = Self(0x04);
+    pub const YAW: Self = Self(0x08);
+    pub const PITCH: Self = Self(0x10);
+
+    pub const fn x(self) -> bool {
+        self.contains(Self::X)
+    }
+
+    pub const fn y(self) -> bool {
+        self.contains(Self::Y)
+    }
+
+    pub const fn z(self) -> bool {
+        self.contains(Self::Z)
+    }
+
+    pub const fn yaw(self) -> bool {
+        self.contains(Self::YAW)
+    }
+
+    pub const fn pitch(self) -> bool {
+        self.contains(Self::PITCH)
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x36)]
+pub struct PlaySynchronizePlayerPosition {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// Yaw in degrees.
+    pub yaw: f32,
+    /// Pitch in degrees.
+    pub pitch: f32,
+    pub flags: RelativeFlags,
+    #[variant(VarInt)]
+    pub teleport_id: i32,
+    pub dismount_vehicle: bool,
+}
+
+/// Confirms [PlaySynchronizePlayerPosition] with the same teleport id.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x00)]
+pub struct PlayConfirmTeleportation {
+    #[variant(VarInt)]
+    pub teleport_id: i32,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum ClientCommandAction {
+    PerformRespawn,
+    RequestStats,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x06)]
+pub struct PlayClientCommand {
+    pub action: ClientCommandAction,
+}
+
+pub const LOCALE_LIMIT: i32 = 16;
+
+pub type Locale = LimitedString<LOCALE_LIMIT>;
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum ChatMode {
+    Enabled,
+    CommandsOnly,
+    Hidden,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum MainHand {
+    Left,
+    Right,
+}
+
+byte_flags! {
+    /// Displayed parts of the skin in [PlayClientInformation].
+    SkinParts
+}
+
+impl SkinParts {
+    pub const CAPE: Self = Self(0x01);
+    pub const JACKET: Self = Self(0x02);
+    pub const LEFT_SLEEVE: Self = Self(0x04);
+    pub const RIGHT_SLEEVE: Self = Self(0x08);
+    pub const LEFT_PANTS: Self = Self(0x10);
+    pub const RIGHT_PANTS: Self = Self(0x20);
+    pub const HAT: Self = Self(0x40);
+    /// All known parts, which vanilla displays by default.
+    pub const ALL: Self = Self(0x7F);
+
+    pub const fn cape(self) -> bool {
+        self.contains(Self::CAPE)
+    }
+
+    pub const fn jacket(self) -> bool {
+        self.contains(Self::JACKET)
+    }
+
+    /// Both sleeves are displayed.
+    pub const fn sleeves(self) -> bool {
+        self.contains(Self(Self::LEFT_SLEEVE.0 | Self::RIGHT_SLEEVE.0))
+    }
+
+    /// Both pants legs are displayed.
+    pub const fn pants(self) -> bool {
+        self.contains(Self(Self::LEFT_PANTS.0 | Self::RIGHT_PANTS.0))
+    }
+
+    pub const fn hat(self) -> bool {
+        self.contains(Self::HAT)
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x07)]
+pub struct PlayClientInformation<'a> {
+    /// For example `en_us`.
+    #[variant(Locale)]
+    pub locale: &'a str,
+    /// Render distance in chunks.
+    pub view_distance: i8,
+    pub chat_mode: ChatMode,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: SkinParts,
+    pub main_hand: MainHand,
+    pub enable_text_filtering: bool,
+    /// Whether the player is shown in the player list of the server status.
+    pub allow_server_listings: bool,
+}
+
+/// Coordinates are [Finite], as vanilla kicks the player for NaN and infinity.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x13)]
+pub struct PlaySetPlayerPosition {
+    #[variant(Finite)]
+    pub x: f64,
+    /// Y of the feet.
+    #[variant(Finite)]
+    pub y: f64,
+    #[variant(Finite)]
+    pub z: f64,
+    pub on_ground: bool,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x14)]
+pub struct PlaySetPlayerPositionAndRotation {
+    #[variant(Finite)]
+    pub x: f64,
+    /// Y of the feet.
+    #[variant(Finite)]
+    pub y: f64,
+    #[variant(Finite)]
+    pub z: f64,
+    /// Yaw in degrees.
+    #[variant(Finite)]
+    pub yaw: f32,
+    /// Pitch in degrees.
+    #[variant(Pitch)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x15)]
+pub struct PlaySetPlayerRotation {
+    /// Yaw in degrees.
+    #[variant(Finite)]
+    pub yaw: f32,
+    /// Pitch in degrees.
+    #[variant(Pitch)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+byte_flags! {
+    /// Flags of [PlayPlayerAbilities].
+    AbilityFlags
+}
+
+impl AbilityFlags {
+    pub const INVULNERABLE: Self = Self(0x01);
+    pub const FLYING: Self = Self(0x02);
+    pub const ALLOW_FLYING: Self = Self(0x04);
+    /// Blocks are broken instantly.
+    pub const CREATIVE_MODE: Self = Self(0x08);
+
+    pub const fn invulnerable(self) -> bool {
+        self.contains(Self::INVULNERABLE)
+    }
+
+    pub fn set_invulnerable(&mut self, value: bool) {
+        self.set(Self::INVULNERABLE, value)
+    }
+
+    pub const fn flying(self) -> bool {
+        self.contains(Self::FLYING)
+    }
+
+    pub fn set_flying(&mut self, value: bool) {
+        self.set(Self::FLYING, value)
+    }
+
+    pub const fn allow_flying(self) -> bool {
+        self.contains(Self::ALLOW_FLYING)
+    }
+
+    pub fn set_allow_flying(&mut self, value: bool) {
+        self.set(Self::ALLOW_FLYING, value)
+    }
+
+    pub const fn creative_mode(self) -> bool {
+        self.contains(Self::CREATIVE_MODE)
+    }
+
+    pub fn set_creative_mode(&mut self, value: bool) {
+        self.set(Self::CREATIVE_MODE, value)
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x2F)]
+pub struct PlayPlayerAbilities {
+    pub flags: AbilityFlags,
+    pub flying_speed: f32,
+    /// Modifies the field of view like the speed effects.
+    pub field_of_view_modifier: f32,
+}
+
+impl PlayPlayerAbilities {
+    pub const DEFAULT_FLYING_SPEED: f32 = 0.05;
+    pub const DEFAULT_FIELD_OF_VIEW_MODIFIER: f32 = 0.1;
+}
+
+/// Sent when the player starts or stops flying, so only [AbilityFlags::FLYING] is meaningful.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x1B)]
+pub struct PlayServerPlayerAbilities {
+    pub flags: AbilityFlags,
+}
+
+/// Event of [PlayGameEvent]. Meaning of its value is told for each event.
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(u8)]
+pub enum GameEvent {
+    /// Bed or respawn anchor of the player is missing or obstructed.
+    NoRespawnBlockAvailable,
+    EndRaining,
+    BeginRaining,
+    /// Value is the id of the [GameMode].
+    ChangeGameMode,
+    /// Value is 0 to respawn the player or 1 to show the credits first.
+    WinGame,
+    /// Value is 0 for the welcome screen or 101..=104 for the movement, jump, inventory and end of the demo hints.
+    DemoEvent,
+    /// Sound of the arrow which has hit another player.
+    ArrowHitPlayer,
+    /// Value is the rain level from 0 to 1.
+    RainLevelChange,
+    /// Value is the thunder level from 0 to 1.
+    ThunderLevelChange,
+    PufferfishSting,
+    ElderGuardianAppearance,
+    /// Value is 0 to show the respawn screen or 1 to respawn immediately.
+    EnableRespawnScreen,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x1B)]
+pub struct PlayGameEvent {
+    pub event: GameEvent,
+    pub value: f32,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[enum_type(u8)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x0B)]
+pub struct PlayChangeDifficulty {
+    pub difficulty: Difficulty,
+    /// Difficulty can't be changed in the options of the client.
+    pub locked: bool,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum StatisticCategory {
+    /// Id is of the `minecraft:block` registry.
+    Mined,
+    /// Id is of the `minecraft:item` registry, as of the next four categories.
+    Crafted,
+    Used,
+    Broken,
+    PickedUp,
+    Dropped,
+    /// Id is of the `minecraft:entity_type` registry.
+    Killed,
+    KilledBy,
+    /// Id is of [CustomStatistic].
+    Custom,
+}
+
+/// Statistics of the `minecraft:custom_stat` registry, which ids are the same in 1.18.2 and 1.19.
+/// Distances are in centimeters and times are in ticks.
+#[derive(PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+pub enum CustomStatistic {
+    LeaveGame,
+    PlayTime,
+    TotalWorldTime,
+    TimeSinceDeath,
+    TimeSinceRest,
+    SneakTime,
+    WalkOneCm,
+    CrouchOneCm,
+    SprintOneCm,
+    WalkOnWaterOneCm,
+    FallOneCm,
+    ClimbOneCm,
+    FlyOneCm,
+    WalkUnderWaterOneCm,
+    MinecartOneCm,
+    BoatOneCm,
+    PigOneCm,
+    HorseOneCm,
+    AviateOneCm,
+    SwimOneCm,
+    StriderOneCm,
+    Jump,
+    Drop,
+    DamageDealt,
+    DamageDealtAbsorbed,
+    DamageDealtResisted,
+    DamageTaken,
+    DamageBlockedByShield,
+    DamageAbsorbed,
+    DamageResisted,
+    Deaths,
+    MobKills,
+    AnimalsBred,
+    PlayerKills,
+    FishCaught,
+    TalkedToVillager,
+    TradedWithVillager,
+    EatCakeSlice,
+    FillCauldron,
+    UseCauldron,
+    CleanArmor,
+    CleanBanner,
+    CleanShulkerBox,
+    InteractWithBrewingstand,
+    InteractWithBeacon,
+    InspectDropper,
+    InspectHopper,
+    InspectDispenser,
+    PlayNoteblock,
+    TuneNoteblock,
+    PotFlower,
+    TriggerTrappedChest,
+    OpenEnderchest,
+    EnchantItem,
+    PlayRecord,
+    InteractWithFurnace,
+    InteractWithCraftingTable,
+    OpenChest,
+    SleepInBed,
+    OpenShulkerBox,
+    OpenBarrel,
+    InteractWithBlastFurnace,
+    InteractWithSmoker,
+    InteractWithLectern,
+    InteractWithCampfire,
+    InteractWithCartographyTable,
+    InteractWithLoom,
+    InteractWithStonecutter,
+    BellRing,
+    RaidTrigger,
+    RaidWin,
+    InteractWithAnvil,
+    InteractWithGrindstone,
+    TargetHit,
+    InteractWithSmithingTable,
+}
+
+/// Statistic of [PlayAwardStatistics], where meaning of the id depends on the category.
+#[derive(PacketWritable, PacketReadable, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Statistic {
+    pub category: StatisticCategory,
+    #[variant(VarInt)]
+    pub id: i32,
+    #[variant(VarInt)]
+    pub value: i32,
+}
+
+impl Statistic {
+    pub fn custom(statistic: CustomStatistic, value: i32) -> Self {
+        Self { category: StatisticCategory::Custom, id: statistic.value(), value }
+    }
+
+    /// Custom statistic of the id, which is none if the category is not custom or the id is unknown.
+    pub fn custom_statistic(&self) -> Option<CustomStatistic> {
+        match self.category {
+            StatisticCategory::Custom => CustomStatistic::from_value(self.id),
+            _ => None,
+        }
+    }
+}
+
+/// Response to [ClientCommandAction::RequestStats], which has only changed statistics.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x04)]
+pub struct PlayAwardStatistics {
+    pub statistics: Vec<Statistic>,
+}
+
+/// Known events of [PlayWorldEvent]. Events of other ids are kept as [OpenEnum::Unknown].
+#[derive(PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+pub enum WorldEvent {
+    DispenserDispense = 1000,
+    DispenserFail,
+    DispenserShoot,
+    EnderEyeLaunch,
+    FireworkShot,
+    IronDoorOpen,
+    WoodenDoorOpen,
+    WoodenTrapdoorOpen,
+    FenceGateOpen,
+    FireExtinguish,
+    /// Data is the id of the record item, 0 stops the record.
+    PlayRecord,
+    IronDoorClose,
+    WoodenDoorClose,
+    WoodenTrapdoorClose,
+    FenceGateClose,
+    /// Sound is global.
+    WitherSpawn = 1023,
+    /// Sound is global.
+    EnderDragonDeath = 1028,
+    AnvilDestroy,
+    AnvilUse,
+    AnvilLand,
+    /// Sound is global.
+    EndPortalOpen = 1038,
+    /// Data is the block state id.
+    BlockBreak = 2001,
+    /// Data is the rgb color of the potion.
+    SplashPotion,
+    EnderEyeBreak,
+    MobSpawn,
+    /// Data is the count of the particles, 0 is 15.
+    BoneMeal,
+    DragonBreath,
+    /// Data is the rgb color of the potion.
+    InstantSplashPotion,
+    EndGatewaySpawn = 3000,
+    EnderDragonGrowl,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x20)]
+pub struct PlayWorldEvent {
+    pub event: OpenEnum<WorldEvent>,
+    pub location: BlockPosition,
+    pub data: i32,
+    /// Sound of global events is played at the same volume at any distance.
+    pub disable_relative_volume: bool,
+}
+
+/// Action of the block like note block play or chest open. Meaning of the action depends on the block.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x08)]
+pub struct PlayBlockAction {
+    pub location: BlockPosition,
+    pub action_id: u8,
+    pub action_param: u8,
+    /// Id in the `minecraft:block` registry, not the block state id.
+    #[variant(VarInt)]
+    pub block_type: i32,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x06)]
+pub struct PlaySetBlockDestroyStage {
+    /// Id of the entity which breaks the block. Each entity has only one block in progress.
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub location: BlockPosition,
+    /// From 0 to 9, other values remove the stage.
+    pub stage: u8,
+}
+
+impl PlaySetBlockDestroyStage {
+    /// Stage which removes the destroy stage of the block.
+    pub const CLEAR_STAGE: u8 = 0xFF;
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x09)]
+pub struct PlayBlockUpdate {
+    pub location: BlockPosition,
+    #[variant(VarInt)]
+    pub block_state: i32,
+}
+
+/// Block change of [PlayUpdateSectionBlocks], which is written as [VarLong] of
+/// block state << 12 | x << 8 | z << 4 | y. Coordinates are relative to the section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SectionBlockUpdate {
+    pub state: i32,
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+}
+
+impl SectionBlockUpdate {
+    pub fn try_pack(&self) -> Result<i64, PacketWritableError> {
+        match (self.state, self.x, self.y, self.z) {
+            (state, ..) if state < 0 =>
+                Err(PacketWritableError::OutOfBounds { value: state as i64, min: 0, max: i32::MAX as i64 }),
+            (_, x @ 16.., _, _) | (_, _, x @ 16.., _) | (_, _, _, x @ 16..) =>
+                Err(PacketWritableError::OutOfBounds { value: x as i64, min: 0, max: 15 }),
+            (state, x, y, z) => Ok((state as i64) << 12 | (x as i64) << 8 | (z as i64) << 4 | y as i64),
+        }
+    }
+
+    pub fn from_packed(value: i64) -> Result<Self, PacketReadableError> {
+        let state = value >> 12;
+        match i32::try_from(state) {
+            Ok(state) if state >= 0 => Ok(Self {
+                state,
+                x: (value >> 8 & 0xF) as u8,
+                y: (value & 0xF) as u8,
+                z: (value >> 4 & 0xF) as u8,
+            }),
+            _ => Err(PacketReadableError::OutOfRange { value: state, min: 0, max: i32::MAX as i64 }),
+        }
+    }
+}
+
+impl<'a> PacketReadable<'a> for SectionBlockUpdate {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Self::from_packed(VarLong::read_variant(read)?)
+    }
+}
+
+impl PacketWritable for SectionBlockUpdate {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        VarLong::write_variant(&self.try_pack()?, write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        VarLong::size_variant(&self.try_pack()?)
+    }
+}
+
+/// Changes blocks of one section.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x3D)]
+pub struct PlayUpdateSectionBlocks {
+    pub section: SectionPosition,
+    /// Light is not updated by the client, it is the inverse of [LightData::trust_edges].
+    pub suppress_light_updates: bool,
+    pub updates: Vec<SectionBlockUpdate>,
+}
+
+type VarIntArray = LengthProvidedSlice<VarInt, VarInt, i32, i32>;
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct Tag<'a> {
+    pub name: Identifier<'a>,
+    /// Ids of the registry entries.
+    #[variant(VarIntArray)]
+    pub entries: Vec<i32>,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct TagGroup<'a> {
+    pub registry: Identifier<'a>,
+    pub tags: Vec<Tag<'a>>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x68)]
+pub struct PlayUpdateTags<'a> {
+    pub groups: Vec<TagGroup<'a>>,
+}
+
+impl<'a> PlayUpdateTags<'a> {
+    /// Creates packet from registries and their tags.
+    ///
+    /// [Identifier] is not hashable, so any iterator of pairs can be used instead of a map.
+    pub fn from_groups<G, T>(groups: G) -> Self
+        where G: IntoIterator<Item=(Identifier<'a>, T)>, T: IntoIterator<Item=(Identifier<'a>, Vec<i32>)> {
+        Self {
+            groups: groups.into_iter()
+                .map(|(registry, tags)| TagGroup {
+                    registry,
+                    tags: tags.into_iter().map(|(name, entries)| Tag { name, entries }).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct PlayerInfoAdd<'a> {
+    pub uuid: Uuid,
+    #[variant(PlayerName)]
+    pub name: &'a str,
+    pub properties: Vec<LoginSuccessProperty<'a>>,
+    #[variant(VarIntGameMode)]
+    pub game_mode: GameMode,
+    /// Latency in milliseconds.
+    #[variant(VarInt)]
+    pub ping: i32,
+    pub display_name: Option<Component<'a>>,
+    /// Public key of the player, which is the same as in [LoginStart].
+    pub signature_data: Option<ProfilePublicKey<'a>>,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct PlayerInfoGameMode {
+    pub uuid: Uuid,
+    #[variant(VarIntGameMode)]
+    pub game_mode: GameMode,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct PlayerInfoLatency {
+    pub uuid: Uuid,
+    /// Latency in milliseconds.
+    #[variant(VarInt)]
+    pub ping: i32,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct PlayerInfoDisplayName<'a> {
+    pub uuid: Uuid,
+    pub display_name: Option<Component<'a>>,
+}
+
+/// Tab list update. Action is written first, so all entries of the packet have the same layout.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x34)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum PlayPlayerInfo<'a> {
+    AddPlayer(Vec<PlayerInfoAdd<'a>>),
+    UpdateGameMode(Vec<PlayerInfoGameMode>),
+    UpdateLatency(Vec<PlayerInfoLatency>),
+    UpdateDisplayName(Vec<PlayerInfoDisplayName<'a>>),
+    RemovePlayer(Vec<Uuid>),
+}
+
+type LengthProvidedBytesSliceVI = LengthProvidedBytesSlice<VarInt, i32>;
+
+/// Max length of the chat message which is sent by the client.
+pub const CHAT_MESSAGE_LIMIT: i32 = 256;
+
+pub type ChatMessageText = LimitedString<CHAT_MESSAGE_LIMIT>;
+
+/// Message is signed by the private key of the [ProfilePublicKey] together with the timestamp and the salt.
+/// If the signature is empty the message is not signed.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x04)]
+pub struct PlayChatMessage<'a> {
+    #[variant(ChatMessageText)]
+    pub message: &'a str,
+    /// In milliseconds since the epoch.
+    pub timestamp: i64,
+    pub salt: i64,
+    #[variant(LengthProvidedBytesSliceVI)]
+    pub signature: &'a [u8],
+    /// True if the message is sent as it is shown by the chat preview.
+    pub signed_preview: bool,
+}
+
+/// Chat message of the player. Only the signed content is covered by the signature,
+/// while the unsigned content is shown instead of it, if it is present.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x30)]
+pub struct PlayPlayerChat<'a> {
+    pub signed_content: Component<'a>,
+    pub unsigned_content: Option<Component<'a>>,
+    /// Id in the `minecraft:chat_type` registry of the join game packet.
+    #[variant(VarInt)]
+    pub chat_type: i32,
+    pub sender: Uuid,
+    pub sender_name: Component<'a>,
+    pub sender_team_name: Option<Component<'a>>,
+    /// In milliseconds since the epoch.
+    pub timestamp: i64,
+    pub salt: i64,
+    #[variant(LengthProvidedBytesSliceVI)]
+    pub signature: &'a [u8],
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum BossBarColor {
+    Pink,
+    Blue,
+    Red,
+    Green,
+    Yellow,
+    Purple,
+    White,
+}
+
+/// Count of notches which the boss bar is divided by.
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum BossBarDivision {
+    None,
+    Notches6,
+    Notches10,
+    Notches12,
+    Notches20,
+}
+
+byte_flags! {
+    /// Flags of the boss bar.
+    BossBarFlags
+}
+
+impl BossBarFlags {
+    pub const DARKEN_SKY: Self = Self(0x01);
+    /// Plays the end music.
+    pub const DRAGON_BAR: Self = Self(0x02);
+    pub const CREATE_FOG: Self = Self(0x04);
+
+    pub const fn darken_sky(self) -> bool {
+        self.contains(Self::DARKEN_SKY)
+    }
+
+    pub const fn dragon_bar(self) -> bool {
+        self.contains(Self::DRAGON_BAR)
+    }
+
+    pub const fn create_fog(self) -> bool {
+        self.contains(Self::CREATE_FOG)
+    }
+}
+
+/// Action of [PlayBossBar]. Unknown action is [PacketReadableError::BadEnumValue].
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum BossBarAction<'a> {
+    Add {
+        title: Component<'a>,
+        /// From 0 to 1.
+        health: f32,
+        color: BossBarColor,
+        division: BossBarDivision,
+        flags: BossBarFlags,
+    },
+    Remove,
+    UpdateHealth(f32),
+    UpdateTitle(Component<'a>),
+    UpdateStyle {
+        color: BossBarColor,
+        division: BossBarDivision,
+    },
+    UpdateFlags(BossBarFlags),
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x0A)]
+pub struct PlayBossBar<'a> {
+    pub uuid: Uuid,
+    pub action: BossBarAction<'a>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x0D)]
+pub struct PlayClearTitles {
+    /// Also resets the subtitle and the animation times.
+    pub reset: bool,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x40)]
+pub struct PlaySetActionBarText<'a> {
+    pub text: Component<'a>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x58)]
+pub struct PlaySetSubtitleText<'a> {
+    pub text: Component<'a>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x5A)]
+pub struct PlaySetTitleText<'a> {
+    pub text: Component<'a>,
+}
+
+/// Times of the title in ticks.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x5B)]
+pub struct PlaySetTitleAnimationTimes {
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum SoundCategory {
+    Master,
+    Music,
+    Record,
+    Weather,
+    Block,
+    Hostile,
+    Neutral,
+    Player,
+    Ambient,
+    Voice,
+}
+
+/// Position of the sound in 1/8 of a block.
+#[derive(PacketWritable, PacketReadable, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct EffectPosition {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl EffectPosition {
+    pub const SCALE: f64 = 8.0;
+
+    /// Converts position in blocks. Position which does not fit in [i32] is an error.
+    pub fn from_block_f64(x: f64, y: f64, z: f64) -> Result<Self, PacketWritableError> {
+        Ok(Self {
+            x: crate::packet_types::fixed_point(x, Self::SCALE, "Effect position x")?,
+            y: crate::packet_types::fixed_point(y, Self::SCALE, "Effect position y")?,
+            z: crate::packet_types::fixed_point(z, Self::SCALE, "Effect position z")?,
+        })
+    }
+
+    /// Returns position in blocks.
+    pub fn to_f64(&self) -> [f64; 3] {
+        [self.x as f64 / Self::SCALE, self.y as f64 / Self::SCALE, self.z as f64 / Self::SCALE]
+    }
+}
+
+/// Sound of the registry.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x5D)]
+pub struct PlaySoundEffect {
+    #[variant(VarInt)]
+    pub sound_id: i32,
+    pub category: SoundCategory,
+    pub position: EffectPosition,
+    /// 1 is 100%, but it can be more.
+    pub volume: f32,
+    /// From 0.5 to 2.
+    pub pitch: f32,
+    /// Seed of the sound variant.
+    pub seed: i64,
+}
+
+/// Sound which is not in the registry, for example of the resource pack.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x16)]
+pub struct PlayCustomSoundEffect<'a> {
+    pub sound: Identifier<'a>,
+    pub category: SoundCategory,
+    pub position: EffectPosition,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+/// Sound of the registry which follows the entity.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x5C)]
+pub struct PlayEntitySoundEffect {
+    #[variant(VarInt)]
+    pub sound_id: i32,
+    pub category: SoundCategory,
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
+
+/// Stops sounds. Absent category or sound matches all of them.
+///
+/// Presence of the fields is marked by flags: [PlayStopSound::CATEGORY] and [PlayStopSound::SOUND].
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x5E)]
+pub struct PlayStopSound<'a> {
+    pub flags: u8,
+    #[present_if(flags & PlayStopSound::CATEGORY != 0)]
+    pub category: MaskedOption<SoundCategory>,
+    #[present_if(flags & PlayStopSound::SOUND != 0)]
+    pub sound: MaskedOption<Identifier<'a>>,
+}
+
+impl<'a> PlayStopSound<'a> {
+    pub const CATEGORY: u8 = 0x01;
+    pub const SOUND: u8 = 0x02;
+
+    /// Creates packet with flags of the present fields.
+    pub fn new(category: Option<SoundCategory>, sound: Option<Identifier<'a>>) -> Self {
+        let mut flags = 0;
+        if category.is_some() {
+            flags |= Self::CATEGORY;
+        }
+        if sound.is_some() {
+            flags |= Self::SOUND;
+        }
+        Self { flags, category: category.into(), sound: sound.into() }
+    }
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum MapIconType {
+    Player,
+    Frame,
+    RedMarker,
+    BlueMarker,
+    TargetX,
+    TargetPoint,
+    PlayerOffMap,
+    PlayerOffLimits,
+    Mansion,
+    Monument,
+    WhiteBanner,
+    OrangeBanner,
+    MagentaBanner,
+    LightBlueBanner,
+    YellowBanner,
+    LimeBanner,
+    PinkBanner,
+    GrayBanner,
+    LightGrayBanner,
+    CyanBanner,
+    PurpleBanner,
+    BlueBanner,
+    BrownBanner,
+    GreenBanner,
+    RedBanner,
+    BlackBanner,
+    RedX,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct MapIcon<'a> {
+    pub icon_type: MapIconType,
+    /// From -128 for the left edge to 127 for the right one.
+    pub x: i8,
+    /// From -128 for the top edge to 127 for the bottom one.
+    pub z: i8,
+    /// From 0 to 15, where 0 is south and every step is 22.5 degrees clockwise.
+    pub direction: u8,
+    pub display_name: Option<Component<'a>>,
+}
+
+/// Updated region of the map colors, which are stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapPatch {
+    pub columns: u8,
+    pub rows: u8,
+    pub x: u8,
+    pub z: u8,
+    pub data: Vec<u8>,
+}
+
+impl MapPatch {
+    /// Width and height of the map.
+    pub const SIZE: usize = 128;
+
+    /// Creates update of the whole map. Colors are indexed as `colors[z][x]`.
+    pub fn full(colors: &[[u8; 128]; 128]) -> Self {
+        Self { columns: 128, rows: 128, x: 0, z: 0, data: colors.concat() }
+    }
+
+    /// Creates update of the region, which has to be inside of the map and not empty.
+    pub fn region(colors: &[[u8; 128]; 128], x: usize, z: usize, columns: usize, rows: usize) -> Result<Self, Error> {
+        if columns == 0 || rows == 0 || x + columns > Self::SIZE || z + rows > Self::SIZE {
+            return Err(Error::msg("Region is out of the map"));
+        }
+        Ok(Self {
+            columns: columns as u8,
+            rows: rows as u8,
+            x: x as u8,
+            z: z as u8,
+            data: colors[z..z + rows].iter().flat_map(|row| &row[x..x + columns]).copied().collect(),
+        })
+    }
+}
+
+/// Variant of `Option<MapPatch>`, which is absent if columns are zero. Other fields are written only if it is present.
+pub struct OptionalMapPatch;
+
+impl<'a> PacketVariantReadable<'a, Option<MapPatch>> for OptionalMapPatch {
+    fn read_variant<R>(read: &mut R) -> Result<Option<MapPatch>, PacketReadableError> where R: PacketRead<'a> {
+        let columns = u8::read(read)?;
+        if columns == 0 {
+            return Ok(None);
+        }
+        let rows = u8::read(read)?;
+        let x = u8::read(read)?;
+        let z = u8::read(read)?;
+        let data: Vec<u8> = LengthProvidedBytesSlice::<VarInt, i32>::read_variant(read)?;
+        match data.len() == columns as usize * rows as usize {
+            true => Ok(Some(MapPatch { columns, rows, x, z, data })),
+            false => Err(PacketReadableError::BadLength),
+        }
+    }
+}
+
+impl PacketVariantWritable<Option<MapPatch>> for OptionalMapPatch {
+    fn write_variant<W>(object: &Option<MapPatch>, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        let Some(patch) = object else { return 0u8.write(write); };
+        if patch.columns == 0 || patch.data.len() != patch.columns as usize * patch.rows as usize {
+            return Err(Error::msg("Bad size of the map patch"));
+        }
+        write.write_bytes_fixed([patch.columns, patch.rows, patch.x, patch.z])?;
+        LengthProvidedBytesSlice::<VarInt, i32>::write_variant(patch.data.as_slice(), write)
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x24)]
+pub struct PlayMapData<'a> {
+    #[variant(VarInt)]
+    pub map_id: i32,
+    /// From 0 for 1 block per pixel to 4 for 16 blocks per pixel.
+    pub scale: i8,
+    pub locked: bool,
+    /// Icons are not changed if they are absent.
+    pub icons: Option<Vec<MapIcon<'a>>>,
+    #[variant(OptionalMapPatch)]
+    pub patch: Option<MapPatch>,
+}
+
+pub const OBJECTIVE_NAME_LIMIT: i32 = 16;
+pub const TEAM_NAME_LIMIT: i32 = 16;
+/// Limit of the entity name in scores and teams. Players are by their names and other entities by their uuids.
+pub const SCORE_HOLDER_LIMIT: i32 = 40;
+pub type ObjectiveName = LimitedString<OBJECTIVE_NAME_LIMIT>;
+pub type TeamName = LimitedString<TEAM_NAME_LIMIT>;
+pub type ScoreHolder = LimitedString<SCORE_HOLDER_LIMIT>;
+pub type TeamEntities<'a> = LengthProvidedSlice<VarInt, ScoreHolder, i32, &'a str>;
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum ObjectiveRenderType {
+    Integer,
+    Hearts,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[enum_type(u8)]
+pub enum ObjectiveAction<'a> {
+    Create {
+        display_name: Component<'a>,
+        render_type: ObjectiveRenderType,
+    },
+    Remove,
+    Update {
+        display_name: Component<'a>,
+        render_type: ObjectiveRenderType,
+    },
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x53)]
+pub struct PlayUpdateObjectives<'a> {
+    #[variant(ObjectiveName)]
+    pub name: &'a str,
+    pub action: ObjectiveAction<'a>,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[enum_type(u8)]
+pub enum ScoreAction<'a> {
+    Update {
+        #[variant(ObjectiveName)]
+        objective_name: &'a str,
+        #[variant(VarInt)]
+        value: i32,
+    },
+    Remove {
+        #[variant(ObjectiveName)]
+        objective_name: &'a str,
+    },
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x56)]
+pub struct PlayUpdateScore<'a> {
+    #[variant(ScoreHolder)]
+    pub entity_name: &'a str,
+    pub action: ScoreAction<'a>,
+}
+
+/// Color of the team, which is also any formatting code.
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum TeamColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    Obfuscated,
+    Bold,
+    Strikethrough,
+    Underlined,
+    Italic,
+    Reset,
+}
+
+/// Slot of the displayed objective. Sidebar of the team is shown only to players of the team with the color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplaySlot {
+    List,
+    Sidebar,
+    BelowName,
+    TeamSidebar(TeamColor),
+}
+
+impl DisplaySlot {
+    const TEAM_SIDEBAR: u8 = 3;
+}
+
+impl<'a> PacketReadable<'a> for DisplaySlot {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        match u8::read(read)? {
+            0 => Ok(DisplaySlot::List),
+            1 => Ok(DisplaySlot::Sidebar),
+            2 => Ok(DisplaySlot::BelowName),
+            slot => match TeamColor::from_value((slot - Self::TEAM_SIDEBAR) as i32) {
+                Some(color) if color.value() <= TeamColor::White.value() => Ok(DisplaySlot::TeamSidebar(color)),
+                _ => Err(PacketReadableError::BadEnumValue),
+            },
+        }
+    }
+}
+
+impl PacketWritable for DisplaySlot {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        match self {
+            DisplaySlot::List => 0u8.write(write),
+            DisplaySlot::Sidebar => 1u8.write(write),
+            DisplaySlot::BelowName => 2u8.write(write),
+            DisplaySlot::TeamSidebar(color) => match color.value() <= TeamColor::White.value() {
+                true => (Self::TEAM_SIDEBAR + color.value() as u8).write(write),
+                false => Err(PacketWritableError::OutOfRange("Team sidebar color").into()),
+            },
+        }
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x4C)]
+pub struct PlayDisplayObjective<'a> {
+    pub slot: DisplaySlot,
+    /// Empty name clears the slot.
+    #[variant(ObjectiveName)]
+    pub name: &'a str,
+}
+
+byte_flags! {
+    /// Flags of the team.
+    TeamFlags
+}
+
+impl TeamFlags {
+    pub const FRIENDLY_FIRE: Self = Self(0x01);
+    pub const SEE_INVISIBLE_TEAMMATES: Self = Self(0x02);
+
+    pub const fn friendly_fire(self) -> bool {
+        self.contains(Self::FRIENDLY_FIRE)
+    }
+
+    pub const fn see_invisible_teammates(self) -> bool {
+        self.contains(Self::SEE_INVISIBLE_TEAMMATES)
+    }
+}
+
+/// Visibility of name tags, which is written as its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameTagVisibility {
+    Always,
+    HideForOtherTeams,
+    HideForOwnTeam,
+    Never,
+}
+
+impl NameTagVisibility {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NameTagVisibility::Always => "always",
+            NameTagVisibility::HideForOtherTeams => "hideForOtherTeams",
+            NameTagVisibility::HideForOwnTeam => "hideForOwnTeam",
+            NameTagVisibility::Never => "never",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "always" => Some(NameTagVisibility::Always),
+            "hideForOtherTeams" => Some(NameTagVisibility::HideForOtherTeams),
+            "hideForOwnTeam" => Some(NameTagVisibility::HideForOwnTeam),
+            "never" => Some(NameTagVisibility::Never),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> PacketReadable<'a> for NameTagVisibility {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let name: &str = LimitedString::<40>::read_variant(read)?;
+        Self::from_name(name).ok_or(PacketReadableError::BadEnumValue)
+    }
+}
+
+impl PacketWritable for NameTagVisibility {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.name().write(write)
+    }
+}
+
+/// Collision rule of the team, which is written as its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionRule {
+    Always,
+    PushOtherTeams,
+    PushOwnTeam,
+    Never,
+}
+
+impl CollisionRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CollisionRule::Always => "always",
+            CollisionRule::PushOtherTeams => "pushOtherTeams",
+            CollisionRule::PushOwnTeam => "pushOwnTeam",
+            CollisionRule::Never => "never",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "always" => Some(CollisionRule::Always),
+            "pushOtherTeams" => Some(CollisionRule::PushOtherTeams),
+            "pushOwnTeam" => Some(CollisionRule::PushOwnTeam),
+            "never" => Some(CollisionRule::Never),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> PacketReadable<'a> for CollisionRule {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let name: &str = LimitedString::<40>::read_variant(read)?;
+        Self::from_name(name).ok_or(PacketReadableError::BadEnumValue)
+    }
+}
+
+impl PacketWritable for CollisionRule {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.name().write(write)
+    }
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct TeamInfo<'a> {
+    pub display_name: Component<'a>,
+    pub flags: TeamFlags,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+    pub color: TeamColor,
+    pub prefix: Component<'a>,
+    pub suffix: Component<'a>,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[enum_type(u8)]
+pub enum TeamAction<'a> {
+    Create {
+        info: TeamInfo<'a>,
+        #[variant(TeamEntities::<'a>)]
+        entities: Vec<&'a str>,
+    },
+    Remove,
+    Update(TeamInfo<'a>),
+    AddEntities(#[variant(TeamEntities::<'a>)] Vec<&'a str>),
+    RemoveEntities(#[variant(TeamEntities::<'a>)] Vec<&'a str>),
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x55)]
+pub struct PlayUpdateTeams<'a> {
+    #[variant(TeamName)]
+    pub name: &'a str,
+    pub action: TeamAction<'a>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x22)]
+pub struct PlayUpdateLight {
+    #[variant(VarInt)]
+    pub chunk_x: i32,
+    #[variant(VarInt)]
+    pub chunk_z: i32,
+    pub light: LightData,
+}
+
+/// Block entity of the chunk data, where x and z are relative to the chunk and written as one byte.
+#[cfg(feature = "fastnbt")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkBlockEntity {
+    pub x: u8,
+    pub z: u8,
+    pub y: i16,
+    /// Id in the `minecraft:block_entity_type` registry.
+    pub kind: i32,
+    pub data: Option<fastnbt::Value>,
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> PacketReadable<'a> for ChunkBlockEntity {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let xz = u8::read(read)?;
+        Ok(Self {
+            x: xz >> 4,
+            z: xz & 0xF,
+            y: i16::read(read)?,
+            kind: VarInt::read_variant(read)?,
+            data: ProtocolOptionalNbt::read_variant(read)?,
+        })
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketWritable for ChunkBlockEntity {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        if self.x > 15 || self.z > 15 {
+            return Err(PacketWritableError::OutOfRange("Block entity is out of the chunk").into());
+        }
+        (self.x << 4 | self.z).write(write)?;
+        self.y.write(write)?;
+        VarInt::write_variant(&self.kind, write)?;
+        ProtocolOptionalNbt::write_variant(&self.data, write)
+    }
+}
+
+/// Chunk column with its light. Sections are written to the buffer prefixed by its size in bytes.
+///
+/// Count of sections depends on the world height. [PacketReadable] reads the overworld,
+/// other worlds should use [PlayChunkDataAndUpdateLight::read_with_world].
+#[cfg(feature = "fastnbt")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayChunkDataAndUpdateLight {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    pub heightmaps: Heightmaps,
+    pub sections: Vec<ChunkSection>,
+    pub block_entities: Vec<ChunkBlockEntity>,
+    pub light: LightData,
+}
+
+#[cfg(feature = "fastnbt")]
+impl PlayChunkDataAndUpdateLight {
+    pub fn read_with_world<'a, R: PacketRead<'a>>(min_y: i32, height: u32, read: &mut R) -> Result<Self, PacketReadableError> {
+        let chunk_x = i32::read(read)?;
+        let chunk_z = i32::read(read)?;
+        let heightmaps = Heightmaps::read_with_world(min_y, height, read)?;
+        let length = VarInt::read_variant(read)?.into_length()?;
+        let mut data = read.take_bounded(length)?;
+        let mut sections = Vec::with_capacity(height as usize / 16);
+        while data.available() > 0 {
+            sections.push(ChunkSection::read(&mut data)?);
+        }
+        Ok(Self {
+            chunk_x,
+            chunk_z,
+            heightmaps,
+            sections,
+            block_entities: PacketReadable::read(read)?,
+            light: LightData::read(read)?,
+        })
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl Packet for PlayChunkDataAndUpdateLight {
+    fn id() -> i32 {
+        0x21
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketScope for PlayChunkDataAndUpdateLight {
+    fn bound() -> PacketBound {
+        PacketBound::Client
+    }
+
+    fn state() -> PacketState {
+        PacketState::Play
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> PacketReadable<'a> for PlayChunkDataAndUpdateLight {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Self::read_with_world(Heightmap::OVERWORLD_MIN_Y, Heightmap::OVERWORLD_HEIGHT, read)
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketWritable for PlayChunkDataAndUpdateLight {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.chunk_x.write(write)?;
+        self.chunk_z.write(write)?;
+        self.heightmaps.write(write)?;
+        let mut length = 0;
+        for section in &self.sections {
+            length += section.size()?;
+        }
+        VarInt::write_variant(&i32::try_from(length).map_err(|_| PacketWritableError::LengthOverflow { length })?, write)?;
+        for section in &self.sections {
+            section.write(write)?;
+        }
+        self.block_entities.write(write)?;
+        self.light.write(write)
+    }
+}
+
+/// Builder of [PlayChunkDataAndUpdateLight], which checks sections and block entities against the world height.
+#[cfg(feature = "fastnbt")]
+#[derive(Debug, Clone)]
+pub struct ChunkPacketBuilder {
+    chunk_x: i32,
+    chunk_z: i32,
+    min_y: i32,
+    height: u32,
+    heightmaps: Option<Heightmaps>,
+    sections: Vec<ChunkSection>,
+    block_entities: Vec<ChunkBlockEntity>,
+    light: LightData,
+}
+
+#[cfg(feature = "fastnbt")]
+impl ChunkPacketBuilder {
+    /// Creates builder of the overworld chunk, which light is trusted on edges.
+    pub fn new(chunk_x: i32, chunk_z: i32) -> Self {
+        Self {
+            chunk_x,
+            chunk_z,
+            min_y: Heightmap::OVERWORLD_MIN_Y,
+            height: Heightmap::OVERWORLD_HEIGHT,
+            heightmaps: None,
+            sections: Vec::new(),
+            block_entities: Vec::new(),
+            light: LightData::new(true),
+        }
+    }
+
+    /// Sets minimum y and height of the world, which are multiples of 16.
+    pub fn with_world(mut self, min_y: i32, height: u32) -> Self {
+        self.min_y = min_y;
+        self.height = height;
+        self
+    }
+
+    /// Sets heightmaps. By default all heights are the world minimum.
+    pub fn with_heightmaps(mut self, heightmaps: Heightmaps) -> Self {
+        self.heightmaps = Some(heightmaps);
+        self
+    }
+
+    /// Sets sections from the bottom of the world, there is one section per 16 blocks of the height.
+    pub fn with_sections(mut self, sections: Vec<ChunkSection>) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    pub fn with_block_entity(mut self, block_entity: ChunkBlockEntity) -> Self {
+        self.block_entities.push(block_entity);
+        self
+    }
+
+    pub fn with_light(mut self, light: LightData) -> Self {
+        self.light = light;
+        self
+    }
+
+    pub fn build(self) -> Result<PlayChunkDataAndUpdateLight, Error> {
+        if self.sections.len() != self.height as usize / 16 {
+            return Err(Error::msg(format!(
+                "Expected {} sections of the world height {}, got {}", self.height / 16, self.height, self.sections.len(),
+            )));
+        }
+        let max_y = i64::from(self.min_y) + i64::from(self.height);
+        if let Some(block_entity) = self.block_entities.iter()
+            .find(|block_entity| !(i64::from(self.min_y)..max_y).contains(&i64::from(block_entity.y))) {
+            return Err(PacketWritableError::OutOfBounds {
+                value: block_entity.y.into(), min: self.min_y.into(), max: max_y - 1,
+            }.into());
+        }
+        Ok(PlayChunkDataAndUpdateLight {
+            chunk_x: self.chunk_x,
+            chunk_z: self.chunk_z,
+            heightmaps: self.heightmaps.unwrap_or_else(|| Heightmaps::with_world(self.min_y, self.height)),
+            sections: self.sections,
+            block_entities: self.block_entities,
+            light: self.light,
+        })
+    }
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(u8)]
+pub enum EquipmentSlot {
+    MainHand,
+    OffHand,
+    Boots,
+    Leggings,
+    Chestplate,
+    Helmet,
+}
+
+/// Variant of the equipment list, where the top bit of the slot byte is set if another entry follows.
+/// The list can't be empty.
+#[cfg(feature = "fastnbt")]
+pub struct EquipmentList;
+
+#[cfg(feature = "fastnbt")]
+impl EquipmentList {
+    pub const CONTINUATION: u8 = 0x80;
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> PacketVariantReadable<'a, Vec<(EquipmentSlot, Option<Slot>)>> for EquipmentList {
+    fn read_variant<R>(read: &mut R) -> Result<Vec<(EquipmentSlot, Option<Slot>)>, PacketReadableError> where R: PacketRead<'a> {
+        let mut equipment = Vec::new();
+        loop {
+            let byte = u8::read(read)?;
+            let slot = EquipmentSlot::from_value(byte & !Self::CONTINUATION)
+                .ok_or(PacketReadableError::BadEnumValue)?;
+            equipment.push((slot, Option::<Slot>::read(read)?));
+            if byte & Self::CONTINUATION == 0 {
+                return Ok(equipment);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketVariantWritable<Vec<(EquipmentSlot, Option<Slot>)>> for EquipmentList {
+    fn write_variant<W>(object: &Vec<(EquipmentSlot, Option<Slot>)>, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        if object.is_empty() {
+            return Err(Error::msg("Equipment list is empty"));
+        }
+        let last = object.len() - 1;
+        for (index, (slot, item)) in object.iter().enumerate() {
+            match index == last {
+                true => slot.value(),
+                false => slot.value() | Self::CONTINUATION,
+            }.write(write)?;
+            item.write(write)?;
+        }
+        Ok(())
+    }
+}
+
+/// Empty item in the equipment slot is [None].
+#[cfg(feature = "fastnbt")]
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x50)]
+pub struct PlaySetEquipment {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    #[variant(EquipmentList)]
+    pub equipment: Vec<(EquipmentSlot, Option<Slot>)>,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum ClickMode {
+    Pickup,
+    QuickMove,
+    Swap,
+    Clone,
+    Throw,
+    /// Dragging, where the button tells the stage and the mouse button.
+    QuickCraft,
+    PickupAll,
+}
+
+/// Variant of the changed slots in [PlayClickContainer] written as [VarInt] count and slot number item pairs.
+/// The count is rejected if it is above [ChangedSlots::MAX] or the remaining bytes can't hold that many entries.
+#[cfg(feature = "fastnbt")]
+pub struct ChangedSlots;
+
+#[cfg(feature = "fastnbt")]
+impl ChangedSlots {
+    pub const MAX: usize = 128;
+    /// Slot number and the "present" boolean of an empty slot.
+    pub const MIN_ENTRY_SIZE: usize = 3;
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> PacketVariantReadable<'a, Vec<(i16, Option<Slot>)>> for ChangedSlots {
+    fn read_variant<R>(read: &mut R) -> Result<Vec<(i16, Option<Slot>)>, PacketReadableError> where R: PacketRead<'a> {
+        let length = VarInt::read_variant(read)?.into_length()?;
+        if length > Self::MAX || length * Self::MIN_ENTRY_SIZE > read.available() {
+            return Err(PacketReadableError::BadLength);
+        }
+        let mut slots = Vec::with_capacity(length);
+        for _ in 0..length {
+            slots.push((i16::read(read)?, Option::<Slot>::read(read)?));
+        }
+        Ok(slots)
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketVariantWritable<Vec<(i16, Option<Slot>)>> for ChangedSlots {
+    fn write_variant<W>(object: &Vec<(i16, Option<Slot>)>, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        if object.len() > Self::MAX {
+            return Err(Error::msg(format!("Too many changed slots: {}", object.len())));
+        }
+        object.write(write)
+    }
+}
+
+/// Slot number -999 means outside of the window.
+#[cfg(feature = "fastnbt")]
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x0A)]
+pub struct PlayClickContainer {
+    pub window_id: u8,
+    #[variant(VarInt)]
+    pub state_id: i32,
+    pub slot: i16,
+    pub button: i8,
+    pub mode: ClickMode,
+    #[variant(ChangedSlots)]
+    pub changed_slots: Vec<(i16, Option<Slot>)>,
+    pub carried_item: Option<Slot>,
+}
+
+#[cfg(feature = "fastnbt")]
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x11)]
+pub struct PlaySetContainerContent {
+    pub window_id: u8,
+    #[variant(VarInt)]
+    pub state_id: i32,
+    pub slots: Vec<Option<Slot>>,
+    pub carried_item: Option<Slot>,
+}
+
+/// Window id -1 with slot -1 sets the carried item, window id -2 sets the slot in the player inventory.
+#[cfg(feature = "fastnbt")]
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x13)]
+pub struct PlaySetContainerSlot {
+    pub window_id: i8,
+    #[variant(VarInt)]
+    pub state_id: i32,
+    pub slot: i16,
+    pub item: Option<Slot>,
+}
+
+/// Type of the window in the `minecraft:menu` registry.
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum ContainerType {
+    Generic9x1,
+    Generic9x2,
+    Generic9x3,
+    Generic9x4,
+    Generic9x5,
+    Generic9x6,
+    Generic3x3,
+    Anvil,
+    Beacon,
+    BlastFurnace,
+    BrewingStand,
+    Crafting,
+    Enchantment,
+    Furnace,
+    Grindstone,
+    Hopper,
+    Lectern,
+    Loom,
+    Merchant,
+    ShulkerBox,
+    Smithing,
+    Smoker,
+    Cartography,
+    Stonecutter,
+}
+
+impl ContainerType {
+    /// Slots of the player inventory which follow the slots of the container in the window.
+    pub const PLAYER_INVENTORY_SLOTS: usize = 36;
+
+    /// Count of the container slots, without the player inventory.
+    pub const fn slot_count(&self) -> usize {
+        match self {
+            ContainerType::Generic9x1 => 9,
+            ContainerType::Generic9x2 => 18,
+            ContainerType::Generic9x3 | ContainerType::ShulkerBox => 27,
+            ContainerType::Generic9x4 => 36,
+            ContainerType::Generic9x5 => 45,
+            ContainerType::Generic9x6 => 54,
+            ContainerType::Generic3x3 => 9,
+            ContainerType::Crafting => 10,
+            ContainerType::BrewingStand | ContainerType::Hopper => 5,
+            ContainerType::Loom => 4,
+            ContainerType::Anvil | ContainerType::BlastFurnace | ContainerType::Furnace
+            | ContainerType::Grindstone | ContainerType::Merchant | ContainerType::Smithing
+            | ContainerType::Smoker | ContainerType::Cartography => 3,
+            ContainerType::Enchantment | ContainerType::Stonecutter => 2,
+            ContainerType::Beacon | ContainerType::Lectern => 1,
+        }
+    }
+
+    /// Count of all slots in the window, including the player inventory.
+    pub const fn window_slot_count(&self) -> usize {
+        self.slot_count() + Self::PLAYER_INVENTORY_SLOTS
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x2B)]
+pub struct PlayOpenScreen<'a> {
+    #[variant(VarInt)]
+    pub window_id: i32,
+    pub window_type: ContainerType,
+    pub title: Component<'a>,
+}
+
+/// Window id 0 is the player inventory.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x10)]
+pub struct PlayCloseContainer {
+    pub window_id: u8,
+}
+
+/// Window id 0 is the player inventory.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x0B)]
+pub struct PlayServerCloseContainer {
+    pub window_id: u8,
+}
+
+/// Meaning of the property depends on the type of the window, e.g. progress of the furnace arrow.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x12)]
+pub struct PlaySetContainerProperty {
+    pub window_id: u8,
+    pub property: i16,
+    pub value: i16,
+}
+
+/// Spawns entity which is not a player or an experience orb.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x00)]
+pub struct PlaySpawnEntity {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub uuid: Uuid,
+    /// Id in the `minecraft:entity_type` registry.
+    #[variant(VarInt)]
+    pub entity_type: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// In radians.
+    #[variant(Angle)]
+    pub pitch: f32,
+    /// In radians.
+    #[variant(Angle)]
+    pub yaw: f32,
+    /// In radians.
+    #[variant(Angle)]
+    pub head_yaw: f32,
+    /// Meaning depends on the entity type, for example block state of the falling block.
+    #[variant(VarInt)]
+    pub data: i32,
+    pub velocity: Velocity,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x01)]
+pub struct PlaySpawnExperienceOrb {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// Amount of experience.
+    pub count: i16,
+}
+
+/// Spawns player, who has to be added by [PlayPlayerInfo] before.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x02)]
+pub struct PlaySpawnPlayer {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub uuid: Uuid,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// In radians.
+    #[variant(Angle)]
+    pub yaw: f32,
+    /// In radians.
+    #[variant(Angle)]
+    pub pitch: f32,
+}
+
+/// Despawns entities on the client. Ids can be reused after it, like by [crate::server::EntityIdAllocator].
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x38)]
+pub struct PlayRemoveEntities {
+    #[variant(VarIntArray)]
+    pub entity_ids: Vec<i32>,
+}
+
+/// Moves the entity shorter than 8 blocks on each axis, longer moves use [PlayTeleportEntity].
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x26)]
+pub struct PlayUpdateEntityPosition {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub delta: PositionDelta,
+    pub on_ground: bool,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x27)]
+pub struct PlayUpdateEntityPositionAndRotation {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub delta: PositionDelta,
+    /// In radians.
+    #[variant(Angle)]
+    pub yaw: f32,
+    /// In radians.
+    #[variant(Angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+/// Rotates the body of the entity, the head is rotated by [PlaySetHeadRotation].
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x28)]
+pub struct PlayUpdateEntityRotation {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    /// In radians.
+    #[variant(Angle)]
+    pub yaw: f32,
+    /// In radians.
+    #[variant(Angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x3C)]
+pub struct PlaySetHeadRotation {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    /// In radians.
+    #[variant(Angle)]
+    pub head_yaw: f32,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x63)]
+pub struct PlayTeleportEntity {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// In radians.
+    #[variant(Angle)]
+    pub yaw: f32,
+    /// In radians.
+    #[variant(Angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
+
+byte_flags! {
+    /// Flags of [PlayEntityEffect].
+    EffectFlags
+}
+
+impl EffectFlags {
+    /// Effect is given by a beacon or a conduit, its particles are less visible.
+    pub const AMBIENT: Self = Self(0x01);
+    pub const SHOW_PARTICLES: Self = Self(0x02);
+    pub const SHOW_ICON: Self = Self(0x04);
+
+    pub const fn ambient(self) -> bool {
+        self.contains(Self::AMBIENT)
+    }
+
+    pub const fn show_particles(self) -> bool {
+        self.contains(Self::SHOW_PARTICLES)
+    }
+
+    pub const fn show_icon(self) -> bool {
+        self.contains(Self::SHOW_ICON)
+    }
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x66)]
+pub struct PlayEntityEffect {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    /// Id in the `minecraft:mob_effect` registry.
+    pub effect_id: u8,
+    /// Level of the effect minus one.
+    pub amplifier: i8,
+    /// In ticks, -1 means infinite duration.
+    #[variant(VarInt)]
+    pub duration: i32,
+    pub flags: EffectFlags,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x39)]
+pub struct PlayRemoveEntityEffect {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    /// Id in the `minecraft:mob_effect` registry.
+    pub effect_id: u8,
+}
+
+/// Operation of [AttributeModifier]. Modifiers are applied in the order of the operations.
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(u8)]
+pub enum AttributeOperation {
+    /// Amount is added to the base value.
+    Add,
+    /// Base value multiplied by the amount is added.
+    MultiplyBase,
+    /// Value is multiplied by one plus the amount.
+    MultiplyTotal,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct AttributeModifier {
+    pub uuid: Uuid,
+    pub amount: f64,
+    pub operation: AttributeOperation,
+}
+
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct AttributeProperty<'a> {
+    /// For example `minecraft:generic.movement_speed`.
+    pub key: Identifier<'a>,
+    pub value: f64,
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x65)]
+pub struct PlayUpdateAttributes<'a> {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub properties: Vec<AttributeProperty<'a>>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x52)]
+pub struct PlaySetHealth {
+    /// 0 or less means the player is dead, 20 is full health.
+    pub health: f32,
+    /// From 0 to 20.
+    #[variant(VarInt)]
+    pub food: i32,
+    /// From 0 to 5.
+    pub saturation: f32,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum Hand {
+    MainHand,
+    OffHand,
+}
+
+/// Type of [PlayInteract]. Target is relative to the position of the entity.
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum InteractType {
+    Interact {
+        hand: Hand,
+    },
+    Attack,
+    InteractAt {
+        target_x: f32,
+        target_y: f32,
+        target_z: f32,
+        hand: Hand,
+    },
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x0F)]
+pub struct PlayInteract {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub interact_type: InteractType,
+    pub sneaking: bool,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum PlayerActionStatus {
+    StartedDigging,
+    CancelledDigging,
+    FinishedDigging,
+    DropItemStack,
+    DropItem,
+    /// Also finishes eating.
+    ShootArrow,
+    SwapItemInHand,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(u8)]
+pub enum BlockFace {
+    Bottom,
+    Top,
+    North,
+    South,
+    West,
+    East,
+}
+
+/// Digging and actions with the held item. Position and face are zeros for the item actions.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x1C)]
+pub struct PlayPlayerAction {
+    pub status: PlayerActionStatus,
+    pub location: BlockPosition,
+    pub face: BlockFace,
+    /// Sequence of the block change, which the server acknowledges.
+    #[variant(VarInt)]
+    pub sequence: i32,
+}
+
+#[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[enum_type(i32)]
+#[enum_variant(VarInt)]
+pub enum PlayerCommandAction {
+    StartSneaking,
+    StopSneaking,
+    LeaveBed,
+    StartSprinting,
+    StopSprinting,
+    StartHorseJump,
+    StopHorseJump,
+    OpenVehicleInventory,
+    StartElytraFlying,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x1D)]
+pub struct PlayPlayerCommand {
+    #[variant(VarInt)]
+    pub entity_id: i32,
+    pub action: PlayerCommandAction,
+    /// From 0 to 100 in [PlayerCommandAction::StartHorseJump], otherwise 0.
+    #[variant(VarInt)]
+    pub jump_boost: i32,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x2E)]
+pub struct PlaySwingArm {
+    pub hand: Hand,
+}
+
+/// Render or simulation distance in chunks.
+pub type ViewDistance = Bounded<i32, 2, 32>;
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x49)]
+pub struct PlaySetRenderDistance {
+    #[variant(BoundedVarInt)]
+    pub view_distance: ViewDistance,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x57)]
+pub struct PlaySetSimulationDistance {
+    #[variant(BoundedVarInt)]
+    pub simulation_distance: ViewDistance,
+}
+
+/// Changes selected slot of the hotbar.
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x47)]
+pub struct PlaySetHeldItem {
+    pub slot: Bounded<i8, 0, 8>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x27)]
+pub struct PlayServerSetHeldItem {
+    pub slot: Bounded<i16, 0, 8>,
+}
+
+pub const BRAND_CHANNEL: &str = "minecraft:brand";
+pub const REGISTER_CHANNEL: &str = "minecraft:register";
+pub const UNREGISTER_CHANNEL: &str = "minecraft:unregister";
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x15)]
+pub struct PlayPluginMessage<'a> {
+    pub channel: Identifier<'a>,
+    #[variant(RemainingBytesSlice)]
+    pub data: &'a [u8],
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Server, state = Play, id = 0x0C)]
+pub struct PlayServerPluginMessage<'a> {
+    pub channel: Identifier<'a>,
+    #[variant(RemainingBytesSlice)]
+    pub data: &'a [u8],
+}
+
+/// Data of [BRAND_CHANNEL], which is the name of the client or the server, like `vanilla`.
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct Brand<'a>(pub &'a str);
+
+/// Data of [REGISTER_CHANNEL] and [UNREGISTER_CHANNEL]. Channels are not prefixed by the length,
+/// but separated by zero bytes until the end of the data. Empty channels are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelList<'a>(pub Vec<&'a str>);
+
+impl<'a> PacketReadable<'a> for ChannelList<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let data: &'a [u8] = RemainingBytesSlice::read_variant(read)?;
+        data.split(|byte| *byte == 0)
+            .filter(|channel| !channel.is_empty())
+            .map(|channel| std::str::from_utf8(channel).map_err(|err| PacketReadableError::Any(err.into())))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl PacketWritable for ChannelList<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        for (index, channel) in self.0.iter().enumerate() {
+            if channel.is_empty() || channel.contains('\0') {
+                return Err(Error::msg("Channel is empty or contains zero byte"));
+            }
+            if index != 0 {
+                write.write_byte(0)?;
+            }
+            write.write_bytes(channel.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Items which are accepted by one slot of the recipe.
+#[cfg(feature = "fastnbt")]
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct Ingredient(pub Vec<Option<Slot>>);
+
+#[cfg(feature = "fastnbt")]
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct ShapelessRecipe<'a> {
+    /// Recipes with the same group are shown together in the recipe book.
+    pub group: &'a str,
+    pub ingredients: Vec<Ingredient>,
+    pub result: Option<Slot>,
+}
+
+/// Shaped recipe, where ingredients are written row by row without length.
+#[cfg(feature = "fastnbt")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapedRecipe<'a> {
+    pub width: i32,
+    pub height: i32,
+    pub group: &'a str,
+    pub ingredients: Vec<Ingredient>,
+    pub result: Option<Slot>,
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> PacketReadable<'a> for ShapedRecipe<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let width = VarInt::read_variant(read)?;
+        let height = VarInt::read_variant(read)?;
+        let group = <&'a str>::read(read)?;
+        let count = match width >= 0 && height >= 0 {
+            true => width as usize * height as usize,
+            false => return Err(PacketReadableError::BadLength),
+        };
+        let mut ingredients = preallocate(count);
+        for _ in 0..count {
+            ingredients.push(Ingredient::read(read)?);
+        }
+        Ok(Self { width, height, group, ingredients, result: Option::read(read)? })
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketWritable for ShapedRecipe<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        if self.width < 0 || self.height < 0 || self.ingredients.len() != self.width as usize * self.height as usize {
+            return Err(Error::msg("Count of the ingredients is not width multiplied by height"));
+        }
+        VarInt::write_variant(&self.width, write)?;
+        VarInt::write_variant(&self.height, write)?;
+        self.group.write(write)?;
+        for ingredient in &self.ingredients {
+            ingredient.write(write)?;
+        }
+        self.result.write(write)
+    }
+}
+
+/// Recipe of the furnace, blast furnace, smoker or campfire.
+#[cfg(feature = "fastnbt")]
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct CookingRecipe<'a> {
+    pub group: &'a str,
+    pub ingredient: Ingredient,
+    pub result: Option<Slot>,
+    pub experience: f32,
+    /// In ticks.
+    #[variant(VarInt)]
+    pub cooking_time: i32,
+}
+
+#[cfg(feature = "fastnbt")]
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct StonecuttingRecipe<'a> {
+    pub group: &'a str,
+    pub ingredient: Ingredient,
+    pub result: Option<Slot>,
+}
+
+#[cfg(feature = "fastnbt")]
+#[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+pub struct SmithingRecipe {
+    pub base: Ingredient,
+    pub addition: Ingredient,
+    pub result: Option<Slot>,
+}
+
+/// Recipe which is handled by the client itself, so it has no body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpecialRecipe {
+    ArmorDye,
+    BookCloning,
+    MapCloning,
+    MapExtending,
+    FireworkRocket,
+    FireworkStar,
+    FireworkStarFade,
+    RepairItem,
+    TippedArrow,
+    BannerDuplicate,
+    ShieldDecoration,
+    ShulkerBoxColoring,
+    SuspiciousStew,
+}
+
+impl SpecialRecipe {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpecialRecipe::ArmorDye => "minecraft:crafting_special_armordye",
+            SpecialRecipe::BookCloning => "minecraft:crafting_special_bookcloning",
+            SpecialRecipe::MapCloning => "minecraft:crafting_special_mapcloning",
+            SpecialRecipe::MapExtending => "minecraft:crafting_special_mapextending",
+            SpecialRecipe::FireworkRocket => "minecraft:crafting_special_firework_rocket",
+            SpecialRecipe::FireworkStar => "minecraft:crafting_special_firework_star",
+            SpecialRecipe::FireworkStarFade => "minecraft:crafting_special_firework_star_fade",
+            SpecialRecipe::RepairItem => "minecraft:crafting_special_repairitem",
+            SpecialRecipe::TippedArrow => "minecraft:crafting_special_tippedarrow",
+            SpecialRecipe::BannerDuplicate => "minecraft:crafting_special_bannerduplicate",
+            SpecialRecipe::ShieldDecoration => "minecraft:crafting_special_shielddecoration",
+            SpecialRecipe::ShulkerBoxColoring => "minecraft:crafting_special_shulkerboxcoloring",
+            SpecialRecipe::SuspiciousStew => "minecraft:crafting_special_suspiciousstew",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "minecraft:crafting_special_armordye" => Some(SpecialRecipe::ArmorDye),
+            "minecraft:crafting_special_bookcloning" => Some(SpecialRecipe::BookCloning),
+            "minecraft:crafting_special_mapcloning" => Some(SpecialRecipe::MapCloning),
+            "minecraft:crafting_special_mapextending" => Some(SpecialRecipe::MapExtending),
+            "minecraft:crafting_special_firework_rocket" => Some(SpecialRecipe::FireworkRocket),
+            "minecraft:crafting_special_firework_star" => Some(SpecialRecipe::FireworkStar),
+            "minecraft:crafting_special_firework_star_fade" => Some(SpecialRecipe::FireworkStarFade),
+            "minecraft:crafting_special_repairitem" => Some(SpecialRecipe::RepairItem),
+            "minecraft:crafting_special_tippedarrow" => Some(SpecialRecipe::TippedArrow),
+            "minecraft:crafting_special_bannerduplicate" => Some(SpecialRecipe::BannerDuplicate),
+            "minecraft:crafting_special_shielddecoration" => Some(SpecialRecipe::ShieldDecoration),
+            "minecraft:crafting_special_shulkerboxcoloring" => Some(SpecialRecipe::ShulkerBoxColoring),
+            "minecraft:crafting_special_suspiciousstew" => Some(SpecialRecipe::SuspiciousStew),
+            _ => None,
+        }
+    }
+}
+
+/// Body of the recipe, which is chosen by the type identifier.
+#[cfg(feature = "fastnbt")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipeBody<'a> {
+    Shapeless(ShapelessRecipe<'a>),
+    Shaped(ShapedRecipe<'a>),
+    Smelting(CookingRecipe<'a>),
+    Blasting(CookingRecipe<'a>),
+    Smoking(CookingRecipe<'a>),
+    CampfireCooking(CookingRecipe<'a>),
+    Stonecutting(StonecuttingRecipe<'a>),
+    Smithing(SmithingRecipe),
+    Special(SpecialRecipe),
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> RecipeBody<'a> {
+    /// Returns the type identifier of the recipe.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RecipeBody::Shapeless(_) => "minecraft:crafting_shapeless",
+            RecipeBody::Shaped(_) => "minecraft:crafting_shaped",
+            RecipeBody::Smelting(_) => "minecraft:smelting",
+            RecipeBody::Blasting(_) => "minecraft:blasting",
+            RecipeBody::Smoking(_) => "minecraft:smoking",
+            RecipeBody::CampfireCooking(_) => "minecraft:campfire_cooking",
+            RecipeBody::Stonecutting(_) => "minecraft:stonecutting",
+            RecipeBody::Smithing(_) => "minecraft:smithing",
+            RecipeBody::Special(special) => special.name(),
+        }
+    }
+
+    /// Reads body of the type. Bodies are not prefixed by their length, so unknown type is an error.
+    pub fn read_body<R>(type_name: &str, read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(match type_name {
+            "minecraft:crafting_shapeless" => RecipeBody::Shapeless(ShapelessRecipe::read(read)?),
+            "minecraft:crafting_shaped" => RecipeBody::Shaped(ShapedRecipe::read(read)?),
+            "minecraft:smelting" => RecipeBody::Smelting(CookingRecipe::read(read)?),
+            "minecraft:blasting" => RecipeBody::Blasting(CookingRecipe::read(read)?),
+            "minecraft:smoking" => RecipeBody::Smoking(CookingRecipe::read(read)?),
+            "minecraft:campfire_cooking" => RecipeBody::CampfireCooking(CookingRecipe::read(read)?),
+            "minecraft:stonecutting" => RecipeBody::Stonecutting(StonecuttingRecipe::read(read)?),
+            "minecraft:smithing" => RecipeBody::Smithing(SmithingRecipe::read(read)?),
+            _ => match SpecialRecipe::from_name(type_name) {
+                Some(special) => RecipeBody::Special(special),
+                None => return Err(PacketReadableError::Any(
+                    Error::msg(format!("Unknown recipe type {type_name}, its body can't be skipped"))
+                )),
+            },
+        })
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketWritable for RecipeBody<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        match self {
+            RecipeBody::Shapeless(recipe) => recipe.write(write),
+            RecipeBody::Shaped(recipe) => recipe.write(write),
+            RecipeBody::Smelting(recipe) | RecipeBody::Blasting(recipe) |
+            RecipeBody::Smoking(recipe) | RecipeBody::CampfireCooking(recipe) => recipe.write(write),
+            RecipeBody::Stonecutting(recipe) => recipe.write(write),
+            RecipeBody::Smithing(recipe) => recipe.write(write),
+            RecipeBody::Special(_) => Ok(()),
+        }
+    }
+}
+
+/// Recipe, which is written as the type identifier of the body, its id and the body.
+#[cfg(feature = "fastnbt")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe<'a> {
+    pub recipe_id: Identifier<'a>,
+    pub body: RecipeBody<'a>,
+}
+
+#[cfg(feature = "fastnbt")]
+impl<'a> PacketReadable<'a> for Recipe<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let type_name = Identifier::read(read)?.to_string();
+        let recipe_id = Identifier::read(read)?;
+        Ok(Self { recipe_id, body: RecipeBody::read_body(&type_name, read)? })
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+impl PacketWritable for Recipe<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.body.type_name().write(write)?;
+        self.recipe_id.write(write)?;
+        self.body.write(write)
+    }
+}
+
+#[cfg(feature = "fastnbt")]
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x67)]
+pub struct PlayDeclareRecipes<'a> {
+    pub recipes: Vec<Recipe<'a>>,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x1D)]
+pub struct PlayInitializeWorldBorder {
+    pub x: f64,
+    pub z: f64,
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    /// Milliseconds until the new diameter is reached.
+    #[variant(VarLong)]
+    pub speed: i64,
+    /// Usually 29999984.
+    #[variant(VarInt)]
+    pub portal_teleport_boundary: i32,
+    #[variant(VarInt)]
+    pub warning_blocks: i32,
+    /// In seconds.
+    #[variant(VarInt)]
+    pub warning_time: i32,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x41)]
+pub struct PlaySetBorderCenter {
+    pub x: f64,
+    pub z: f64,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x42)]
+pub struct PlaySetBorderLerpSize {
+    pub old_diameter: f64,
+    pub new_diameter: f64,
+    /// Milliseconds until the new diameter is reached.
+    #[variant(VarLong)]
+    pub speed: i64,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x43)]
+pub struct PlaySetBorderSize {
+    pub diameter: f64,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x44)]
+pub struct PlaySetBorderWarningDelay {
+    /// In seconds.
+    #[variant(VarInt)]
+    pub warning_time: i32,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x45)]
+pub struct PlaySetBorderWarningDistance {
+    #[variant(VarInt)]
+    pub warning_blocks: i32,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x59)]
+pub struct PlayUpdateTime {
+    /// In ticks, not changed by commands.
+    pub world_age: i64,
+    /// In ticks. Negative time stops the cycle of the day and its absolute value is used.
+    pub time_of_day: i64,
+}
+
+#[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+#[packet(bound = Client, state = Play, id = 0x4A)]
+pub struct PlaySetDefaultSpawnPosition {
+    pub location: BlockPosition,
+    /// Yaw in degrees.
+    pub angle: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play_round_trip<'a, T>(packet: &T, write: &'a mut Vec<u8>) -> T
+        where T: PacketReadable<'a> + PacketWritable + Packet + std::fmt::Debug + PartialEq {
+        PacketVariant::write_variant(packet, write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), T::id());
+        let result = T::read(&mut read).unwrap();
+        assert_eq!(read.available(), 0);
+        assert_eq!(&result, packet);
+        result
+    }
+
+    byte_flags! {
+        TestFlags
+    }
+
+    #[test]
+    fn byte_flags_tests() {
+        // Unknown bits are kept, so the flags are written back as they are read
+        let flags = TestFlags::read(&mut SlicePacketRead::new(&[0xA5])).unwrap();
+        assert_eq!(flags.bits(), 0xA5);
+        let mut write = Vec::new();
+        flags.write(&mut write).unwrap();
+        assert_eq!(write, &[0xA5]);
+        let mut flags = TestFlags::empty() | TestFlags::from_bits(0x03);
+        flags.set(TestFlags::from_bits(0x01), false);
+        assert!(flags.contains(TestFlags::from_bits(0x02)) && !flags.contains(TestFlags::from_bits(0x01)));
+        assert_eq!(TestFlags::default(), TestFlags::empty());
+    }
+
+    #[test]
+    fn keep_alive_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayKeepAlive { id: 0x0102030405060708 }, &mut write);
+        assert_eq!(write, &[0x1E, 1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayKeepAliveResponse { id: -2 }, &mut write);
+        assert_eq!(write, &[0x11, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE]);
+        let mut write = vec![0x17];
+        r#"{"text":"Kicked","extra":[]}"#.write(&mut write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), PlayDisconnect::ID);
+        assert!(matches!(PlayDisconnect::read(&mut read).unwrap().reason, Component::Text(text) if text.text == "Kicked"));
+    }
+
+    #[test]
+    fn previous_game_mode_tests() {
+        let mut write = Vec::new();
+        PreviousGameMode::write_variant(&None, &mut write).unwrap();
+        PreviousGameMode::write_variant(&Some(GameMode::Spectator), &mut write).unwrap();
+        assert_eq!(write, &[0xFF, 0x03]);
+        let mut read = SlicePacketRead::new(&[0xFF, 0x03, 0x04]);
+        assert_eq!(PreviousGameMode::read_variant(&mut read).unwrap(), None);
+        assert_eq!(PreviousGameMode::read_variant(&mut read).unwrap(), Some(GameMode::Spectator));
+        assert!(matches!(PreviousGameMode::read_variant(&mut read), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn join_game_tests() {
+        let overworld = Identifier::new_fulled("minecraft:overworld").unwrap();
+        let nether = Identifier::new_fulled("minecraft:the_nether").unwrap();
+        let packet = PlayJoinGame {
+            entity_id: 25,
+            hardcore: false,
+            game_mode: GameMode::Creative,
+            previous_game_mode: None,
+            dimensions: vec![overworld.clone(), nether],
+            registry_codec: fastnbt::Value::Compound(Default::default()),
+            dimension_type: overworld.clone(),
+            dimension_name: overworld.clone(),
+            hashed_seed: -1,
+            max_players: 20,
+            view_distance: 10,
+            simulation_distance: 12,
+            reduced_debug_info: false,
+            enable_respawn_screen: true,
+            debug: false,
+            flat: true,
+            death_location: None,
+        };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        let mut expected = vec![0x23, 0x00, 0x00, 0x00, 0x19, 0x00, 0x01, 0xFF, 0x02];
+        expected.push(19);
+        expected.extend_from_slice(b"minecraft:overworld");
+        expected.push(20);
+        expected.extend_from_slice(b"minecraft:the_nether");
+        expected.extend_from_slice(&[0x0A, 0x00, 0x00, 0x00]);
+        for _ in 0..2 {
+            expected.push(19);
+            expected.extend_from_slice(b"minecraft:overworld");
+        }
+        expected.extend_from_slice(&[0xFF; 8]);
+        expected.extend_from_slice(&[20, 10, 12, 0x00, 0x01, 0x00, 0x01, 0x00]);
+        assert_eq!(write, expected);
+        let packet = PlayJoinGame {
+            death_location: Some(GlobalPosition { dimension: overworld, position: BlockPosition { x: 1, y: -2, z: 3 } }),
+            ..packet
+        };
+        play_round_trip(&packet, &mut Vec::new());
+    }
+
+    #[test]
+    fn movement_tests() {
+        let mut flags = RelativeFlags::empty();
+        flags.set(RelativeFlags::YAW, true);
+        assert!(flags.yaw() && !flags.x() && !flags.pitch());
+        let packet = PlaySynchronizePlayerPosition {
+            x: 0.5, y: 64.0, z: -0.5, yaw: 90.0, pitch: 0.0, flags, teleport_id: 1, dismount_vehicle: false,
+        };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(&write[33..], &[0x08, 0x01, 0x00]);
+        let flags = RelativeFlags::X | RelativeFlags::Y | RelativeFlags::Z | RelativeFlags::PITCH;
+        assert_eq!(flags.bits(), 0x17);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySynchronizePlayerPosition { flags, ..packet }, &mut write);
+        assert_eq!(write[33], 0x17);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetPlayerPosition { x: 1.0, y: 2.0, z: 3.0, on_ground: true }, &mut write);
+        assert_eq!(write.len(), 1 + 24 + 1);
+        assert_eq!(&write[..9], &[0x13, 0x3F, 0xF0, 0, 0, 0, 0, 0, 0]);
+        play_round_trip(&PlaySetPlayerPositionAndRotation {
+            x: 1.0, y: 2.0, z: 3.0, yaw: -45.0, pitch: 30.0, on_ground: false,
+        }, &mut Vec::new());
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetPlayerRotation { yaw: 1.0, pitch: -1.0, on_ground: true }, &mut write);
+        assert_eq!(write, &[0x15, 0x3F, 0x80, 0, 0, 0xBF, 0x80, 0, 0, 0x01]);
+    }
+
+    #[test]
+    fn non_finite_movement_tests() {
+        let mut write = Vec::new();
+        PlaySetPlayerPosition { x: 1.5, y: -64.0, z: 3.0e7, on_ground: true }.write(&mut write).unwrap();
+        assert_eq!(
+            PlaySetPlayerPosition::read(&mut SlicePacketRead::new(&write)).unwrap(),
+            PlaySetPlayerPosition { x: 1.5, y: -64.0, z: 3.0e7, on_ground: true },
+        );
+        // Crafted NaN, infinity and negative infinity of each coordinate
+        for bits in [0x7FF8000000000000u64, 0x7FF0000000000001, 0x7FF0000000000000, 0xFFF0000000000000] {
+            for axis in 0..3 {
+                let mut crafted = write.clone();
+                crafted[axis * 8..axis * 8 + 8].copy_from_slice(&bits.to_be_bytes());
+                let error = PlaySetPlayerPosition::read_with_path(&mut SlicePacketRead::new(&crafted)).unwrap_err();
+                assert!(matches!(error.error, PacketReadableError::NonFinite));
+                assert_eq!((error.field_path.unwrap(), error.offset), (["x", "y", "z"][axis].to_string(), axis * 8));
+            }
+        }
+        let mut rotation = vec![0x7F, 0xC0, 0, 0, 0, 0, 0, 0, 0x01];
+        assert!(matches!(PlaySetPlayerRotation::read(&mut SlicePacketRead::new(&rotation)), Err(PacketReadableError::NonFinite)));
+        rotation[..4].copy_from_slice(&f32::NEG_INFINITY.to_be_bytes());
+        assert!(matches!(PlaySetPlayerRotation::read(&mut SlicePacketRead::new(&rotation)), Err(PacketReadableError::NonFinite)));
+        // Pitch is clamped like in vanilla
+        rotation[..4].copy_from_slice(&720.5f32.to_be_bytes());
+        rotation[4..8].copy_from_slice(&(-135.0f32).to_be_bytes());
+        let read = PlaySetPlayerRotation::read(&mut SlicePacketRead::new(&rotation)).unwrap();
+        assert_eq!((read.yaw, read.pitch), (720.5, -90.0));
+        rotation[4..8].copy_from_slice(&f32::INFINITY.to_be_bytes());
+        assert!(matches!(PlaySetPlayerRotation::read(&mut SlicePacketRead::new(&rotation)), Err(PacketReadableError::NonFinite)));
+
+        // Writing is rejected as well
+        let error = PlaySetPlayerPosition { x: f64::NAN, y: 0.0, z: 0.0, on_ground: true }.write(&mut Vec::new()).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketWritableError::NonFinite)));
+        let packet = PlaySetPlayerPositionAndRotation { x: 0.0, y: 0.0, z: 0.0, yaw: 0.0, pitch: 90.5, on_ground: true };
+        let error = packet.write(&mut Vec::new()).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketWritableError::FloatOutOfBounds { value, .. }) if *value == 90.5));
+        assert_eq!(error.to_string(), "90.5 is out of range -90..=90");
+        let error = PlaySetPlayerRotation { yaw: 0.0, pitch: f32::NAN, on_ground: true }.write(&mut Vec::new()).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketWritableError::NonFinite)));
+        play_round_trip(&PlaySetPlayerPositionAndRotation { pitch: -90.0, ..packet }, &mut Vec::new());
+    }
+
+    #[test]
+    fn player_info_tests() {
+        let uuid = Uuid::from_u128(0x0102030405060708090A0B0C0D0E0F10);
+        let signed = PlayerInfoAdd {
+            uuid,
+            name: "Notch",
+            properties: vec![
+                LoginSuccessProperty { name: "textures", value: "dGV4dHVyZXM=", signature: Some("c2lnbmF0dXJl") },
+                LoginSuccessProperty { name: "cape", value: "Y2FwZQ==", signature: None },
+            ],
+            game_mode: GameMode::Creative,
+            ping: 150,
+            display_name: None,
+            signature_data: Some(ProfilePublicKey { timestamp: 1, public_key: &[1, 2], signature: &[3] }),
+        };
+        let unsigned = PlayerInfoAdd {
+            uuid: Uuid::nil(),
+            name: "jeb_",
+            properties: vec![],
+            game_mode: GameMode::Spectator,
+            ping: 0,
+            display_name: None,
+            signature_data: None,
+        };
+        let mut write = Vec::new();
+        play_round_trip(&PlayPlayerInfo::AddPlayer(vec![signed, unsigned]), &mut write);
+        assert_eq!(&write[..3], &[0x34, 0x00, 0x02]);
+        assert_eq!(&write[19..25], &[0x05, b'N', b'o', b't', b'c', b'h']);
+        // Property count, textures with the signature
+        assert_eq!(&write[25..27], &[0x02, 0x08]);
+        assert_eq!(write[48], 0x01);
+        assert_eq!(&write[write.len() - 5..], &[0x00, 0x03, 0x00, 0x00, 0x00]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayPlayerInfo::UpdateGameMode(vec![PlayerInfoGameMode { uuid, game_mode: GameMode::Adventure }]), &mut write);
+        assert_eq!(&write[..3], &[0x34, 0x01, 0x01]);
+        assert_eq!(write[19], 0x02);
+        let mut write = Vec::new();
+        play_round_trip(&PlayPlayerInfo::UpdateLatency(vec![PlayerInfoLatency { uuid, ping: 300 }]), &mut write);
+        assert_eq!(&write[19..], &[0xAC, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayPlayerInfo::UpdateDisplayName(vec![
+            PlayerInfoDisplayName { uuid, display_name: None },
+            PlayerInfoDisplayName { uuid: Uuid::nil(), display_name: None },
+        ]), &mut write);
+        assert_eq!(&write[..3], &[0x34, 0x03, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayPlayerInfo::RemovePlayer(vec![uuid, Uuid::nil()]), &mut write);
+        assert_eq!(write.len(), 3 + 32);
+        assert!(matches!(PlayPlayerInfo::read(&mut SlicePacketRead::new(&[0x05, 0x00])), Err(PacketReadableError::BadEnumValue)));
+        // Game mode 4 doesn't exist
+        let mut write = vec![0x01, 0x01];
+        write.extend_from_slice(&[0; 16]);
+        write.push(0x04);
+        assert!(matches!(PlayPlayerInfo::read(&mut SlicePacketRead::new(&write)), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn tags_tests() {
+        let identifier = |name: &str| Identifier::new_fulled(name.to_string()).unwrap();
+        let blocks: Vec<_> = (0..8)
+            .map(|index| (identifier(&format!("minecraft:block_tag_{}", index)), (0..index * 100).collect()))
+            .collect();
+        let items: Vec<_> = (0..5)
+            .map(|index| (identifier(&format!("minecraft:item_tag_{}", index)), vec![index; index as usize]))
+            .collect();
+        let packet = PlayUpdateTags::from_groups([
+            (identifier("minecraft:block"), blocks),
+            (identifier("minecraft:item"), items),
+        ]);
+        assert_eq!(packet.groups[0].tags[3].entries.len(), 300);
+        assert_eq!(packet.groups[1].tags[4].entries, &[4, 4, 4, 4]);
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(&write[..3], &[0x68, 0x02, 15]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateTags::from_groups([(identifier("minecraft:fluid"), vec![])]), &mut write);
+        assert_eq!(&write[write.len() - 1..], &[0x00]);
+    }
+
+    /// Chat components are written without empty `extra`, which they can't be read without,
+    /// so packets with them are read from the json with `extra`.
+    fn component_bytes(text: &str) -> Vec<u8> {
+        let mut write = Vec::new();
+        format!(r#"{{"text":"{}","extra":[]}}"#, text).write(&mut write).unwrap();
+        write
+    }
+
+    fn read_text(component: &Component) -> String {
+        match component {
+            Component::Text(text) => text.text.to_string(),
+            _ => panic!("Not a text component"),
+        }
+    }
+
+    #[test]
+    fn boss_bar_tests() {
+        let uuid = Uuid::from_u128(1);
+        let flags = BossBarFlags::DARKEN_SKY | BossBarFlags::CREATE_FOG;
+        assert!(flags.darken_sky() && flags.create_fog() && !flags.dragon_bar());
+        let mut bytes = uuid.as_bytes().to_vec();
+        bytes.push(0x00);
+        bytes.extend_from_slice(&component_bytes("Ender Dragon"));
+        bytes.extend_from_slice(&[0x3F, 0x80, 0x00, 0x00, 0x05, 0x04, 0x05]);
+        let packet = PlayBossBar::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        match &packet.action {
+            BossBarAction::Add { title, health, color, division, flags: read_flags } => {
+                assert_eq!(read_text(title), "Ender Dragon");
+                assert_eq!((*health, *color, *division, *read_flags), (1.0, BossBarColor::Purple, BossBarDivision::Notches20, flags));
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+        let mut write = Vec::new();
+        packet.write(&mut write).unwrap();
+        assert_eq!(&write[..17], &bytes[..17]);
+        assert_eq!(&write[write.len() - 7..], &bytes[bytes.len() - 7..]);
+        let mut bytes = uuid.as_bytes().to_vec();
+        bytes.push(0x03);
+        bytes.extend_from_slice(&component_bytes("Wither"));
+        let packet = PlayBossBar::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert!(matches!(&packet.action, BossBarAction::UpdateTitle(title) if read_text(title) == "Wither"));
+        let mut write = Vec::new();
+        play_round_trip(&PlayBossBar { uuid, action: BossBarAction::Remove }, &mut write);
+        assert_eq!(&write[17..], &[0x01]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayBossBar { uuid, action: BossBarAction::UpdateHealth(0.5) }, &mut write);
+        assert_eq!(&write[17..], &[0x02, 0x3F, 0x00, 0x00, 0x00]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayBossBar {
+            uuid,
+            action: BossBarAction::UpdateStyle { color: BossBarColor::White, division: BossBarDivision::None },
+        }, &mut write);
+        assert_eq!(&write[17..], &[0x04, 0x06, 0x00]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayBossBar { uuid, action: BossBarAction::UpdateFlags(BossBarFlags::from_bits(0x0A)) }, &mut write);
+        assert_eq!(&write[17..], &[0x05, 0x0A]);
+        let mut bytes = vec![0; 16];
+        bytes.push(0x06);
+        assert!(matches!(PlayBossBar::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BadEnumValue)));
+        bytes[16] = 0x04;
+        bytes.extend_from_slice(&[0x07, 0x00]);
+        assert!(matches!(PlayBossBar::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn title_tests() {
+        let bytes = component_bytes("Hello");
+        let title = PlaySetTitleText::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert_eq!(read_text(&title.text), "Hello");
+        let subtitle = PlaySetSubtitleText::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        let action_bar = PlaySetActionBarText::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert_eq!((&subtitle.text, &action_bar.text), (&title.text, &title.text));
+        let mut write = Vec::new();
+        PacketVariant::write_variant(&action_bar, &mut write).unwrap();
+        let mut expected = vec![0x40];
+        r#"{"text":"Hello"}"#.write(&mut expected).unwrap();
+        assert_eq!(write, expected);
+        let mut write = Vec::new();
+        PacketVariant::write_variant(&subtitle, &mut write).unwrap();
+        assert_eq!(write[0], 0x58);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetTitleAnimationTimes { fade_in: 10, stay: 70, fade_out: 20 }, &mut write);
+        assert_eq!(write, &[0x5B, 0, 0, 0, 10, 0, 0, 0, 70, 0, 0, 0, 20]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayClearTitles { reset: true }, &mut write);
+        assert_eq!(write, &[0x0D, 0x01]);
+    }
+
+    #[test]
+    fn sound_tests() {
+        let position = EffectPosition::from_block_f64(1.5, -64.0, 0.1).unwrap();
+        assert_eq!(position, EffectPosition { x: 12, y: -512, z: 1 });
+        assert_eq!(position.to_f64(), [1.5, -64.0, 0.125]);
+        assert!(EffectPosition::from_block_f64(f64::NAN, 0.0, 0.0).is_err());
+        assert!(EffectPosition::from_block_f64(0.0, 3e8, 0.0).is_err());
+        let mut write = Vec::new();
+        play_round_trip(&PlaySoundEffect {
+            sound_id: 300,
+            category: SoundCategory::Hostile,
+            position,
+            volume: 1.0,
+            pitch: 0.5,
+            seed: 7,
+        }, &mut write);
+        assert_eq!(&write[..4], &[0x5D, 0xAC, 0x02, 0x05]);
+        assert_eq!(&write[4..16], &[0, 0, 0, 12, 0xFF, 0xFF, 0xFE, 0x00, 0, 0, 0, 1]);
+        assert_eq!(&write[16..], &[0x3F, 0x80, 0, 0, 0x3F, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayCustomSoundEffect {
+            sound: Identifier::new_fulled("bird:ping").unwrap(),
+            category: SoundCategory::Voice,
+            position,
+            volume: 2.0,
+            pitch: 1.0,
+            seed: 0,
+        }, &mut write);
+        assert_eq!(&write[..12], &[0x16, 0x09, b'b', b'i', b'r', b'd', b':', b'p', b'i', b'n', b'g', 0x09]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayEntitySoundEffect {
+            sound_id: 1,
+            category: SoundCategory::Player,
+            entity_id: 25,
+            volume: 1.0,
+            pitch: 1.0,
+            seed: -1,
+        }, &mut write);
+        assert_eq!(&write[..4], &[0x5C, 0x01, 0x07, 0x19]);
+        let mut read = SlicePacketRead::new(&[0x0A]);
+        assert!(matches!(SoundCategory::read(&mut read), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn stop_sound_tests() {
+        let sound = Identifier::new_fulled("minecraft:music.end").unwrap();
+        let mut sound_bytes = vec![19];
+        sound_bytes.extend_from_slice(b"minecraft:music.end");
+        let cases = [
+            (None, None, vec![0x00]),
+            (Some(SoundCategory::Music), None, vec![0x01, 0x01]),
+            (None, Some(sound.clone()), [vec![0x02], sound_bytes.clone()].concat()),
+            (Some(SoundCategory::Ambient), Some(sound.clone()), [vec![0x03, 0x08], sound_bytes].concat()),
+        ];
+        for (category, sound, expected) in cases {
+            let mut write = Vec::new();
+            play_round_trip(&PlayStopSound::new(category, sound), &mut write);
+            assert_eq!(&write[..1], &[0x5E]);
+            assert_eq!(&write[1..], expected.as_slice());
+        }
+        // Flags without the fields
+        let mut read = SlicePacketRead::new(&[0x03, 0x01]);
+        assert!(matches!(PlayStopSound::read(&mut read), Err(PacketReadableError::BytesExceeded)));
+        // Unknown flags are kept, field which is not marked by them can't be written
+        let mut read = SlicePacketRead::new(&[0x05, 0x02]);
+        let packet = PlayStopSound::read(&mut read).unwrap();
+        assert_eq!((packet.flags, packet.category, packet.sound), (0x05, MaskedOption(Some(SoundCategory::Record)), MaskedOption(None)));
+        let packet = PlayStopSound { flags: 0, category: MaskedOption(Some(SoundCategory::Weather)), sound: MaskedOption(None) };
+        assert!(packet.write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn map_data_tests() {
+        let icon = MapIcon { icon_type: MapIconType::RedX, x: -128, z: 127, direction: 15, display_name: None };
+        let packet = PlayMapData { map_id: 3, scale: 0, locked: false, icons: Some(vec![icon]), patch: None };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(write, &[0x24, 0x03, 0x00, 0x00, 0x01, 0x01, 0x1A, 0x80, 0x7F, 0x0F, 0x00, 0x00]);
+        // Absent icons and patch
+        let packet = PlayMapData { map_id: 3, scale: 4, locked: true, icons: None, patch: None };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(write, &[0x24, 0x03, 0x04, 0x01, 0x00, 0x00]);
+        let mut colors = [[0u8; 128]; 128];
+        colors[0][1] = 4;
+        colors[127][0] = 34;
+        let packet = PlayMapData { map_id: 0, scale: 0, locked: false, icons: None, patch: Some(MapPatch::full(&colors)) };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(&write[..10], &[0x24, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00, 0x80]);
+        assert_eq!(write.len(), 12 + 128 * 128);
+        assert_eq!((write[12 + 1], write[12 + 127 * 128]), (4, 34));
+        let patch = MapPatch::region(&colors, 0, 126, 2, 2).unwrap();
+        assert_eq!(patch.data, vec![0, 0, 34, 0]);
+        let packet = PlayMapData { map_id: 0, scale: 0, locked: false, icons: None, patch: Some(patch) };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(&write[5..], &[0x02, 0x02, 0x00, 0x7E, 0x04, 0, 0, 34, 0]);
+        assert!(MapPatch::region(&colors, 127, 0, 2, 1).is_err());
+        assert!(MapPatch::region(&colors, 0, 0, 0, 1).is_err());
+        let bad = MapPatch { columns: 2, rows: 2, x: 0, z: 0, data: vec![0; 3] };
+        assert!(OptionalMapPatch::write_variant(&Some(bad), &mut Vec::new()).is_err());
+        let mut read = SlicePacketRead::new(&[0x02, 0x02, 0x00, 0x00, 0x03, 0, 0, 0]);
+        assert!(matches!(OptionalMapPatch::read_variant(&mut read), Err(PacketReadableError::BadLength)));
+    }
+
+    #[test]
+    fn objective_tests() {
+        let mut bytes = vec![0x04];
+        bytes.extend_from_slice(b"kill");
+        bytes.push(0x00);
+        bytes.extend_from_slice(&component_bytes("Kills"));
+        bytes.push(0x01);
+        let packet = PlayUpdateObjectives::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert_eq!(packet.name, "kill");
+        match &packet.action {
+            ObjectiveAction::Create { display_name, render_type } => {
+                assert_eq!((read_text(display_name), *render_type), ("Kills".into(), ObjectiveRenderType::Hearts));
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+        bytes[5] = 0x02;
+        let packet = PlayUpdateObjectives::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert!(matches!(packet.action, ObjectiveAction::Update { render_type: ObjectiveRenderType::Hearts, .. }));
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateObjectives { name: "kill", action: ObjectiveAction::Remove }, &mut write);
+        assert_eq!(write, &[0x53, 0x04, b'k', b'i', b'l', b'l', 0x01]);
+        bytes[5] = 0x03;
+        assert!(matches!(PlayUpdateObjectives::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BadEnumValue)));
+        let long = PlayUpdateObjectives { name: "seventeen_symbols", action: ObjectiveAction::Remove };
+        assert!(long.write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn score_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateScore {
+            entity_name: "Notch",
+            action: ScoreAction::Update { objective_name: "kill", value: 300 },
+        }, &mut write);
+        assert_eq!(&write[..7], &[0x56, 0x05, b'N', b'o', b't', b'c', b'h']);
+        assert_eq!(&write[7..], &[0x00, 0x04, b'k', b'i', b'l', b'l', 0xAC, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateScore { entity_name: "Notch", action: ScoreAction::Remove { objective_name: "kill" } }, &mut write);
+        assert_eq!(&write[7..], &[0x01, 0x04, b'k', b'i', b'l', b'l']);
+        let mut write = Vec::new();
+        play_round_trip(&PlayDisplayObjective { slot: DisplaySlot::Sidebar, name: "kill" }, &mut write);
+        assert_eq!(&write[..2], &[0x4C, 0x01]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayDisplayObjective { slot: DisplaySlot::TeamSidebar(TeamColor::White), name: "" }, &mut write);
+        assert_eq!(write, &[0x4C, 0x12, 0x00]);
+        let slot = DisplaySlot::TeamSidebar(TeamColor::Bold);
+        assert!(slot.write(&mut Vec::new()).is_err());
+        assert!(matches!(DisplaySlot::read(&mut SlicePacketRead::new(&[0x13])), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn team_tests() {
+        let mut info = component_bytes("Red team");
+        info.push(0x03);
+        info.extend_from_slice(&[0x0E]);
+        info.extend_from_slice(b"hideForOwnTeam");
+        info.extend_from_slice(&[0x05]);
+        info.extend_from_slice(b"never");
+        info.push(0x0C);
+        info.extend_from_slice(&component_bytes("[R] "));
+        info.extend_from_slice(&component_bytes(""));
+        let mut bytes = vec![0x03, b'r', b'e', b'd', 0x00];
+        bytes.extend_from_slice(&info);
+        bytes.extend_from_slice(&[0x02, 0x05]);
+        bytes.extend_from_slice(b"Notch");
+        bytes.extend_from_slice(&[0x03]);
+        bytes.extend_from_slice(b"jeb");
+        let packet = PlayUpdateTeams::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert_eq!(packet.name, "red");
+        match &packet.action {
+            TeamAction::Create { info, entities } => {
+                assert_eq!(read_text(&info.display_name), "Red team");
+                assert!(info.flags.friendly_fire() && info.flags.see_invisible_teammates());
+                assert_eq!(
+                    (info.name_tag_visibility, info.collision_rule, info.color),
+                    (NameTagVisibility::HideForOwnTeam, CollisionRule::Never, TeamColor::Red)
+                );
+                assert_eq!((read_text(&info.prefix), read_text(&info.suffix)), ("[R] ".into(), "".into()));
+                assert_eq!(entities, &["Notch", "jeb"]);
+            }
+            action => panic!("Unexpected action {:?}", action),
+        }
+        let mut bytes = vec![0x03, b'r', b'e', b'd', 0x02];
+        bytes.extend_from_slice(&info);
+        let packet = PlayUpdateTeams::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert!(matches!(&packet.action, TeamAction::Update(info) if info.color == TeamColor::Red));
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateTeams { name: "red", action: TeamAction::Remove }, &mut write);
+        assert_eq!(write, &[0x55, 0x03, b'r', b'e', b'd', 0x01]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateTeams { name: "red", action: TeamAction::AddEntities(vec!["jeb"]) }, &mut write);
+        assert_eq!(&write[5..], &[0x03, 0x01, 0x03, b'j', b'e', b'b']);
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateTeams { name: "red", action: TeamAction::RemoveEntities(vec![]) }, &mut write);
+        assert_eq!(&write[5..], &[0x04, 0x00]);
+        let read = PlayUpdateTeams::read(&mut SlicePacketRead::new(&[0x03, b'r', b'e', b'd', 0x05]));
+        assert!(matches!(read, Err(PacketReadableError::BadEnumValue)));
+        let mut bytes = vec![0x11];
+        bytes.extend_from_slice(b"seventeen_symbols");
+        bytes.push(0x01);
+        assert!(PlayUpdateTeams::read(&mut SlicePacketRead::new(&bytes)).is_err());
+        let long = PlayUpdateTeams { name: "seventeen_symbols", action: TeamAction::Remove };
+        assert!(long.write(&mut Vec::new()).is_err());
+        let entity = "e".repeat(41);
+        let long = PlayUpdateTeams { name: "red", action: TeamAction::AddEntities(vec![&entity]) };
+        assert!(long.write(&mut Vec::new()).is_err());
+        assert!(matches!(CollisionRule::read(&mut SlicePacketRead::new(&[0x01, b'a'])), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn client_settings_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayConfirmTeleportation { teleport_id: 300 }, &mut write);
+        assert_eq!(write, &[0x00, 0xAC, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayClientCommand { action: ClientCommandAction::RequestStats }, &mut write);
+        assert_eq!(write, &[0x06, 0x01]);
+        assert!(matches!(
+            PlayClientCommand::read(&mut SlicePacketRead::new(&[0x02])),
+            Err(PacketReadableError::BadEnumValue)
+        ));
+
+        // Locale is at the limit and unknown high bit of the skin parts is kept
+        let information = PlayClientInformation {
+            locale: "abcdefghijklmnop",
+            view_distance: 12,
+            chat_mode: ChatMode::CommandsOnly,
+            chat_colors: true,
+            displayed_skin_parts: SkinParts::from_bits(0xFF),
+            main_hand: MainHand::Right,
+            enable_text_filtering: false,
+            allow_server_listings: true,
+        };
+        let mut write = Vec::new();
+        let parts = play_round_trip(&information, &mut write).displayed_skin_parts;
+        assert_eq!(&write[..2], &[0x07, 0x10]);
+        assert_eq!(&write[18..], &[0x0C, 0x01, 0x01, 0xFF, 0x01, 0x00, 0x01]);
+        assert_eq!(parts.bits(), 0xFF);
+        assert!(parts.contains(SkinParts::ALL) && parts.cape() && parts.jacket() && parts.sleeves() && parts.pants() && parts.hat());
+        let mut parts = SkinParts::CAPE | SkinParts::LEFT_SLEEVE;
+        assert!(!parts.sleeves());
+        parts.set(SkinParts::RIGHT_SLEEVE, true);
+        parts.set(SkinParts::CAPE, false);
+        assert!(parts.sleeves() && !parts.cape());
+        assert_eq!(parts.bits(), 0x0C);
+
+        assert!(PlayClientInformation { locale: "abcdefghijklmnopq", ..information.clone() }.write(&mut Vec::new()).is_err());
+        let main_hand = write.len() - 3;
+        write[main_hand] = 0x02;
+        assert!(matches!(
+            PlayClientInformation::read(&mut SlicePacketRead::new(&write[1..])),
+            Err(PacketReadableError::BadEnumValue)
+        ));
+        write[20] = 0x03;
+        assert!(PlayClientInformation::read(&mut SlicePacketRead::new(&write[1..])).is_err());
+    }
+
+    #[test]
+    fn update_light_tests() {
+        let mut light = LightData::new(true);
+        light.block_light.set_empty(3);
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateLight { chunk_x: -1, chunk_z: 2, light }, &mut write);
+        assert_eq!(&write[..7], &[0x22, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x02]);
+        assert_eq!(&write[7..], &[0x01, 0x00, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x08, 0x00, 0x00]);
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn chunk_data_tests() {
+        use crate::packet_chunk::ChunkSection;
+        // Superflat chunk of bedrock, two layers of dirt and grass at the bottom of the overworld
+        let mut bottom = ChunkSection::empty(0, 1);
+        for x in 0..16 {
+            for z in 0..16 {
+                for (y, block) in [79, 10, 10, 9].into_iter().enumerate() {
+                    bottom.block_states.set(x, y, z, block);
+                }
+            }
+        }
+        bottom.block_count = 1024;
+        let mut sections = vec![ChunkSection::empty(0, 1); 24];
+        sections[0] = bottom;
+        sections[5].block_states.set(3, 4, 5, 1);
+        sections[5].block_count = 1;
+        let mut heightmaps = Heightmaps::default();
+        heightmaps.motion_blocking.set_height(0, 0, -60).unwrap();
+        let mut light = LightData::new(true);
+        light.set_sky_section(25, [0xFF; 2048]);
+        let chest = ChunkBlockEntity { x: 3, z: 5, y: 20, kind: 2, data: None };
+        let packet = ChunkPacketBuilder::new(-2, 7)
+            .with_sections(sections)
+            .with_heightmaps(heightmaps)
+            .with_block_entity(chest.clone())
+            .with_light(light)
+            .build()
+            .unwrap();
+        let mut write = Vec::new();
+        let read = play_round_trip(&packet, &mut write);
+        assert_eq!(&write[..9], &[0x21, 0xFF, 0xFF, 0xFF, 0xFE, 0x00, 0x00, 0x00, 0x07]);
+        let sections_size: usize = read.sections.iter().map(|section| section.size().unwrap()).sum();
+        let heightmaps_size = read.heightmaps.size().unwrap();
+        let mut prefix = SlicePacketRead::new(&write[9 + heightmaps_size..]);
+        assert_eq!(VarInt::read_variant(&mut prefix).unwrap() as usize, sections_size);
+        assert_eq!(read.sections.len(), 24);
+        for (x, y, z, block) in [(0, 0, 0, 79), (15, 1, 7, 10), (8, 3, 15, 9), (4, 4, 4, 0)] {
+            assert_eq!(read.sections[0].block_states.get(x, y, z), block);
+        }
+        assert_eq!(read.sections[5].block_states.get(3, 4, 5), 1);
+        assert_eq!(read.sections[23].block_states.get(15, 15, 15), 0);
+        assert_eq!(read.heightmaps.motion_blocking.get_height(0, 0).unwrap(), -60);
+        assert_eq!(read.block_entities, vec![chest]);
+
+        // Block entity with nbt and the packed xz
+        let sign = ChunkBlockEntity { x: 15, z: 1, y: -64, kind: 7, data: Some(fastnbt::nbt!({ "Text1": "a" })) };
+        let mut write = Vec::new();
+        sign.write(&mut write).unwrap();
+        assert_eq!(&write[..5], &[0xF1, 0xFF, 0xC0, 0x07, 0x0A]);
+        assert_eq!(ChunkBlockEntity::read(&mut SlicePacketRead::new(&write)).unwrap(), sign);
+        assert!(ChunkBlockEntity { x: 16, ..sign.clone() }.write(&mut Vec::new()).is_err());
+
+        let builder = ChunkPacketBuilder::new(0, 0).with_sections(vec![ChunkSection::empty(0, 1); 24]);
+        let error = builder.clone().with_block_entity(ChunkBlockEntity { y: 320, ..sign }).build().unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketWritableError::OutOfBounds { value: 320, min: -64, max: 319 })));
+        assert_eq!(
+            builder.clone().with_world(0, 256).build().unwrap_err().to_string(),
+            "Expected 16 sections of the world height 256, got 24",
+        );
+        // Other worlds are read with their height
+        let nether = ChunkPacketBuilder::new(0, 0)
+            .with_world(0, 256)
+            .with_sections(vec![ChunkSection::empty(0, 1); 16])
+            .build()
+            .unwrap();
+        let mut write = Vec::new();
+        nether.write(&mut write).unwrap();
+        let read = PlayChunkDataAndUpdateLight::read_with_world(0, 256, &mut SlicePacketRead::new(&write)).unwrap();
+        assert_eq!(read, nether);
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn set_equipment_tests() {
+        let sword = Some(Slot { item_id: 200, count: 1, nbt: None });
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetEquipment { entity_id: 5, equipment: vec![(EquipmentSlot::MainHand, sword.clone())] }, &mut write);
+        assert_eq!(write, &[0x50, 0x05, 0x00, 0x01, 0xC8, 0x01, 0x01, 0x00]);
+
+        let slots = [
+            EquipmentSlot::MainHand, EquipmentSlot::OffHand, EquipmentSlot::Boots,
+            EquipmentSlot::Leggings, EquipmentSlot::Chestplate, EquipmentSlot::Helmet,
+        ];
+        let equipment: Vec<_> = slots.iter().map(|slot| (*slot, None)).collect();
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetEquipment { entity_id: 1, equipment }, &mut write);
+        assert_eq!(&write[2..], &[0x80, 0x00, 0x81, 0x00, 0x82, 0x00, 0x83, 0x00, 0x84, 0x00, 0x05, 0x00]);
+
+        let bytes = [0x07, 0x85, 0x00, 0x01, 0x01, 0xC8, 0x01, 0x01, 0x00];
+        let mut read = SlicePacketRead::new(&bytes);
+        assert_eq!(PlaySetEquipment::read(&mut read).unwrap(), PlaySetEquipment {
+            entity_id: 7,
+            equipment: vec![(EquipmentSlot::Helmet, None), (EquipmentSlot::OffHand, sword)],
+        });
+        assert_eq!(read.available(), 0);
+        assert!(matches!(PlaySetEquipment::read(&mut SlicePacketRead::new(&[0x07, 0x06, 0x00])), Err(PacketReadableError::BadEnumValue)));
+        assert!(PlaySetEquipment { entity_id: 7, equipment: vec![] }.write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn world_border_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayInitializeWorldBorder {
+            x: 0.5,
+            z: -0.5,
+            old_diameter: 100.0,
+            new_diameter: 200.0,
+            speed: 1 << 32,
+            portal_teleport_boundary: 29999984,
+            warning_blocks: 5,
+            warning_time: 15,
+        }, &mut write);
+        assert_eq!(&write[..9], &[0x1D, 0x3F, 0xE0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&write[33..], &[0x80, 0x80, 0x80, 0x80, 0x10, 0xF0, 0x86, 0xA7, 0x0E, 0x05, 0x0F]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetBorderCenter { x: 1.0, z: 2.0 }, &mut write);
+        assert_eq!(write.len(), 17);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetBorderLerpSize { old_diameter: 1.0, new_diameter: 2.0, speed: i64::MAX }, &mut write);
+        assert_eq!(&write[17..], &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetBorderSize { diameter: 2.0 }, &mut write);
+        assert_eq!(write, &[0x43, 0x40, 0, 0, 0, 0, 0, 0, 0]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetBorderWarningDelay { warning_time: 300 }, &mut write);
+        assert_eq!(write, &[0x44, 0xAC, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetBorderWarningDistance { warning_blocks: 7 }, &mut write);
+        assert_eq!(write, &[0x45, 0x07]);
+    }
+
+    #[test]
+    fn update_time_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateTime { world_age: 1 << 40, time_of_day: -6000 }, &mut write);
+        assert_eq!(write, &[0x59, 0, 0, 0x01, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE8, 0x90]);
+    }
+
+    #[test]
+    fn default_spawn_position_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetDefaultSpawnPosition { location: BlockPosition { x: 1, y: 2, z: 3 }, angle: 90.0 }, &mut write);
+        assert_eq!(write[0], 0x4A);
+        assert_eq!(&write[9..], &[0x42, 0xB4, 0, 0]);
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn declare_recipes_tests() {
+        let identifier = |name: &str| Identifier::new_fulled(name.to_string()).unwrap();
+        let item = |item_id: i32| Some(Slot { item_id, count: 1, nbt: None });
+        let planks = Ingredient(vec![item(23), item(24)]);
+        let recipes = vec![
+            Recipe {
+                recipe_id: identifier("minecraft:stick"),
+                body: RecipeBody::Shaped(ShapedRecipe {
+                    width: 1,
+                    height: 2,
+                    group: "sticks",
+                    ingredients: vec![planks.clone(), planks.clone()],
+                    result: Some(Slot { item_id: 800, count: 4, nbt: None }),
+                }),
+            },
+            Recipe {
+                recipe_id: identifier("minecraft:oak_planks"),
+                body: RecipeBody::Shapeless(ShapelessRecipe {
+                    group: "planks",
+                    ingredients: vec![Ingredient(vec![item(110)])],
+                    result: item(23),
+                }),
+            },
+            Recipe {
+                recipe_id: identifier("minecraft:glass"),
+                body: RecipeBody::Smelting(CookingRecipe {
+                    group: "",
+                    ingredient: Ingredient(vec![item(50)]),
+                    result: item(60),
+                    experience: 0.1,
+                    cooking_time: 200,
+                }),
+            },
+            Recipe {
+                recipe_id: identifier("minecraft:armor_dye"),
+                body: RecipeBody::Special(SpecialRecipe::ArmorDye),
+            },
+        ];
+        let mut write = Vec::new();
+        play_round_trip(&PlayDeclareRecipes { recipes }, &mut write);
+        assert_eq!(&write[..3], &[0x67, 0x04, 0x19]);
+        assert_eq!(&write[3..28], b"minecraft:crafting_shaped");
+        // Width and height are followed by the group and the ingredients without count
+        assert_eq!(&write[44..53], &[0x01, 0x02, 0x06, b's', b't', b'i', b'c', b'k', b's']);
+        assert_eq!(&write[53..56], &[0x02, 0x01, 0x17]);
+        // Special recipe has no body
+        assert!(write.ends_with(b"minecraft:crafting_special_armordye\x13minecraft:armor_dye"));
+
+        let bad = Recipe {
+            recipe_id: identifier("minecraft:stick"),
+            body: RecipeBody::Shaped(ShapedRecipe { width: 2, height: 2, group: "", ingredients: vec![planks], result: None }),
+        };
+        assert!(bad.write(&mut Vec::new()).is_err());
+        let mut bytes = Vec::new();
+        "minecraft:unknown".write(&mut bytes).unwrap();
+        "minecraft:stick".write(&mut bytes).unwrap();
+        let error = Recipe::read(&mut SlicePacketRead::new(&bytes)).unwrap_err();
+        assert!(error.to_string().contains("minecraft:unknown"));
+    }
+
+    #[test]
+    fn plugin_message_tests() {
+        let mut data = Vec::new();
+        Brand("vanilla").write(&mut data).unwrap();
+        let brand = PlayPluginMessage { channel: Identifier::new_fulled(BRAND_CHANNEL).unwrap(), data: &data };
+        let mut write = Vec::new();
+        play_round_trip(&brand, &mut write);
+        assert_eq!(&write[..17], b"\x15\x0Fminecraft:brand");
+        assert_eq!(&write[17..], b"\x07vanilla");
+        assert_eq!(Brand::read(&mut SlicePacketRead::new(&write[17..])).unwrap(), Brand("vanilla"));
+
+        let mut read = SlicePacketRead::new(b"bird:first\0bird:second\0");
+        assert_eq!(ChannelList::read(&mut read).unwrap(), ChannelList(vec!["bird:first", "bird:second"]));
+        assert_eq!(read.available(), 0);
+        let mut write = Vec::new();
+        ChannelList(vec!["bird:first", "bird:second"]).write(&mut write).unwrap();
+        assert_eq!(write, b"bird:first\0bird:second");
+        assert!(ChannelList(vec!["bird:\0"]).write(&mut Vec::new()).is_err());
+        assert_eq!(ChannelList::read(&mut SlicePacketRead::new(&[])).unwrap(), ChannelList(vec![]));
+    }
+
+    #[test]
+    fn bounded_packets_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetRenderDistance { view_distance: ViewDistance::new(32).unwrap() }, &mut write);
+        assert_eq!(write, &[0x49, 0x20]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetSimulationDistance { simulation_distance: ViewDistance::new(2).unwrap() }, &mut write);
+        assert_eq!(write, &[0x57, 0x02]);
+        assert!(matches!(
+            PlaySetRenderDistance::read(&mut SlicePacketRead::new(&[0x21])),
+            Err(PacketReadableError::OutOfRange { value: 33, min: 2, max: 32 })
+        ));
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetHeldItem { slot: Bounded::new(8).unwrap() }, &mut write);
+        assert_eq!(write, &[0x47, 0x08]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayServerSetHeldItem { slot: Bounded::new(0).unwrap() }, &mut write);
+        assert_eq!(write, &[0x27, 0x00, 0x00]);
+        assert!(matches!(
+            PlayServerSetHeldItem::read(&mut SlicePacketRead::new(&[0xFF, 0xFF])),
+            Err(PacketReadableError::OutOfRange { value: -1, min: 0, max: 8 })
+        ));
+    }
+
+    #[test]
+    fn entity_movement_tests() {
+        let delta = PositionDelta::from_blocks(1.0, -0.5, 7.5).unwrap();
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateEntityPosition { entity_id: 7, delta, on_ground: true }, &mut write);
+        assert_eq!(write, &[0x26, 0x07, 0x10, 0x00, 0xF8, 0x00, 0x78, 0x00, 0x01]);
+        let mut write = Vec::new();
+        let yaw = Angle::to_radians(64);
+        let pitch = Angle::to_radians(192);
+        play_round_trip(&PlayUpdateEntityPositionAndRotation { entity_id: 7, delta, yaw, pitch, on_ground: false }, &mut write);
+        assert_eq!(&write[8..], &[0x40, 0xC0, 0x00]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateEntityRotation { entity_id: 300, yaw, pitch, on_ground: true }, &mut write);
+        assert_eq!(write, &[0x28, 0xAC, 0x02, 0x40, 0xC0, 0x01]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetHeadRotation { entity_id: 7, head_yaw: Angle::to_radians(255) }, &mut write);
+        assert_eq!(write, &[0x3C, 0x07, 0xFF]);
+        let teleport = PlayTeleportEntity { entity_id: 7, x: 100.5, y: 64.0, z: -3.25, yaw, pitch, on_ground: true };
+        let mut write = Vec::new();
+        play_round_trip(&teleport, &mut write);
+        assert_eq!(&write[..10], &[0x63, 0x07, 0x40, 0x59, 0x20, 0, 0, 0, 0, 0]);
+        assert_eq!(&write[write.len() - 3..], &[0x40, 0xC0, 0x01]);
+        // Angle is one byte, so the rotation is read as the nearest 1/256 of the turn
+        let mut write = Vec::new();
+        PlaySetHeadRotation { entity_id: 7, head_yaw: -std::f32::consts::FRAC_PI_2 }.write(&mut write).unwrap();
+        assert_eq!(write, &[0x07, 0xC0]);
+    }
+
+    /// Teleport and relative moves, which are computed from the position known by the client,
+    /// end not farther than half of 1/4096 of a block from the real position.
+    #[cfg(feature = "euclid")]
+    #[test]
+    fn entity_movement_precision_tests() {
+        let path = [
+            euclid::vec3(100.3, 64.0, -20.7),
+            euclid::vec3(100.41, 64.0, -20.71),
+            euclid::vec3(103.9, 63.123456, -25.0),
+            euclid::vec3(96.0001, 63.5, -32.9999),
+            euclid::vec3(95.123, 70.987, -33.333),
+        ];
+        let [start, moves @ ..] = path;
+        let mut write = Vec::new();
+        let teleport = play_round_trip(&PlayTeleportEntity {
+            entity_id: 1, x: start.x, y: start.y, z: start.z, yaw: 0.0, pitch: 0.0, on_ground: true,
+        }, &mut write);
+        let mut client = euclid::vec3(teleport.x, teleport.y, teleport.z);
+        for target in moves {
+            let delta = PositionDelta::from_positions(client, target).unwrap();
+            let mut write = Vec::new();
+            let packet = play_round_trip(&PlayUpdateEntityPosition { entity_id: 1, delta, on_ground: true }, &mut write);
+            client = packet.delta.apply(client);
+            let error = client - target;
+            for axis in [error.x, error.y, error.z] {
+                assert!(axis.abs() <= 0.5 / PositionDelta::SCALE, "{:?} is far from {:?}", client, target);
+            }
+        }
+    }
+
+    #[test]
+    fn spawn_tests() {
+        let uuid = Uuid::from_u128(0x0102030405060708090A0B0C0D0E0F10);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySpawnEntity {
+            entity_id: 300,
+            uuid,
+            entity_type: 2,
+            x: 0.5,
+            y: 64.0,
+            z: -0.5,
+            pitch: Angle::to_radians(64),
+            yaw: Angle::to_radians(128),
+            head_yaw: Angle::to_radians(255),
+            data: 1 << 20,
+            velocity: Velocity::from_blocks(3.9, 0.0, -100.0),
+        }, &mut write);
+        assert_eq!(&write[..4], &[0x00, 0xAC, 0x02, 0x01]);
+        assert_eq!(&write[18..20], &[0x10, 0x02]);
+        assert_eq!(&write[44..], &[0x40, 0x80, 0xFF, 0x80, 0x80, 0x40, 0x79, 0xE0, 0x00, 0x00, 0x86, 0x20]);
+
+        let mut write = Vec::new();
+        play_round_trip(&PlaySpawnExperienceOrb { entity_id: 1, x: 1.0, y: 2.0, z: 3.0, count: 10 }, &mut write);
+        assert_eq!(&write[..2], &[0x01, 0x01]);
+        assert_eq!(&write[26..], &[0x00, 0x0A]);
+
+        let mut write = Vec::new();
+        play_round_trip(&PlaySpawnPlayer {
+            entity_id: 7, uuid, x: 0.0, y: 0.0, z: 0.0, yaw: Angle::to_radians(32), pitch: Angle::to_radians(224),
+        }, &mut write);
+        assert_eq!(write.len(), 44);
+        assert_eq!(&write[42..], &[0x20, 0xE0]);
+
+        let mut write = Vec::new();
+        play_round_trip(&PlayRemoveEntities { entity_ids: vec![1, 300, -1] }, &mut write);
+        assert_eq!(&write[..5], &[0x38, 0x03, 0x01, 0xAC, 0x02]);
+        assert_eq!(&write[5..], &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        let mut write = Vec::new();
+        let entity_ids: Vec<_> = (0..1000).map(|id| id * 4099).collect();
+        play_round_trip(&PlayRemoveEntities { entity_ids }, &mut write);
+        assert_eq!(&write[..3], &[0x38, 0xE8, 0x07]);
+    }
+
+    #[test]
+    fn interact_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayInteract {
+            entity_id: 5,
+            interact_type: InteractType::Interact { hand: Hand::OffHand },
+            sneaking: false,
+        }, &mut write);
+        assert_eq!(write, &[0x0F, 0x05, 0x00, 0x01, 0x00]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayInteract { entity_id: 5, interact_type: InteractType::Attack, sneaking: true }, &mut write);
+        assert_eq!(write, &[0x0F, 0x05, 0x01, 0x01]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayInteract {
+            entity_id: 5,
+            interact_type: InteractType::InteractAt { target_x: 0.5, target_y: 1.0, target_z: -0.5, hand: Hand::MainHand },
+            sneaking: false,
+        }, &mut write);
+        assert_eq!(&write[..3], &[0x0F, 0x05, 0x02]);
+        assert_eq!(&write[3..15], &[0x3F, 0, 0, 0, 0x3F, 0x80, 0, 0, 0xBF, 0, 0, 0]);
+        assert_eq!(&write[15..], &[0x00, 0x00]);
+        assert!(matches!(PlayInteract::read(&mut SlicePacketRead::new(&[0x05, 0x03, 0x00])), Err(PacketReadableError::BadEnumValue)));
+        assert!(matches!(PlayInteract::read(&mut SlicePacketRead::new(&[0x05, 0x00, 0x02, 0x00])), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn player_action_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayPlayerAction {
+            status: PlayerActionStatus::FinishedDigging,
+            location: BlockPosition { x: 1, y: 2, z: 3 },
+            face: BlockFace::East,
+            sequence: 300,
+        }, &mut write);
+        assert_eq!(&write[..2], &[0x1C, 0x02]);
+        assert_eq!(&write[10..], &[0x05, 0xAC, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayPlayerCommand { entity_id: 9, action: PlayerCommandAction::StartHorseJump, jump_boost: 100 }, &mut write);
+        assert_eq!(write, &[0x1D, 0x09, 0x05, 0x64]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySwingArm { hand: Hand::OffHand }, &mut write);
+        assert_eq!(write, &[0x2E, 0x01]);
+        assert!(matches!(BlockFace::read(&mut SlicePacketRead::new(&[0x06])), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn click_container_tests() {
+        let stone = Some(Slot { item_id: 1, count: 1, nbt: None });
+        let mut write = Vec::new();
+        play_round_trip(&PlayClickContainer {
+            window_id: 1,
+            state_id: 5,
+            slot: 36,
+            button: 0,
+            mode: ClickMode::Pickup,
+            changed_slots: vec![(36, None)],
+            carried_item: stone.clone(),
+        }, &mut write);
+        assert_eq!(write, &[0x0A, 0x01, 0x05, 0x00, 0x24, 0x00, 0x00, 0x01, 0x00, 0x24, 0x00, 0x01, 0x01, 0x01, 0x00]);
+
+        // Dragging one stone over two slots: start, two added slots, end.
+        let drag = [(-999, 0, vec![]), (10, 1, vec![]), (11, 1, vec![]), (-999, 2, vec![(10, stone.clone()), (11, None)])];
+        for (slot, button, changed_slots) in drag {
+            let mut write = Vec::new();
+            play_round_trip(&PlayClickContainer {
+                window_id: 0,
+                state_id: 7,
+                slot,
+                button,
+                mode: ClickMode::QuickCraft,
+                changed_slots,
+                carried_item: None,
+            }, &mut write);
+            assert_eq!(&write[6..7], &[0x05]);
+        }
+        let mut write = Vec::new();
+        play_round_trip(&PlayClickContainer {
+            window_id: 0,
+            state_id: 7,
+            slot: -999,
+            button: 2,
+            mode: ClickMode::QuickCraft,
+            changed_slots: vec![],
+            carried_item: None,
+        }, &mut write);
+        assert_eq!(&write[3..], &[0xFC, 0x19, 0x02, 0x05, 0x00, 0x00]);
+
+        // Five changed slots can't fit in three bytes.
+        let bytes = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x01, 0x00];
+        assert!(matches!(PlayClickContainer::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BadLength)));
+        let mut bytes = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x81, 0x01];
+        bytes.extend([0x00; 129 * 3]);
+        assert!(matches!(PlayClickContainer::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BadLength)));
+        assert!(PlayClickContainer {
+            window_id: 0,
+            state_id: 0,
+            slot: 0,
+            button: 0,
+            mode: ClickMode::Pickup,
+            changed_slots: vec![(0, None); ChangedSlots::MAX + 1],
+            carried_item: None,
+        }.write(&mut Vec::new()).is_err());
+        assert!(matches!(ClickMode::read(&mut SlicePacketRead::new(&[0x07])), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[cfg(feature = "fastnbt")]
+    #[test]
+    fn container_content_tests() {
+        let nbt = fastnbt::Value::Compound(std::collections::HashMap::from([
+            ("Damage".to_string(), fastnbt::Value::Int(3)),
+        ]));
+        let pickaxe = Some(Slot { item_id: 745, count: 1, nbt: Some(nbt) });
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetContainerContent {
+            window_id: 0,
+            state_id: 1,
+            slots: vec![None, pickaxe.clone(), None],
+            carried_item: None,
+        }, &mut write);
+        assert_eq!(&write[..6], &[0x11, 0x00, 0x01, 0x03, 0x00, 0x01]);
+        assert_eq!(write.last(), Some(&0x00));
+
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetContainerContent { window_id: 2, state_id: 0, slots: vec![], carried_item: pickaxe.clone() }, &mut write);
+        assert_eq!(&write[..5], &[0x11, 0x02, 0x00, 0x00, 0x01]);
+
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetContainerSlot { window_id: -1, state_id: 3, slot: -1, item: pickaxe }, &mut write);
+        assert_eq!(&write[..6], &[0x13, 0xFF, 0x03, 0xFF, 0xFF, 0x01]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetContainerSlot { window_id: 0, state_id: 3, slot: 5, item: None }, &mut write);
+        assert_eq!(write, &[0x13, 0x00, 0x03, 0x00, 0x05, 0x00]);
+    }
+
+    #[test]
+    fn open_screen_tests() {
+        let json = r#"{"translate":"container.chest","with":[{"text":"Bird","extra":[]}],"extra":[]}"#;
+        let mut bytes = vec![0x2B, 0x03, 0x02];
+        json.write(&mut bytes).unwrap();
+        let mut read = SlicePacketRead::new(&bytes);
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), PlayOpenScreen::id());
+        let packet = PlayOpenScreen::read(&mut read).unwrap();
+        assert_eq!(read.available(), 0);
+        assert_eq!((packet.window_id, packet.window_type), (3, ContainerType::Generic9x3));
+        match &packet.title {
+            Component::Translatable(title) => {
+                assert_eq!(title.translate, "container.chest");
+                assert_eq!(read_text(&title.with[0]), "Bird");
+            }
+            _ => panic!("Not a translatable component"),
+        }
+        // Empty arrays are skipped when written
+        let mut write = Vec::new();
+        PacketVariant::write_variant(&packet, &mut write).unwrap();
+        let mut expected = vec![0x2B, 0x03, 0x02];
+        r#"{"translate":"container.chest","with":[{"text":"Bird"}]}"#.write(&mut expected).unwrap();
+        assert_eq!(write, expected);
+
+        let mut write = Vec::new();
+        play_round_trip(&PlayCloseContainer { window_id: 2 }, &mut write);
+        assert_eq!(write, &[0x10, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayServerCloseContainer { window_id: 2 }, &mut write);
+        assert_eq!(write, &[0x0B, 0x02]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetContainerProperty { window_id: 1, property: 2, value: -200 }, &mut write);
+        assert_eq!(write, &[0x12, 0x01, 0x00, 0x02, 0xFF, 0x38]);
+    }
+
+    #[test]
+    fn container_type_tests() {
+        let types: Vec<_> = (0..).map_while(ContainerType::from_value).collect();
+        assert_eq!(types.len(), 24);
+        for (value, container_type) in types.iter().enumerate() {
+            assert_eq!(container_type.value(), value as i32);
+            assert!(container_type.slot_count() > 0);
+            assert_eq!(container_type.window_slot_count(), container_type.slot_count() + 36);
+            let mut write = Vec::new();
+            container_type.write(&mut write).unwrap();
+            assert_eq!(ContainerType::read(&mut SlicePacketRead::new(&write)).unwrap(), *container_type);
+        }
+        assert_eq!(ContainerType::Generic9x6.slot_count(), 54);
+        assert_eq!(ContainerType::Stonecutter.value(), 23);
+        assert!(matches!(ContainerType::read(&mut SlicePacketRead::new(&[0x18])), Err(PacketReadableError::BadEnumValue)));
+        assert!(matches!(ContainerType::read(&mut SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F])), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn chat_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayChatMessage { message: "hi", timestamp: 2, salt: -1, signature: &[7; 256], signed_preview: false }, &mut write);
+        assert_eq!(&write[..4], &[0x04, 0x02, b'h', b'i']);
+        assert_eq!(&write[20..23], &[0x80, 0x02, 0x07]);
+        assert_eq!(write.last(), Some(&0x00));
+        let long = "a".repeat(257);
+        assert!(PlayChatMessage { message: &long, timestamp: 0, salt: 0, signature: &[], signed_preview: false }.write(&mut Vec::new()).is_err());
+
+        let sender = Uuid::from_u128(5);
+        let mut bytes = vec![0x30];
+        bytes.extend(component_bytes("hello"));
+        bytes.push(0x00);
+        bytes.push(0x01);
+        bytes.extend(sender.as_bytes());
+        bytes.extend(component_bytes("Bird"));
+        bytes.push(0x01);
+        bytes.extend(component_bytes("Team"));
+        bytes.extend([0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 3, 0x02, 0xAA, 0xBB]);
+        let mut read = SlicePacketRead::new(&bytes);
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), PlayPlayerChat::id());
+        let packet = PlayPlayerChat::read(&mut read).unwrap();
+        assert_eq!(read.available(), 0);
+        assert_eq!(read_text(&packet.signed_content), "hello");
+        assert!(packet.unsigned_content.is_none());
+        assert_eq!((packet.chat_type, packet.sender), (1, sender));
+        assert_eq!(read_text(&packet.sender_name), "Bird");
+        assert_eq!(read_text(packet.sender_team_name.as_ref().unwrap()), "Team");
+        assert_eq!((packet.timestamp, packet.salt, packet.signature), (9, 3, &[0xAA, 0xBB][..]));
+    }
+
+    #[test]
+    fn entity_effect_tests() {
+        let flags = EffectFlags::SHOW_PARTICLES | EffectFlags::SHOW_ICON;
+        assert!(!flags.ambient() && flags.show_particles() && flags.show_icon());
+        let mut write = Vec::new();
+        play_round_trip(&PlayEntityEffect { entity_id: 5, effect_id: 1, amplifier: 2, duration: 600, flags }, &mut write);
+        assert_eq!(write, &[0x66, 0x05, 0x01, 0x02, 0xD8, 0x04, 0x06]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayRemoveEntityEffect { entity_id: 5, effect_id: 1 }, &mut write);
+        assert_eq!(write, &[0x39, 0x05, 0x01]);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetHealth { health: 20.0, food: 18, saturation: 5.0 }, &mut write);
+        assert_eq!(write, &[0x52, 0x41, 0xA0, 0x00, 0x00, 0x12, 0x40, 0xA0, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn update_attributes_tests() {
+        let sprinting = Uuid::from_u128(0x662A6B8DDA3E4C1C88135A02ACE8DC30);
+        let packet = PlayUpdateAttributes {
+            entity_id: 7,
+            properties: vec![
+                AttributeProperty {
+                    key: Identifier::new_fulled("minecraft:generic.movement_speed").unwrap(),
+                    value: 0.1,
+                    modifiers: vec![
+                        AttributeModifier { uuid: sprinting, amount: 0.3, operation: AttributeOperation::MultiplyTotal },
+                        AttributeModifier { uuid: Uuid::from_u128(1), amount: -0.5, operation: AttributeOperation::MultiplyBase },
+                    ],
+                },
+                AttributeProperty {
+                    key: Identifier::new_fulled("minecraft:generic.max_health").unwrap(),
+                    value: 20.0,
+                    modifiers: vec![
+                        AttributeModifier { uuid: Uuid::from_u128(2), amount: 4.0, operation: AttributeOperation::Add },
+                    ],
+                },
+                AttributeProperty {
+                    key: Identifier::new_fulled("minecraft:generic.armor").unwrap(),
+                    value: 0.0,
+                    modifiers: Vec::new(),
+                },
+            ],
+        };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(&write[..3], &[0x65, 0x07, 0x03]);
+        let mut expected_modifier = sprinting.as_bytes().to_vec();
+        expected_modifier.extend_from_slice(&0.3f64.to_be_bytes());
+        expected_modifier.push(0x02);
+        let speed = 3 + 1 + "minecraft:generic.movement_speed".len() + 8;
+        assert_eq!(write[speed], 0x02);
+        assert_eq!(&write[speed + 1..speed + 26], expected_modifier.as_slice());
+
+        // Operation of the second modifier is unknown
+        let mut bytes = write.clone();
+        bytes[speed + 1 + 25 * 2 - 1] = 0x03;
+        let mut read = SlicePacketRead::new(&bytes[1..]);
+        assert!(matches!(PlayUpdateAttributes::read(&mut read), Err(PacketReadableError::BadEnumValue)));
+        assert_eq!(AttributeOperation::from_value(3), None);
+    }
+
+    #[test]
+    fn player_abilities_tests() {
+        let flags = AbilityFlags::FLYING | AbilityFlags::CREATIVE_MODE;
+        assert_eq!(flags.bits(), 0x0A);
+        assert!(flags.flying() && flags.creative_mode() && !flags.invulnerable() && !flags.allow_flying());
+        let mut built = AbilityFlags::empty();
+        built.set_flying(true);
+        built.set_creative_mode(true);
+        built.set_allow_flying(true);
+        built.set_allow_flying(false);
+        assert_eq!(built, flags);
+        built.set_invulnerable(true);
+        assert_eq!(built.bits(), 0x0B);
+        let packet = PlayPlayerAbilities {
+            flags: flags | AbilityFlags::ALLOW_FLYING | AbilityFlags::INVULNERABLE,
+            flying_speed: PlayPlayerAbilities::DEFAULT_FLYING_SPEED,
+            field_of_view_modifier: PlayPlayerAbilities::DEFAULT_FIELD_OF_VIEW_MODIFIER,
+        };
+        let mut write = Vec::new();
+        play_round_trip(&packet, &mut write);
+        assert_eq!(write, &[0x2F, 0x0F, 0x3D, 0x4C, 0xCC, 0xCD, 0x3D, 0xCC, 0xCC, 0xCD]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayServerPlayerAbilities { flags: AbilityFlags::FLYING }, &mut write);
+        assert_eq!(write, &[0x1B, 0x02]);
+    }
+
+    #[test]
+    fn game_event_tests() {
+        let mut write = Vec::new();
+        play_round_trip(&PlayGameEvent { event: GameEvent::ChangeGameMode, value: 1.0 }, &mut write);
+        assert_eq!(write, &[0x1B, 0x03, 0x3F, 0x80, 0x00, 0x00]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayGameEvent { event: GameEvent::EnableRespawnScreen, value: 0.0 }, &mut write);
+        assert_eq!(write, &[0x1B, 0x0B, 0x00, 0x00, 0x00, 0x00]);
+        let events: Vec<_> = (0..).map_while(GameEvent::from_value).collect();
+        assert_eq!(events.len(), 12);
+        assert_eq!(events[GameEvent::PufferfishSting.value() as usize], GameEvent::PufferfishSting);
+        let mut read = SlicePacketRead::new(&[0x0C, 0x00, 0x00, 0x00, 0x00]);
+        assert!(matches!(PlayGameEvent::read(&mut read), Err(PacketReadableError::BadEnumValue)));
+
+        let mut write = Vec::new();
+        play_round_trip(&PlayChangeDifficulty { difficulty: Difficulty::Hard, locked: true }, &mut write);
+        assert_eq!(write, &[0x0B, 0x03, 0x01]);
+        assert!(matches!(Difficulty::read(&mut SlicePacketRead::new(&[0x04])), Err(PacketReadableError::BadEnumValue)));
+    }
+
+    #[test]
+    fn award_statistics_tests() {
+        let statistics = PlayAwardStatistics {
+            statistics: vec![
+                Statistic::custom(CustomStatistic::Deaths, 3),
+                Statistic { category: StatisticCategory::Mined, id: 1, value: 200 },
+                Statistic { category: StatisticCategory::KilledBy, id: 107, value: 1 },
+                Statistic::custom(CustomStatistic::PlayTime, 72000),
+                Statistic::custom(CustomStatistic::InteractWithSmithingTable, 1),
+            ],
+        };
+        let mut write = Vec::new();
+        let read = play_round_trip(&statistics, &mut write);
+        assert_eq!(&write[..8], &[0x04, 0x05, 0x08, 0x1E, 0x03, 0x00, 0x01, 0xC8]);
+        assert_eq!(&write[write.len() - 3..], &[0x08, 0x4A, 0x01]);
+        let custom: Vec<_> = read.statistics.iter().map(Statistic::custom_statistic).collect();
+        assert_eq!(custom, [Some(CustomStatistic::Deaths), None, None, Some(CustomStatistic::PlayTime), Some(CustomStatistic::InteractWithSmithingTable)]);
+        assert_eq!(CustomStatistic::Jump.value(), 21);
+        assert_eq!(Statistic { category: StatisticCategory::Custom, id: 75, value: 0 }.custom_statistic(), None);
+        // Unknown category
+        assert!(matches!(
+            PlayAwardStatistics::read(&mut SlicePacketRead::new(&[0x01, 0x09, 0x00, 0x00])),
+            Err(PacketReadableError::BadEnumValue)
+        ));
+    }
+
+    #[test]
+    fn block_tests() {
+        let location = BlockPosition { x: -5, y: 70, z: 12 };
+        let mut write = Vec::new();
+        play_round_trip(&PlayWorldEvent {
+            event: WorldEvent::BlockBreak.into(), location, data: 9, disable_relative_volume: false,
+        }, &mut write);
+        assert_eq!(&write[..5], &[0x20, 0x00, 0x00, 0x07, 0xD1]);
+        assert_eq!(&write[13..], &[0x00, 0x00, 0x00, 0x09, 0x00]);
+        let mut write = Vec::new();
+        play_round_trip(&PlayWorldEvent {
+            event: WorldEvent::PlayRecord.into(), location, data: 0, disable_relative_volume: false,
+        }, &mut write);
+        assert_eq!(&write[1..5], &[0x00, 0x00, 0x03, 0xF2]);
+        assert_eq!(WorldEvent::from_value(1023), Some(WorldEvent::WitherSpawn));
+        assert_eq!(WorldEvent::EndPortalOpen.value(), 1038);
+        assert_eq!(WorldEvent::InstantSplashPotion.value(), 2007);
+
+        // Unknown event is kept with its id
+        let mut write = Vec::new();
+        let packet = play_round_trip(&PlayWorldEvent {
+            event: OpenEnum::Unknown(1505), location, data: -1, disable_relative_volume: true,
+        }, &mut write);
+        assert_eq!(packet.event.known(), None);
+        assert_eq!(packet.event.value(), 1505);
+        assert_eq!(&write[1..5], &[0x00, 0x00, 0x05, 0xE1]);
+        assert_eq!(write.last(), Some(&0x01));
+
+        let mut write = Vec::new();
+        play_round_trip(&PlayBlockAction { location, action_id: 1, action_param: 2, block_type: 300 }, &mut write);
+        assert_eq!(write[0], 0x08);
+        assert_eq!(&write[9..], &[0x01, 0x02, 0xAC, 0x02]);
+
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetBlockDestroyStage { entity_id: 7, location, stage: 9 }, &mut write);
+        assert_eq!(&write[..2], &[0x06, 0x07]);
+        assert_eq!(write[10], 0x09);
+        let mut write = Vec::new();
+        play_round_trip(&PlaySetBlockDestroyStage {
+            entity_id: 7, location, stage: PlaySetBlockDestroyStage::CLEAR_STAGE,
+        }, &mut write);
+        assert_eq!(write[10], 0xFF);
+
+        let mut write = Vec::new();
+        play_round_trip(&PlayBlockUpdate { location, block_state: 20342 }, &mut write);
+        assert_eq!(write.len(), 12);
+        assert_eq!(&write[9..], &[0xF6, 0x9E, 0x01]);
+    }
+
+    #[test]
+    fn update_section_blocks_tests() {
+        let updates = [
+            (SectionBlockUpdate { state: 0, x: 0, y: 0, z: 0 }, 0i64),
+            (SectionBlockUpdate { state: 1, x: 15, y: 1, z: 2 }, 0x1F21),
+            // Block state of the full 20 bits
+            (SectionBlockUpdate { state: 0xFFFFF, x: 3, y: 15, z: 7 }, 0xFFFFF37F),
+        ];
+        for (update, packed) in updates {
+            assert_eq!(update.try_pack().unwrap(), packed);
+            assert_eq!(SectionBlockUpdate::from_packed(packed).unwrap(), update);
+        }
+        for update in [
+            SectionBlockUpdate { state: 1, x: 16, y: 0, z: 0 },
+            SectionBlockUpdate { state: 1, x: 0, y: 16, z: 0 },
+            SectionBlockUpdate { state: 1, x: 0, y: 0, z: 255 },
+        ] {
+            assert!(matches!(update.try_pack(), Err(PacketWritableError::OutOfBounds { min: 0, max: 15, .. })));
+            assert!(update.write(&mut Vec::new()).is_err());
+        }
+        assert!(SectionBlockUpdate { state: -1, x: 0, y: 0, z: 0 }.try_pack().is_err());
+        assert!(matches!(SectionBlockUpdate::from_packed(-1), Err(PacketReadableError::OutOfRange { value: -1, .. })));
+        assert!(SectionBlockUpdate::from_packed(1 << 44).is_err());
+
+        let positions = [
+            (SectionPosition { x: 0, y: 0, z: 0 }, 0u64),
+            (SectionPosition { x: 1, y: 2, z: 3 }, 0x0000040000300002),
+            (SectionPosition { x: -1, y: -1, z: -1 }, u64::MAX),
+            (SectionPosition { x: -2, y: -4, z: 5 }, 0xFFFFF800005FFFFC),
+            (SectionPosition { x: SectionPosition::MIN_XZ, y: SectionPosition::MIN_Y, z: SectionPosition::MAX_XZ }, 0x800001FFFFF80000),
+        ];
+        for (position, packed) in positions {
+            assert_eq!(position.try_pack().unwrap(), packed);
+            assert_eq!(SectionPosition::from_packed(packed), position);
+        }
+        assert!(SectionPosition { x: SectionPosition::MAX_XZ + 1, y: 0, z: 0 }.try_pack().is_err());
+        assert!(SectionPosition { x: 0, y: SectionPosition::MIN_Y - 1, z: 0 }.try_pack().is_err());
+        assert_eq!(
+            SectionPosition::containing(BlockPosition { x: -1, y: -64, z: 31 }),
+            SectionPosition { x: -1, y: -4, z: 1 },
+        );
+
+        let mut write = Vec::new();
+        play_round_trip(&PlayUpdateSectionBlocks {
+            section: SectionPosition { x: -2, y: -4, z: 5 },
+            suppress_light_updates: true,
+            updates: vec![
+                SectionBlockUpdate { state: 1, x: 15, y: 1, z: 2 },
+                SectionBlockUpdate { state: 0xFFFFF, x: 3, y: 15, z: 7 },
+            ],
+        }, &mut write);
+        assert_eq!(&write[..9], &[0x3D, 0xFF, 0xFF, 0xF8, 0x00, 0x00, 0x5F, 0xFF, 0xFC]);
+        assert_eq!(&write[9..14], &[0x01, 0x02, 0xA1, 0x3E, 0xFF]);
+        assert_eq!(&write[14..], &[0xE6, 0xFF, 0xFF, 0x0F]);
+    }
+}
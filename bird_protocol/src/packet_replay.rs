@@ -0,0 +1,227 @@
+use std::borrow::Cow;
+use crate::packet::{DecodeMode, PacketRead, PacketReadableById, PacketReadableError, PacketScope, PacketVariantReadable, SlicePacketRead};
+#[cfg(feature = "compression")]
+use crate::packet_compression::PacketCompression;
+use crate::packet_types::VarInt;
+
+/// Frame of the captured stream. Offset is the position of its length prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayFrame<'a> {
+    pub offset: usize,
+    pub id: i32,
+    /// Body after the id, which is owned only if the frame was compressed.
+    pub body: Cow<'a, [u8]>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Frame at {offset:#x}: {error}")]
+pub struct ReplayError {
+    pub offset: usize,
+    #[source]
+    pub error: PacketReadableError,
+}
+
+/// Iterates over consecutive frames of the captured stream.
+///
+/// If a frame can't be split, because its length is bad or exceeds the stream, the error is returned and iteration stops.
+/// Other errors are returned for the frame and iteration continues with the next one.
+#[derive(Debug)]
+pub struct FrameIterator<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    #[cfg(feature = "compression")]
+    compression: Option<PacketCompression>,
+}
+
+impl<'a> FrameIterator<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            #[cfg(feature = "compression")]
+            compression: None,
+        }
+    }
+
+    /// Frames are read in the compressed format, which is used after the set compression packet.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, threshold: usize) -> Self {
+        self.compression = Some(PacketCompression::new(threshold));
+        self
+    }
+
+    /// Offset of the next frame.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Collects frames and errors of the remaining stream.
+    pub fn split(self) -> (Vec<ReplayFrame<'a>>, Vec<ReplayError>) {
+        let mut frames = Vec::new();
+        let mut errors = Vec::new();
+        for frame in self {
+            match frame {
+                Ok(frame) => frames.push(frame),
+                Err(error) => errors.push(error),
+            }
+        }
+        (frames, errors)
+    }
+
+    fn next_frame(&mut self) -> Result<&'a [u8], PacketReadableError> {
+        let mut read = SlicePacketRead::new(&self.bytes[self.offset..]);
+        let length = VarInt::read_variant(&mut read)?;
+        let length = usize::try_from(length).map_err(|_| PacketReadableError::BadLength)?;
+        let frame = read.take_slice(length)?;
+        self.offset = self.bytes.len() - read.available();
+        Ok(frame)
+    }
+
+    #[cfg(feature = "compression")]
+    fn uncompress(&self, frame: &'a [u8]) -> Result<Cow<'a, [u8]>, PacketReadableError> {
+        match &self.compression {
+            Some(compression) => compression.decompress(frame),
+            None => Ok(Cow::Borrowed(frame)),
+        }
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn uncompress(&self, frame: &'a [u8]) -> Result<Cow<'a, [u8]>, PacketReadableError> {
+        Ok(Cow::Borrowed(frame))
+    }
+}
+
+fn split_id(frame: Cow<[u8]>) -> Result<(i32, Cow<[u8]>), PacketReadableError> {
+    let mut read = SlicePacketRead::new(&frame);
+    let id = VarInt::read_variant(&mut read)?;
+    let start = frame.len() - read.available();
+    Ok((id, match frame {
+        Cow::Borrowed(frame) => Cow::Borrowed(&frame[start..]),
+        Cow::Owned(mut frame) => {
+            frame.drain(..start);
+            Cow::Owned(frame)
+        }
+    }))
+}
+
+impl<'a> Iterator for FrameIterator<'a> {
+    type Item = Result<ReplayFrame<'a>, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset == self.bytes.len() {
+            return None;
+        }
+        let offset = self.offset;
+        let frame = match self.next_frame() {
+            Ok(frame) => frame,
+            Err(error) => {
+                self.offset = self.bytes.len();
+                return Some(Err(ReplayError { offset, error }));
+            }
+        };
+        Some(self.uncompress(frame)
+            .and_then(split_id)
+            .map(|(id, body)| ReplayFrame { offset, id, body })
+            .map_err(|error| ReplayError { offset, error }))
+    }
+}
+
+/// Packets which are decoded by [decode_all] with offsets of their frames.
+#[derive(Debug)]
+pub struct ReplayReport<P> {
+    pub packets: Vec<(usize, P)>,
+    pub errors: Vec<ReplayError>,
+}
+
+/// Decodes every frame by its id. Failed frames are reported with their offsets, so the following ones are still decoded.
+///
+/// Left bytes are checked by [DecodeMode::for_bound] of the packets.
+pub fn decode_all<'a, P>(frames: &'a [ReplayFrame<'_>]) -> ReplayReport<P> where P: PacketReadableById<'a> + PacketScope {
+    decode_all_with_mode(frames, DecodeMode::for_bound(P::bound()))
+}
+
+/// Same as [decode_all], but left bytes are checked by the given mode.
+pub fn decode_all_with_mode<'a, P>(frames: &'a [ReplayFrame<'_>], mode: DecodeMode) -> ReplayReport<P>
+    where P: PacketReadableById<'a> {
+    let mut report = ReplayReport { packets: Vec::new(), errors: Vec::new() };
+    for frame in frames {
+        let mut read = SlicePacketRead::new(&frame.body);
+        match P::read_by_id(frame.id, &mut read).and_then(|packet| mode.finish(&read).map(|_| packet)) {
+            Ok(packet) => report.packets.push((frame.offset, packet)),
+            Err(error) => report.errors.push(ReplayError { offset: frame.offset, error }),
+        }
+    }
+    report
+}
+
+#[cfg(all(test, feature = "packet_default"))]
+mod tests {
+    use crate::packet_default::{ServerStatusPacket, StatusPingRequest, StatusRequest};
+    use crate::packet_frame::PacketFramer;
+    use super::*;
+
+    #[test]
+    fn replay_tests() {
+        let mut bytes = Vec::new();
+        let mut framer = PacketFramer::new();
+        framer.write_packet(&StatusRequest, &mut bytes).unwrap();
+        // Ping with a truncated payload
+        bytes.extend_from_slice(&[0x05, 0x01, 0x00, 0x00, 0x00, 0x07]);
+        // Unknown id
+        bytes.extend_from_slice(&[0x01, 0x7F]);
+        framer.write_packet(&StatusPingRequest { payload: 7 }, &mut bytes).unwrap();
+        let (frames, errors) = FrameIterator::new(&bytes).split();
+        assert!(errors.is_empty());
+        assert_eq!(frames.iter().map(|frame| (frame.offset, frame.id)).collect::<Vec<_>>(), vec![(0, 0x00), (2, 0x01), (8, 0x7F), (10, 0x01)]);
+        assert_eq!(frames[1].body, &[0x00, 0x00, 0x00, 0x07][..]);
+        let report = decode_all::<ServerStatusPacket>(&frames);
+        assert_eq!(report.packets, vec![
+            (0, ServerStatusPacket::Request(StatusRequest)),
+            (10, ServerStatusPacket::PingRequest(StatusPingRequest { payload: 7 })),
+        ]);
+        assert_eq!(report.errors.iter().map(|error| error.offset).collect::<Vec<_>>(), vec![2, 8]);
+        assert!(matches!(report.errors[0].error, PacketReadableError::BytesExceeded));
+        assert!(matches!(report.errors[1].error, PacketReadableError::BadPacketId(0x7F)));
+        assert_eq!(report.errors[1].to_string(), "Frame at 0x8: Bad packet id: 127");
+        // Frame which exceeds the capture stops the iteration
+        bytes.extend_from_slice(&[0x09, 0x00]);
+        let mut frames = FrameIterator::new(&bytes).skip(4);
+        assert_eq!(frames.next().unwrap().unwrap_err().offset, 20);
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn trailing_bytes_tests() {
+        // Request with two trailing bytes and an exactly consumed ping
+        let mut bytes = vec![0x03, 0x00, 0xAA, 0xBB];
+        PacketFramer::new().write_packet(&StatusPingRequest { payload: 7 }, &mut bytes).unwrap();
+        let (frames, _) = FrameIterator::new(&bytes).split();
+        let report = decode_all::<ServerStatusPacket>(&frames);
+        assert_eq!(report.packets, vec![(4, ServerStatusPacket::PingRequest(StatusPingRequest { payload: 7 }))]);
+        assert_eq!(report.errors.len(), 1);
+        assert!(matches!(report.errors[0].error, PacketReadableError::TrailingBytes { remaining: 2 }));
+        assert_eq!(report.errors[0].to_string(), "Frame at 0x0: 2 bytes are left after the packet");
+        let report = decode_all_with_mode::<ServerStatusPacket>(&frames, DecodeMode::Lenient);
+        assert!(report.errors.is_empty());
+        assert_eq!(report.packets, vec![
+            (0, ServerStatusPacket::Request(StatusRequest)),
+            (4, ServerStatusPacket::PingRequest(StatusPingRequest { payload: 7 })),
+        ]);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compressed_replay_tests() {
+        let mut body = vec![0x01];
+        body.extend_from_slice(&[0x00; 8]);
+        let mut bytes = vec![0x05, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let mut compressed = Vec::new();
+        PacketCompression::new(1).compress(&body, &mut compressed).unwrap();
+        crate::packet_frame::write_frame_bytes(&compressed, &mut bytes).unwrap();
+        let (frames, errors) = FrameIterator::new(&bytes).with_compression(1).split();
+        assert!(errors.is_empty());
+        assert_eq!((frames[0].id, frames[0].body.as_ref()), (0x00, &[0x00, 0x00, 0x00][..]));
+        assert_eq!((frames[1].offset, frames[1].id, frames[1].body.as_ref()), (6, 0x01, &[0x00; 8][..]));
+        assert!(matches!(frames[1].body, Cow::Owned(_)));
+    }
+}
@@ -0,0 +1,177 @@
+/// Declares enum of the packets which belong to the same state and bound.
+///
+/// Ids are taken from [Packet](crate::packet::Packet) implementations of the packets.
+/// Generated enum has `id`, `read_by_id` and is readable and writable with [VarInt](crate::packet_types::VarInt) id.
+/// It implements [PacketScope](crate::packet::PacketScope) with the given bound and state
+/// and [PacketReadableById](crate::packet::PacketReadableById).
+///
+/// ```ignore
+/// packet_state! {
+///     pub enum ClientStatusPacket<'a>: Client, Status {
+///         Response(StatusResponse<'a>),
+///         PingResponse(StatusPingResponse),
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! packet_state {
+    (
+        $(#[$meta: meta])*
+        $vis: vis enum $name: ident<$lt: lifetime>: $bound: ident, $state: ident {
+            $($variant: ident($ty: ty)),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        // Packets are read and written one at a time, so variants are not boxed
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Debug, Clone, PartialEq)]
+        $vis enum $name<$lt> {
+            $($variant($ty)),*
+        }
+
+        $crate::packet_state!(@impl $name [$lt] $lt $bound $state $($variant $ty)*);
+    };
+    (
+        $(#[$meta: meta])*
+        $vis: vis enum $name: ident: $bound: ident, $state: ident {
+            $($variant: ident($ty: ty)),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        // Packets are read and written one at a time, so variants are not boxed
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Debug, Clone, PartialEq)]
+        $vis enum $name {
+            $($variant($ty)),*
+        }
+
+        $crate::packet_state!(@impl $name [] 'a $bound $state $($variant $ty)*);
+    };
+    (@impl $name: ident [$($lt: lifetime)?] $read_lt: lifetime $bound: ident $state: ident $($variant: ident $ty: ty)*) => {
+        impl<$($lt)?> $name<$($lt)?> {
+            pub const BOUND: $crate::packet::PacketBound = $crate::packet::PacketBound::$bound;
+            pub const STATE: $crate::packet::PacketState = $crate::packet::PacketState::$state;
+
+            pub fn id(&self) -> i32 {
+                match self {
+                    $(Self::$variant(_) => <$ty as $crate::packet::Packet>::id()),*
+                }
+            }
+        }
+
+        impl<$($lt)?> $crate::packet::PacketScope for $name<$($lt)?> {
+            fn bound() -> $crate::packet::PacketBound {
+                Self::BOUND
+            }
+
+            fn state() -> $crate::packet::PacketState {
+                Self::STATE
+            }
+        }
+
+        impl<$read_lt> $name<$($lt)?> {
+            /// Reads body of the packet with the given id.
+            pub fn read_by_id<R>(id: i32, read: &mut R) -> Result<Self, $crate::packet::PacketReadableError>
+                where R: $crate::packet::PacketRead<$read_lt> {
+                $(
+                    if id == <$ty as $crate::packet::Packet>::id() {
+                        return Ok(Self::$variant(<$ty as $crate::packet::PacketReadable<$read_lt>>::read(read)?));
+                    }
+                )*
+                Err($crate::packet::PacketReadableError::BadPacketId(id))
+            }
+        }
+
+        impl<$read_lt> $crate::packet::PacketReadableById<$read_lt> for $name<$($lt)?> {
+            fn read_by_id<R>(id: i32, read: &mut R) -> Result<Self, $crate::packet::PacketReadableError>
+                where R: $crate::packet::PacketRead<$read_lt> {
+                Self::read_by_id(id, read)
+            }
+        }
+
+        impl<$read_lt> $crate::packet::PacketReadable<$read_lt> for $name<$($lt)?> {
+            fn read<R>(read: &mut R) -> Result<Self, $crate::packet::PacketReadableError>
+                where R: $crate::packet::PacketRead<$read_lt> {
+                let id = <$crate::packet_types::VarInt as $crate::packet::PacketVariantReadable<i32>>::read_variant(read)?;
+                Self::read_by_id(id, read)
+            }
+        }
+
+        impl<$($lt)?> $crate::packet::PacketWritable for $name<$($lt)?> {
+            fn write<W>(&self, write: &mut W) -> Result<(), anyhow::Error> where W: $crate::packet::PacketWrite {
+                match self {
+                    $(Self::$variant(packet) => <$crate::packet_types::PacketVariant as $crate::packet::PacketVariantWritable<$ty>>
+                        ::write_variant(packet, write)),*
+                }
+            }
+        }
+    };
+}
+
+#[cfg(all(test, feature = "packet_default"))]
+mod tests {
+    use crate::packet::{PacketBound, PacketReadable, PacketReadableError, PacketState, PacketWritable, SlicePacketRead};
+    use crate::packet_default::{LoginPluginRequest, LoginSetCompression, StatusPingRequest, StatusRequest};
+
+    packet_state! {
+        enum ServerStatusPacket: Server, Status {
+            Request(StatusRequest),
+            Ping(StatusPingRequest),
+        }
+    }
+
+    packet_state! {
+        enum ClientLoginPacket<'a>: Client, Login {
+            SetCompression(LoginSetCompression),
+            PluginRequest(LoginPluginRequest<'a>),
+        }
+    }
+
+    #[test]
+    fn borrowed_packet_state_tests() {
+        let packet = ClientLoginPacket::PluginRequest(LoginPluginRequest {
+            message_id: 1,
+            channel: bird_chat::identifier::Identifier::new_fulled("minecraft:brand").unwrap(),
+            data: &[1, 2, 3],
+        });
+        assert_eq!(packet.id(), 0x04);
+        assert_eq!((ClientLoginPacket::BOUND, ClientLoginPacket::STATE), (PacketBound::Client, PacketState::Login));
+        let mut write = Vec::new();
+        ClientLoginPacket::SetCompression(LoginSetCompression { threshold: 256 }).write(&mut write).unwrap();
+        packet.write(&mut write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(
+            ClientLoginPacket::read(&mut read).unwrap(),
+            ClientLoginPacket::SetCompression(LoginSetCompression { threshold: 256 })
+        );
+        // Plugin data takes the remaining bytes
+        assert_eq!(ClientLoginPacket::read(&mut read).unwrap(), packet);
+    }
+
+    #[test]
+    fn packet_state_tests() {
+        assert_eq!(ServerStatusPacket::BOUND, PacketBound::Server);
+        assert_eq!(ServerStatusPacket::STATE, PacketState::Status);
+        let packets = [
+            ServerStatusPacket::Request(StatusRequest),
+            ServerStatusPacket::Ping(StatusPingRequest { payload: 5 }),
+        ];
+        assert_eq!(packets[1].id(), 0x01);
+        let mut write = Vec::new();
+        for packet in &packets {
+            packet.write(&mut write).unwrap();
+        }
+        assert_eq!(write, &[0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 5]);
+        let mut read = SlicePacketRead::new(write.as_slice());
+        for packet in &packets {
+            assert_eq!(&ServerStatusPacket::read(&mut read).unwrap(), packet);
+        }
+        let mut read = SlicePacketRead::new(&[0, 0, 0, 0, 0, 0, 0, 7]);
+        assert_eq!(
+            ServerStatusPacket::read_by_id(0x01, &mut read).unwrap(),
+            ServerStatusPacket::Ping(StatusPingRequest { payload: 7 })
+        );
+        let mut read = SlicePacketRead::new(&[0x02]);
+        assert!(matches!(ServerStatusPacket::read(&mut read), Err(PacketReadableError::BadPacketId(0x02))));
+    }
+}
@@ -1,9 +1,13 @@
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
 use anyhow::Error;
 use uuid::Uuid;
 use crate::Packet;
-use crate::packet::{PacketRead, PacketReadable, PacketReadableError, PacketWritable, PacketVariantReadable, PacketVariantWritable, PacketWrite};
+use crate::packet::{PacketDecodeError, PacketEnum, PacketRead, PacketReadable, PacketReadableError, PacketWritable, PacketWritableError, PacketVariantReadable, PacketVariantWritable, PacketWrite};
 
 pub struct VarInt;
 
@@ -16,8 +20,18 @@ pub struct RemainingSlice<
     PhantomData<Value>, PhantomData<ValueInner>,
 );
 
+/// Bytes until the end of the reader, which are taken by one call without copying.
 pub struct RemainingBytesSlice;
 
+/// Array of the fixed length which is not prefixed by the length.
+pub struct FixedSlice<
+    Value,
+    ValueInner = Value,
+>(
+    PhantomData<Value>, PhantomData<ValueInner>,
+);
+
+/// Elements prefixed by the length. For bytes prefer [LengthProvidedBytesSlice], which does not read them one by one.
 pub struct LengthProvidedSlice<
     Length,
     Value,
@@ -27,6 +41,7 @@ pub struct LengthProvidedSlice<
     PhantomData<Length>, PhantomData<LengthInner>, PhantomData<Value>, PhantomData<ValueInner>,
 );
 
+/// Bytes prefixed by the length, which are taken by one call without copying.
 pub struct LengthProvidedBytesSlice<
     Length,
     LengthInner: PacketLength = Length
@@ -35,19 +50,162 @@ pub struct LengthProvidedBytesSlice<
     PhantomData<LengthInner>,
 );
 
-pub struct ProtocolJson;
+/// String which length in UTF-16 units is limited by `LIMIT` as vanilla does.
+pub struct LimitedString<const LIMIT: i32>;
+
+/// Identifier which length is limited by `LIMIT` instead of [IDENTIFIER_LIMIT].
+pub struct LimitedIdentifier<const LIMIT: i32>;
+
+/// Json prefixed by the length in bytes, which is limited by `LIMIT`. Chat components use [CHAT_LIMIT].
+pub struct ProtocolJson<const LIMIT: i32 = DEFAULT_LIMIT>;
 
 pub struct ProtocolNbt;
 
+/// Nbt variant where the single `TAG_End` byte means absence of the value.
+pub struct ProtocolOptionalNbt;
+
+/// With `serde` feature position is serialized as `[x, y, z]`.
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "(i32, i16, i32)", into = "(i32, i16, i32)"))]
 pub struct BlockPosition {
     pub x: i32,
     pub y: i16,
     pub z: i32,
 }
 
+/// Position of the chunk section, which is 16 blocks on each axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "(i32, i32, i32)", into = "(i32, i32, i32)"))]
+pub struct SectionPosition {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Relative movement of the entity in 1/4096 of a block, so each axis is in range from -8 to 8 blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "[i16; 3]", into = "[i16; 3]"))]
+pub struct PositionDelta {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+/// Velocity of the entity in 1/8000 of a block per tick. Vanilla clamps each axis to 3.9 blocks per tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "[i16; 3]", into = "[i16; 3]"))]
+pub struct Velocity {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+/// Position in the specific dimension, which is used for example as death location.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalPosition<'a> {
+    pub dimension: bird_chat::identifier::Identifier<'a>,
+    pub position: BlockPosition,
+}
+
+/// Bit set, which is written as [VarInt] length and longs like `BitSet.toLongArray()` in java.
+///
+/// Trailing zero longs are never stored, so bit sets with the same bits are equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<u64>", into = "Vec<u64>"))]
+pub struct BitSet {
+    longs: Vec<u64>,
+}
+
+/// Angle variant for [f32] in radians. Angle is written as one byte, which is 1/256 of a full turn.
 pub struct Angle;
 
+impl Angle {
+    pub fn from_radians(radians: f32) -> u8 {
+        // NaN and infinity are cast to 0, whole turn is wrapped by the cast to u8
+        (radians.rem_euclid(std::f32::consts::TAU) * 256f32 / std::f32::consts::TAU).round() as u32 as u8
+    }
+
+    pub fn from_degrees(degrees: f32) -> u8 {
+        Self::from_radians(degrees.to_radians())
+    }
+
+    pub fn to_radians(angle: u8) -> f32 {
+        angle as f32 * std::f32::consts::TAU / 256f32
+    }
+
+    pub fn to_degrees(angle: u8) -> f32 {
+        angle as f32 * 360f32 / 256f32
+    }
+}
+
+/// Variant for [f64] in blocks, which is written as [i8] in 1/32 of a block like in relative moves of old versions.
+pub struct FixedPointByte;
+
+impl FixedPointByte {
+    pub const SCALE: f64 = 32.0;
+
+    pub fn from_blocks(blocks: f64) -> Result<i8, PacketWritableError> {
+        fixed_point(blocks, Self::SCALE, "Fixed point byte")
+    }
+
+    pub fn to_blocks(value: i8) -> f64 {
+        value as f64 / Self::SCALE
+    }
+}
+
+/// Rounds `value * scale` to the nearest value of the integer type.
+pub(crate) fn fixed_point<T: TryFrom<i64>>(value: f64, scale: f64, name: &'static str) -> Result<T, PacketWritableError> {
+    let scaled = (value * scale).round();
+    // Casting saturates and NaN becomes zero, so these values are checked before the conversion
+    match scaled.is_finite() && scaled.abs() <= i32::MAX as f64 {
+        true => T::try_from(scaled as i64).map_err(|_| PacketWritableError::OutOfRange(name)),
+        false => Err(PacketWritableError::OutOfRange(name)),
+    }
+}
+
+/// Value of [PacketEnum] which keeps unknown values, for example of newer versions, instead of failing.
+///
+/// Unknown value is written back as it is, so passed through packets are not changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpenEnum<E: PacketEnum> {
+    Known(E),
+    Unknown(E::Value),
+}
+
+/// Optional value without the presence prefix, because the presence is marked by flags of the packet.
+///
+/// Value is written only if it is some. It is read by [MaskedOption::read_if] or by derive with
+/// `#[present_if(flags & 0x01 != 0)]` attribute, where the condition can use fields before this one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MaskedOption<T>(pub Option<T>);
+
+/// Optional [VarInt], which is written as value + 1, where zero means absence.
+///
+/// Negative values are not written, because they collide with the absence or are read as another value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct OptVarInt(pub Option<i32>);
+
+/// Optional uuid of the entity metadata, which presence is prefixed by bool like any [Option].
+pub type OptUuid = Option<Uuid>;
+
+/// Integer which is in range `MIN..=MAX`. It is checked on construction and on read.
+///
+/// Integers of other variants are read by [BoundedVariant], like `#[variant(BoundedVarInt)] distance: Bounded<i32, 2, 32>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bounded<T, const MIN: i64, const MAX: i64>(T);
+
+/// Variant of [Bounded], which value is written by the variant `V`.
+pub struct BoundedVariant<V>(PhantomData<V>);
+
+pub type BoundedVarInt = BoundedVariant<VarInt>;
+
 /// Packet variant for PacketWritable and PacketReadable.
 ///
 /// Writable: Write packet id as [VarInt] and then packet itself.
@@ -65,6 +223,10 @@ impl PacketWritable for u8 {
     fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
         write.write_byte(*self)
     }
+
+    fn size(&self) -> Result<usize, Error> {
+        Ok(1)
+    }
 }
 
 impl<'a> PacketReadable<'a> for i8 {
@@ -77,6 +239,10 @@ impl PacketWritable for i8 {
     fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
         write.write_byte(*self as u8)
     }
+
+    fn size(&self) -> Result<usize, Error> {
+        Ok(1)
+    }
 }
 
 impl<'a> PacketReadable<'a> for bool {
@@ -92,12 +258,46 @@ impl PacketWritable for bool {
             false => 0u8
         }.write(write)
     }
+
+    fn size(&self) -> Result<usize, Error> {
+        Ok(1)
+    }
+}
+
+/// Vanilla limits strings by UTF-16 units, so string can be up to 3 times longer in UTF-8 bytes.
+pub(crate) fn str_fits_limit(str: &str, limit: i32) -> bool {
+    let limit = limit as usize;
+    str.len() <= limit || (str.len() <= limit * 3 && str.encode_utf16().count() <= limit)
 }
 
 fn read_str_with_limit<'a, R>(read: &mut R, limit: i32) -> Result<&'a str, PacketReadableError>
     where R: PacketRead<'a> {
-    let slice = read_bytes_with_limit(read, limit)?;
-    std::str::from_utf8(slice).map_err(|err| PacketReadableError::Any(err.into()))
+    let length = VarInt::read_variant(read)?;
+    match length < 0 || length as i64 > limit as i64 * 3 {
+        true => Err(PacketReadableError::Any(anyhow::Error::msg("Too big string"))),
+        false => {
+            let str = std::str::from_utf8(read.take_slice(length as usize)?)
+                .map_err(|err| PacketReadableError::Any(err.into()))?;
+            match str_fits_limit(str, limit) {
+                true => Ok(str),
+                false => Err(PacketReadableError::Any(anyhow::Error::msg("Too big string"))),
+            }
+        }
+    }
+}
+
+pub(crate) fn write_str_with_limit<W>(str: &str, limit: i32, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+    match str_fits_limit(str, limit) {
+        true => LengthProvidedBytesSlice::<VarInt, i32>::write_variant(str.as_bytes(), write),
+        false => Err(PacketWritableError::StringLimit { limit, length: str.encode_utf16().count() }.into()),
+    }
+}
+
+fn str_size_with_limit(str: &str, limit: i32) -> Result<usize, Error> {
+    match str_fits_limit(str, limit) {
+        true => VarInt::size_variant(&(str.len() as i32)).map(|size| size + str.len()),
+        false => Err(PacketWritableError::StringLimit { limit, length: str.encode_utf16().count() }.into()),
+    }
 }
 
 fn read_bytes_with_limit<'a, R>(read: &mut R, limit: i32) -> Result<&'a [u8], PacketReadableError>
@@ -111,8 +311,12 @@ fn read_bytes_with_limit<'a, R>(read: &mut R, limit: i32) -> Result<&'a [u8], Pa
     }
 }
 
-const DEFAULT_LIMIT: i32 = 32767;
-const CHAT_LIMIT: i32 = 262144;
+/// Default limit of strings and json.
+pub const DEFAULT_LIMIT: i32 = 32767;
+/// Limit of chat components json.
+pub const CHAT_LIMIT: i32 = 262144;
+/// Limit of identifiers in most of the packets, for example of channels and recipes.
+pub const IDENTIFIER_LIMIT: i32 = 256;
 
 impl<'a> PacketReadable<'a> for &'a str {
     fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
@@ -132,12 +336,25 @@ impl<'a> PacketReadable<'a> for Cow<'a, str> {
     }
 }
 
+impl<'a> PacketReadable<'a> for &'a [u8] {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        LengthProvidedBytesSlice::<VarInt, i32>::read_variant(read)
+    }
+}
+
+impl<'a> PacketReadable<'a> for Cow<'a, [u8]> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(Cow::Borrowed(<&'a [u8]>::read(read)?))
+    }
+}
+
 impl PacketWritable for &str {
     fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        match self.len() > DEFAULT_LIMIT as usize {
-            true => Err(Error::msg("Too big string")),
-            false => LengthProvidedBytesSlice::<VarInt, i32>::write_variant(self.as_bytes(), write)
-        }
+        write_str_with_limit(self, DEFAULT_LIMIT, write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        str_size_with_limit(self, DEFAULT_LIMIT)
     }
 }
 
@@ -145,6 +362,10 @@ impl PacketWritable for String {
     fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
         self.as_str().write(write)
     }
+
+    fn size(&self) -> Result<usize, Error> {
+        self.as_str().size()
+    }
 }
 
 impl PacketWritable for Cow<'_, str> {
@@ -156,6 +377,48 @@ impl PacketWritable for Cow<'_, str> {
     }
 }
 
+impl<'a, const LIMIT: i32> PacketVariantReadable<'a, &'a str> for LimitedString<LIMIT> {
+    fn read_variant<R>(read: &mut R) -> Result<&'a str, PacketReadableError> where R: PacketRead<'a> {
+        read_str_with_limit(read, LIMIT)
+    }
+}
+
+impl<'a, const LIMIT: i32> PacketVariantReadable<'a, String> for LimitedString<LIMIT> {
+    fn read_variant<R>(read: &mut R) -> Result<String, PacketReadableError> where R: PacketRead<'a> {
+        read_str_with_limit(read, LIMIT).map(|str| str.into())
+    }
+}
+
+impl<'a, const LIMIT: i32> PacketVariantReadable<'a, Cow<'a, str>> for LimitedString<LIMIT> {
+    fn read_variant<R>(read: &mut R) -> Result<Cow<'a, str>, PacketReadableError> where R: PacketRead<'a> {
+        read_str_with_limit(read, LIMIT).map(Cow::Borrowed)
+    }
+}
+
+impl<const LIMIT: i32> PacketVariantWritable<str> for LimitedString<LIMIT> {
+    fn write_variant<W>(object: &str, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_str_with_limit(object, LIMIT, write)
+    }
+}
+
+impl<const LIMIT: i32> PacketVariantWritable<&str> for LimitedString<LIMIT> {
+    fn write_variant<W>(object: &&str, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_str_with_limit(object, LIMIT, write)
+    }
+}
+
+impl<const LIMIT: i32> PacketVariantWritable<String> for LimitedString<LIMIT> {
+    fn write_variant<W>(object: &String, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_str_with_limit(object, LIMIT, write)
+    }
+}
+
+impl<const LIMIT: i32> PacketVariantWritable<Cow<'_, str>> for LimitedString<LIMIT> {
+    fn write_variant<W>(object: &Cow<'_, str>, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_str_with_limit(object, LIMIT, write)
+    }
+}
+
 impl<'a> PacketVariantReadable<'a, &'a [u8]> for RemainingBytesSlice {
     fn read_variant<R>(read: &mut R) -> Result<&'a [u8], PacketReadableError> where R: PacketRead<'a> {
         read.take_slice(read.available())
@@ -253,10 +516,123 @@ impl<
     }
 }
 
-pub trait PacketLength {
-    fn into_length(self) -> usize;
+impl<
+    'a,
+    Value: PacketVariantReadable<'a, ValueInner>,
+    ValueInner,
+    const N: usize
+> PacketVariantReadable<'a, [ValueInner; N]> for FixedSlice<Value, ValueInner> {
+    fn read_variant<R>(read: &mut R) -> Result<[ValueInner; N], PacketReadableError> where R: PacketRead<'a> {
+        // Already read elements are dropped if one of them fails
+        std::array::try_from_fn(|_| Value::read_variant(read))
+    }
+}
+
+impl<
+    Value: PacketVariantWritable<ValueInner>,
+    ValueInner,
+    const N: usize
+> PacketVariantWritable<[ValueInner; N]> for FixedSlice<Value, ValueInner> {
+    fn write_variant<W>(object: &[ValueInner; N], write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        RemainingSlice::<Value, ValueInner>::write_variant(object.as_slice(), write)
+    }
+}
+
+impl<'a, T: PacketReadable<'a>> PacketReadable<'a> for Vec<T> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        LengthProvidedSlice::<VarInt, T, i32>::read_variant(read)
+    }
+}
+
+impl<T: PacketWritable> PacketWritable for Vec<T> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        LengthProvidedSlice::<VarInt, T, i32>::write_variant(self.as_slice(), write)
+    }
+}
+
+fn write_map<'b, K, V, W, I>(length: usize, entries: I, write: &mut W) -> Result<(), Error>
+    where K: PacketWritable + 'b, V: PacketWritable + 'b, W: PacketWrite, I: Iterator<Item=(&'b K, &'b V)> {
+    VarInt::write_variant(&i32::from_length(length)?, write)?;
+    for (key, value) in entries {
+        key.write(write)?;
+        value.write(write)?;
+    }
+    Ok(())
+}
+
+/// Read as [VarInt] count and key value pairs. Duplicate keys are not allowed.
+impl<'a, K, V> PacketReadable<'a> for HashMap<K, V>
+    where K: PacketReadable<'a> + Hash + Eq, V: PacketReadable<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let length = VarInt::read_variant(read)?.into_length()?;
+        let mut map = HashMap::with_capacity(preallocation_capacity::<(K, V)>(length.min(read.available())));
+        for _ in 0..length {
+            if map.insert(K::read(read)?, V::read(read)?).is_some() {
+                return Err(PacketReadableError::DuplicateKey);
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl<K: PacketWritable, V: PacketWritable> PacketWritable for HashMap<K, V> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_map(self.len(), self.iter(), write)
+    }
+}
+
+/// Read as [VarInt] count and key value pairs. Duplicate keys are not allowed.
+impl<'a, K, V> PacketReadable<'a> for BTreeMap<K, V>
+    where K: PacketReadable<'a> + Ord, V: PacketReadable<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let length = VarInt::read_variant(read)?.into_length()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..length {
+            if map.insert(K::read(read)?, V::read(read)?).is_some() {
+                return Err(PacketReadableError::DuplicateKey);
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl<K: PacketWritable, V: PacketWritable> PacketWritable for BTreeMap<K, V> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_map(self.len(), self.iter(), write)
+    }
+}
+
+impl<'a, T: PacketReadable<'a>, const N: usize> PacketReadable<'a> for [T; N] {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        FixedSlice::<T>::read_variant(read)
+    }
+}
+
+impl<T: PacketWritable, const N: usize> PacketWritable for [T; N] {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        FixedSlice::<T>::write_variant(self, write)
+    }
+}
 
-    fn from_length(length: usize) -> Self;
+/// Maximum count of bytes which is allocated for the declared length before values are actually read.
+pub const PREALLOCATION_LIMIT: usize = 65536;
+
+/// Returns capacity for the declared length of values, which is not trusted, so it is limited by [PREALLOCATION_LIMIT].
+pub fn preallocation_capacity<T>(length: usize) -> usize {
+    length.min(PREALLOCATION_LIMIT / std::mem::size_of::<T>().max(1))
+}
+
+/// Creates vector for the declared length, which is not trusted, so capacity is limited by [PREALLOCATION_LIMIT].
+pub fn preallocate<T>(length: usize) -> Vec<T> {
+    Vec::with_capacity(preallocation_capacity::<T>(length))
+}
+
+/// Length prefix of arrays. Both conversions are checked, because lengths come from untrusted input
+/// and the prefix type can be smaller than [usize].
+pub trait PacketLength: Sized {
+    fn into_length(self) -> Result<usize, PacketReadableError>;
+
+    fn from_length(length: usize) -> Result<Self, PacketWritableError>;
 }
 
 impl<
@@ -265,7 +641,7 @@ impl<
     LengthInner: PacketLength
 > PacketVariantReadable<'a, &'a [u8]> for LengthProvidedBytesSlice<Length, LengthInner> {
     fn read_variant<R>(read: &mut R) -> Result<&'a [u8], PacketReadableError> where R: PacketRead<'a> {
-        let length = Length::read_variant(read)?.into_length();
+        let length = Length::read_variant(read)?.into_length()?;
         read.take_slice(length)
     }
 }
@@ -293,7 +669,9 @@ impl<
     LengthInner: PacketLength
 > PacketVariantWritable<[u8]> for LengthProvidedBytesSlice<Length, LengthInner> {
     fn write_variant<W>(object: &[u8], write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        Length::write_variant(&LengthInner::from_length(object.len()), write)?;
+        let length = LengthInner::from_length(object.len())?;
+        write.reserve(Length::size_variant(&length)? + object.len());
+        Length::write_variant(&length, write)?;
         write.write_bytes(object)
     }
 }
@@ -324,11 +702,11 @@ impl<
     Length: PacketVariantReadable<'a, LengthInner>,
     Value: PacketVariantReadable<'a, ValueInner>,
     LengthInner: PacketLength,
-    ValueInner: 'a
+    ValueInner
 > PacketVariantReadable<'a, Vec<ValueInner>> for LengthProvidedSlice<Length, Value, LengthInner, ValueInner> {
     fn read_variant<R>(read: &mut R) -> Result<Vec<ValueInner>, PacketReadableError> where R: PacketRead<'a> {
-        let length = Length::read_variant(read)?.into_length();
-        let mut result = Vec::with_capacity(length);
+        let length = Length::read_variant(read)?.into_length()?;
+        let mut result = preallocate(length.min(read.available()));
         for _ in 0..length {
             result.push(Value::read_variant(read)?);
         }
@@ -355,7 +733,10 @@ impl<
     ValueInner
 > PacketVariantWritable<[ValueInner]> for LengthProvidedSlice<Length, Value, LengthInner, ValueInner> {
     fn write_variant<W>(object: &[ValueInner], write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        Length::write_variant(&LengthInner::from_length(object.len()), write)?;
+        let length = LengthInner::from_length(object.len())?;
+        // Sizes of elements are not computed, because nested arrays would be written again on each level
+        write.reserve(Length::size_variant(&length)? + std::mem::size_of_val(object));
+        Length::write_variant(&length, write)?;
         for element in object {
             Value::write_variant(element, write)?
         }
@@ -388,153 +769,897 @@ impl<
     }
 }
 
-impl<'a, T: 'a + serde::Deserialize<'a>> PacketVariantReadable<'a, T> for ProtocolJson {
+/// Json is written to [PacketWrite] directly, so it is not collected to the intermediate buffer.
+struct JsonPacketWrite<'w, W: PacketWrite>(&'w mut W);
+
+impl<W: PacketWrite> std::io::Write for JsonPacketWrite<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write_bytes(buf).map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct JsonCountingWrite(usize);
+
+impl std::io::Write for JsonCountingWrite {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn json_length<T: serde::Serialize>(object: &T, limit: i32) -> Result<i32, Error> {
+    let mut counting = JsonCountingWrite::default();
+    serde_json::to_writer(&mut counting, object)?;
+    match counting.0 > limit as usize {
+        true => Err(Error::msg("Too big json")),
+        false => Ok(counting.0 as i32),
+    }
+}
+
+impl<'a, T: 'a + serde::Deserialize<'a>, const LIMIT: i32> PacketVariantReadable<'a, T> for ProtocolJson<LIMIT> {
     fn read_variant<R>(read: &mut R) -> Result<T, PacketReadableError> where R: PacketRead<'a> {
-        let slice = read_bytes_with_limit(read, DEFAULT_LIMIT)?;
+        let slice = read_bytes_with_limit(read, LIMIT)?;
         serde_json::from_slice(slice).map_err(|err| PacketReadableError::Any(err.into()))
     }
 }
 
-impl<T: serde::Serialize> PacketVariantWritable<T> for ProtocolJson {
+impl<T: serde::Serialize, const LIMIT: i32> PacketVariantWritable<T> for ProtocolJson<LIMIT> {
     fn write_variant<W>(object: &T, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        let bytes = serde_json::to_vec(object)?;
-        match bytes.len() > DEFAULT_LIMIT as usize {
-            true => Err(Error::msg("Too big json")),
-            false => LengthProvidedBytesSlice::<VarInt, i32>::write_variant(
-                &bytes, write,
-            )
-        }
+        let length = json_length(object, LIMIT)?;
+        write.reserve(VarInt::size_variant(&length)? + length as usize);
+        VarInt::write_variant(&length, write)?;
+        Ok(serde_json::to_writer(JsonPacketWrite(write), object)?)
+    }
+
+    fn size_variant(object: &T) -> Result<usize, Error> {
+        let length = json_length(object, LIMIT)?;
+        Ok(VarInt::size_variant(&length)? + length as usize)
     }
 }
 
 impl<'a> PacketVariantReadable<'a, f32> for Angle {
     fn read_variant<R>(read: &mut R) -> Result<f32, PacketReadableError> where R: PacketRead<'a> {
-        Ok(u8::read(read)? as f32 * std::f32::consts::PI / 256f32)
+        Ok(Self::to_radians(u8::read(read)?))
     }
 }
 
 impl PacketVariantWritable<f32> for Angle {
     fn write_variant<W>(object: &f32, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        // TODO should we check for panic situations?
-        ((*object * 256f32 / std::f32::consts::PI) as u8).write(write)
+        Self::from_radians(*object).write(write)
     }
 }
 
-impl<'a> PacketReadable<'a> for BlockPosition {
-    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
-        let value = u64::read(read)?;
-        let mut x = (value >> 38) as i32;
-        let mut y = (value & 0xFFF) as i16;
-        let mut z = ((value >> 12) & 0x3FFFFFF) as i32;
-        if x >= 0x2000000 {
-            x -= 0x4000000
-        }
-        if y >= 0x800 {
-            y -= 0x1000
-        }
-        if z >= 0x2000000 {
-            z -= 0x4000000
-        }
-        Ok(BlockPosition { x, y, z })
+/// Variant for [f32] and [f64], which are rejected if they are NaN or infinite like coordinates of the movement in vanilla.
+pub struct Finite;
+
+macro_rules! finite_variant_impl {
+    ($($ty: ty),*) => {
+        $(
+            impl<'a> PacketVariantReadable<'a, $ty> for Finite {
+                fn read_variant<R>(read: &mut R) -> Result<$ty, PacketReadableError> where R: PacketRead<'a> {
+                    match <$ty>::read(read)? {
+                        value if value.is_finite() => Ok(value),
+                        _ => Err(PacketReadableError::NonFinite),
+                    }
+                }
+            }
+
+            impl PacketVariantWritable<$ty> for Finite {
+                fn write_variant<W>(object: &$ty, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+                    match object.is_finite() {
+                        true => object.write(write),
+                        false => Err(PacketWritableError::NonFinite.into()),
+                    }
+                }
+            }
+        )*
     }
 }
 
-impl PacketWritable for BlockPosition {
-    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        (
-            ((self.x as i64 & 0x3FFFFFF) << 38) |
-                ((self.z as i64 & 0x3FFFFFF) << 12) |
-                (self.y as i64 & 0xFFF)
-        ).write(write)
+finite_variant_impl!(f32, f64);
+
+/// Variant for [f32] pitch in degrees, which must be finite.
+///
+/// Pitch out of [Pitch::MIN]..=[Pitch::MAX] is clamped on read like in vanilla, but it is rejected on write.
+pub struct Pitch;
+
+impl Pitch {
+    pub const MIN: f32 = -90.0;
+    pub const MAX: f32 = 90.0;
+}
+
+impl<'a> PacketVariantReadable<'a, f32> for Pitch {
+    fn read_variant<R>(read: &mut R) -> Result<f32, PacketReadableError> where R: PacketRead<'a> {
+        Finite::read_variant(read).map(|pitch: f32| pitch.clamp(Self::MIN, Self::MAX))
     }
 }
 
-impl<'a> PacketReadable<'a> for bird_chat::component::Component<'a> {
-    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
-        let bytes = read_bytes_with_limit(read, CHAT_LIMIT)?;
-        serde_json::from_slice(bytes).map_err(|err| PacketReadableError::Any(err.into()))
+impl PacketVariantWritable<f32> for Pitch {
+    fn write_variant<W>(object: &f32, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        match object.is_finite() && !(Self::MIN..=Self::MAX).contains(object) {
+            true => Err(PacketWritableError::FloatOutOfBounds {
+                value: *object as f64, min: Self::MIN as f64, max: Self::MAX as f64,
+            }.into()),
+            false => Finite::write_variant(object, write),
+        }
     }
 }
 
-impl PacketWritable for bird_chat::component::Component<'_> {
-    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        let bytes = serde_json::to_vec(self)?;
-        match bytes.len() > CHAT_LIMIT as usize {
-            true => Err(Error::msg("Too big component json")),
-            false => LengthProvidedBytesSlice::<VarInt, i32>::write_variant(&bytes, write)
+impl BlockPosition {
+    pub const MIN_XZ: i32 = -(1 << 25);
+    pub const MAX_XZ: i32 = (1 << 25) - 1;
+    pub const MIN_Y: i16 = -(1 << 11);
+    pub const MAX_Y: i16 = (1 << 11) - 1;
+
+    /// Packs position as x (26 bits), z (26 bits) and y (12 bits).
+    pub fn try_pack(&self) -> Result<u64, PacketWritableError> {
+        match (
+            (Self::MIN_XZ..=Self::MAX_XZ).contains(&self.x),
+            (Self::MIN_Y..=Self::MAX_Y).contains(&self.y),
+            (Self::MIN_XZ..=Self::MAX_XZ).contains(&self.z),
+        ) {
+            (false, _, _) => Err(PacketWritableError::OutOfRange("Block position x")),
+            (_, false, _) => Err(PacketWritableError::OutOfRange("Block position y")),
+            (_, _, false) => Err(PacketWritableError::OutOfRange("Block position z")),
+            (true, true, true) => Ok(
+                ((self.x as u64 & 0x3FFFFFF) << 38) |
+                    ((self.z as u64 & 0x3FFFFFF) << 12) |
+                    (self.y as u64 & 0xFFF)
+            ),
+        }
+    }
+
+    pub fn from_packed(value: u64) -> Self {
+        // Arithmetic shifts of the signed value extend the sign of each part
+        let value = value as i64;
+        BlockPosition {
+            x: (value >> 38) as i32,
+            y: ((value << 52) >> 52) as i16,
+            z: ((value << 26) >> 38) as i32,
         }
     }
 }
 
-impl<'a> PacketReadable<'a> for bird_chat::identifier::Identifier<'a> {
-    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
-        bird_chat::identifier::Identifier::new_fulled(<&'a str>::read(read)?)
-            .map_err(|err| PacketReadableError::Any(err.into()))
+impl From<(i32, i16, i32)> for BlockPosition {
+    fn from((x, y, z): (i32, i16, i32)) -> Self {
+        Self { x, y, z }
     }
 }
 
-impl PacketWritable for bird_chat::identifier::Identifier<'_> {
-    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        self.get_fulled().write(write)
+impl From<BlockPosition> for (i32, i16, i32) {
+    fn from(position: BlockPosition) -> Self {
+        (position.x, position.y, position.z)
     }
 }
 
-impl<'a> PacketReadable<'a> for Uuid {
+impl<'a> PacketReadable<'a> for BlockPosition {
     fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
-        Uuid::from_slice(read.take_slice(16)?)
-            .map_err(|err| PacketReadableError::Any(err.into()))
+        u64::read(read).map(Self::from_packed)
     }
 }
 
-impl PacketWritable for Uuid {
+impl PacketWritable for BlockPosition {
     fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        write.write_bytes(self.as_bytes().as_slice())
+        self.try_pack()?.write(write)
     }
 }
 
-impl<'a, T: PacketReadable<'a> + Packet> PacketVariantReadable<'a, T> for PacketVariant {
-    fn read_variant<R>(read: &mut R) -> Result<T, PacketReadableError> where R: PacketRead<'a> {
-        T::read(read)
+impl SectionPosition {
+    pub const MIN_XZ: i32 = -(1 << 21);
+    pub const MAX_XZ: i32 = (1 << 21) - 1;
+    pub const MIN_Y: i32 = -(1 << 19);
+    pub const MAX_Y: i32 = (1 << 19) - 1;
+
+    /// Section which contains the block.
+    pub fn containing(position: BlockPosition) -> Self {
+        Self { x: position.x >> 4, y: position.y as i32 >> 4, z: position.z >> 4 }
     }
-}
 
-impl<T: PacketWritable + Packet> PacketVariantWritable<T> for PacketVariant {
-    fn write_variant<W>(object: &T, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        VarInt::write_variant(&T::id(), write)?;
-        T::write(object, write)
+    /// Packs position as x (22 bits), z (22 bits) and y (20 bits).
+    pub fn try_pack(&self) -> Result<u64, PacketWritableError> {
+        match (
+            (Self::MIN_XZ..=Self::MAX_XZ).contains(&self.x),
+            (Self::MIN_Y..=Self::MAX_Y).contains(&self.y),
+            (Self::MIN_XZ..=Self::MAX_XZ).contains(&self.z),
+        ) {
+            (false, _, _) => Err(PacketWritableError::OutOfRange("Section position x")),
+            (_, false, _) => Err(PacketWritableError::OutOfRange("Section position y")),
+            (_, _, false) => Err(PacketWritableError::OutOfRange("Section position z")),
+            (true, true, true) => Ok(
+                ((self.x as u64 & 0x3FFFFF) << 42) |
+                    ((self.z as u64 & 0x3FFFFF) << 20) |
+                    (self.y as u64 & 0xFFFFF)
+            ),
+        }
     }
-}
 
-impl<T: PacketWritable> PacketWritable for Option<T> {
-    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
-        match self {
-            Some(ref obj) => {
-                true.write(write)?;
-                obj.write(write)
-            }
-            None => false.write(write)
+    pub fn from_packed(value: u64) -> Self {
+        // Arithmetic shifts of the signed value extend the sign of each part
+        let value = value as i64;
+        SectionPosition {
+            x: (value >> 42) as i32,
+            y: ((value << 44) >> 44) as i32,
+            z: ((value << 22) >> 42) as i32,
         }
     }
 }
 
-impl<'a, T: PacketReadable<'a>> PacketReadable<'a> for Option<T> {
-    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
-        Ok(match bool::read(read)? {
-            true => Some(T::read(read)?),
-            false => None
-        })
+impl From<(i32, i32, i32)> for SectionPosition {
+    fn from((x, y, z): (i32, i32, i32)) -> Self {
+        Self { x, y, z }
     }
 }
 
-macro_rules! length_impl {
-    ($num: ident) => {
-        impl const PacketLength for $num {
-            fn into_length(self) -> usize {
-                self as usize
+impl From<SectionPosition> for (i32, i32, i32) {
+    fn from(position: SectionPosition) -> Self {
+        (position.x, position.y, position.z)
+    }
+}
+
+impl<'a> PacketReadable<'a> for SectionPosition {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        u64::read(read).map(Self::from_packed)
+    }
+}
+
+impl PacketWritable for SectionPosition {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.try_pack()?.write(write)
+    }
+}
+
+impl PositionDelta {
+    pub const SCALE: f64 = 4096.0;
+
+    /// Converts movement in blocks. Movement which does not fit in the range is an error.
+    pub fn from_blocks(x: f64, y: f64, z: f64) -> Result<Self, PacketWritableError> {
+        Ok(Self {
+            x: fixed_point(x, Self::SCALE, "Position delta x")?,
+            y: fixed_point(y, Self::SCALE, "Position delta y")?,
+            z: fixed_point(z, Self::SCALE, "Position delta z")?,
+        })
+    }
+
+    /// Returns movement in blocks.
+    pub fn to_blocks(&self) -> [f64; 3] {
+        [self.x as f64 / Self::SCALE, self.y as f64 / Self::SCALE, self.z as f64 / Self::SCALE]
+    }
+}
+
+impl From<[i16; 3]> for PositionDelta {
+    fn from([x, y, z]: [i16; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl From<PositionDelta> for [i16; 3] {
+    fn from(delta: PositionDelta) -> Self {
+        [delta.x, delta.y, delta.z]
+    }
+}
+
+impl Velocity {
+    pub const SCALE: f64 = 8000.0;
+    /// Max speed on each axis in blocks per tick.
+    pub const MAX_BLOCKS_PER_TICK: f64 = 3.9;
+
+    /// Converts velocity in blocks per tick. Each axis is clamped like in vanilla and NaN becomes zero.
+    pub fn from_blocks(x: f64, y: f64, z: f64) -> Self {
+        let scale = |blocks: f64| {
+            // Casting of NaN is zero
+            (blocks.clamp(-Self::MAX_BLOCKS_PER_TICK, Self::MAX_BLOCKS_PER_TICK) * Self::SCALE).round() as i16
+        };
+        Self { x: scale(x), y: scale(y), z: scale(z) }
+    }
+
+    /// Returns velocity in blocks per tick.
+    pub fn to_blocks(&self) -> [f64; 3] {
+        [self.x as f64 / Self::SCALE, self.y as f64 / Self::SCALE, self.z as f64 / Self::SCALE]
+    }
+}
+
+impl From<[i16; 3]> for Velocity {
+    fn from([x, y, z]: [i16; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl From<Velocity> for [i16; 3] {
+    fn from(velocity: Velocity) -> Self {
+        [velocity.x, velocity.y, velocity.z]
+    }
+}
+
+impl<'a> PacketReadable<'a> for Velocity {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(Self { x: i16::read(read)?, y: i16::read(read)?, z: i16::read(read)? })
+    }
+}
+
+impl PacketWritable for Velocity {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.x.write(write)?;
+        self.y.write(write)?;
+        self.z.write(write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        Ok(6)
+    }
+}
+
+impl<'a> PacketReadable<'a> for PositionDelta {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(Self { x: i16::read(read)?, y: i16::read(read)?, z: i16::read(read)? })
+    }
+}
+
+impl PacketWritable for PositionDelta {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.x.write(write)?;
+        self.y.write(write)?;
+        self.z.write(write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        Ok(6)
+    }
+}
+
+impl<'a> PacketVariantReadable<'a, f64> for FixedPointByte {
+    fn read_variant<R>(read: &mut R) -> Result<f64, PacketReadableError> where R: PacketRead<'a> {
+        Ok(Self::to_blocks(i8::read(read)?))
+    }
+}
+
+impl PacketVariantWritable<f64> for FixedPointByte {
+    fn write_variant<W>(object: &f64, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        Self::from_blocks(*object)?.write(write)
+    }
+
+    fn size_variant(_object: &f64) -> Result<usize, Error> {
+        Ok(1)
+    }
+}
+
+impl<E: PacketEnum> OpenEnum<E> {
+    pub fn known(&self) -> Option<&E> {
+        match self {
+            OpenEnum::Known(known) => Some(known),
+            OpenEnum::Unknown(_) => None,
+        }
+    }
+
+    pub fn value(&self) -> E::Value {
+        match self {
+            OpenEnum::Known(known) => known.value(),
+            OpenEnum::Unknown(value) => *value,
+        }
+    }
+}
+
+impl<E: PacketEnum> From<E> for OpenEnum<E> {
+    fn from(known: E) -> Self {
+        OpenEnum::Known(known)
+    }
+}
+
+impl<'a, E: PacketEnum> PacketReadable<'a> for OpenEnum<E> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        let value = E::read_value(read)?;
+        Ok(match E::from_value(value) {
+            Some(known) => OpenEnum::Known(known),
+            None => OpenEnum::Unknown(value),
+        })
+    }
+}
+
+impl<E: PacketEnum> PacketWritable for OpenEnum<E> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        E::write_value(&self.value(), write)
+    }
+}
+
+impl<T> MaskedOption<T> {
+    pub fn is_some(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+
+    /// Reads the value only if it is present.
+    pub fn read_if<'a, R>(present: bool, read: &mut R) -> Result<Self, PacketReadableError>
+        where T: PacketReadable<'a>, R: PacketRead<'a> {
+        match present {
+            true => T::read(read).map(|value| Self(Some(value))),
+            false => Ok(Self(None)),
+        }
+    }
+
+    /// Writes the value and checks that it is present only if flags say so.
+    pub fn write_if<W>(&self, present: bool, write: &mut W) -> Result<(), Error>
+        where T: PacketWritable, W: PacketWrite {
+        match present == self.is_some() {
+            true => self.write(write),
+            false => Err(Error::msg("Presence of the masked option does not match the flags")),
+        }
+    }
+}
+
+impl<T: Copy + Into<i64>, const MIN: i64, const MAX: i64> Bounded<T, MIN, MAX> {
+    pub fn new(value: T) -> Result<Self, PacketWritableError> {
+        match (MIN..=MAX).contains(&value.into()) {
+            true => Ok(Self(value)),
+            false => Err(PacketWritableError::OutOfBounds { value: value.into(), min: MIN, max: MAX }),
+        }
+    }
+
+    fn checked(value: T) -> Result<Self, PacketReadableError> {
+        match (MIN..=MAX).contains(&value.into()) {
+            true => Ok(Self(value)),
+            false => Err(PacketReadableError::OutOfRange { value: value.into(), min: MIN, max: MAX }),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.0
+    }
+}
+
+impl<T, const MIN: i64, const MAX: i64> std::ops::Deref for Bounded<T, MIN, MAX> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<'a, T, const MIN: i64, const MAX: i64> PacketReadable<'a> for Bounded<T, MIN, MAX>
+    where T: PacketReadable<'a> + Copy + Into<i64> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Self::checked(T::read(read)?)
+    }
+}
+
+impl<T: PacketWritable, const MIN: i64, const MAX: i64> PacketWritable for Bounded<T, MIN, MAX> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.0.write(write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        self.0.size()
+    }
+}
+
+impl<'a, V, T, const MIN: i64, const MAX: i64> PacketVariantReadable<'a, Bounded<T, MIN, MAX>> for BoundedVariant<V>
+    where V: PacketVariantReadable<'a, T>, T: Copy + Into<i64> {
+    fn read_variant<R>(read: &mut R) -> Result<Bounded<T, MIN, MAX>, PacketReadableError> where R: PacketRead<'a> {
+        Bounded::checked(V::read_variant(read)?)
+    }
+}
+
+impl<V, T, const MIN: i64, const MAX: i64> PacketVariantWritable<Bounded<T, MIN, MAX>> for BoundedVariant<V>
+    where V: PacketVariantWritable<T> {
+    fn write_variant<W>(object: &Bounded<T, MIN, MAX>, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        V::write_variant(&object.0, write)
+    }
+
+    fn size_variant(object: &Bounded<T, MIN, MAX>) -> Result<usize, Error> {
+        V::size_variant(&object.0)
+    }
+}
+
+macro_rules! bounded_impl {
+    ($($num: ident)*) => {
+        $(
+            impl<const MIN: i64, const MAX: i64> From<Bounded<$num, MIN, MAX>> for $num {
+                fn from(value: Bounded<$num, MIN, MAX>) -> Self {
+                    value.0
+                }
+            }
+
+            impl<const MIN: i64, const MAX: i64> TryFrom<$num> for Bounded<$num, MIN, MAX> {
+                type Error = PacketWritableError;
+
+                fn try_from(value: $num) -> Result<Self, PacketWritableError> {
+                    Self::new(value)
+                }
+            }
+        )*
+    }
+}
+
+bounded_impl!(i8 i16 i32 i64 u8 u16 u32);
+
+impl<T> From<Option<T>> for MaskedOption<T> {
+    fn from(option: Option<T>) -> Self {
+        Self(option)
+    }
+}
+
+impl<T> From<MaskedOption<T>> for Option<T> {
+    fn from(option: MaskedOption<T>) -> Self {
+        option.0
+    }
+}
+
+impl<T: PacketWritable> PacketWritable for MaskedOption<T> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        match &self.0 {
+            Some(value) => value.write(write),
+            None => Ok(()),
+        }
+    }
+}
+
+impl OptVarInt {
+    pub fn is_some(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn into_inner(self) -> Option<i32> {
+        self.0
+    }
+
+    fn wire_value(&self) -> Result<i32, PacketWritableError> {
+        match self.0 {
+            Some(value @ 0..=0x7FFF_FFFE) => Ok(value + 1),
+            Some(value) => Err(PacketWritableError::OutOfBounds { value: value as i64, min: 0, max: i32::MAX as i64 - 1 }),
+            None => Ok(0),
+        }
+    }
+}
+
+impl From<Option<i32>> for OptVarInt {
+    fn from(option: Option<i32>) -> Self {
+        Self(option)
+    }
+}
+
+impl From<OptVarInt> for Option<i32> {
+    fn from(option: OptVarInt) -> Self {
+        option.0
+    }
+}
+
+impl<'a> PacketReadable<'a> for OptVarInt {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        match VarInt::read_variant(read)? {
+            0 => Ok(Self(None)),
+            value @ 1.. => Ok(Self(Some(value - 1))),
+            value => Err(PacketReadableError::OutOfRange { value: value as i64, min: 0, max: i32::MAX as i64 }),
+        }
+    }
+}
+
+impl PacketWritable for OptVarInt {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        VarInt::write_variant(&self.wire_value()?, write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        VarInt::size_variant(&self.wire_value()?)
+    }
+}
+
+impl<'a> PacketReadable<'a> for bird_chat::component::Component<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        ProtocolJson::<CHAT_LIMIT>::read_variant(read)
+    }
+}
+
+impl PacketWritable for bird_chat::component::Component<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        ProtocolJson::<CHAT_LIMIT>::write_variant(self, write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        ProtocolJson::<CHAT_LIMIT>::size_variant(self)
+    }
+}
+
+/// Checks characters like vanilla: namespace is `[a-z0-9_.-]`, path may also contain `/`.
+pub fn validate_identifier(identifier: &str) -> Result<(), PacketReadableError> {
+    let (namespace, path) = identifier.split_once(':')
+        .ok_or(PacketReadableError::BadIdentifier("No namespace"))?;
+    let valid = |byte: u8| matches!(byte, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b'-');
+    match (namespace.bytes().all(valid), path.bytes().all(|byte| valid(byte) || byte == b'/')) {
+        (false, _) => Err(PacketReadableError::BadIdentifier("Bad character in namespace")),
+        (_, false) => Err(PacketReadableError::BadIdentifier("Bad character in path")),
+        (true, true) => Ok(()),
+    }
+}
+
+fn read_identifier_with_limit<'a, R>(read: &mut R, limit: i32)
+                                     -> Result<bird_chat::identifier::Identifier<'a>, PacketReadableError>
+    where R: PacketRead<'a> {
+    let length = VarInt::read_variant(read)?;
+    // Valid identifiers are ascii, so bytes are compared with the limit
+    if length < 0 || length > limit {
+        return Err(PacketReadableError::BadIdentifierLength { limit, length });
+    }
+    let str = std::str::from_utf8(read.take_slice(length as usize)?)
+        .map_err(|_| PacketReadableError::BadIdentifier("Not utf-8"))?;
+    validate_identifier(str)?;
+    // Characters are validated, so there is only one colon
+    bird_chat::identifier::Identifier::new_fulled(str)
+        .map_err(|err| PacketReadableError::Any(err.into()))
+}
+
+fn write_identifier_with_limit<W>(identifier: &bird_chat::identifier::Identifier, limit: i32, write: &mut W)
+                                  -> Result<(), Error> where W: PacketWrite {
+    let fulled = identifier.get_fulled();
+    match fulled.len() > limit as usize {
+        true => Err(PacketWritableError::StringLimit { limit, length: fulled.len() }.into()),
+        false => LengthProvidedBytesSlice::<VarInt, i32>::write_variant(fulled.as_bytes(), write),
+    }
+}
+
+impl<'a> PacketReadable<'a> for bird_chat::identifier::Identifier<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        read_identifier_with_limit(read, IDENTIFIER_LIMIT)
+    }
+}
+
+impl PacketWritable for bird_chat::identifier::Identifier<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write_identifier_with_limit(self, IDENTIFIER_LIMIT, write)
+    }
+}
+
+impl<'a, const LIMIT: i32> PacketVariantReadable<'a, bird_chat::identifier::Identifier<'a>> for LimitedIdentifier<LIMIT> {
+    fn read_variant<R>(read: &mut R) -> Result<bird_chat::identifier::Identifier<'a>, PacketReadableError>
+        where R: PacketRead<'a> {
+        read_identifier_with_limit(read, LIMIT)
+    }
+}
+
+impl<const LIMIT: i32> PacketVariantWritable<bird_chat::identifier::Identifier<'_>> for LimitedIdentifier<LIMIT> {
+    fn write_variant<W>(object: &bird_chat::identifier::Identifier<'_>, write: &mut W) -> Result<(), Error>
+        where W: PacketWrite {
+        write_identifier_with_limit(object, LIMIT, write)
+    }
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.longs.get(index / 64).map(|long| (long >> (index % 64)) & 1 != 0).unwrap_or(false)
+    }
+
+    pub fn set(&mut self, index: usize) {
+        if self.longs.len() <= index / 64 {
+            self.longs.resize(index / 64 + 1, 0);
+        }
+        self.longs[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn clear(&mut self, index: usize) {
+        if let Some(long) = self.longs.get_mut(index / 64) {
+            *long &= !(1 << (index % 64));
+            self.trim();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.longs.is_empty()
+    }
+
+    /// Count of longs which will be written.
+    pub fn len_longs(&self) -> usize {
+        self.longs.len()
+    }
+
+    pub fn as_longs(&self) -> &[u64] {
+        &self.longs
+    }
+
+    /// Iterates over indices of set bits in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item=usize> + '_ {
+        self.longs.iter().enumerate().flat_map(|(long_index, long)| {
+            let long = *long;
+            (0..64).filter(move |bit| (long >> bit) & 1 != 0).map(move |bit| long_index * 64 + bit)
+        })
+    }
+
+    fn trim(&mut self) {
+        while self.longs.last() == Some(&0) {
+            self.longs.pop();
+        }
+    }
+}
+
+impl From<Vec<u64>> for BitSet {
+    fn from(longs: Vec<u64>) -> Self {
+        let mut bit_set = BitSet { longs };
+        bit_set.trim();
+        bit_set
+    }
+}
+
+impl From<BitSet> for Vec<u64> {
+    fn from(bit_set: BitSet) -> Self {
+        bit_set.longs
+    }
+}
+
+impl<'a> PacketReadable<'a> for BitSet {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Vec::<u64>::read(read).map(Self::from)
+    }
+}
+
+impl PacketWritable for BitSet {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.longs.write(write)
+    }
+}
+
+impl<'a> PacketReadable<'a> for GlobalPosition<'a> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(GlobalPosition {
+            dimension: bird_chat::identifier::Identifier::read(read)?,
+            position: BlockPosition::read(read)?,
+        })
+    }
+}
+
+impl PacketWritable for GlobalPosition<'_> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        self.dimension.write(write)?;
+        self.position.write(write)
+    }
+}
+
+impl<'a> PacketReadable<'a> for Uuid {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Uuid::from_slice(read.take_slice(16)?)
+            .map_err(|err| PacketReadableError::Any(err.into()))
+    }
+}
+
+impl PacketWritable for Uuid {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        write.write_bytes(self.as_bytes().as_slice())
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        Ok(16)
+    }
+}
+
+impl<'a, T: PacketReadable<'a> + Packet> PacketVariantReadable<'a, T> for PacketVariant {
+    fn read_variant<R>(read: &mut R) -> Result<T, PacketReadableError> where R: PacketRead<'a> {
+        T::read(read)
+    }
+}
+
+impl<T: PacketWritable + Packet> PacketVariantWritable<T> for PacketVariant {
+    fn write_variant<W>(object: &T, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        VarInt::write_variant(&T::id(), write)?;
+        T::write(object, write)
+    }
+}
+
+/// Writes [VarInt] id of the packet and then its body. Same as [PacketVariant].
+pub fn write_packet<T, W>(packet: &T, write: &mut W) -> Result<(), Error>
+    where T: PacketWritable + Packet, W: PacketWrite {
+    PacketVariant::write_variant(packet, write)
+}
+
+/// Reads body of the packet, which id is already read.
+pub fn read_packet_body<'a, T, R>(read: &mut R) -> Result<T, PacketReadableError>
+    where T: PacketReadable<'a> + Packet, R: PacketRead<'a> {
+    PacketVariant::read_variant(read)
+}
+
+/// Reads body of the packet like [read_packet_body], but the error tells the packet id and the failed field.
+pub fn decode_packet_body<'a, T, R>(read: &mut R) -> Result<T, PacketDecodeError>
+    where T: PacketReadable<'a> + Packet, R: PacketRead<'a> {
+    T::read_with_path(read).map_err(|error| PacketDecodeError::new(T::id(), error))
+}
+
+impl<T: PacketWritable> PacketWritable for Option<T> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        match self {
+            Some(ref obj) => {
+                true.write(write)?;
+                obj.write(write)
             }
+            None => false.write(write)
+        }
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        match self {
+            Some(ref obj) => obj.size().map(|size| size + 1),
+            None => Ok(1),
+        }
+    }
+}
 
-            fn from_length(length: usize) -> Self {
-                length as Self
+impl<'a, T: PacketReadable<'a>> PacketReadable<'a> for Option<T> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        Ok(match bool::read(read)? {
+            true => Some(T::read(read)?),
+            false => None
+        })
+    }
+}
+
+macro_rules! pointer_writable_impl {
+    ($($pointer: ty => $inner: ident),*) => {
+        $(
+            impl<$inner: PacketWritable + ?Sized> PacketWritable for $pointer {
+                fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+                    $inner::write(self, write)
+                }
+
+                fn size(&self) -> Result<usize, Error> {
+                    $inner::size(self)
+                }
+            }
+        )*
+    }
+}
+
+pointer_writable_impl!(&T => T, Box<T> => T, Arc<T> => T, Rc<T> => T);
+
+impl<T: PacketWritable + ToOwned + ?Sized> PacketWritable for Cow<'_, T> {
+    fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        T::write(self, write)
+    }
+
+    fn size(&self) -> Result<usize, Error> {
+        T::size(self)
+    }
+}
+
+macro_rules! pointer_readable_impl {
+    ($($pointer: ident),*) => {
+        $(
+            impl<'a, T: PacketReadable<'a>> PacketReadable<'a> for $pointer<T> {
+                fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+                    T::read(read).map($pointer::new)
+                }
+            }
+        )*
+    }
+}
+
+pointer_readable_impl!(Box, Arc, Rc);
+
+/// Read as [Cow::Owned], because read value is not borrowed from anywhere.
+impl<'a, T: PacketReadable<'a> + Clone> PacketReadable<'a> for Cow<'a, T> {
+    fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+        T::read(read).map(Cow::Owned)
+    }
+}
+
+macro_rules! length_impl {
+    ($num: ident) => {
+        impl PacketLength for $num {
+            fn into_length(self) -> Result<usize, PacketReadableError> {
+                usize::try_from(self).map_err(|_| PacketReadableError::BadLength)
+            }
+
+            fn from_length(length: usize) -> Result<Self, PacketWritableError> {
+                Self::try_from(length).map_err(|_| PacketWritableError::LengthOverflow { length })
             }
         }
     };
@@ -561,6 +1686,10 @@ macro_rules! number_impl {
             fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
                 write.write_bytes_fixed(self.to_be_bytes())
             }
+
+            fn size(&self) -> Result<usize, Error> {
+                Ok(std::mem::size_of::<Self>())
+            }
         }
     };
     ($($num:ident$(,)*)*) => {
@@ -572,41 +1701,96 @@ macro_rules! var_number_impl {
     ($var_num: ident, $num: ident, $unsigned_num: ident) => {
         impl<'a> PacketVariantReadable<'a, $num> for $var_num {
             fn read_variant<R>(read: &mut R) -> Result<$num, PacketReadableError> where R: PacketRead<'a> {
+                const BITS: u32 = $num::BITS;
                 let mut value: $num = 0;
-                let mut position: u8 = 0;
+                let mut position: u32 = 0;
                 loop {
                     let byte = read.take_byte()?;
+                    // Last byte can hold fewer than 7 bits, others must be zeros
+                    if position + 7 > BITS && ((byte & 0x7F) >> (BITS - position)) != 0 {
+                        break Err(PacketReadableError::BadVarNumber("Var number does not fit"))
+                    }
                     value |= ((byte & 0x7F) as $num) << position;
                     if (byte & 0x80) == 0 {
+                        if byte == 0 && position != 0 {
+                            break Err(PacketReadableError::BadVarNumber("Var number is not canonical"))
+                        }
                         break Ok(value)
                     }
                     position += 7;
-                    if position >= (std::mem::size_of::<$num>() * 8) as u8 {
-                        break Err(PacketReadableError::Any(anyhow::Error::msg("Var number is too long")))
+                    if position >= BITS {
+                        break Err(PacketReadableError::BadVarNumber("Var number is too long"))
                     }
                 }
             }
         }
 
+        impl $var_num {
+            /// Max count of bytes in the encoded value.
+            pub const MAX_SIZE: usize = ($num::BITS as usize).div_ceil(7);
+
+            /// Returns count of bytes in the encoded value without encoding it.
+            pub const fn size_of(value: $num) -> usize {
+                let bits = $num::BITS - (value as $unsigned_num).leading_zeros();
+                let bits = match bits {
+                    0 => 1,
+                    bits => bits,
+                };
+                (bits as usize).div_ceil(7)
+            }
+        }
+
         impl PacketVariantWritable<$num> for $var_num {
+            /// Value is encoded on the stack and written by one call.
             fn write_variant<W>(object: & $num, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+                let mut bytes = [0u8; Self::MAX_SIZE];
                 let mut value: $unsigned_num = *object as $unsigned_num;
-                loop {
-                    if (value & !0x7F) == 0 {
-                        write.write_byte((value as u8))?;
-                        break;
-                    }
-                    write.write_byte(((value & 0x7F) | 0x80) as u8)?;
+                let mut size = 0;
+                while (value & !0x7F) != 0 {
+                    bytes[size] = ((value & 0x7F) | 0x80) as u8;
                     value >>= 7;
+                    size += 1;
                 }
-                Ok(())
+                bytes[size] = value as u8;
+                write.write_bytes(&bytes[..size + 1])
+            }
+
+            fn size_variant(object: & $num) -> Result<usize, Error> {
+                Ok(Self::size_of(*object))
             }
         }
 
     }
 }
 
+macro_rules! tuple_impl {
+    ($($name: ident)*) => {
+        impl<'a, $($name: PacketReadable<'a>),*> PacketReadable<'a> for ($($name,)*) {
+            fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+                Ok(($($name::read(read)?,)*))
+            }
+        }
+
+        impl<$($name: PacketWritable),*> PacketWritable for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn write<W>(&self, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+                let ($($name,)*) = self;
+                $($name.write(write)?;)*
+                Ok(())
+            }
+        }
+    }
+}
+
 length_impl!(u8 i8 u16 i16 u32 i32 u64 i64);
-number_impl!(u16 i16 u32 i32 u64 i64 u128 i128);
+number_impl!(u16 i16 u32 i32 u64 i64 u128 i128 f32 f64);
 var_number_impl!(VarInt, i32, u32);
-var_number_impl!(VarLong, i64, u64);
\ No newline at end of file
+var_number_impl!(VarLong, i64, u64);
+tuple_impl!(A);
+tuple_impl!(A B);
+tuple_impl!(A B C);
+tuple_impl!(A B C D);
+tuple_impl!(A B C D E);
+tuple_impl!(A B C D E F);
+tuple_impl!(A B C D E F G);
+tuple_impl!(A B C D E F G H);
\ No newline at end of file
@@ -0,0 +1,189 @@
+use crate::packet::{PacketRead, PacketReadableError, PacketWrite};
+
+macro_rules! protocol_versions {
+    ($($variant: ident = $number: literal => $name: literal),* $(,)?) => {
+        /// Release of the game. Versions which share the protocol number are one variant.
+        #[repr(i32)]
+        ///
+        /// With `serde` feature version is serialized as its number.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde", serde(try_from = "i32", into = "i32"))]
+        pub enum ProtocolVersion {
+            $($variant = $number),*
+        }
+
+        impl ProtocolVersion {
+            /// All known versions from the oldest to the latest.
+            pub const ALL: &'static [ProtocolVersion] = &[$(ProtocolVersion::$variant),*];
+
+            pub const fn number(self) -> i32 {
+                self as i32
+            }
+
+            pub const fn name(self) -> &'static str {
+                match self {
+                    $(ProtocolVersion::$variant => $name),*
+                }
+            }
+
+            pub const fn from_number(number: i32) -> Option<Self> {
+                match number {
+                    $($number => Some(ProtocolVersion::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+protocol_versions! {
+    V1_8 = 47 => "1.8-1.8.9",
+    V1_9 = 107 => "1.9",
+    V1_9_1 = 108 => "1.9.1",
+    V1_9_2 = 109 => "1.9.2",
+    V1_9_4 = 110 => "1.9.3-1.9.4",
+    V1_10 = 210 => "1.10-1.10.2",
+    V1_11 = 315 => "1.11",
+    V1_11_2 = 316 => "1.11.1-1.11.2",
+    V1_12 = 335 => "1.12",
+    V1_12_1 = 338 => "1.12.1",
+    V1_12_2 = 340 => "1.12.2",
+    V1_13 = 393 => "1.13",
+    V1_13_1 = 401 => "1.13.1",
+    V1_13_2 = 404 => "1.13.2",
+    V1_14 = 477 => "1.14",
+    V1_14_1 = 480 => "1.14.1",
+    V1_14_2 = 485 => "1.14.2",
+    V1_14_3 = 490 => "1.14.3",
+    V1_14_4 = 498 => "1.14.4",
+    V1_15 = 573 => "1.15",
+    V1_15_1 = 575 => "1.15.1",
+    V1_15_2 = 578 => "1.15.2",
+    V1_16 = 735 => "1.16",
+    V1_16_1 = 736 => "1.16.1",
+    V1_16_2 = 751 => "1.16.2",
+    V1_16_3 = 753 => "1.16.3",
+    V1_16_5 = 754 => "1.16.4-1.16.5",
+    V1_17 = 755 => "1.17",
+    V1_17_1 = 756 => "1.17.1",
+    V1_18_1 = 757 => "1.18-1.18.1",
+    V1_18_2 = 758 => "1.18.2",
+    V1_19 = 759 => "1.19",
+    V1_19_2 = 760 => "1.19.1-1.19.2",
+    V1_19_3 = 761 => "1.19.3",
+    V1_19_4 = 762 => "1.19.4",
+}
+
+/// Versions which packets of the enabled features are written for.
+pub const SUPPORTED_VERSIONS: &[ProtocolVersion] = &[
+    #[cfg(feature = "packet_default")]
+    ProtocolVersion::V1_19,
+];
+
+impl ProtocolVersion {
+    pub fn is_supported(self) -> bool {
+        SUPPORTED_VERSIONS.contains(&self)
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl TryFrom<i32> for ProtocolVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(number: i32) -> Result<Self, Self::Error> {
+        Self::from_number(number).ok_or_else(|| anyhow::Error::msg(format!("Unknown protocol version {}", number)))
+    }
+}
+
+impl From<ProtocolVersion> for i32 {
+    fn from(version: ProtocolVersion) -> Self {
+        version.number()
+    }
+}
+
+/// Inclusive range of protocol numbers, for example versions where the packet exists.
+///
+/// Numbers are compared, so unknown snapshots inside the range are contained as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionRange {
+    pub min: i32,
+    pub max: i32,
+}
+
+impl VersionRange {
+    pub const fn new(min: ProtocolVersion, max: ProtocolVersion) -> Self {
+        Self { min: min.number(), max: max.number() }
+    }
+
+    pub const fn from_numbers(min: i32, max: i32) -> Self {
+        Self { min, max }
+    }
+
+    pub const fn contains(&self, number: i32) -> bool {
+        self.min <= number && number <= self.max
+    }
+
+    pub const fn contains_version(&self, version: ProtocolVersion) -> bool {
+        self.contains(version.number())
+    }
+}
+
+impl From<std::ops::RangeInclusive<ProtocolVersion>> for VersionRange {
+    fn from(range: std::ops::RangeInclusive<ProtocolVersion>) -> Self {
+        Self::new(*range.start(), *range.end())
+    }
+}
+
+/// Version which [PacketReadable](crate::packet::PacketReadable) and [PacketWritable](crate::packet::PacketWritable)
+/// of versioned types use.
+pub const DEFAULT_VERSION: ProtocolVersion = ProtocolVersion::V1_19;
+
+/// Reading of the type whose fields depend on the version.
+///
+/// The derive macro implements it if any field has `#[protocol(since = .., until = ..)]` attribute,
+/// which bounds are inclusive. Fields which don't exist in the version are [Default], for example [None].
+/// Inner fields are read without the version.
+pub trait VersionedReadable<'a>: Sized {
+    fn read_versioned<R>(version: ProtocolVersion, read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a>;
+}
+
+/// Writing of the type whose fields depend on the version. Fields which don't exist in the version are skipped.
+pub trait VersionedWritable {
+    fn write_versioned<W>(&self, version: ProtocolVersion, write: &mut W) -> Result<(), anyhow::Error> where W: PacketWrite;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_tests() {
+        for version in ProtocolVersion::ALL {
+            assert_eq!(ProtocolVersion::from_number(version.number()), Some(*version));
+            assert_eq!(ProtocolVersion::try_from(version.number()).unwrap(), *version);
+        }
+        assert!(ProtocolVersion::ALL.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(ProtocolVersion::from_number(756).map(ProtocolVersion::name), Some("1.17.1"));
+        assert_eq!(ProtocolVersion::V1_16_5.to_string(), "1.16.4-1.16.5");
+        assert_eq!(ProtocolVersion::from_number(100), None);
+        assert!(ProtocolVersion::try_from(-1).is_err());
+        assert!(ProtocolVersion::V1_8 < ProtocolVersion::V1_19);
+        let range = VersionRange::from(ProtocolVersion::V1_17..=ProtocolVersion::V1_18_2);
+        assert!(range.contains(758) && range.contains_version(ProtocolVersion::V1_17));
+        assert!(!range.contains(754) && !range.contains_version(ProtocolVersion::V1_19));
+        assert_eq!(range, VersionRange::from_numbers(755, 758));
+    }
+
+    #[test]
+    fn supported_versions_tests() {
+        assert_eq!(ProtocolVersion::V1_19.is_supported(), cfg!(feature = "packet_default"));
+        assert!(!ProtocolVersion::V1_18_2.is_supported());
+    }
+}
@@ -0,0 +1,1512 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use anyhow::Error;
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use crate::packet::{DecodeMode, Packet, PacketBound, PacketScope, PacketState, PacketRead, PacketReadable, PacketReadableError, PacketVariantReadable, PacketVariantWritable, PacketWritable, PacketWrite, SlicePacketRead};
+use crate::packet_bytes::{PacketStreamReader, PacketStreamWriter};
+use crate::packet_types::{preallocate, ProtocolJson, VarInt};
+use bird_chat::identifier::Identifier;
+use crate::packet_play::{PlayKeepAlive, PlayServerPluginMessage};
+use crate::packet_default::{HandshakeNextState, HandshakePacket, StatusPingRequest, StatusPingResponse, StatusRequest, StatusResponse};
+
+/// First byte of the legacy (before 1.7) server list ping.
+pub const LEGACY_PING_BYTE: u8 = 0xFE;
+/// Id of the kick packet, which is the response to the legacy ping.
+pub const LEGACY_KICK_BYTE: u8 = 0xFF;
+const LEGACY_PING_HOST: &str = "MC|PingHost";
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandshakeInfo {
+    pub protocol_version: i32,
+    pub server_address: String,
+    pub server_port: u16,
+    pub next_state: HandshakeNextState,
+}
+
+impl From<HandshakePacket<'_>> for HandshakeInfo {
+    fn from(handshake: HandshakePacket<'_>) -> Self {
+        Self {
+            protocol_version: handshake.protocol_version,
+            server_address: handshake.server_address.into(),
+            server_port: handshake.server_port,
+            next_state: handshake.next_state,
+        }
+    }
+}
+
+/// Legacy server list ping, which is sent by clients before 1.7.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LegacyPingRequest {
+    /// Beta 1.8 - 1.3 clients send only the ping byte.
+    Beta,
+    /// 1.4 - 1.5 clients send the ping byte and 0x01.
+    Payload,
+    /// 1.6 clients also send `MC|PingHost` plugin message with the address they connect to.
+    PingHost {
+        protocol_version: u8,
+        server_address: String,
+        server_port: i32,
+    },
+}
+
+/// Response to the legacy ping, which is sent as the kick packet with UTF-16BE string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyPingResponse {
+    pub protocol_version: i32,
+    pub server_version: String,
+    pub motd: String,
+    pub online: i32,
+    pub max: i32,
+}
+
+impl LegacyPingResponse {
+    pub fn new(protocol_version: i32, server_version: impl Into<String>, motd: impl Into<String>, online: i32, max: i32) -> Self {
+        Self { protocol_version, server_version: server_version.into(), motd: motd.into(), online, max }
+    }
+
+    /// Returns kick string for the request.
+    /// Beta clients understand only motd, online and max players separated by `§`.
+    /// Other clients get `§1` and all fields separated by null characters.
+    pub fn kick_string(&self, request: &LegacyPingRequest) -> String {
+        match request {
+            LegacyPingRequest::Beta => format!("{}§{}§{}", self.motd, self.online, self.max),
+            _ => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                self.protocol_version, self.server_version, self.motd, self.online, self.max
+            ),
+        }
+    }
+
+    pub fn write<W>(&self, request: &LegacyPingRequest, write: &mut W) -> Result<(), Error> where W: PacketWrite {
+        let units: Vec<u16> = self.kick_string(request).encode_utf16().collect();
+        let length = u16::try_from(units.len()).map_err(|_| Error::msg("Too long legacy ping response"))?;
+        write.reserve(3 + units.len() * 2);
+        LEGACY_KICK_BYTE.write(write)?;
+        length.write(write)?;
+        for unit in units {
+            unit.write(write)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_utf16_string<'a, R>(read: &mut R) -> Result<String, Error> where R: PacketRead<'a> {
+    let length = u16::read(read)?;
+    let mut units = preallocate::<u16>(length as usize);
+    for _ in 0..length {
+        units.push(u16::read(read)?);
+    }
+    Ok(String::from_utf16(&units)?)
+}
+
+/// Reads legacy ping if the first received byte is [LEGACY_PING_BYTE], otherwise nothing is consumed.
+///
+/// Like vanilla server, version of the ping is recognized by bytes which are received together with the first one.
+pub async fn read_legacy_ping<S>(reader: &mut PacketStreamReader<S>) -> Result<Option<LegacyPingRequest>, Error>
+    where S: AsyncRead + Unpin {
+    if reader.peek(1).await.map_err(stream_error)?[0] != LEGACY_PING_BYTE {
+        return Ok(None);
+    }
+    match reader.buffered() {
+        [_] => return Ok(Some(LegacyPingRequest::Beta)),
+        [_, 0x01] => return Ok(Some(LegacyPingRequest::Payload)),
+        [_, 0x01, 0xFA, ..] => {}
+        _ => return Err(Error::msg("Bad legacy ping")),
+    }
+    // Ping byte, 0x01, plugin message id, channel of 11 UTF-16 units and data length
+    const HEADER: usize = 3 + 2 + LEGACY_PING_HOST.len() * 2 + 2;
+    let header = reader.peek(HEADER).await.map_err(stream_error)?;
+    let data_length = u16::from_be_bytes([header[HEADER - 2], header[HEADER - 1]]) as usize;
+    let bytes = reader.peek(HEADER + data_length).await.map_err(stream_error)?;
+    let mut read = SlicePacketRead::new(&bytes[3..]);
+    if read_utf16_string(&mut read)? != LEGACY_PING_HOST {
+        return Err(Error::msg("Bad legacy ping channel"));
+    }
+    read.take_slice(2)?;
+    Ok(Some(LegacyPingRequest::PingHost {
+        protocol_version: u8::read(&mut read)?,
+        server_address: read_utf16_string(&mut read)?,
+        server_port: i32::read(&mut read)?,
+    }))
+}
+
+// Outcome is returned once per connection, so the reader is not boxed
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum HandshakeOutcome<S> {
+    /// Status was sent. Stream is dropped, so the connection is closed.
+    Status,
+    /// Legacy ping was received and answered if the response is set. Stream is dropped, so the connection is closed.
+    LegacyPing(LegacyPingRequest),
+    /// Client continues with the login state. Reader keeps bytes which are received after the handshake.
+    Login(HandshakeInfo, PacketStreamReader<S>),
+}
+
+#[cfg(feature = "tracing")]
+fn outcome_name<S>(outcome: &HandshakeOutcome<S>) -> &'static str {
+    match outcome {
+        HandshakeOutcome::Status => "status",
+        HandshakeOutcome::LegacyPing(_) => "legacy ping",
+        HandshakeOutcome::Login(..) => "login",
+    }
+}
+
+enum Exchange {
+    Status,
+    LegacyPing(LegacyPingRequest),
+    Login(HandshakeInfo),
+}
+
+/// Io errors of the stream are returned as they are, so they can be downcasted.
+fn stream_error(error: PacketReadableError) -> Error {
+    match error {
+        PacketReadableError::Any(error) => error,
+        error => error.into(),
+    }
+}
+
+/// Writes packet with its id as the frame and flushes the stream.
+pub async fn write_packet_async<S, T>(stream: &mut S, packet: &T) -> Result<(), Error>
+    where S: AsyncWrite + Unpin, T: PacketWritable + Packet {
+    let mut writer = PacketStreamWriter::new(stream);
+    writer.write_packet(packet).await?;
+    writer.flush().await
+}
+
+/// Reads packet from the frame body and checks that its id is the expected one.
+///
+/// Left bytes are checked by [DecodeMode::for_bound] of the packet.
+pub fn read_packet_body<'a, T>(body: &'a [u8]) -> Result<T, Error> where T: PacketReadable<'a> + Packet {
+    read_packet_body_with_mode(body, DecodeMode::for_bound(T::bound()))
+}
+
+/// Same as [read_packet_body], but left bytes are checked by the given mode.
+pub fn read_packet_body_with_mode<'a, T>(body: &'a [u8], mode: DecodeMode) -> Result<T, Error>
+    where T: PacketReadable<'a> + Packet {
+    let mut read = SlicePacketRead::new(body);
+    match VarInt::read_variant(&mut read)? == T::id() {
+        true => {
+            let packet = T::read(&mut read)?;
+            mode.finish(&read)?;
+            Ok(packet)
+        }
+        false => Err(Error::msg("Unexpected packet id")),
+    }
+}
+
+type PluginHandler = Box<dyn for<'a> FnMut(&mut SlicePacketRead<'a>) -> Result<(), Error> + Send>;
+
+/// Dispatcher of plugin messages by their channels.
+///
+/// Handler reads data of the message with the usual [PacketReadable] types, for example [Brand](crate::packet_play::Brand)
+/// or [ChannelList](crate::packet_play::ChannelList).
+#[derive(Default)]
+pub struct PluginChannelRegistry {
+    handlers: HashMap<String, PluginHandler>,
+    default: Option<PluginHandler>,
+}
+
+impl PluginChannelRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets handler of the channel, like `minecraft:brand`. Previous handler of the channel is replaced.
+    pub fn register<F>(&mut self, channel: impl Into<String>, handler: F)
+        where F: for<'a> FnMut(&mut SlicePacketRead<'a>) -> Result<(), Error> + Send + 'static {
+        self.handlers.insert(channel.into(), Box::new(handler));
+    }
+
+    pub fn unregister(&mut self, channel: &str) -> bool {
+        self.handlers.remove(channel).is_some()
+    }
+
+    /// Sets handler of the channels which are not registered.
+    pub fn set_default<F>(&mut self, handler: F)
+        where F: for<'a> FnMut(&mut SlicePacketRead<'a>) -> Result<(), Error> + Send + 'static {
+        self.default = Some(Box::new(handler));
+    }
+
+    pub fn is_registered(&self, channel: &str) -> bool {
+        self.handlers.contains_key(channel)
+    }
+
+    /// Channels which are registered, for example to send them in [REGISTER_CHANNEL](crate::packet_play::REGISTER_CHANNEL).
+    pub fn channels(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// Passes data to the handler of the channel or to the default one.
+    /// Returns false if there is no handler.
+    pub fn handle(&mut self, channel: &Identifier, data: &[u8]) -> Result<bool, Error> {
+        let handler = match self.handlers.get_mut(channel.to_string().as_str()) {
+            Some(handler) => handler,
+            None => match self.default {
+                Some(ref mut handler) => handler,
+                None => return Ok(false),
+            }
+        };
+        handler(&mut SlicePacketRead::new(data))?;
+        Ok(true)
+    }
+
+    pub fn handle_message(&mut self, message: &PlayServerPluginMessage) -> Result<bool, Error> {
+        self.handle(&message.channel, message.data)
+    }
+}
+
+impl std::fmt::Debug for PluginChannelRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginChannelRegistry")
+            .field("channels", &self.handlers.keys().collect::<Vec<_>>())
+            .field("default", &self.default.is_some())
+            .finish()
+    }
+}
+
+type LegacyResponse = Box<dyn Fn(&LegacyPingRequest) -> LegacyPingResponse + Send + Sync>;
+
+/// Server list ping handler, which reads handshake and answers to status and ping requests.
+///
+/// Status is built by the callback and is written as json.
+/// Legacy ping is answered only if [StatusServer::with_legacy_response] is set, otherwise the connection is closed.
+pub struct StatusServer<F> {
+    status: F,
+    legacy: Option<LegacyResponse>,
+    timeout: Duration,
+}
+
+impl<F, Fut, T> StatusServer<F>
+    where F: Fn(HandshakeInfo) -> Fut, Fut: Future<Output=T>, T: serde::Serialize {
+    pub fn new(status: F) -> Self {
+        Self { status, legacy: None, timeout: DEFAULT_TIMEOUT }
+    }
+
+    pub fn with_legacy_response<L>(mut self, legacy: L) -> Self
+        where L: Fn(&LegacyPingRequest) -> LegacyPingResponse + Send + Sync + 'static {
+        self.legacy = Some(Box::new(legacy));
+        self
+    }
+
+    /// Sets time which client has to finish handshake and status exchange.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn serve<S>(&self, stream: S) -> Result<HandshakeOutcome<S>, Error>
+        where S: AsyncRead + AsyncWrite + Unpin {
+        let mut reader = PacketStreamReader::new(stream);
+        let result = match tokio::time::timeout(self.timeout, self.exchange(&mut reader)).await {
+            Ok(Ok(Exchange::Status)) => Ok(HandshakeOutcome::Status),
+            Ok(Ok(Exchange::LegacyPing(request))) => Ok(HandshakeOutcome::LegacyPing(request)),
+            Ok(Ok(Exchange::Login(handshake))) => Ok(HandshakeOutcome::Login(handshake, reader)),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(Error::msg("Handshake timed out")),
+        };
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(outcome) => tracing::debug!(outcome = outcome_name(outcome), "handshake is finished"),
+            Err(error) => tracing::warn!(%error, "handshake is failed"),
+        }
+        result
+    }
+
+    async fn exchange<S>(&self, reader: &mut PacketStreamReader<S>) -> Result<Exchange, Error>
+        where S: AsyncRead + AsyncWrite + Unpin {
+        if let Some(request) = read_legacy_ping(reader).await? {
+            if let Some(legacy) = &self.legacy {
+                let mut bytes = Vec::new();
+                legacy(&request).write(&request, &mut bytes)?;
+                reader.get_mut().write_all(&bytes).await?;
+            }
+            reader.get_mut().shutdown().await?;
+            return Ok(Exchange::LegacyPing(request));
+        }
+        let frame = reader.next_packet().await.map_err(stream_error)?;
+        let handshake: HandshakeInfo = read_packet_body::<HandshakePacket>(&frame)?.into();
+        if handshake.next_state == HandshakeNextState::Login {
+            return Ok(Exchange::Login(handshake));
+        }
+        let Some(frame) = reader.try_next_packet().await.map_err(stream_error)? else {
+            return Ok(Exchange::Status);
+        };
+        read_packet_body::<StatusRequest>(&frame)?;
+        let status = (self.status)(handshake).await;
+        let mut body = Vec::new();
+        VarInt::write_variant(&StatusResponse::ID, &mut body)?;
+        <ProtocolJson>::write_variant(&status, &mut body)?;
+        let mut writer = PacketStreamWriter::new(reader.get_mut());
+        writer.write_frame(&body).await?;
+        writer.flush().await?;
+        // Client may close the connection without ping
+        if let Some(frame) = reader.try_next_packet().await.map_err(stream_error)? {
+            let ping: StatusPingRequest = read_packet_body(&frame)?;
+            write_packet_async(reader.get_mut(), &StatusPingResponse { payload: ping.payload }).await?;
+        }
+        Ok(Exchange::Status)
+    }
+}
+
+/// Source of the time for the rate limiters, so tests can drive it with [ManualClock].
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock which goes only by [ManualClock::advance]. Clones share the time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now(), elapsed: Arc::new(Mutex::new(Duration::ZERO)) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+}
+
+/// Bucket which holds up to one second of tokens. Tokens can go below zero, which is the debt to wait for.
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, now: Instant) -> Self {
+        Self { rate: rate as f64, tokens: rate as f64, last: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last = now;
+    }
+
+    /// Takes tokens only if there are enough of them.
+    fn try_take(&mut self, amount: f64, now: Instant) -> bool {
+        self.refill(now);
+        match self.tokens >= amount {
+            true => {
+                self.tokens -= amount;
+                true
+            }
+            false => false,
+        }
+    }
+
+    /// Takes tokens and returns time to wait until the debt is paid.
+    fn take(&mut self, amount: f64, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= amount;
+        match self.tokens >= 0.0 {
+            true => Duration::ZERO,
+            // Zero rate never pays the debt
+            false => Duration::try_from_secs_f64(-self.tokens / self.rate).unwrap_or(Duration::MAX),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDecision {
+    Allow,
+    /// The packet is accepted, but the next one should not be read until the duration passes.
+    Throttle(Duration),
+    Disconnect,
+}
+
+/// Limits of the traffic of one connection. Up to one second of traffic can be received at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits {
+    pub packets_per_second: u32,
+    pub bytes_per_second: u32,
+    /// The connection is disconnected instead of being throttled for longer.
+    pub max_throttle: Duration,
+}
+
+impl RateLimits {
+    pub fn new(packets_per_second: u32, bytes_per_second: u32) -> Self {
+        Self { packets_per_second, bytes_per_second, max_throttle: Duration::from_secs(1) }
+    }
+
+    pub fn with_max_throttle(mut self, max_throttle: Duration) -> Self {
+        self.max_throttle = max_throttle;
+        self
+    }
+}
+
+/// Token bucket limiter of packets and bytes of one connection, which is checked after each received frame.
+#[derive(Debug, Clone)]
+pub struct RateLimiter<C = SystemClock> {
+    limits: RateLimits,
+    packets: TokenBucket,
+    bytes: TokenBucket,
+    clock: C,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self::with_clock(limits, SystemClock)
+    }
+}
+
+impl<C: Clock> RateLimiter<C> {
+    pub fn with_clock(limits: RateLimits, clock: C) -> Self {
+        let now = clock.now();
+        Self {
+            packets: TokenBucket::new(limits.packets_per_second, now),
+            bytes: TokenBucket::new(limits.bytes_per_second, now),
+            limits,
+            clock,
+        }
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.limits
+    }
+
+    /// Accounts the received packet with the given length of the frame body.
+    pub fn check(&mut self, packet_len: usize) -> RateDecision {
+        let now = self.clock.now();
+        let wait = self.packets.take(1.0, now).max(self.bytes.take(packet_len as f64, now));
+        match wait {
+            Duration::ZERO => RateDecision::Allow,
+            wait if wait > self.limits.max_throttle => RateDecision::Disconnect,
+            wait => RateDecision::Throttle(wait),
+        }
+    }
+}
+
+/// Limiter of new connections per ip address, which remembers only the most recent addresses.
+#[derive(Debug)]
+pub struct ConnectionThrottle<C = SystemClock> {
+    connections_per_second: u32,
+    capacity: usize,
+    addresses: HashMap<IpAddr, (TokenBucket, u64)>,
+    /// Addresses by the number of their last use, the first is the least recent.
+    recent: BTreeMap<u64, IpAddr>,
+    uses: u64,
+    clock: C,
+}
+
+impl ConnectionThrottle {
+    pub fn new(connections_per_second: u32, capacity: usize) -> Self {
+        Self::with_clock(connections_per_second, capacity, SystemClock)
+    }
+}
+
+impl<C: Clock> ConnectionThrottle<C> {
+    pub fn with_clock(connections_per_second: u32, capacity: usize, clock: C) -> Self {
+        Self {
+            connections_per_second,
+            capacity: capacity.max(1),
+            addresses: HashMap::new(),
+            recent: BTreeMap::new(),
+            uses: 0,
+            clock,
+        }
+    }
+
+    /// Accounts the new connection from the address. Refused connections are not counted. Port of the address is ignored.
+    pub fn allow(&mut self, address: &SocketAddr) -> bool {
+        let now = self.clock.now();
+        let ip = address.ip();
+        self.uses += 1;
+        let mut bucket = match self.addresses.remove(&ip) {
+            Some((bucket, last_use)) => {
+                self.recent.remove(&last_use);
+                bucket
+            }
+            None => {
+                if self.addresses.len() >= self.capacity {
+                    if let Some((_, oldest)) = self.recent.pop_first() {
+                        self.addresses.remove(&oldest);
+                    }
+                }
+                TokenBucket::new(self.connections_per_second, now)
+            }
+        };
+        let allowed = bucket.try_take(1.0, now);
+        self.addresses.insert(ip, (bucket, self.uses));
+        self.recent.insert(self.uses, ip);
+        allowed
+    }
+
+    /// Count of the remembered addresses.
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+}
+
+/// Interval of [PlayKeepAlive] packets, vanilla sends them every 15 seconds.
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// Time which client has to answer [PlayKeepAlive].
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAliveVerdict {
+    /// The response matches the sent keep alive, latency is the time of the round trip.
+    Accepted { latency: Duration },
+    /// The id was not sent or is answered already. Vanilla disconnects the client in this case.
+    Unexpected,
+}
+
+/// The oldest keep alive, which is not answered in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAliveTimeout {
+    pub id: i64,
+    pub elapsed: Duration,
+}
+
+/// Timer of keep alive packets of one play connection.
+///
+/// Read loop sends [KeepAliveDriver::poll_send] packets, passes responses to [KeepAliveDriver::on_keep_alive_response]
+/// and disconnects the client when [KeepAliveDriver::poll_timeout] returns the timeout.
+/// Latency is the rolling average of the round trips like vanilla, which is shown as ping in the player list.
+#[derive(Debug)]
+pub struct KeepAliveDriver<C = SystemClock> {
+    interval: Duration,
+    timeout: Duration,
+    clock: C,
+    next_send: Instant,
+    /// Sent ids with the time of sending, the first is the oldest.
+    pending: VecDeque<(i64, Instant)>,
+    latency: Option<Duration>,
+    ids: std::collections::hash_map::RandomState,
+    sent: u64,
+}
+
+impl KeepAliveDriver {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for KeepAliveDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> KeepAliveDriver<C> {
+    /// First keep alive is sent right away.
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            interval: KEEP_ALIVE_INTERVAL,
+            timeout: KEEP_ALIVE_TIMEOUT,
+            next_send: clock.now(),
+            clock,
+            pending: VecDeque::new(),
+            latency: None,
+            ids: Default::default(),
+            sent: 0,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Time when the next keep alive should be sent, for example to sleep until it.
+    pub fn next_send(&self) -> Instant {
+        self.next_send
+    }
+
+    /// Returns the keep alive to send if it is time for it and remembers its id.
+    pub fn poll_send(&mut self) -> Option<PlayKeepAlive> {
+        let now = self.clock.now();
+        if now < self.next_send {
+            return None;
+        }
+        self.next_send = now + self.interval;
+        self.sent += 1;
+        // Ids are random, so the client can't answer them in advance
+        let id = std::hash::BuildHasher::hash_one(&self.ids, self.sent) as i64;
+        self.pending.push_back((id, now));
+        Some(PlayKeepAlive { id })
+    }
+
+    /// Sends the keep alive to the connection if it is time for it.
+    pub async fn send_due<S>(&mut self, connection: &mut Connection<S>) -> Result<(), Error>
+        where S: AsyncRead + AsyncWrite {
+        match self.poll_send() {
+            Some(keep_alive) => connection.write_packet(&keep_alive).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the response. Keep alives which are sent before the answered one are not waited for anymore.
+    pub fn on_keep_alive_response(&mut self, id: i64) -> KeepAliveVerdict {
+        let Some(position) = self.pending.iter().position(|(pending, _)| *pending == id) else {
+            return KeepAliveVerdict::Unexpected;
+        };
+        let (_, sent) = self.pending.drain(..=position).next_back().unwrap();
+        let latency = self.clock.now().saturating_duration_since(sent);
+        // Same rolling average as vanilla, where the new round trip has weight of 1/4
+        self.latency = Some(match self.latency {
+            Some(average) => (average * 3 + latency) / 4,
+            None => latency,
+        });
+        KeepAliveVerdict::Accepted { latency }
+    }
+
+    /// Returns the oldest keep alive which is not answered in time.
+    pub fn poll_timeout(&self) -> Option<KeepAliveTimeout> {
+        let &(id, sent) = self.pending.front()?;
+        let elapsed = self.clock.now().saturating_duration_since(sent);
+        match elapsed >= self.timeout {
+            true => Some(KeepAliveTimeout { id, elapsed }),
+            false => None,
+        }
+    }
+
+    /// Rolling average of the round trips, it is zero until the first response.
+    pub fn latency(&self) -> Duration {
+        self.latency.unwrap_or_default()
+    }
+
+    /// Latency in milliseconds like the ping of [PlayerInfoLatency](crate::packet_play::PlayerInfoLatency).
+    pub fn latency_millis(&self) -> i32 {
+        self.latency().as_millis().min(i32::MAX as u128) as i32
+    }
+}
+
+/// Allocator of protocol entity ids, which are shared by all connections. Clones share the ids.
+///
+/// Ids start from 1, because 0 is kept for nothing. Freed ids are reused in the order of freeing,
+/// so an id is given again as late as possible, after [crate::packet_play::PlayRemoveEntities] of it
+/// has reached the clients.
+#[derive(Debug, Clone, Default)]
+pub struct EntityIdAllocator {
+    ids: Arc<Mutex<EntityIds>>,
+}
+
+#[derive(Debug, Default)]
+struct EntityIds {
+    /// Largest id which is given ever.
+    last: i32,
+    free: VecDeque<i32>,
+    free_set: HashSet<i32>,
+}
+
+impl EntityIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives the unused id. Panics if all positive ids are used.
+    pub fn allocate(&self) -> i32 {
+        let mut ids = self.ids.lock().unwrap();
+        match ids.free.pop_front() {
+            Some(id) => {
+                ids.free_set.remove(&id);
+                id
+            }
+            None => {
+                ids.last = ids.last.checked_add(1).expect("Entity ids are exhausted");
+                ids.last
+            }
+        }
+    }
+
+    /// Returns the id to reuse. Returns false if the id is not given or is already freed.
+    pub fn free(&self, id: i32) -> bool {
+        let mut ids = self.ids.lock().unwrap();
+        if id < 1 || id > ids.last || !ids.free_set.insert(id) {
+            return false;
+        }
+        ids.free.push_back(id);
+        true
+    }
+
+    /// Count of the given ids, which are not freed.
+    pub fn allocated(&self) -> usize {
+        let ids = self.ids.lock().unwrap();
+        ids.last as usize - ids.free.len()
+    }
+}
+
+/// Connection of either side, which keeps the state and applies framing, compression and encryption.
+///
+/// Packets of other state or bound are refused, so the state has to be changed by [Connection::set_state]
+/// right after the handshake or the login success is sent or received.
+pub struct Connection<S> {
+    reader: PacketStreamReader<ReadHalf<S>>,
+    writer: PacketStreamWriter<WriteHalf<S>>,
+    frame: BytesMut,
+    /// Bound of the packets which are read.
+    bound: PacketBound,
+    state: PacketState,
+    protocol_version: Option<i32>,
+    compression_threshold: Option<usize>,
+    encrypted: bool,
+    rate_limiter: Option<RateLimiter>,
+    decode_mode: DecodeMode,
+}
+
+impl<S: AsyncRead + AsyncWrite> Connection<S> {
+    /// Connection of the server, which reads server bound packets.
+    pub fn server(stream: S) -> Self {
+        Self::new(stream, PacketBound::Server)
+    }
+
+    /// Connection of the client, which reads client bound packets.
+    pub fn client(stream: S) -> Self {
+        Self::new(stream, PacketBound::Client)
+    }
+
+    fn new(stream: S, bound: PacketBound) -> Self {
+        let (read, write) = tokio::io::split(stream);
+        Self {
+            reader: PacketStreamReader::new(read),
+            writer: PacketStreamWriter::new(write),
+            frame: BytesMut::new(),
+            bound,
+            state: PacketState::Handshake,
+            protocol_version: None,
+            compression_threshold: None,
+            encrypted: false,
+            rate_limiter: None,
+            decode_mode: DecodeMode::for_bound(bound),
+        }
+    }
+
+    pub fn state(&self) -> PacketState {
+        self.state
+    }
+
+    /// Changes the state. Only transitions of the protocol are allowed: handshake to status or login and login to play.
+    pub fn set_state(&mut self, state: PacketState) -> Result<(), Error> {
+        match (self.state, state) {
+            (PacketState::Handshake, PacketState::Status | PacketState::Login) |
+            (PacketState::Login, PacketState::Play) => {
+                self.state = state;
+                Ok(())
+            }
+            (from, to) => Err(Error::msg(format!("Bad state transition from {:?} to {:?}", from, to))),
+        }
+    }
+
+    /// Protocol version which is sent in the handshake.
+    pub fn protocol_version(&self) -> Option<i32> {
+        self.protocol_version
+    }
+
+    pub fn set_protocol_version(&mut self, protocol_version: i32) {
+        self.protocol_version = Some(protocol_version);
+    }
+
+    pub fn compression_threshold(&self) -> Option<usize> {
+        self.compression_threshold
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// Sets compression of both directions. It is applied to the frames after the set compression packet.
+    #[cfg(feature = "compression")]
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        let compression = threshold.map(crate::packet_compression::PacketCompression::new);
+        self.reader.set_compression(compression);
+        self.writer.set_compression(compression);
+        self.compression_threshold = threshold;
+    }
+
+    /// Enables encryption of both directions. It is applied to the bytes after the encryption response.
+    #[cfg(feature = "encryption")]
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), Error> {
+        self.reader.enable_encryption(shared_secret)?;
+        self.writer.enable_encryption(shared_secret)?;
+        self.encrypted = true;
+        Ok(())
+    }
+
+    /// Sets limiter which is checked after each received frame. Throttled reads wait, exceeded limits close the connection.
+    pub fn set_rate_limiter(&mut self, rate_limiter: Option<RateLimiter>) {
+        self.rate_limiter = rate_limiter;
+    }
+
+    pub fn decode_mode(&self) -> DecodeMode {
+        self.decode_mode
+    }
+
+    /// Sets how bytes left after the read packets are treated.
+    /// By default server reads strictly and client leniently, see [DecodeMode::for_bound].
+    pub fn set_decode_mode(&mut self, decode_mode: DecodeMode) {
+        self.decode_mode = decode_mode;
+    }
+
+    fn check_scope<T: PacketScope>(&self, bound: PacketBound) -> Result<(), Error> {
+        match T::bound() == bound && T::state() == self.state {
+            true => Ok(()),
+            false => Err(Error::msg(format!(
+                "Packet of {:?} bound {:?} state is not allowed in {:?} state",
+                T::bound(), T::state(), self.state,
+            ))),
+        }
+    }
+
+    fn write_bound(&self) -> PacketBound {
+        match self.bound {
+            PacketBound::Client => PacketBound::Server,
+            PacketBound::Server => PacketBound::Client,
+        }
+    }
+
+    async fn next_frame(&mut self) -> Result<&[u8], Error> {
+        self.frame = self.reader.next_packet().await.map_err(stream_error)?;
+        if let Some(rate_limiter) = &mut self.rate_limiter {
+            match rate_limiter.check(self.frame.len()) {
+                RateDecision::Allow => {}
+                RateDecision::Throttle(duration) => tokio::time::sleep(duration).await,
+                RateDecision::Disconnect => return Err(Error::msg("Rate limit is exceeded")),
+            }
+        }
+        Ok(&self.frame)
+    }
+
+    /// Reads the next frame as the given packet. The packet can borrow the frame until the next read.
+    pub async fn read_packet<'a, T>(&'a mut self) -> Result<T, Error> where T: PacketReadable<'a> + Packet {
+        self.check_scope::<T>(self.bound)?;
+        let (mode, state) = (self.decode_mode, self.state);
+        decode_frame(self.next_frame().await?, mode, T::bound(), state, |read| {
+            match VarInt::read_variant(read)? == T::id() {
+                true => Ok(T::read(read)?),
+                false => Err(Error::msg("Unexpected packet id")),
+            }
+        })
+    }
+
+    /// Reads the next frame as any packet of the enum, which is generated by [packet_state](crate::packet_state).
+    pub async fn read_any<'a, T>(&'a mut self) -> Result<T, Error> where T: PacketReadable<'a> + PacketScope {
+        self.check_scope::<T>(self.bound)?;
+        let (mode, state) = (self.decode_mode, self.state);
+        decode_frame(self.next_frame().await?, mode, T::bound(), state, |read| Ok(T::read(read)?))
+    }
+
+    /// Writes packet with its id as the frame and flushes the stream.
+    pub async fn write_packet<T>(&mut self, packet: &T) -> Result<(), Error> where T: PacketWritable + Packet {
+        self.check_scope::<T>(self.write_bound())?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(id = T::id(), bound = ?T::bound(), state = ?self.state, "packet is written");
+        self.writer.write_packet(packet).await?;
+        self.writer.flush().await
+    }
+
+    /// Writes any packet of the enum, which is generated by [packet_state](crate::packet_state), and flushes the stream.
+    pub async fn write_any<T>(&mut self, packet: &T) -> Result<(), Error> where T: PacketWritable + PacketScope {
+        self.check_scope::<T>(self.write_bound())?;
+        let mut body = Vec::new();
+        packet.write(&mut body)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            id = VarInt::read_variant(&mut SlicePacketRead::new(&body)).ok(), bound = ?T::bound(), state = ?self.state,
+            "packet is written",
+        );
+        self.writer.write_frame(&body).await?;
+        self.writer.flush().await
+    }
+}
+
+/// Reads the packet of the frame by `read_body` and checks left bytes by the mode.
+///
+/// With `tracing` feature decoding is in `read_packet` span, which has id, bound, state, length of the frame and
+/// decode duration. Failure is logged as warning with the offset where reading stopped.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn decode_frame<'a, T, F>(frame: &'a [u8], mode: DecodeMode, bound: PacketBound, state: PacketState, read_body: F)
+                          -> Result<T, Error> where F: FnOnce(&mut SlicePacketRead<'a>) -> Result<T, Error> {
+    #[cfg(feature = "tracing")]
+    let (span, started) = (
+        tracing::debug_span!(
+            "read_packet",
+            id = VarInt::read_variant(&mut SlicePacketRead::new(frame)).ok(),
+            bound = ?bound,
+            state = ?state,
+            length = frame.len(),
+            decode_micros = tracing::field::Empty,
+        ).entered(),
+        Instant::now(),
+    );
+    let mut read = SlicePacketRead::new(frame);
+    let result = read_body(&mut read).and_then(|packet| {
+        mode.finish(&read)?;
+        Ok(packet)
+    });
+    #[cfg(feature = "tracing")]
+    {
+        span.record("decode_micros", started.elapsed().as_micros() as u64);
+        if let Err(error) = &result {
+            tracing::warn!(offset = read.offset(), %error, "packet is not decoded");
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+    use crate::packet_frame::PacketFramer;
+    use crate::packet_types::PacketVariant;
+    use crate::packet_default::{ClientStatusPacket, ServerStatusPacket, StatusResponseObject, StatusResponseVersion};
+    use super::*;
+
+    async fn status_server(timeout: Duration) -> (std::net::SocketAddr, tokio::task::JoinHandle<Result<HandshakeOutcome<TcpStream>, Error>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            StatusServer::new(|handshake: HandshakeInfo| async move {
+                StatusResponseObject::new(
+                    StatusResponseVersion { name: "1.19".into(), protocol: handshake.protocol_version },
+                    20, 1,
+                    either::Either::Left("A Minecraft Server".into()),
+                )
+            }).with_timeout(timeout).serve(stream).await
+        });
+        (address, handle)
+    }
+
+    fn handshake(next_state: HandshakeNextState) -> HandshakePacket<'static> {
+        HandshakePacket { protocol_version: 759, server_address: "localhost", server_port: 25565, next_state }
+    }
+
+    #[tokio::test]
+    async fn status_exchange_tests() {
+        let (address, handle) = status_server(DEFAULT_TIMEOUT).await;
+        let mut client = PacketStreamReader::new(TcpStream::connect(address).await.unwrap());
+        write_packet_async(client.get_mut(), &handshake(HandshakeNextState::Status)).await.unwrap();
+        write_packet_async(client.get_mut(), &StatusRequest).await.unwrap();
+        let frame = client.next_packet().await.unwrap();
+        let StatusResponse(status) = read_packet_body(&frame).unwrap();
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            serde_json::json!({
+                "version": {"name": "1.19", "protocol": 759},
+                "players": {"max": 20, "online": 1},
+                "description": "A Minecraft Server",
+            })
+        );
+        write_packet_async(client.get_mut(), &StatusPingRequest { payload: 0x1234567890 }).await.unwrap();
+        let frame = client.next_packet().await.unwrap();
+        assert_eq!(read_packet_body::<StatusPingResponse>(&frame).unwrap().payload, 0x1234567890);
+        assert!(client.try_next_packet().await.unwrap().is_none());
+        assert!(matches!(handle.await.unwrap().unwrap(), HandshakeOutcome::Status));
+    }
+
+    #[tokio::test]
+    async fn login_and_legacy_tests() {
+        let (address, handle) = status_server(DEFAULT_TIMEOUT).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+        // Next frame is sent together with the handshake, so it is buffered by the server
+        let mut bytes = Vec::new();
+        PacketFramer::new().write_frame_variant::<PacketVariant, _, _>(&handshake(HandshakeNextState::Login), &mut bytes).unwrap();
+        bytes.extend_from_slice(&[0x02, 0x00, 0x07]);
+        client.write_all(&bytes).await.unwrap();
+        match handle.await.unwrap().unwrap() {
+            HandshakeOutcome::Login(handshake, mut reader) => {
+                assert_eq!(handshake.server_address, "localhost");
+                assert_eq!(&reader.next_packet().await.unwrap()[..], &[0x00, 0x07]);
+            }
+            _ => panic!("Expected login"),
+        }
+        let (address, handle) = status_server(DEFAULT_TIMEOUT).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client.write_all(&[LEGACY_PING_BYTE]).await.unwrap();
+        let mut rest = Vec::new();
+        client.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(handle.await.unwrap().unwrap(), HandshakeOutcome::LegacyPing(LegacyPingRequest::Beta)));
+    }
+
+    async fn legacy_exchange(request: &[u8]) -> (Vec<u8>, HandshakeOutcome<TcpStream>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            StatusServer::new(|_| async { "unused" })
+                .with_legacy_response(|_| LegacyPingResponse::new(127, "1.19", "A Minecraft Server", 1, 20))
+                .serve(stream).await
+        });
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client.write_all(request).await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        (response, handle.await.unwrap().unwrap())
+    }
+
+    fn utf16(str: &str) -> Vec<u8> {
+        str.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    #[tokio::test]
+    async fn legacy_ping_tests() {
+        // 1.6 request for localhost:25565 with protocol 73
+        let mut request = vec![0xFE, 0x01, 0xFA, 0x00, 0x0B];
+        request.extend(utf16("MC|PingHost"));
+        request.extend_from_slice(&[0x00, 0x19, 0x49, 0x00, 0x09]);
+        request.extend(utf16("localhost"));
+        request.extend_from_slice(&[0x00, 0x00, 0x63, 0xDD]);
+        let (response, outcome) = legacy_exchange(&request).await;
+        let mut expected = vec![0xFF, 0x00, 0x23];
+        expected.extend(utf16(&["§1", "127", "1.19", "A Minecraft Server", "1", "20"].join("\0")));
+        assert_eq!(response, expected);
+        assert_eq!(&response[3..9], &[0x00, 0xA7, 0x00, 0x31, 0x00, 0x00]);
+        match outcome {
+            HandshakeOutcome::LegacyPing(request) => assert_eq!(request, LegacyPingRequest::PingHost {
+                protocol_version: 73,
+                server_address: "localhost".into(),
+                server_port: 25565,
+            }),
+            _ => panic!("Expected legacy ping"),
+        }
+        let (response, outcome) = legacy_exchange(&[0xFE, 0x01]).await;
+        assert_eq!(response, expected);
+        assert!(matches!(outcome, HandshakeOutcome::LegacyPing(LegacyPingRequest::Payload)));
+        let (response, outcome) = legacy_exchange(&[0xFE]).await;
+        let mut expected = vec![0xFF, 0x00, 0x17];
+        expected.extend(utf16("A Minecraft Server§1§20"));
+        assert_eq!(response, expected);
+        assert!(matches!(outcome, HandshakeOutcome::LegacyPing(LegacyPingRequest::Beta)));
+    }
+
+    #[tokio::test]
+    async fn timeout_tests() {
+        let (address, handle) = status_server(Duration::from_millis(50)).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+        client.write_all(&[0x10]).await.unwrap();
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn truncated_frame_tests() {
+        let (address, handle) = status_server(DEFAULT_TIMEOUT).await;
+        let mut client = TcpStream::connect(address).await.unwrap();
+        // Declared length is 2097151, but only 3 bytes are sent
+        client.write_all(&[0xFF, 0xFF, 0x7F, 0x00, 0x01, 0x02]).await.unwrap();
+        client.shutdown().await.unwrap();
+        let error = handle.await.unwrap().unwrap_err();
+        assert_eq!(error.downcast_ref::<std::io::Error>().unwrap().kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn connection_status_tests() {
+        let (client, server) = tokio::io::duplex(64);
+        let server = tokio::spawn(async move {
+            let mut connection = Connection::server(server);
+            let handshake: HandshakePacket = connection.read_packet().await.unwrap();
+            assert_eq!(handshake.next_state, HandshakeNextState::Status);
+            let protocol_version = handshake.protocol_version;
+            connection.set_protocol_version(protocol_version);
+            connection.set_state(PacketState::Status).unwrap();
+            assert!(matches!(connection.read_any().await.unwrap(), ServerStatusPacket::Request(StatusRequest)));
+            let status = StatusResponseObject::new(
+                StatusResponseVersion { name: "1.19".into(), protocol: protocol_version },
+                20, 1,
+                either::Either::Left("A Minecraft Server".into()),
+            );
+            connection.write_packet(&StatusResponse(status)).await.unwrap();
+            let ping: StatusPingRequest = connection.read_packet().await.unwrap();
+            connection.write_packet(&StatusPingResponse { payload: ping.payload }).await.unwrap();
+            connection.protocol_version()
+        });
+        let mut connection = Connection::client(client);
+        assert_eq!(connection.state(), PacketState::Handshake);
+        assert!(connection.write_packet(&StatusRequest).await.is_err());
+        connection.write_packet(&handshake(HandshakeNextState::Status)).await.unwrap();
+        connection.set_state(PacketState::Status).unwrap();
+        assert!(connection.set_state(PacketState::Play).is_err());
+        assert!(connection.write_packet(&StatusPingResponse { payload: 0 }).await.is_err());
+        connection.write_packet(&StatusRequest).await.unwrap();
+        let StatusResponse(status) = connection.read_packet().await.unwrap();
+        assert_eq!(status.version.protocol, 759);
+        connection.write_any(&ServerStatusPacket::PingRequest(StatusPingRequest { payload: 0x1234567890 })).await.unwrap();
+        match connection.read_any().await.unwrap() {
+            ClientStatusPacket::PingResponse(pong) => assert_eq!(pong.payload, 0x1234567890),
+            packet => panic!("Expected pong, got {:?}", packet),
+        }
+        assert_eq!(server.await.unwrap(), Some(759));
+    }
+
+    #[tokio::test]
+    async fn trailing_bytes_tests() {
+        let mut exact = Vec::new();
+        PacketVariant::write_variant(&StatusPingRequest { payload: 7 }, &mut exact).unwrap();
+        let mut trailing = exact.clone();
+        trailing.extend_from_slice(&[0xAA, 0xBB]);
+        for mode in [DecodeMode::Strict, DecodeMode::Lenient] {
+            assert_eq!(read_packet_body_with_mode::<StatusPingRequest>(&exact, mode).unwrap().payload, 7);
+        }
+        assert_eq!(read_packet_body_with_mode::<StatusPingRequest>(&trailing, DecodeMode::Lenient).unwrap().payload, 7);
+        let error = read_packet_body::<StatusPingRequest>(&trailing).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketReadableError::TrailingBytes { remaining: 2 })));
+        // Client bound packets are read leniently
+        let mut pong = Vec::new();
+        PacketVariant::write_variant(&StatusPingResponse { payload: 7 }, &mut pong).unwrap();
+        pong.push(0xAA);
+        assert_eq!(read_packet_body::<StatusPingResponse>(&pong).unwrap().payload, 7);
+
+        let (client, server) = tokio::io::duplex(64);
+        let mut server = Connection::server(server);
+        let mut client = Connection::client(client);
+        assert_eq!((server.decode_mode(), client.decode_mode()), (DecodeMode::Strict, DecodeMode::Lenient));
+        for connection in [&mut server, &mut client] {
+            connection.set_state(PacketState::Status).unwrap();
+        }
+        client.writer.write_frame(&trailing).await.unwrap();
+        client.writer.write_frame(&trailing).await.unwrap();
+        client.writer.flush().await.unwrap();
+        let error = server.read_packet::<StatusPingRequest>().await.unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketReadableError::TrailingBytes { remaining: 2 })));
+        server.set_decode_mode(DecodeMode::Lenient);
+        assert!(matches!(server.read_any().await.unwrap(), ServerStatusPacket::PingRequest(StatusPingRequest { payload: 7 })));
+        server.writer.write_frame(&pong).await.unwrap();
+        server.writer.flush().await.unwrap();
+        assert!(matches!(client.read_any().await.unwrap(), ClientStatusPacket::PingResponse(StatusPingResponse { payload: 7 })));
+    }
+
+    #[test]
+    fn plugin_channel_registry_tests() {
+        use std::sync::{Arc, Mutex};
+        use crate::packet_play::{Brand, BRAND_CHANNEL, ChannelList, REGISTER_CHANNEL};
+        let brands = Arc::new(Mutex::new(Vec::new()));
+        let unknown = Arc::new(Mutex::new(0));
+        let mut registry = PluginChannelRegistry::new();
+        let brands_handler = brands.clone();
+        registry.register(BRAND_CHANNEL, move |read: &mut SlicePacketRead| {
+            brands_handler.lock().unwrap().push(Brand::read(read)?.0.to_string());
+            Ok(())
+        });
+        registry.register(REGISTER_CHANNEL, |read: &mut SlicePacketRead| {
+            assert_eq!(ChannelList::read(read)?, ChannelList(vec!["bird:first", "bird:second"]));
+            Ok(())
+        });
+        let mut data = Vec::new();
+        Brand("vanilla").write(&mut data).unwrap();
+        let brand = PlayServerPluginMessage { channel: Identifier::new_fulled(BRAND_CHANNEL).unwrap(), data: &data };
+        assert!(registry.handle_message(&brand).unwrap());
+        assert_eq!(*brands.lock().unwrap(), vec!["vanilla".to_string()]);
+        let register = Identifier::new_fulled(REGISTER_CHANNEL).unwrap();
+        assert!(registry.handle(&register, b"bird:first\0bird:second\0").unwrap());
+
+        let channel = Identifier::new_fulled("bird:unknown").unwrap();
+        assert!(!registry.handle(&channel, &[1, 2]).unwrap());
+        let unknown_handler = unknown.clone();
+        registry.set_default(move |read: &mut SlicePacketRead| {
+            *unknown_handler.lock().unwrap() += read.available();
+            Ok(())
+        });
+        assert!(registry.handle(&channel, &[1, 2]).unwrap());
+        assert_eq!(*unknown.lock().unwrap(), 2);
+        // Errors of the handler are passed through
+        assert!(registry.handle(&brand.channel, &[0x05]).is_err());
+        assert!(registry.unregister(BRAND_CHANNEL));
+        assert!(!registry.is_registered(BRAND_CHANNEL));
+        assert_eq!(registry.channels().collect::<Vec<_>>(), vec![REGISTER_CHANNEL]);
+    }
+
+    #[test]
+    fn rate_limiter_tests() {
+        let clock = ManualClock::new();
+        let limits = RateLimits::new(20, 1000).with_max_throttle(Duration::from_millis(500));
+        let mut limiter = RateLimiter::with_clock(limits, clock.clone());
+        for _ in 0..20 {
+            assert_eq!(limiter.check(10), RateDecision::Allow);
+        }
+        for packet in 1..=10 {
+            assert_eq!(limiter.check(10), RateDecision::Throttle(Duration::from_millis(50 * packet)));
+        }
+        assert_eq!(limiter.check(10), RateDecision::Disconnect);
+        // Debt is paid and the bucket is full again
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(limiter.check(10), RateDecision::Allow);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.check(900), RateDecision::Allow);
+        assert_eq!(limiter.check(300), RateDecision::Throttle(Duration::from_millis(200)));
+        assert_eq!(limiter.check(2000), RateDecision::Disconnect);
+
+        let mut limiter = RateLimiter::with_clock(limits, clock.clone());
+        for _ in 0..100_000 {
+            clock.advance(Duration::from_millis(100));
+            assert_eq!(limiter.check(50), RateDecision::Allow);
+        }
+        let mut limiter = RateLimiter::with_clock(RateLimits::new(0, 1000), clock.clone());
+        assert_eq!(limiter.check(0), RateDecision::Disconnect);
+    }
+
+    #[test]
+    fn connection_throttle_tests() {
+        let clock = ManualClock::new();
+        let mut throttle = ConnectionThrottle::with_clock(2, 2, clock.clone());
+        let first: SocketAddr = "10.0.0.1:50000".parse().unwrap();
+        let second: SocketAddr = "10.0.0.2:50000".parse().unwrap();
+        let third: SocketAddr = "10.0.0.3:50000".parse().unwrap();
+        assert!(throttle.allow(&first));
+        assert!(throttle.allow(&"10.0.0.1:50001".parse().unwrap()));
+        assert!(!throttle.allow(&first));
+        assert!(throttle.allow(&second));
+        clock.advance(Duration::from_millis(500));
+        assert!(throttle.allow(&first));
+        assert!(!throttle.allow(&first));
+        // The second address is the least recent one, so it is forgotten
+        assert!(throttle.allow(&third));
+        assert_eq!(throttle.len(), 2);
+        assert!(!throttle.allow(&first));
+        for _ in 0..3 {
+            assert!(throttle.allow(&second));
+            assert!(throttle.allow(&third));
+            assert!(throttle.allow(&first));
+        }
+        for _ in 0..1000 {
+            clock.advance(Duration::from_millis(500));
+            assert!(throttle.allow(&first));
+        }
+    }
+
+    #[tokio::test]
+    async fn connection_rate_limit_tests() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut server = Connection::server(server);
+        server.set_rate_limiter(Some(RateLimiter::new(RateLimits::new(1, 1000).with_max_throttle(Duration::ZERO))));
+        let mut client = Connection::client(client);
+        client.write_packet(&handshake(HandshakeNextState::Status)).await.unwrap();
+        client.write_packet(&handshake(HandshakeNextState::Status)).await.unwrap();
+        server.read_packet::<HandshakePacket>().await.unwrap();
+        assert!(server.read_packet::<HandshakePacket>().await.is_err());
+    }
+
+    #[test]
+    fn keep_alive_tests() {
+        let clock = ManualClock::new();
+        let mut driver = KeepAliveDriver::with_clock(clock.clone());
+        assert_eq!(driver.latency(), Duration::ZERO);
+        let first = driver.poll_send().unwrap();
+        assert_eq!(driver.poll_send(), None);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(driver.on_keep_alive_response(first.id), KeepAliveVerdict::Accepted { latency: Duration::from_millis(100) });
+        assert_eq!(driver.latency_millis(), 100);
+        // Each id is answered once
+        assert_eq!(driver.on_keep_alive_response(first.id), KeepAliveVerdict::Unexpected);
+
+        clock.advance(KEEP_ALIVE_INTERVAL);
+        let second = driver.poll_send().unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(driver.on_keep_alive_response(second.id.wrapping_add(1)), KeepAliveVerdict::Unexpected);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(driver.on_keep_alive_response(second.id), KeepAliveVerdict::Accepted { latency: Duration::from_millis(500) });
+        assert_eq!(driver.latency(), Duration::from_millis(200));
+        assert_eq!(driver.poll_timeout(), None);
+
+        // Old keep alive is not waited for after the newer one is answered
+        clock.advance(KEEP_ALIVE_INTERVAL);
+        let third = driver.poll_send().unwrap();
+        clock.advance(KEEP_ALIVE_INTERVAL);
+        let fourth = driver.poll_send().unwrap();
+        assert!(matches!(driver.on_keep_alive_response(fourth.id), KeepAliveVerdict::Accepted { .. }));
+        assert_eq!(driver.on_keep_alive_response(third.id), KeepAliveVerdict::Unexpected);
+
+        clock.advance(KEEP_ALIVE_INTERVAL);
+        let fifth = driver.poll_send().unwrap();
+        clock.advance(KEEP_ALIVE_TIMEOUT - Duration::from_millis(1));
+        assert!(driver.poll_send().is_some());
+        assert_eq!(driver.poll_timeout(), None);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(driver.poll_timeout(), Some(KeepAliveTimeout { id: fifth.id, elapsed: KEEP_ALIVE_TIMEOUT }));
+
+        let mut driver = KeepAliveDriver::with_clock(clock.clone())
+            .with_interval(Duration::from_secs(1))
+            .with_timeout(Duration::from_secs(2));
+        let start = driver.next_send();
+        driver.poll_send().unwrap();
+        assert_eq!(driver.next_send(), start + Duration::from_secs(1));
+        clock.advance(Duration::from_secs(2));
+        assert!(driver.poll_timeout().is_some());
+    }
+
+    #[tokio::test]
+    async fn keep_alive_connection_tests() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut server = Connection::server(server);
+        let mut client = Connection::client(client);
+        for connection in [&mut server, &mut client] {
+            connection.set_state(PacketState::Login).unwrap();
+            connection.set_state(PacketState::Play).unwrap();
+        }
+        let clock = ManualClock::new();
+        let mut driver = KeepAliveDriver::with_clock(clock.clone());
+        driver.send_due(&mut server).await.unwrap();
+        driver.send_due(&mut server).await.unwrap();
+        let keep_alive = client.read_packet::<PlayKeepAlive>().await.unwrap();
+        client.write_packet(&crate::packet_play::PlayKeepAliveResponse { id: keep_alive.id }).await.unwrap();
+        clock.advance(Duration::from_millis(30));
+        let response = server.read_packet::<crate::packet_play::PlayKeepAliveResponse>().await.unwrap();
+        assert_eq!(driver.on_keep_alive_response(response.id), KeepAliveVerdict::Accepted { latency: Duration::from_millis(30) });
+    }
+
+    #[test]
+    fn entity_id_allocator_tests() {
+        let allocator = EntityIdAllocator::new();
+        assert_eq!((allocator.allocate(), allocator.allocate(), allocator.allocate()), (1, 2, 3));
+        assert!(allocator.free(2));
+        assert!(!allocator.free(2));
+        assert!(!allocator.free(0));
+        assert!(!allocator.free(4));
+        assert!(allocator.free(1));
+        assert_eq!(allocator.allocated(), 1);
+        // Freed ids are reused in the order of freeing before new ones
+        assert_eq!((allocator.allocate(), allocator.allocate(), allocator.allocate()), (2, 1, 4));
+        assert_eq!(allocator.allocated(), 4);
+        assert!(allocator.free(1));
+        assert_eq!(allocator.clone().allocate(), 1);
+
+        let allocator = EntityIdAllocator::new();
+        let threads: Vec<_> = (0..8).map(|thread| {
+            let allocator = allocator.clone();
+            std::thread::spawn(move || {
+                let mut ids = Vec::new();
+                for index in 0..1000 {
+                    let id = allocator.allocate();
+                    if (index + thread) % 3 == 0 {
+                        assert!(allocator.free(id));
+                    } else {
+                        ids.push(id);
+                    }
+                }
+                ids
+            })
+        }).collect();
+        let ids: Vec<i32> = threads.into_iter().flat_map(|thread| thread.join().unwrap()).collect();
+        let unique: HashSet<_> = ids.iter().copied().collect();
+        assert_eq!(unique.len(), ids.len());
+        assert_eq!(allocator.allocated(), ids.len());
+        assert!(ids.iter().all(|&id| id > 0 && id <= 8000));
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_tests {
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+        use super::*;
+
+        /// Fields formatted as `name=value`.
+        type Recorded<T> = Arc<Mutex<Vec<(T, Vec<String>)>>>;
+
+        /// Names of the spans and the levels of the events with their fields.
+        #[derive(Clone, Default)]
+        struct Recorder {
+            spans: Recorded<String>,
+            events: Recorded<tracing::Level>,
+        }
+
+        struct Fields<'a>(&'a mut Vec<String>);
+
+        impl Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push(format!("{}={:?}", field.name(), value));
+            }
+        }
+
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Recorder {
+            fn on_new_span(&self, attributes: &Attributes<'_>, _id: &Id, _context: Context<'_, S>) {
+                let mut fields = Vec::new();
+                attributes.record(&mut Fields(&mut fields));
+                self.spans.lock().unwrap().push((attributes.metadata().name().to_string(), fields));
+            }
+
+            fn on_record(&self, _id: &Id, values: &Record<'_>, _context: Context<'_, S>) {
+                let mut spans = self.spans.lock().unwrap();
+                values.record(&mut Fields(&mut spans.last_mut().unwrap().1));
+            }
+
+            fn on_event(&self, event: &tracing::Event<'_>, _context: Context<'_, S>) {
+                let mut fields = Vec::new();
+                event.record(&mut Fields(&mut fields));
+                self.events.lock().unwrap().push((*event.metadata().level(), fields));
+            }
+        }
+
+        #[tokio::test]
+        async fn read_packet_span_tests() {
+            let recorder = Recorder::default();
+            let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+            let (client, server) = tokio::io::duplex(64);
+            let mut server = Connection::server(server);
+            let mut client = Connection::client(client);
+            for connection in [&mut server, &mut client] {
+                connection.set_state(PacketState::Status).unwrap();
+            }
+            client.write_packet(&StatusPingRequest { payload: 7 }).await.unwrap();
+            assert_eq!(server.read_packet::<StatusPingRequest>().await.unwrap().payload, 7);
+            {
+                let spans = recorder.spans.lock().unwrap();
+                assert_eq!(spans.len(), 1);
+                let (name, fields) = &spans[0];
+                assert_eq!(name, "read_packet");
+                assert_eq!(&fields[..4], &["id=1", "bound=Server", "state=Status", "length=9"]);
+                assert!(fields[4].starts_with("decode_micros="), "{:?}", fields);
+                let events = recorder.events.lock().unwrap();
+                assert!(events.iter().any(|(level, fields)| {
+                    *level == tracing::Level::DEBUG && fields.contains(&"id=1".to_string())
+                }), "{:?}", events);
+                assert!(events.iter().all(|(level, _)| *level != tracing::Level::WARN));
+            }
+
+            // Failure is a warning with the offset
+            let mut body = Vec::new();
+            PacketVariant::write_variant(&StatusPingRequest { payload: 7 }, &mut body).unwrap();
+            body.push(0xAA);
+            client.writer.write_frame(&body).await.unwrap();
+            client.writer.flush().await.unwrap();
+            assert!(server.read_packet::<StatusPingRequest>().await.is_err());
+            assert_eq!(recorder.spans.lock().unwrap().len(), 2);
+            let events = recorder.events.lock().unwrap();
+            let (level, fields) = events.last().unwrap();
+            assert_eq!(*level, tracing::Level::WARN);
+            assert!(fields.contains(&"offset=9".to_string()), "{:?}", fields);
+        }
+    }
+}
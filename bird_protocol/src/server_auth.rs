@@ -0,0 +1,526 @@
+use std::borrow::Cow;
+use std::future::Future;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::Error;
+use md5::Md5;
+use rsa::RsaPublicKey;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use tokio::io::{AsyncRead, AsyncWrite};
+use uuid::Uuid;
+use crate::packet_default::{encode_base64, LoginEncryptionResponse, LoginEncryptionResponseData, LoginStart, LoginSuccess, LoginSuccessProperty, ProfilePublicKey};
+use crate::packet_encryption::{generate_verify_token, ServerKey};
+use crate::server::Connection;
+
+/// Url of the session server endpoint, which is completed by the username and the server hash.
+pub const HAS_JOINED_URL: &str = "https://sessionserver.mojang.com/session/minecraft/hasJoined";
+
+/// Hex of the sha1 digest as java `new BigInteger(digest).toString(16)`.
+///
+/// Digest is a signed two's complement number, so the negative one is written as minus and its absolute value.
+/// Leading zeros are not written.
+pub fn minecraft_sha1_hex(digest: &[u8; 20]) -> String {
+    let negative = digest[0] & 0x80 != 0;
+    let mut bytes = *digest;
+    if negative {
+        // Absolute value is the inverted bits plus one
+        let mut carry = true;
+        for byte in bytes.iter_mut().rev() {
+            (*byte, carry) = (!*byte).overflowing_add(carry as u8);
+        }
+    }
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let hex = match hex.trim_start_matches('0') {
+        "" => "0",
+        hex => hex,
+    };
+    match negative {
+        true => format!("-{}", hex),
+        false => hex.to_string(),
+    }
+}
+
+/// Server id of the session server, which is the hash of the server id of the encryption request,
+/// the shared secret and the public key.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(server_id.as_bytes());
+    sha1.update(shared_secret);
+    sha1.update(public_key_der);
+    minecraft_sha1_hex(&sha1.finalize().into())
+}
+
+/// Uuid of the player in the offline mode, which is `UUID.nameUUIDFromBytes` of `OfflinePlayer:<name>`.
+pub fn offline_uuid(name: &str) -> Uuid {
+    let digest = Md5::digest(format!("OfflinePlayer:{}", name).as_bytes());
+    uuid::Builder::from_md5_bytes(digest.into()).into_uuid()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Profile of the player, which is returned by the session server in the online mode.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct GameProfile {
+    #[serde(rename = "id")]
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+impl GameProfile {
+    /// Profile of the offline mode, which has no properties.
+    pub fn offline(name: &str) -> Self {
+        Self { uuid: offline_uuid(name), name: name.to_string(), properties: Vec::new() }
+    }
+
+    pub fn login_success(&self) -> LoginSuccess<'_> {
+        LoginSuccess {
+            uuid: self.uuid,
+            name: &self.name,
+            properties: Cow::Owned(self.properties.iter().map(|property| LoginSuccessProperty {
+                name: &property.name,
+                value: &property.value,
+                signature: property.signature.as_deref(),
+            }).collect()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// Client of the session server, so any http library can be used.
+pub trait HttpClient {
+    /// Sends GET request to the url.
+    fn get(&self, url: &str) -> impl Future<Output=Result<HttpResponse, Error>> + Send;
+}
+
+/// Client of [LoginFlow::offline], which never sends requests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoHttpClient;
+
+impl HttpClient for NoHttpClient {
+    async fn get(&self, _url: &str) -> Result<HttpResponse, Error> {
+        Err(Error::msg("Http client is not set"))
+    }
+}
+
+/// Encodes everything except unreserved characters of the url.
+fn percent_encode(value: &str) -> String {
+    value.bytes().map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+        byte => format!("%{:02X}", byte),
+    }).collect()
+}
+
+/// Checks the salted signature of the verify token, which clients with the profile key send instead of the token.
+fn verify_signature(key: &ProfilePublicKey, verify_token: &[u8], salt: i64, signature: &[u8]) -> Result<(), Error> {
+    let key = VerifyingKey::<Sha256>::new(RsaPublicKey::from_public_key_der(key.public_key)?);
+    let message = [verify_token, &salt.to_be_bytes()].concat();
+    key.verify(&message, &Signature::try_from(signature)?)
+        .map_err(|_| Error::msg("Bad signature of the verify token"))
+}
+
+/// Public key as java `Crypt.rsaPublicKeyToString`, which is PEM of the der with base64 lines of 76 characters.
+fn public_key_pem(der: &[u8]) -> String {
+    let mut base64 = String::with_capacity(der.len().div_ceil(3) * 4);
+    encode_base64(der, &mut base64);
+    let lines: Vec<&str> = base64.as_bytes().chunks(76).map(|line| std::str::from_utf8(line).unwrap()).collect();
+    format!("-----BEGIN RSA PUBLIC KEY-----\n{}\n-----END RSA PUBLIC KEY-----\n", lines.join("\n"))
+}
+
+/// Verifier of the profile public key from the login start, which is signed by Mojang services,
+/// so the key made by the client itself is refused.
+pub trait ProfileKeyVerifier {
+    fn verify(&self, key: &ProfilePublicKey) -> Result<(), Error>;
+}
+
+/// Verifier of the 1.19 profile key by the public key of Mojang services.
+///
+/// Signature is `SHA1withRSA` of the expiry timestamp in milliseconds followed by the key in PEM.
+#[derive(Debug, Clone)]
+pub struct ServicesKeyVerifier {
+    key: VerifyingKey<Sha1>,
+}
+
+impl ServicesKeyVerifier {
+    /// Creates verifier from the der of the services key, which is published by `api.minecraftservices.com/publickeys`.
+    pub fn from_public_key_der(der: &[u8]) -> Result<Self, Error> {
+        Ok(Self { key: VerifyingKey::new(RsaPublicKey::from_public_key_der(der)?) })
+    }
+}
+
+impl ProfileKeyVerifier for ServicesKeyVerifier {
+    fn verify(&self, key: &ProfilePublicKey) -> Result<(), Error> {
+        let payload = format!("{}{}", key.timestamp, public_key_pem(key.public_key));
+        self.key.verify(payload.as_bytes(), &Signature::try_from(key.signature)?)
+            .map_err(|_| Error::msg("Bad signature of the profile key"))
+    }
+}
+
+/// Checks that the profile key is not expired and is signed by the services.
+fn validate_profile_key(verifier: &dyn ProfileKeyVerifier, key: &ProfilePublicKey, now: i64) -> Result<(), Error> {
+    match key.timestamp > now {
+        true => verifier.verify(key),
+        false => Err(Error::msg("Profile key is expired")),
+    }
+}
+
+/// Login of the client on the server side from the login start to the login success.
+///
+/// In the online mode the connection is encrypted and the player is authenticated by the session server.
+/// In the offline mode the profile is [GameProfile::offline] and the connection is not encrypted.
+///
+/// Signature of the verify token is accepted only with [LoginFlow::with_profile_key_verifier],
+/// otherwise the client has to send the encrypted verify token.
+pub struct LoginFlow<H = NoHttpClient> {
+    online: Option<(ServerKey, H)>,
+    session_url: String,
+    key_verifier: Option<Box<dyn ProfileKeyVerifier + Send + Sync>>,
+    #[cfg(feature = "compression")]
+    compression_threshold: Option<usize>,
+}
+
+impl LoginFlow {
+    pub fn offline() -> Self {
+        Self {
+            online: None,
+            session_url: HAS_JOINED_URL.to_string(),
+            key_verifier: None,
+            #[cfg(feature = "compression")]
+            compression_threshold: None,
+        }
+    }
+}
+
+impl<H: HttpClient> LoginFlow<H> {
+    pub fn online(key: ServerKey, http: H) -> Self {
+        Self {
+            online: Some((key, http)),
+            session_url: HAS_JOINED_URL.to_string(),
+            key_verifier: None,
+            #[cfg(feature = "compression")]
+            compression_threshold: None,
+        }
+    }
+
+    /// Sets url of the `hasJoined` endpoint, for example of the authentication proxy.
+    pub fn with_session_url(mut self, session_url: impl Into<String>) -> Self {
+        self.session_url = session_url.into();
+        self
+    }
+
+    /// Accepts signature of the verify token by the profile key, which is verified by `verifier` and has to be not expired.
+    pub fn with_profile_key_verifier(mut self, verifier: impl ProfileKeyVerifier + Send + Sync + 'static) -> Self {
+        self.key_verifier = Some(Box::new(verifier));
+        self
+    }
+
+    /// Sends set compression before the login success, so packets of the play state are compressed.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, threshold: usize) -> Self {
+        self.compression_threshold = Some(threshold);
+        self
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online.is_some()
+    }
+
+    /// Logins the client of the connection in the login state. After the login success the state is play.
+    pub async fn login<S>(&self, connection: &mut Connection<S>) -> Result<GameProfile, Error>
+        where S: AsyncRead + AsyncWrite {
+        let start = connection.read_packet::<LoginStart>().await?;
+        let name = start.name.to_string();
+        let profile_key = start.signature_data.map(|key| (key.public_key.to_vec(), key.timestamp, key.signature.to_vec()));
+        let profile = match &self.online {
+            Some((key, http)) => {
+                let profile_key = profile_key.as_ref().map(|(public_key, timestamp, signature)| ProfilePublicKey {
+                    timestamp: *timestamp, public_key, signature,
+                });
+                self.authenticate(connection, key, http, &name, profile_key.as_ref()).await?
+            }
+            None => GameProfile::offline(&name),
+        };
+        #[cfg(feature = "compression")]
+        if let Some(threshold) = self.compression_threshold {
+            connection.write_packet(&crate::packet_default::LoginSetCompression {
+                threshold: i32::try_from(threshold).map_err(|_| Error::msg("Too big compression threshold"))?,
+            }).await?;
+            connection.set_compression(Some(threshold));
+        }
+        connection.write_packet(&profile.login_success()).await?;
+        connection.set_state(crate::packet::PacketState::Play)?;
+        Ok(profile)
+    }
+
+    async fn authenticate<S>(
+        &self, connection: &mut Connection<S>, key: &ServerKey, http: &H, name: &str, profile_key: Option<&ProfilePublicKey<'_>>,
+    ) -> Result<GameProfile, Error> where S: AsyncRead + AsyncWrite {
+        let verify_token = generate_verify_token();
+        connection.write_packet(&key.encryption_request("", &verify_token)).await?;
+        let response = connection.read_packet::<LoginEncryptionResponse>().await?;
+        let shared_secret = match (response.data, profile_key) {
+            (LoginEncryptionResponseData::VerifyToken { verify_token: encrypted }, _) =>
+                key.verify_response(&verify_token, response.shared_secret, encrypted)?,
+            (LoginEncryptionResponseData::MessageSignature { salt, message_signature }, Some(profile_key)) => {
+                let verifier = self.key_verifier.as_deref()
+                    .ok_or_else(|| Error::msg("Profile key can't be verified, so the verify token is required"))?;
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+                validate_profile_key(verifier, profile_key, now)?;
+                verify_signature(profile_key, &verify_token, salt, message_signature)?;
+                key.decrypt(response.shared_secret)?.as_slice().try_into()
+                    .map_err(|_| Error::msg("Bad shared secret length"))?
+            }
+            (LoginEncryptionResponseData::MessageSignature { .. }, None) =>
+                return Err(Error::msg("Signature is sent without the profile key")),
+        };
+        connection.enable_encryption(&shared_secret)?;
+        let url = format!(
+            "{}?username={}&serverId={}",
+            self.session_url, percent_encode(name), server_hash("", &shared_secret, key.public_key_der()),
+        );
+        let response = http.get(&url).await?;
+        match response.status {
+            200 => Ok(serde_json::from_slice(&response.body)?),
+            204 => Err(Error::msg("Player is not authenticated by the session server")),
+            status => Err(Error::msg(format!("Session server responded with status {}", status))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::pkcs8::EncodePublicKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use crate::packet::PacketState;
+    use crate::packet_default::LoginEncryptionRequest;
+    #[cfg(feature = "compression")]
+    use crate::packet_default::LoginSetCompression;
+    use super::*;
+
+    #[test]
+    fn minecraft_sha1_hex_tests() {
+        let hex = |name: &str| minecraft_sha1_hex(&Sha1::digest(name.as_bytes()).into());
+        assert_eq!(hex("Notch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(hex("jeb_"), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        // Leading zero is not written
+        assert_eq!(hex("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
+        assert_eq!(minecraft_sha1_hex(&[0; 20]), "0");
+        assert_eq!(minecraft_sha1_hex(&[0xFF; 20]), "-1");
+        let mut min = [0; 20];
+        min[0] = 0x80;
+        assert_eq!(minecraft_sha1_hex(&min), format!("-8{}", "0".repeat(39)));
+        assert_eq!(server_hash("", b"jeb", b"_"), hex("jeb_"));
+    }
+
+    #[test]
+    fn offline_tests() {
+        assert_eq!(offline_uuid("Notch"), Uuid::parse_str("b50ad385-829d-3141-a216-7e7d7539ba7f").unwrap());
+        assert_eq!(offline_uuid("Notch").get_version_num(), 3);
+        assert_eq!(percent_encode("a_B-9 &="), "a_B-9%20%26%3D");
+        let profile: GameProfile = serde_json::from_str(
+            r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{"name":"textures","value":"e30=","signature":"c2ln"}]}"#,
+        ).unwrap();
+        assert_eq!(profile.uuid, Uuid::from_u128(0x069a79f444e94726a5befca90e38aaf5));
+        let success = profile.login_success();
+        assert_eq!((success.name, success.properties[0].signature), ("Notch", Some("c2ln")));
+    }
+
+    /// Session server which answers with the profile and remembers the requested urls.
+    #[derive(Clone, Default)]
+    struct MockSessionServer {
+        urls: Arc<Mutex<Vec<String>>>,
+        status: u16,
+    }
+
+    impl HttpClient for MockSessionServer {
+        async fn get(&self, url: &str) -> Result<HttpResponse, Error> {
+            self.urls.lock().unwrap().push(url.to_string());
+            Ok(HttpResponse {
+                status: self.status,
+                body: br#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{"name":"textures","value":"e30="}]}"#.to_vec(),
+            })
+        }
+    }
+
+    fn login_connections() -> (Connection<tokio::io::DuplexStream>, Connection<tokio::io::DuplexStream>) {
+        let (client, server) = tokio::io::duplex(4096);
+        let mut server = Connection::server(server);
+        let mut client = Connection::client(client);
+        for connection in [&mut server, &mut client] {
+            connection.set_state(PacketState::Login).unwrap();
+        }
+        (server, client)
+    }
+
+    /// Profile key of the player which expires at `timestamp` and is signed by the services key.
+    fn signed_profile_key(player: &RsaPrivateKey, services: &RsaPrivateKey, timestamp: i64) -> (Vec<u8>, Vec<u8>) {
+        let public_key = player.to_public_key().to_public_key_der().unwrap().into_vec();
+        let payload = format!("{}{}", timestamp, public_key_pem(&public_key));
+        let signature = SigningKey::<Sha1>::new(services.clone()).sign(payload.as_bytes()).to_vec();
+        (public_key, signature)
+    }
+
+    fn services_verifier(services: &RsaPrivateKey) -> ServicesKeyVerifier {
+        ServicesKeyVerifier::from_public_key_der(services.to_public_key().to_public_key_der().unwrap().as_bytes()).unwrap()
+    }
+
+    /// Client side of the login, which answers with the signature if the key is given with its expiry and signature.
+    async fn client_login(
+        client: &mut Connection<tokio::io::DuplexStream>, key: Option<(&RsaPrivateKey, i64, &[u8])>,
+    ) -> String {
+        let public_key = key.map(|(key, ..)| key.to_public_key().to_public_key_der().unwrap().into_vec());
+        client.write_packet(&LoginStart {
+            name: "Notch",
+            signature_data: public_key.as_ref().zip(key).map(|(public_key, (_, timestamp, signature))| ProfilePublicKey {
+                timestamp, public_key, signature,
+            }),
+        }).await.unwrap();
+        let request = client.read_packet::<LoginEncryptionRequest>().await.unwrap();
+        assert_eq!(request.server_id, "");
+        let server_key = RsaPublicKey::from_public_key_der(request.public_key).unwrap();
+        let public_key_der = request.public_key.to_vec();
+        let verify_token = request.verify_token.to_vec();
+        let shared_secret = crate::packet_encryption::generate_shared_secret();
+        let mut rng = rand::thread_rng();
+        let encrypted_secret = server_key.encrypt(&mut rng, Pkcs1v15Encrypt, &shared_secret).unwrap();
+        let (encrypted_token, signature);
+        let data = match key {
+            Some((key, ..)) => {
+                signature = SigningKey::<Sha256>::new(key.clone()).sign(&[&verify_token[..], &7i64.to_be_bytes()].concat()).to_vec();
+                LoginEncryptionResponseData::MessageSignature { salt: 7, message_signature: &signature }
+            }
+            None => {
+                encrypted_token = server_key.encrypt(&mut rng, Pkcs1v15Encrypt, &verify_token).unwrap();
+                LoginEncryptionResponseData::VerifyToken { verify_token: &encrypted_token }
+            }
+        };
+        client.write_packet(&LoginEncryptionResponse { shared_secret: &encrypted_secret, data }).await.unwrap();
+        client.enable_encryption(&shared_secret).unwrap();
+        server_hash("", &shared_secret, &public_key_der)
+    }
+
+    #[test]
+    fn profile_key_tests() {
+        assert_eq!(public_key_pem(b"Ma"), "-----BEGIN RSA PUBLIC KEY-----\nTWE=\n-----END RSA PUBLIC KEY-----\n");
+        let pem = public_key_pem(&[0; 60]);
+        assert_eq!(pem.lines().skip(1).map(str::len).collect::<Vec<_>>(), [76, 4, 28]);
+        let mut rng = rand::thread_rng();
+        let (player, services) = (RsaPrivateKey::new(&mut rng, 1024).unwrap(), RsaPrivateKey::new(&mut rng, 1024).unwrap());
+        let verifier = services_verifier(&services);
+        let (public_key, signature) = signed_profile_key(&player, &services, 2000);
+        let key = ProfilePublicKey { timestamp: 2000, public_key: &public_key, signature: &signature };
+        validate_profile_key(&verifier, &key, 1999).unwrap();
+        assert_eq!(validate_profile_key(&verifier, &key, 2000).unwrap_err().to_string(), "Profile key is expired");
+        // Expiry is signed, so it can't be extended
+        let extended = ProfilePublicKey { timestamp: 3000, ..key };
+        assert_eq!(validate_profile_key(&verifier, &extended, 1999).unwrap_err().to_string(), "Bad signature of the profile key");
+        // Key signed by the player itself is refused
+        let (public_key, signature) = signed_profile_key(&player, &player, 2000);
+        let key = ProfilePublicKey { timestamp: 2000, public_key: &public_key, signature: &signature };
+        assert_eq!(validate_profile_key(&verifier, &key, 1999).unwrap_err().to_string(), "Bad signature of the profile key");
+    }
+
+    #[tokio::test]
+    async fn online_login_tests() {
+        let key = ServerKey::generate().unwrap();
+        let mut rng = rand::thread_rng();
+        let (private_key, services) = (RsaPrivateKey::new(&mut rng, 1024).unwrap(), RsaPrivateKey::new(&mut rng, 1024).unwrap());
+        let expires_at = i64::MAX;
+        let (_, signature) = signed_profile_key(&private_key, &services, expires_at);
+        for player_key in [None, Some((&private_key, expires_at, signature.as_slice()))] {
+            let session = MockSessionServer { status: 200, ..Default::default() };
+            let flow = LoginFlow::online(key.clone(), session.clone())
+                .with_session_url("http://session/hasJoined")
+                .with_profile_key_verifier(services_verifier(&services));
+            assert!(flow.is_online());
+            let (mut server, mut client) = login_connections();
+            let (profile, server_hash) = tokio::join!(flow.login(&mut server), client_login(&mut client, player_key));
+            let profile = profile.unwrap();
+            assert_eq!((profile.name.as_str(), profile.properties[0].value.as_str()), ("Notch", "e30="));
+            assert_eq!(
+                session.urls.lock().unwrap().as_slice(),
+                [format!("http://session/hasJoined?username=Notch&serverId={}", server_hash)],
+            );
+            let success = client.read_packet::<LoginSuccess>().await.unwrap();
+            assert_eq!(success, profile.login_success());
+            assert_eq!(server.state(), PacketState::Play);
+            assert!(server.is_encrypted());
+        }
+
+        // Player who is not authenticated is refused
+        let flow = LoginFlow::online(key.clone(), MockSessionServer { status: 204, ..Default::default() });
+        let (mut server, mut client) = login_connections();
+        let (profile, _) = tokio::join!(flow.login(&mut server), client_login(&mut client, None));
+        assert_eq!(profile.unwrap_err().to_string(), "Player is not authenticated by the session server");
+        // Signature without the verifier is refused, because the profile key can't be trusted
+        let flow = LoginFlow::online(key.clone(), MockSessionServer { status: 200, ..Default::default() });
+        let (mut server, mut client) = login_connections();
+        let (profile, _) = tokio::join!(
+            flow.login(&mut server),
+            client_login(&mut client, Some((&private_key, expires_at, signature.as_slice()))),
+        );
+        assert_eq!(profile.unwrap_err().to_string(), "Profile key can't be verified, so the verify token is required");
+        // Expired profile key is refused
+        let flow = flow.with_profile_key_verifier(services_verifier(&services));
+        let (_, expired_signature) = signed_profile_key(&private_key, &services, 1);
+        let (mut server, mut client) = login_connections();
+        let (profile, _) = tokio::join!(
+            flow.login(&mut server),
+            client_login(&mut client, Some((&private_key, 1, expired_signature.as_slice()))),
+        );
+        assert_eq!(profile.unwrap_err().to_string(), "Profile key is expired");
+        // Signature of the other key is refused
+        let (public_key, signature) = signed_profile_key(&services, &services, expires_at);
+        let (mut server, mut client) = login_connections();
+        client.write_packet(&LoginStart {
+            name: "Notch",
+            signature_data: Some(ProfilePublicKey { timestamp: expires_at, public_key: &public_key, signature: &signature }),
+        }).await.unwrap();
+        let (profile, _) = tokio::join!(flow.login(&mut server), async {
+            client.read_packet::<LoginEncryptionRequest>().await.unwrap();
+            client.write_packet(&LoginEncryptionResponse {
+                shared_secret: &[0; 128],
+                data: LoginEncryptionResponseData::MessageSignature { salt: 7, message_signature: &[0; 128] },
+            }).await.unwrap();
+        });
+        assert_eq!(profile.unwrap_err().to_string(), "Bad signature of the verify token");
+    }
+
+    #[tokio::test]
+    async fn offline_login_tests() {
+        let flow = LoginFlow::offline();
+        assert!(!flow.is_online());
+        #[cfg(feature = "compression")]
+        let flow = flow.with_compression(256);
+        let (mut server, mut client) = login_connections();
+        client.write_packet(&LoginStart { name: "Notch", signature_data: None }).await.unwrap();
+        let profile = flow.login(&mut server).await.unwrap();
+        assert_eq!(profile, GameProfile::offline("Notch"));
+        #[cfg(feature = "compression")]
+        {
+            assert_eq!(client.read_packet::<LoginSetCompression>().await.unwrap().threshold, 256);
+            client.set_compression(Some(256));
+        }
+        let success = client.read_packet::<LoginSuccess>().await.unwrap();
+        assert_eq!((success.uuid, success.name), (offline_uuid("Notch"), "Notch"));
+        assert!(!server.is_encrypted());
+    }
+}
@@ -1,7 +1,10 @@
 use std::borrow::Cow;
-use anyhow::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use std::sync::Arc;
 use crate::packet::*;
 use crate::packet_types::*;
+use crate::packet_frame::*;
 
 #[test]
 fn number_tests() {
@@ -30,6 +33,63 @@ fn string_tests() {
         assert_eq!(<&str>::read(&mut read).unwrap(), "jenya705 is good boy");
         assert_eq!(String::read(&mut read).unwrap(), "женя705 ис гуд бой");
     }
+    {
+        let mut write = Vec::new();
+        "borrowed".write(&mut write).unwrap();
+        LengthProvidedBytesSlice::<VarInt, i32>::write_variant(&[1u8, 2, 3][..], &mut write).unwrap();
+        "cow".write(&mut write).unwrap();
+        LengthProvidedBytesSlice::<VarInt, i32>::write_variant(&[4u8][..], &mut write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        let str = <&str>::read(&mut read).unwrap();
+        assert_eq!(str, "borrowed");
+        assert_eq!(str.as_ptr(), write[1..].as_ptr());
+        let bytes = <&[u8]>::read(&mut read).unwrap();
+        assert_eq!(bytes, &[1, 2, 3]);
+        assert_eq!(bytes.as_ptr(), write[10..].as_ptr());
+        assert!(matches!(Cow::<str>::read(&mut read).unwrap(), Cow::Borrowed("cow")));
+        assert!(matches!(Cow::<[u8]>::read(&mut read).unwrap(), Cow::Borrowed(&[4])));
+    }
+    {
+        let mut read = SlicePacketRead::new(&[0x02, 0xC3, 0x28]);
+        assert!(matches!(<&str>::read(&mut read).unwrap_err(), PacketReadableError::Any(_)));
+        let mut read = SlicePacketRead::new(&[0x04, b'a', b'b']);
+        assert!(matches!(<&str>::read(&mut read).unwrap_err(), PacketReadableError::BytesExceeded));
+        let mut read = SlicePacketRead::new(&[0x04, 0x01]);
+        assert!(matches!(<&[u8]>::read(&mut read).unwrap_err(), PacketReadableError::BytesExceeded));
+    }
+    {
+        // 3 UTF-8 bytes, but 1 UTF-16 unit each
+        let fits = "ж€".repeat(8);
+        let too_big = format!("{}a", fits);
+        let mut write = Vec::new();
+        LimitedString::<16>::write_variant(&fits, &mut write).unwrap();
+        let error = LimitedString::<16>::write_variant(&too_big, &mut Vec::new()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PacketWritableError>(),
+            Some(PacketWritableError::StringLimit { limit: 16, length: 17 })
+        ));
+        // Emoji is 2 UTF-16 units
+        LimitedString::<2>::write_variant("😀", &mut write).unwrap();
+        assert!(LimitedString::<1>::write_variant("😀", &mut Vec::new()).is_err());
+        LengthProvidedBytesSlice::<VarInt, i32>::write_variant(too_big.as_bytes(), &mut write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(
+            <LimitedString<16> as PacketVariantReadable<'_, &str>>::read_variant(&mut read).unwrap(),
+            fits
+        );
+        assert_eq!(
+            <LimitedString<2> as PacketVariantReadable<'_, &str>>::read_variant(&mut read).unwrap(),
+            "😀"
+        );
+        assert!(matches!(
+            <LimitedString<16> as PacketVariantReadable<'_, String>>::read_variant(&mut read).unwrap_err(),
+            PacketReadableError::Any(_)
+        ));
+        let mut read = SlicePacketRead::new(&[0x04, 0xF0, 0x9F, 0x98, 0x80]);
+        assert!(<LimitedString<1> as PacketVariantReadable<'_, &str>>::read_variant(&mut read).is_err());
+        let mut read = SlicePacketRead::new(&[0x02, b'a', b'b']);
+        assert!(<LimitedString<1> as PacketVariantReadable<'_, &str>>::read_variant(&mut read).is_err());
+    }
 }
 
 #[test]
@@ -47,6 +107,94 @@ fn var_number_tests() {
         VarInt::write_variant(&-1, &mut write).unwrap();
         assert_eq!(write, &[0x80, 0x01, 0xff, 0x01, 0xff, 0xff, 0xff, 0xff, 0x0f]);
     }
+    {
+        let var_ints: &[(i32, &[u8])] = &[
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (25565, &[0xdd, 0xc7, 0x01]),
+            (2097151, &[0xff, 0xff, 0x7f]),
+            (i32::MAX, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+            (i32::MIN, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+        ];
+        for (value, bytes) in var_ints {
+            let mut write = Vec::new();
+            VarInt::write_variant(value, &mut write).unwrap();
+            assert_eq!(&write, bytes);
+            assert_eq!(VarInt::read_variant(&mut SlicePacketRead::new(bytes)).unwrap(), *value);
+        }
+        let var_longs: &[(i64, &[u8])] = &[
+            (2147483647, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+            (i64::MAX, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+            (-2147483648, &[0x80, 0x80, 0x80, 0x80, 0xf8, 0xff, 0xff, 0xff, 0xff, 0x01]),
+            (i64::MIN, &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]),
+        ];
+        for (value, bytes) in var_longs {
+            let mut write = Vec::new();
+            VarLong::write_variant(value, &mut write).unwrap();
+            assert_eq!(&write, bytes);
+            assert_eq!(VarLong::read_variant(&mut SlicePacketRead::new(bytes)).unwrap(), *value);
+        }
+    }
+    {
+        let bad_var_ints: &[&[u8]] = &[
+            &[0x80, 0x80, 0x80, 0x80, 0x80, 0x00],
+            &[0xff, 0xff, 0xff, 0xff, 0x1f],
+            &[0xff, 0xff, 0xff, 0xff, 0x8f, 0x01],
+            &[0x80, 0x00],
+            &[0xff, 0x80, 0x00],
+        ];
+        for bytes in bad_var_ints {
+            let result: Result<i32, _> = VarInt::read_variant(&mut SlicePacketRead::new(bytes));
+            assert!(matches!(result.unwrap_err(), PacketReadableError::BadVarNumber(_)));
+        }
+        let bad_var_longs: &[&[u8]] = &[
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02],
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x81, 0x00],
+            &[0x80, 0x80, 0x00],
+        ];
+        for bytes in bad_var_longs {
+            let result: Result<i64, _> = VarLong::read_variant(&mut SlicePacketRead::new(bytes));
+            assert!(matches!(result.unwrap_err(), PacketReadableError::BadVarNumber(_)));
+        }
+        let result: Result<i32, _> = VarInt::read_variant(&mut SlicePacketRead::new(&[0xff, 0xff]));
+        assert!(matches!(result.unwrap_err(), PacketReadableError::BytesExceeded));
+    }
+}
+
+#[test]
+fn var_number_sweep_tests() {
+    // Encoder which writes byte by byte, as it was written before
+    fn bytewise(value: u64) -> Vec<u8> {
+        let mut value = value;
+        let mut bytes = Vec::new();
+        while (value & !0x7F) != 0 {
+            bytes.push(((value & 0x7F) | 0x80) as u8);
+            value >>= 7;
+        }
+        bytes.push(value as u8);
+        bytes
+    }
+    let mut values: Vec<i64> = (0..64).flat_map(|bit| [1i64 << bit, (1i64 << bit).wrapping_sub(1), (1i64 << bit).wrapping_neg()]).collect();
+    values.extend([0, -1, i32::MIN as i64, i32::MAX as i64, i64::MIN, i64::MAX]);
+    values.extend((0..10_000).map(|index: i64| index.wrapping_mul(0x9E3779B97F4A7C15u64 as i64)));
+    for value in values {
+        let mut write = Vec::new();
+        VarLong::write_variant(&value, &mut write).unwrap();
+        assert_eq!(write, bytewise(value as u64), "{}", value);
+        assert_eq!(VarLong::size_of(value), write.len());
+        let value = value as i32;
+        let mut write = Vec::new();
+        VarInt::write_variant(&value, &mut write).unwrap();
+        assert_eq!(write, bytewise(value as u32 as u64), "{}", value);
+        assert_eq!(VarInt::size_of(value), write.len());
+        assert_eq!(VarInt::read_variant(&mut SlicePacketRead::new(&write)).unwrap(), value);
+    }
+    assert_eq!((VarInt::MAX_SIZE, VarLong::MAX_SIZE), (5, 10));
+    assert_eq!((VarInt::size_of(i32::MIN), VarLong::size_of(i64::MIN)), (5, 10));
 }
 
 #[test]
@@ -69,4 +217,868 @@ fn slice_tests() {
             &["hello", "bye", "yeah"]
         );
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn bulk_bytes_tests() {
+    let payload: Vec<u8> = (0..1 << 20).map(|i: u32| (i * 31 % 251) as u8).collect();
+    let mut bulk = Vec::new();
+    LengthProvidedBytesSlice::<VarInt, i32>::write_variant(&payload, &mut bulk).unwrap();
+    RemainingBytesSlice::write_variant(&payload, &mut bulk).unwrap();
+    let mut generic = Vec::new();
+    LengthProvidedSlice::<VarInt, u8, i32>::write_variant(&payload, &mut generic).unwrap();
+    RemainingSlice::<u8>::write_variant(&payload, &mut generic).unwrap();
+    assert_eq!(bulk, generic);
+    let mut read = SlicePacketRead::new(bulk.as_slice());
+    let length_provided: &[u8] = LengthProvidedBytesSlice::<VarInt, i32>::read_variant(&mut read).unwrap();
+    assert_eq!(length_provided, payload.as_slice());
+    assert_eq!(length_provided.as_ptr(), bulk[3..].as_ptr());
+    let remaining: &[u8] = RemainingBytesSlice::read_variant(&mut read).unwrap();
+    assert_eq!(remaining, payload.as_slice());
+    assert_eq!(read.available(), 0);
+    let mut read = SlicePacketRead::new(generic.as_slice());
+    assert_eq!(
+        <LengthProvidedSlice<VarInt, u8, i32> as PacketVariantReadable<'_, Vec<u8>>>::read_variant(&mut read).unwrap(),
+        payload
+    );
+}
+
+#[test]
+fn size_tests() {
+    fn assert_size<T: PacketWritable>(value: T) {
+        let mut write = Vec::new();
+        value.write(&mut write).unwrap();
+        assert_eq!(value.size().unwrap(), write.len());
+    }
+    fn assert_variant_size<V: PacketVariantWritable<T>, T: ?Sized>(value: &T) {
+        let mut write = Vec::new();
+        V::write_variant(value, &mut write).unwrap();
+        assert_eq!(V::size_variant(value).unwrap(), write.len());
+    }
+    assert_size(7u8);
+    assert_size(-1i64);
+    assert_size(1.5f32);
+    assert_size(true);
+    assert_size(uuid::Uuid::nil());
+    assert_size("");
+    assert_size("женя705 €😀");
+    assert_size("a".repeat(300));
+    assert_size(Some(Some(-5i16)));
+    assert_size(None::<u128>);
+    assert_size([Some("a"), None, Some("bc")]);
+    assert_size((1u8, "nested", (Some(2u32), [3i8; 4])));
+    assert_size(BlockPosition { x: 1, y: 2, z: 3 });
+    for value in [0, 1, 127, 128, 16383, 16384, 2097151, 2097152, i32::MAX, -1, i32::MIN] {
+        assert_variant_size::<VarInt, i32>(&value);
+    }
+    for value in [0, 127, 128, i64::MAX, -1, i64::MIN] {
+        assert_variant_size::<VarLong, i64>(&value);
+    }
+    assert_variant_size::<LengthProvidedSlice<VarInt, LengthProvidedSlice<VarInt, &str, i32>, i32, Vec<&str>>, [Vec<&str>]>(
+        &[vec!["a", "ж"], vec![], vec!["bcd"]]
+    );
+    assert_eq!(VarInt::size_variant(&-1).unwrap(), 5);
+    assert_eq!(VarLong::size_variant(&-1).unwrap(), 10);
+    assert!("a".repeat(40000).as_str().size().is_err());
+    let mut write = CountingPacketWrite::new();
+    write.write_bytes_fixed([0; 5]).unwrap();
+    write.write_bytes_owned(vec![0; 3]).unwrap();
+    assert_eq!(write.length(), 8);
+}
+
+#[test]
+fn vec_tests() {
+    let values = vec![Some("first"), None, Some("third")];
+    let mut write = Vec::new();
+    values.write(&mut write).unwrap();
+    vec![0u8; 300].write(&mut write).unwrap();
+    Vec::<i64>::new().write(&mut write).unwrap();
+    assert_eq!(&write[..3], &[0x03, 0x01, 0x05]);
+    let mut read = SlicePacketRead::new(write.as_slice());
+    assert_eq!(Vec::<Option<&str>>::read(&mut read).unwrap(), values);
+    assert_eq!(Vec::<u8>::read(&mut read).unwrap(), vec![0u8; 300]);
+    assert!(Vec::<i64>::read(&mut read).unwrap().is_empty());
+    assert_eq!(read.available(), 0);
+    // Declared length of i32::MAX elements in 8 bytes
+    let mut read = SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x01, 0x02, 0x03]);
+    assert!(matches!(Vec::<u64>::read(&mut read), Err(PacketReadableError::BytesExceeded)));
+}
+
+#[test]
+fn preallocation_tests() {
+    assert!(preallocate::<u8>(usize::MAX).capacity() <= PREALLOCATION_LIMIT);
+    assert!(preallocate::<[u64; 64]>(i32::MAX as usize).capacity() <= PREALLOCATION_LIMIT / 512);
+    assert_eq!(preallocate::<u32>(10).capacity(), 10);
+    assert_eq!(preallocation_capacity::<(u64, u64)>(usize::MAX), PREALLOCATION_LIMIT / 16);
+    let mut bytes = vec![0xFF, 0xFF, 0xFF, 0xFF, 0x07];
+    bytes.extend_from_slice(&[0; 4096]);
+    let mut read = SlicePacketRead::new(&bytes);
+    assert!(matches!(Vec::<[u64; 64]>::read(&mut read), Err(PacketReadableError::BytesExceeded)));
+    let mut read = SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x07, b'a']);
+    assert!(<&str>::read(&mut read).is_err());
+    assert!(String::read(&mut SlicePacketRead::new(&[0xFF, 0xFF, 0x01, b'a'])).is_err());
+    assert!(matches!(
+        <&[u8]>::read(&mut SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x01])),
+        Err(PacketReadableError::BytesExceeded)
+    ));
+}
+
+#[test]
+fn array_tests() {
+    {
+        let bytes: [u8; 16] = std::array::from_fn(|i| i as u8);
+        let mut write = Vec::new();
+        bytes.write(&mut write).unwrap();
+        assert_eq!(write.as_slice(), bytes.as_slice());
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(<[u8; 16]>::read(&mut read).unwrap(), bytes);
+        assert_eq!(read.available(), 0);
+    }
+    {
+        let mut write = Vec::new();
+        FixedSlice::<VarInt, i32>::write_variant(&[0, 128, -1, 25565], &mut write).unwrap();
+        assert_eq!(write.len(), 1 + 2 + 5 + 3);
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(
+            <FixedSlice<VarInt, i32> as PacketVariantReadable<'_, [i32; 4]>>::read_variant(&mut read).unwrap(),
+            [0, 128, -1, 25565]
+        );
+        let mut read = SlicePacketRead::new(&write[..8]);
+        assert!(matches!(
+            <FixedSlice<VarInt, i32> as PacketVariantReadable<'_, [i32; 4]>>::read_variant(&mut read),
+            Err(PacketReadableError::BytesExceeded)
+        ));
+    }
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static DROPPED: AtomicUsize = AtomicUsize::new(0);
+        struct Counted;
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPPED.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        impl<'a> PacketReadable<'a> for Counted {
+            fn read<R>(read: &mut R) -> Result<Self, PacketReadableError> where R: PacketRead<'a> {
+                read.take_byte().map(|_| Counted)
+            }
+        }
+        let mut read = SlicePacketRead::new(&[0; 3]);
+        assert!(<[Counted; 5]>::read(&mut read).is_err());
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 3);
+        let mut read = SlicePacketRead::new(&[0; 3]);
+        drop(<[Counted; 3]>::read(&mut read).unwrap());
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 6);
+    }
+}
+
+#[test]
+fn tuple_tests() {
+    let uuid = uuid::Uuid::from_u128(0x0123456789abcdef);
+    let mut write = Vec::new();
+    (7u8,).write(&mut write).unwrap();
+    (-1i32, uuid, "player", Some(true)).write(&mut write).unwrap();
+    let mut read = SlicePacketRead::new(write.as_slice());
+    assert_eq!(<(u8,)>::read(&mut read).unwrap(), (7,));
+    assert_eq!(
+        <(i32, uuid::Uuid, &str, Option<bool>)>::read(&mut read).unwrap(),
+        (-1, uuid, "player", Some(true))
+    );
+    assert_eq!(read.available(), 0);
+    let mut read = SlicePacketRead::new(&write[1..20]);
+    assert!(matches!(
+        <(i32, uuid::Uuid, &str, Option<bool>)>::read(&mut read),
+        Err(PacketReadableError::BytesExceeded)
+    ));
+}
+
+#[test]
+fn angle_tests() {
+    use std::f32::consts::{PI, TAU};
+    assert_eq!(Angle::from_degrees(180.0), 128);
+    assert_eq!(Angle::from_degrees(-90.0), 192);
+    assert_eq!(Angle::from_degrees(450.0), 64);
+    assert_eq!(Angle::from_degrees(359.9), 0);
+    assert_eq!(Angle::from_radians(f32::NAN), 0);
+    assert_eq!(Angle::to_degrees(128), 180.0);
+    let mut write = Vec::new();
+    Angle::write_variant(&PI, &mut write).unwrap();
+    assert_eq!(write, &[128]);
+    for radians in [0.0, 0.1, 1.0, PI, 4.0, TAU - 0.01, -2.5, 100.0] {
+        let mut write = Vec::new();
+        Angle::write_variant(&radians, &mut write).unwrap();
+        assert_eq!(write.len(), 1);
+        let read: f32 = Angle::read_variant(&mut SlicePacketRead::new(&write)).unwrap();
+        let difference = (read - radians).rem_euclid(TAU);
+        assert!(difference.min(TAU - difference) <= TAU / 512.0 + f32::EPSILON * 128.0);
+    }
+}
+
+#[test]
+fn position_delta_tests() {
+    let delta = PositionDelta::from_blocks(7.99975, -8.0, 0.5).unwrap();
+    assert_eq!(delta, PositionDelta { x: i16::MAX, y: i16::MIN, z: 2048 });
+    assert_eq!(delta.to_blocks(), [i16::MAX as f64 / 4096.0, -8.0, 0.5]);
+    // Rounding to the nearest step, not towards zero
+    assert_eq!(PositionDelta::from_blocks(0.00018, -0.00018, 0.0001).unwrap(), PositionDelta { x: 1, y: -1, z: 0 });
+    assert_eq!(PositionDelta::from_blocks(-7.999, 7.999, 0.0).unwrap(), PositionDelta { x: -32764, y: 32764, z: 0 });
+    for blocks in [8.0, -8.0002, f64::INFINITY, f64::NAN] {
+        assert!(matches!(PositionDelta::from_blocks(0.0, blocks, 0.0), Err(PacketWritableError::OutOfRange(_))));
+    }
+    let mut write = Vec::new();
+    delta.write(&mut write).unwrap();
+    assert_eq!(write, [0x7F, 0xFF, 0x80, 0x00, 0x08, 0x00]);
+    assert_eq!(delta.size().unwrap(), 6);
+    assert_eq!(PositionDelta::read(&mut SlicePacketRead::new(&write)).unwrap(), delta);
+}
+
+#[test]
+fn velocity_tests() {
+    let velocity = Velocity::from_blocks(3.9, -3.9, 0.5);
+    assert_eq!(velocity, Velocity { x: 31200, y: -31200, z: 4000 });
+    assert_eq!(Velocity::from_blocks(1e9, f64::NEG_INFINITY, f64::NAN), Velocity { x: 31200, y: -31200, z: 0 });
+    // Precision is the step of 1/8000
+    assert_eq!(Velocity::from_blocks(0.0000624, -0.0000626, 0.0), Velocity { x: 0, y: -1, z: 0 });
+    assert_eq!(velocity.to_blocks(), [3.9, -3.9, 0.5]);
+    let mut write = Vec::new();
+    velocity.write(&mut write).unwrap();
+    assert_eq!(write, [0x79, 0xE0, 0x86, 0x20, 0x0F, 0xA0]);
+    assert_eq!(Velocity::read(&mut SlicePacketRead::new(&write)).unwrap(), velocity);
+}
+
+#[test]
+fn fixed_point_byte_tests() {
+    assert_eq!(FixedPointByte::from_blocks(1.0).unwrap(), 32);
+    assert_eq!(FixedPointByte::from_blocks(-4.0).unwrap(), -128);
+    assert_eq!(FixedPointByte::from_blocks(3.97).unwrap(), 127);
+    assert!(FixedPointByte::from_blocks(4.0).is_err());
+    assert!(FixedPointByte::from_blocks(f64::NAN).is_err());
+    let mut write = Vec::new();
+    FixedPointByte::write_variant(&-0.5, &mut write).unwrap();
+    assert_eq!(write, [0xF0]);
+    let read: f64 = FixedPointByte::read_variant(&mut SlicePacketRead::new(&write)).unwrap();
+    assert_eq!(read, -0.5);
+    assert!(FixedPointByte::write_variant(&10.0, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn block_position_tests() {
+    let positions = [
+        (BlockPosition { x: 0, y: 0, z: 0 }, 0u64),
+        (BlockPosition { x: 18357644, y: 831, z: -20882616 }, 0x4607632C15B4833F),
+        (BlockPosition { x: -1, y: -1, z: -1 }, u64::MAX),
+        (BlockPosition { x: BlockPosition::MAX_XZ, y: BlockPosition::MAX_Y, z: BlockPosition::MAX_XZ }, 0x7FFFFFDFFFFFF7FF),
+        (BlockPosition { x: BlockPosition::MIN_XZ, y: BlockPosition::MIN_Y, z: BlockPosition::MIN_XZ }, 0x8000002000000800),
+        (BlockPosition { x: 0, y: -64, z: 0 }, 0xFC0),
+    ];
+    for (position, packed) in positions {
+        assert_eq!(position.try_pack().unwrap(), packed);
+        assert_eq!(BlockPosition::from_packed(packed), position);
+        let mut write = Vec::new();
+        position.write(&mut write).unwrap();
+        assert_eq!(write, packed.to_be_bytes());
+        assert_eq!(BlockPosition::read(&mut SlicePacketRead::new(&write)).unwrap(), position);
+    }
+    let out_of_range = [
+        BlockPosition { x: BlockPosition::MAX_XZ + 1, y: 0, z: 0 },
+        BlockPosition { x: BlockPosition::MIN_XZ - 1, y: 0, z: 0 },
+        BlockPosition { x: 0, y: BlockPosition::MAX_Y + 1, z: 0 },
+        BlockPosition { x: 0, y: BlockPosition::MIN_Y - 1, z: 0 },
+        BlockPosition { x: 0, y: 0, z: BlockPosition::MAX_XZ + 1 },
+        BlockPosition { x: 0, y: 0, z: BlockPosition::MIN_XZ - 1 },
+    ];
+    for position in out_of_range {
+        assert!(matches!(position.try_pack(), Err(PacketWritableError::OutOfRange(_))));
+        let error = position.write(&mut Vec::new()).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(PacketWritableError::OutOfRange(_))));
+    }
+}
+
+#[test]
+fn global_position_tests() {
+    use bird_chat::identifier::Identifier;
+    let death_location = GlobalPosition {
+        dimension: Identifier::new_fulled("minecraft:the_nether").unwrap(),
+        position: BlockPosition { x: -1234, y: -64, z: -33554432 },
+    };
+    let mut write = Vec::new();
+    Some(death_location.clone()).write(&mut write).unwrap();
+    None::<GlobalPosition>.write(&mut write).unwrap();
+    assert_eq!(write.len(), 1 + 21 + 8 + 1);
+    let mut read = SlicePacketRead::new(write.as_slice());
+    assert_eq!(Option::<GlobalPosition>::read(&mut read).unwrap(), Some(death_location));
+    assert_eq!(Option::<GlobalPosition>::read(&mut read).unwrap(), None);
+    let mut read = SlicePacketRead::new(&write[1..26]);
+    assert!(matches!(GlobalPosition::read(&mut read), Err(PacketReadableError::BytesExceeded)));
+}
+
+#[test]
+fn identifier_tests() {
+    use bird_chat::identifier::Identifier;
+    let identifier = |str: &str| {
+        let mut write = Vec::new();
+        str.write(&mut write).unwrap();
+        write
+    };
+    let valid = identifier("minecraft:textures/block/stone_1.png");
+    assert_eq!(Identifier::read(&mut SlicePacketRead::new(&valid)).unwrap().get_partial(), ("minecraft", "textures/block/stone_1.png"));
+    let long = format!("bird:{}", "a".repeat(300));
+    let bytes = identifier(&long);
+    assert!(matches!(
+        Identifier::read(&mut SlicePacketRead::new(&bytes)),
+        Err(PacketReadableError::BadIdentifierLength { limit: IDENTIFIER_LIMIT, length: 305 })
+    ));
+    let read: Identifier = LimitedIdentifier::<DEFAULT_LIMIT>::read_variant(&mut SlicePacketRead::new(&bytes)).unwrap();
+    assert_eq!(read.get_fulled(), long);
+    let error = read.write(&mut Vec::new()).unwrap_err();
+    assert!(matches!(error.downcast_ref(), Some(PacketWritableError::StringLimit { limit: IDENTIFIER_LIMIT, length: 305 })));
+    let mut write = Vec::new();
+    LimitedIdentifier::<DEFAULT_LIMIT>::write_variant(&read, &mut write).unwrap();
+    assert_eq!(write, bytes);
+    for (bad, message) in [
+        ("Minecraft:stone", "Bad character in namespace"),
+        ("minecraft:stone block", "Bad character in path"),
+        ("mine/craft:stone", "Bad character in namespace"),
+        ("minecraft:stone:1", "Bad character in path"),
+        ("stone", "No namespace"),
+    ] {
+        let bytes = identifier(bad);
+        assert!(matches!(
+            Identifier::read(&mut SlicePacketRead::new(&bytes)),
+            Err(PacketReadableError::BadIdentifier(error)) if error == message
+        ), "{}", bad);
+    }
+}
+
+#[test]
+fn bit_set_tests() {
+    let mut write = Vec::new();
+    BitSet::new().write(&mut write).unwrap();
+    assert_eq!(write, &[0x00]);
+    let mut bit_set = BitSet::new();
+    for index in [0, 63, 64, 128, 191] {
+        bit_set.set(index);
+    }
+    assert!(bit_set.get(64) && bit_set.get(191) && !bit_set.get(65) && !bit_set.get(10000));
+    assert_eq!(bit_set.iter().collect::<Vec<_>>(), &[0, 63, 64, 128, 191]);
+    assert_eq!(bit_set.len_longs(), 3);
+    assert_eq!(bit_set.as_longs(), &[1 | 1 << 63, 1, 1 | 1 << 63]);
+    bit_set.clear(191);
+    assert_eq!(bit_set.len_longs(), 3);
+    bit_set.clear(128);
+    assert_eq!(bit_set.len_longs(), 2);
+    let mut write = Vec::new();
+    bit_set.write(&mut write).unwrap();
+    assert_eq!(write.len(), 1 + 16);
+    assert_eq!(BitSet::read(&mut SlicePacketRead::new(&write)).unwrap(), bit_set);
+    // Trailing zero longs are trimmed on read
+    let mut write = Vec::new();
+    vec![0u64, 1 << 5, 0, 0].write(&mut write).unwrap();
+    let read = BitSet::read(&mut SlicePacketRead::new(&write)).unwrap();
+    assert_eq!(read, BitSet::from(vec![0, 1 << 5]));
+    assert_eq!(read.iter().collect::<Vec<_>>(), &[69]);
+    assert!(BitSet::from(vec![0, 0]).is_empty());
+}
+
+#[test]
+fn map_tests() {
+    let map = BTreeMap::from([(3u8, "three"), (1, "one"), (2, "two")]);
+    let mut write = Vec::new();
+    map.write(&mut write).unwrap();
+    assert_eq!(write.len(), map.size().unwrap());
+    assert_eq!(&write[..6], &[0x03, 0x01, 0x03, b'o', b'n', b'e']);
+    assert_eq!(BTreeMap::<u8, &str>::read(&mut SlicePacketRead::new(&write)).unwrap(), map);
+    let map: HashMap<String, Vec<i32>> = HashMap::from([
+        ("minecraft:logs".to_string(), vec![1, 2, 3]),
+        ("minecraft:planks".to_string(), vec![]),
+    ]);
+    let mut write = Vec::new();
+    map.write(&mut write).unwrap();
+    assert_eq!(HashMap::<String, Vec<i32>>::read(&mut SlicePacketRead::new(&write)).unwrap(), map);
+    // Duplicate key
+    let bytes = [0x02, 0x01, 0x0A, 0x01, 0x0B];
+    assert!(matches!(BTreeMap::<u8, u8>::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::DuplicateKey)));
+    assert!(matches!(HashMap::<u8, u8>::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::DuplicateKey)));
+    // Negative and too big lengths
+    assert!(HashMap::<u8, u8>::read(&mut SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F])).is_err());
+    let bytes = [0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x01, 0x02];
+    assert!(matches!(HashMap::<u8, u8>::read(&mut SlicePacketRead::new(&bytes)), Err(PacketReadableError::BytesExceeded)));
+}
+
+#[test]
+fn length_tests() {
+    let mut write = Vec::new();
+    let error = LengthProvidedSlice::<i16, u8>::write_variant(&vec![0u8; 70000], &mut write).unwrap_err();
+    assert!(matches!(error.downcast_ref(), Some(PacketWritableError::LengthOverflow { length: 70000 })));
+    let error = LengthProvidedBytesSlice::<i16>::write_variant(&[0u8; 32768][..], &mut write).unwrap_err();
+    assert!(matches!(error.downcast_ref(), Some(PacketWritableError::LengthOverflow { length: 32768 })));
+    assert!(write.is_empty());
+    LengthProvidedBytesSlice::<i16>::write_variant(&[0u8; 32767][..], &mut write).unwrap();
+    assert_eq!(&write[..2], &[0x7F, 0xFF]);
+    // Negative lengths are not read as huge ones
+    let mut read = SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x01]);
+    assert!(matches!(Vec::<u8>::read(&mut read), Err(PacketReadableError::BadLength)));
+    let mut read = SlicePacketRead::new(&[0xFF, 0xFF, 0x01]);
+    assert!(matches!(
+        <LengthProvidedSlice::<i16, u8> as PacketVariantReadable<Vec<u8>>>::read_variant(&mut read),
+        Err(PacketReadableError::BadLength)
+    ));
+    let mut read = SlicePacketRead::new(&[0x80, 0x00]);
+    assert!(matches!(
+        <LengthProvidedBytesSlice::<i16> as PacketVariantReadable<&[u8]>>::read_variant(&mut read),
+        Err(PacketReadableError::BadLength)
+    ));
+    assert_eq!(u64::MAX.into_length().is_ok(), usize::BITS >= 64);
+    assert!(matches!(u8::from_length(256), Err(PacketWritableError::LengthOverflow { length: 256 })));
+}
+
+#[test]
+fn json_tests() {
+    let motd = |length: usize| serde_json::json!({"text": "a".repeat(length)});
+    let mut write = Vec::new();
+    <ProtocolJson>::write_variant(&motd(32756), &mut write).unwrap();
+    assert_eq!(<ProtocolJson>::size_variant(&motd(32756)).unwrap(), write.len());
+    // Same bytes as json collected to the vector and written with the length
+    let mut expected = Vec::new();
+    LengthProvidedBytesSlice::<VarInt, i32>::write_variant(&serde_json::to_vec(&motd(32756)).unwrap(), &mut expected).unwrap();
+    assert_eq!(write, expected);
+    assert_eq!(write.len(), 3 + DEFAULT_LIMIT as usize);
+    let mut read = SlicePacketRead::new(&write);
+    assert_eq!(<ProtocolJson as PacketVariantReadable<serde_json::Value>>::read_variant(&mut read).unwrap(), motd(32756));
+    let mut over = Vec::new();
+    assert!(<ProtocolJson>::write_variant(&motd(32757), &mut over).is_err());
+    assert!(<ProtocolJson>::size_variant(&motd(32757)).is_err());
+    assert!(over.is_empty());
+    ProtocolJson::<CHAT_LIMIT>::write_variant(&motd(32757), &mut over).unwrap();
+    let mut read = SlicePacketRead::new(&over);
+    assert!(<ProtocolJson as PacketVariantReadable<serde_json::Value>>::read_variant(&mut read).is_err());
+    let mut read = SlicePacketRead::new(&over);
+    assert_eq!(
+        <ProtocolJson<CHAT_LIMIT> as PacketVariantReadable<serde_json::Value>>::read_variant(&mut read).unwrap(),
+        motd(32757)
+    );
+}
+
+#[test]
+fn pointer_tests() {
+    fn bytes<T: PacketWritable + ?Sized>(value: &T) -> Vec<u8> {
+        let mut write = Vec::new();
+        value.write(&mut write).unwrap();
+        assert_eq!(value.size().unwrap(), write.len());
+        write
+    }
+    let motd = "A Minecraft Server".to_string();
+    let position = BlockPosition { x: -1, y: 64, z: 300 };
+    assert_eq!(bytes(&Arc::new(motd.clone())), bytes(&motd));
+    assert_eq!(bytes(&Rc::new(motd.clone())), bytes(&motd));
+    assert_eq!(bytes(&Box::new(position)), bytes(&position));
+    assert_eq!(bytes(&&position), bytes(&position));
+    assert_eq!(bytes(&Cow::Borrowed(&position)), bytes(&position));
+    let write = bytes(&vec![Arc::new(position), Arc::new(position)]);
+    assert_eq!(write, bytes(&vec![position, position]));
+    let mut read = SlicePacketRead::new(&write);
+    assert_eq!(Vec::<Box<BlockPosition>>::read(&mut read).unwrap(), vec![Box::new(position); 2]);
+    let mut read = SlicePacketRead::new(&write[1..]);
+    assert_eq!(Arc::<BlockPosition>::read(&mut read).unwrap(), Arc::new(position));
+    assert!(matches!(Cow::<BlockPosition>::read(&mut read).unwrap(), Cow::Owned(read) if read == position));
+}
+
+#[test]
+fn frame_tests() {
+    {
+        let mut write = Vec::new();
+        let mut framer = PacketFramer::new();
+        write_frame_bytes(&[], &mut write).unwrap();
+        framer.write_frame(&"frame", &mut write).unwrap();
+        framer.write_frame_variant::<RemainingBytesSlice, [u8], _>(&[7u8; 200], &mut write).unwrap();
+        assert_eq!(&write[..8], &[0x00, 0x06, 0x05, b'f', b'r', b'a', b'm', b'e']);
+        assert_eq!(&write[8..10], &[0xC8, 0x01]);
+        let mut read = SlicePacketRead::new(write.as_slice());
+        let empty = read_frame(&mut read).unwrap();
+        assert_eq!(empty.available(), 0);
+        let mut frame = read_frame(&mut read).unwrap();
+        assert_eq!(<&str>::read(&mut frame).unwrap(), "frame");
+        assert_eq!(frame.available(), 0);
+        let frame = read_frame(&mut read).unwrap();
+        assert_eq!(frame.bytes, &[7u8; 200]);
+        assert_eq!(read.available(), 0);
+    }
+    {
+        let mut read = SlicePacketRead::new(&[0x05, 0x01, 0x02]);
+        assert!(matches!(read_frame(&mut read).unwrap_err(), PacketReadableError::BytesExceeded));
+        let mut read = SlicePacketRead::new(&[0xC8]);
+        assert!(matches!(read_frame(&mut read).unwrap_err(), PacketReadableError::BytesExceeded));
+    }
+    {
+        let mut read = SlicePacketRead::new(&[0x02, 0x05, b'h', 0x01]);
+        let mut frame = read_frame(&mut read).unwrap();
+        assert!(matches!(<&str>::read(&mut frame).unwrap_err(), PacketReadableError::BytesExceeded));
+        assert_eq!(u8::read(&mut read).unwrap(), 0x01);
+    }
+}
+
+#[test]
+fn bounded_tests() {
+    type Distance = Bounded<i32, 2, 32>;
+    assert_eq!(*Distance::new(2).unwrap(), 2);
+    assert_eq!(i32::from(Distance::new(32).unwrap()), 32);
+    assert!(matches!(Distance::new(1), Err(PacketWritableError::OutOfBounds { value: 1, min: 2, max: 32 })));
+    assert!(matches!(Distance::try_from(33), Err(PacketWritableError::OutOfBounds { value: 33, .. })));
+    let difficulty = Bounded::<u8, 0, 3>::new(3).unwrap();
+    let mut write = Vec::new();
+    difficulty.write(&mut write).unwrap();
+    assert_eq!(write, &[0x03]);
+    assert_eq!(Bounded::<u8, 0, 3>::read(&mut SlicePacketRead::new(&write)).unwrap(), difficulty);
+    let error = Bounded::<u8, 0, 3>::read(&mut SlicePacketRead::new(&[0x04])).unwrap_err();
+    assert!(matches!(error, PacketReadableError::OutOfRange { value: 4, min: 0, max: 3 }));
+    assert_eq!(error.to_string(), "4 is out of range 0..=3");
+
+    let mut write = Vec::new();
+    BoundedVarInt::write_variant(&Distance::new(12).unwrap(), &mut write).unwrap();
+    assert_eq!(write, &[0x0C]);
+    let read: Distance = BoundedVarInt::read_variant(&mut SlicePacketRead::new(&write)).unwrap();
+    assert_eq!(read.get(), 12);
+    let read: Result<Distance, _> = BoundedVarInt::read_variant(&mut SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]));
+    assert!(matches!(read, Err(PacketReadableError::OutOfRange { value: -1, .. })));
+}
+
+#[test]
+fn opt_var_int_tests() {
+    for (value, bytes) in [
+        (None, &[0x00][..]),
+        (Some(0), &[0x01]),
+        (Some(126), &[0x7F]),
+        (Some(127), &[0x80, 0x01]),
+        (Some(i32::MAX - 1), &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+    ] {
+        let value = OptVarInt::from(value);
+        let mut write = Vec::new();
+        value.write(&mut write).unwrap();
+        assert_eq!(write, bytes);
+        assert_eq!(value.size().unwrap(), bytes.len());
+        assert_eq!(OptVarInt::read(&mut SlicePacketRead::new(bytes)).unwrap(), value);
+    }
+    assert_eq!(Option::<i32>::from(OptVarInt(Some(5))), Some(5));
+    assert!(!OptVarInt::default().is_some());
+    // Value + 1 overflows or collides with the absence
+    for value in [i32::MAX, -1, i32::MIN] {
+        let error = OptVarInt(Some(value)).write(&mut Vec::new()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PacketWritableError>(),
+            Some(&PacketWritableError::OutOfBounds { value: error_value, min: 0, max: 0x7FFF_FFFE }) if error_value == value as i64
+        ), "{}", error);
+    }
+    let error = OptVarInt::read(&mut SlicePacketRead::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F])).unwrap_err();
+    assert!(matches!(error, PacketReadableError::OutOfRange { value: -1, .. }));
+
+    let uuid: OptUuid = Some(uuid::Uuid::from_u128(0x1234));
+    let mut write = Vec::new();
+    uuid.write(&mut write).unwrap();
+    assert_eq!(write.len(), 17);
+    assert_eq!(write[0], 0x01);
+    assert_eq!(OptUuid::read(&mut SlicePacketRead::new(&write)).unwrap(), uuid);
+    assert_eq!(OptUuid::read(&mut SlicePacketRead::new(&[0x00])).unwrap(), None);
+}
+
+#[cfg(feature = "derive")]
+mod derive_tests {
+    use crate::derive::*;
+    use super::*;
+
+    #[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq)]
+    #[enum_type(i32)]
+    #[enum_variant(VarInt)]
+    enum NextState {
+        Status = 1,
+        Login,
+    }
+
+    #[derive(PacketWritable, PacketReadable, PacketEnum, Debug, Clone, Copy, PartialEq)]
+    #[enum_type(u8)]
+    enum Color {
+        Red,
+        Green = 5,
+        Blue,
+    }
+
+    #[derive(Packet, PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    #[packet(bound = Server, state = Handshake, id = 0x00)]
+    struct Handshake<'a> {
+        #[variant(VarInt)]
+        protocol_version: i32,
+        server_address: &'a str,
+        server_port: u16,
+        next_state: NextState,
+    }
+
+    #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    struct Colors(Color, Color, #[variant(VarLong)] i64);
+
+    #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    struct Shared {
+        motd: Arc<String>,
+        position: Box<BlockPosition>,
+    }
+
+    #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    struct Empty;
+
+    #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    struct Ordered {
+        #[order(1)]
+        first: u8,
+        #[order(0)]
+        second: u8,
+    }
+
+    #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    #[enum_type(u8)]
+    enum Action<'a> {
+        Add { name: &'a str, #[variant(VarInt)] value: i32 },
+        Remove(#[variant(VarInt)] i32),
+        Clear,
+    }
+
+    #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    struct Masked {
+        flags: u8,
+        #[present_if(flags & 0x01 != 0)]
+        first: MaskedOption<u8>,
+        #[present_if(*flags & 0x02 == 0x02)]
+        second: MaskedOption<i16>,
+    }
+
+    #[derive(PacketWritable, PacketReadable, Debug, Clone, PartialEq)]
+    struct Nested<'a> {
+        id: u8,
+        colors: Colors,
+        action: Action<'a>,
+    }
+
+    fn round_trip<'a, T>(value: &T, write: &'a mut Vec<u8>) -> &'a [u8]
+        where T: PacketWritable + PacketReadable<'a> + PartialEq + std::fmt::Debug {
+        value.write(write).unwrap();
+        let mut read = SlicePacketRead::new(write.as_slice());
+        assert_eq!(&T::read(&mut read).unwrap(), value);
+        assert_eq!(read.available(), 0);
+        write.as_slice()
+    }
+
+    #[test]
+    fn field_path_tests() {
+        let bytes = [0xF7, 0x05, 0x01, b'a', 0x63, 0xDD, 0x07];
+        let error = decode_packet_body::<Handshake, _>(&mut SlicePacketRead::new(&bytes)).unwrap_err();
+        assert_eq!((error.id, error.offset, error.field_path.as_deref()), (0x00, 6, Some("next_state")));
+        assert!(matches!(error.source, PacketReadableError::BadEnumValue));
+        assert_eq!(error.to_string(), "Packet 0x0: Bad enum value at 0x6 in field next_state");
+
+        let nested = Nested { id: 1, colors: Colors(Color::Red, Color::Blue, 300), action: Action::Add { name: "a", value: 1 } };
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&nested, &mut write), &[0x01, 0x00, 0x06, 0xAC, 0x02, 0x00, 0x01, b'a', 0x01]);
+        let mut read = SlicePacketRead::new(&write);
+        assert_eq!(Nested::read_with_path(&mut read).unwrap(), nested);
+        assert_eq!(read.offset(), write.len());
+
+        let read_corrupted = |index: usize, byte: u8| {
+            let mut bytes = write.clone();
+            bytes[index] = byte;
+            let error = Nested::read_with_path(&mut SlicePacketRead::new(&bytes)).unwrap_err();
+            (error.offset, error.field_path)
+        };
+        assert_eq!(read_corrupted(2, 0x09), (2, Some("colors.1".to_string())));
+        assert_eq!(read_corrupted(5, 0x09), (5, Some("action".to_string())));
+        let error = Nested::read_with_path(&mut SlicePacketRead::new(&write[..8])).unwrap_err();
+        assert_eq!((error.offset, error.field_path.as_deref()), (8, Some("action.value")));
+        assert!(matches!(error.error, PacketReadableError::BytesExceeded));
+        // Types without the derive report only their start
+        let error = u8::read_with_path(&mut SlicePacketRead::new(&[])).unwrap_err();
+        assert_eq!((error.offset, error.field_path), (0, None));
+    }
+
+    #[test]
+    fn derive_struct_tests() {
+        let handshake = Handshake {
+            protocol_version: 759,
+            server_address: "localhost",
+            server_port: 25565,
+            next_state: NextState::Login,
+        };
+        let mut write = Vec::new();
+        assert_eq!(
+            round_trip(&handshake, &mut write),
+            &[0xF7, 0x05, 0x09, b'l', b'o', b'c', b'a', b'l', b'h', b'o', b's', b't', 0x63, 0xDD, 0x02]
+        );
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&Colors(Color::Red, Color::Blue, 300), &mut write), &[0x00, 0x06, 0xAC, 0x02]);
+        let shared = Shared { motd: Arc::new("motd".into()), position: Box::new(BlockPosition { x: 0, y: 0, z: 0 }) };
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&shared, &mut write), &[0x04, b'm', b'o', b't', b'd', 0, 0, 0, 0, 0, 0, 0, 0]);
+        let mut write = Vec::new();
+        assert!(round_trip(&Empty, &mut write).is_empty());
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&Ordered { first: 1, second: 2 }, &mut write), &[2, 1]);
+    }
+
+    #[test]
+    fn packet_tests() {
+        let handshake = Handshake {
+            protocol_version: 759,
+            server_address: "a",
+            server_port: 25565,
+            next_state: NextState::Status,
+        };
+        assert_eq!((Handshake::bound(), Handshake::state()), (PacketBound::Server, PacketState::Handshake));
+        let mut write = Vec::new();
+        write_packet(&handshake, &mut write).unwrap();
+        assert_eq!(write, &[0x00, 0xF7, 0x05, 0x01, b'a', 0x63, 0xDD, 0x01]);
+        let mut read = SlicePacketRead::new(&write);
+        assert_eq!(VarInt::read_variant(&mut read).unwrap(), Handshake::ID);
+        assert_eq!(read_packet_body::<Handshake, _>(&mut read).unwrap(), handshake);
+        assert_eq!(read.available(), 0);
+        // Frame length counts the id too
+        let mut frames = Vec::new();
+        let mut framer = PacketFramer::new();
+        framer.write_packet(&handshake, &mut frames).unwrap();
+        framer.write_packet(&handshake, &mut frames).unwrap();
+        assert_eq!(&frames[..1], &[0x08]);
+        assert_eq!(&frames[1..9], write.as_slice());
+        let mut read = SlicePacketRead::new(&frames);
+        read_frame(&mut read).unwrap();
+        let mut frame = read_frame(&mut read).unwrap();
+        assert_eq!(VarInt::read_variant(&mut frame).unwrap(), 0x00);
+        assert_eq!(read_packet_body::<Handshake, _>(&mut frame).unwrap(), handshake);
+        assert_eq!(read.available(), 0);
+    }
+
+    #[test]
+    fn masked_option_tests() {
+        let cases = [
+            (None, None, vec![0x00]),
+            (Some(7), None, vec![0x01, 0x07]),
+            (None, Some(-1), vec![0x02, 0xFF, 0xFF]),
+            (Some(7), Some(-1), vec![0x03, 0x07, 0xFF, 0xFF]),
+        ];
+        for (first, second, expected) in cases {
+            let flags = first.map_or(0, |_| 0x01) | second.map_or(0, |_| 0x02);
+            let masked = Masked { flags, first: first.into(), second: second.into() };
+            let mut write = Vec::new();
+            assert_eq!(round_trip(&masked, &mut write), expected.as_slice());
+        }
+        let masked = Masked { flags: 0x02, first: MaskedOption(Some(7)), second: MaskedOption(Some(-1)) };
+        assert!(masked.write(&mut Vec::new()).is_err());
+        let mut read = SlicePacketRead::new(&[0x05]);
+        assert_eq!(MaskedOption::<u8>::read_if(false, &mut read).unwrap(), MaskedOption(None));
+        assert_eq!(MaskedOption::<u8>::read_if(true, &mut read).unwrap(), MaskedOption(Some(5)));
+        assert!(MaskedOption::<u8>::read_if(true, &mut read).is_err());
+    }
+
+    #[test]
+    fn derive_enum_tests() {
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&Action::Add { name: "a", value: 3 }, &mut write), &[0x00, 0x01, b'a', 0x03]);
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&Action::Remove(128), &mut write), &[0x01, 0x80, 0x01]);
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&Action::Clear, &mut write), &[0x02]);
+        let mut read = SlicePacketRead::new(&[0x03]);
+        assert!(Action::read(&mut read).is_err());
+        let mut read = SlicePacketRead::new(&[0x04]);
+        assert!(NextState::read(&mut read).is_err());
+    }
+
+    #[test]
+    fn open_enum_tests() {
+        assert_eq!(NextState::Login.value(), 2);
+        assert_eq!(Color::from_value(6), Some(Color::Blue));
+        assert_eq!(Color::from_value(1), None);
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&OpenEnum::from(NextState::Status), &mut write), &[0x01]);
+        let mut write = Vec::new();
+        assert_eq!(round_trip(&OpenEnum::<Color>::Known(Color::Green), &mut write), &[0x05]);
+        // Unknown value is kept and written back byte to byte
+        let bytes = [0xAC, 0x02];
+        let open = OpenEnum::<NextState>::read(&mut SlicePacketRead::new(&bytes)).unwrap();
+        assert_eq!(open, OpenEnum::Unknown(300));
+        assert_eq!((open.known(), open.value()), (None, 300));
+        let mut write = Vec::new();
+        open.write(&mut write).unwrap();
+        assert_eq!(write, bytes);
+        let open = OpenEnum::<Color>::read(&mut SlicePacketRead::new(&[0xFF])).unwrap();
+        assert_eq!(open, OpenEnum::Unknown(0xFF));
+        assert!(OpenEnum::<NextState>::read(&mut SlicePacketRead::new(&[0x80])).is_err());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_tests() {
+    use crate::packet_version::{ProtocolVersion, VersionRange};
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Fixture<'a> {
+        position: BlockPosition,
+        delta: PositionDelta,
+        death: GlobalPosition<'a>,
+        mask: BitSet,
+        version: ProtocolVersion,
+        range: VersionRange,
+    }
+
+    let fixture = Fixture {
+        position: BlockPosition { x: 10, y: -64, z: -3 },
+        delta: PositionDelta { x: 4096, y: 0, z: -1 },
+        death: GlobalPosition {
+            dimension: bird_chat::identifier::Identifier::new_fulled("minecraft:overworld").unwrap(),
+            position: BlockPosition { x: 1, y: 2, z: 3 },
+        },
+        mask: BitSet::from(vec![5, 0]),
+        version: ProtocolVersion::V1_19,
+        range: VersionRange::from(ProtocolVersion::V1_17..=ProtocolVersion::V1_19),
+    };
+    let json = serde_json::to_value(&fixture).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "position": [10, -64, -3],
+        "delta": [4096, 0, -1],
+        "death": { "dimension": "minecraft:overworld", "position": [1, 2, 3] },
+        "mask": [5],
+        "version": 759,
+        "range": { "min": 755, "max": 759 },
+    }));
+    assert_eq!(serde_json::from_value::<Fixture>(json).unwrap(), fixture);
+    assert!(serde_json::from_str::<ProtocolVersion>("100").is_err());
+    assert!(serde_json::from_str::<BlockPosition>("[1, 2]").is_err());
+    assert_eq!(serde_json::from_str::<BitSet>("[1, 0, 0]").unwrap().len_longs(), 1);
+}
+
+/// Deterministic inputs for the fuzz harness: random bytes and encodings of small values with flipped bytes.
+#[cfg(feature = "fuzz")]
+#[test]
+fn fuzz_harness_tests() {
+    use crate::packet_fuzz::*;
+    let mut state = 0x2545F4914F6CDD1Du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for _ in 0..20000 {
+        let length = (next() % 48) as usize;
+        let mut data: Vec<u8> = (0..length).map(|_| next() as u8).collect();
+        match next() % 4 {
+            0 => data.iter_mut().for_each(|byte| *byte &= 0x0F),
+            1 if length > 0 => data[0] |= 0x80,
+            2 if length > 0 => data[0] = (length - 1) as u8,
+            _ => {}
+        }
+        fuzz_numbers(&data);
+        fuzz_strings(&data);
+        fuzz_collections(&data);
+        #[cfg(feature = "packet_default")]
+        fuzz_packets(&data);
+    }
+    let mut buffer = Vec::new();
+    assert_eq!(fuzz_roundtrip::<&str>(&[0x03, b'a', b'b', b'c'], &mut buffer), Some("abc"));
+    assert_eq!(fuzz_variant_roundtrip::<VarInt, i32>(&[0xAC, 0x02], &mut Vec::new()), Some(300));
+    assert_eq!(fuzz_roundtrip::<String>(&[0x05], &mut Vec::new()), None);
+}
@@ -0,0 +1,58 @@
+//! Allocations of framing, counted by the global allocator of this test binary.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use bytes::BytesMut;
+use bird_protocol::*;
+use bird_protocol::packet_bytes::{FramingStrategy, write_framed_into};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Count of allocations of the current thread while the function runs.
+fn allocations<T>(function: impl FnOnce() -> T) -> (usize, T) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = function();
+    (ALLOCATIONS.with(Cell::get) - before, result)
+}
+
+type Body = (u8, i64, BlockPosition, bool);
+
+fn naive_frame(id: i32, body: &Body) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    VarInt::write_variant(&id, &mut buffer).unwrap();
+    body.write(&mut buffer).unwrap();
+    let mut frame = Vec::new();
+    write_frame_bytes(&buffer, &mut frame).unwrap();
+    frame
+}
+
+#[test]
+fn framed_into_allocation_tests() {
+    let body: Body = (7, -1, BlockPosition { x: 5, y: -3, z: 100 }, true);
+    let mut buf = BytesMut::with_capacity(1024);
+    let (count, expected) = allocations(|| naive_frame(0x21, &body));
+    assert!(count >= 2, "Body and frame buffers are allocated, but {} allocations are counted", count);
+    for _ in 0..16 {
+        buf.clear();
+        let (count, strategy) = allocations(|| write_framed_into(&mut buf, 0x21, &body).unwrap());
+        assert_eq!((count, strategy), (0, FramingStrategy::Sized));
+        assert_eq!(&buf[..], expected.as_slice());
+    }
+}
@@ -0,0 +1,94 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Path};
+use syn::spanned::Spanned;
+use crate::util::{DATA_ATTRIBUTES, DataAttributes, get_attributes, get_bird_protocol_crate, VariantAttributes, VariantVisitor, visit_derive_input};
+use crate::write::write_ts;
+
+pub struct EnumVariantVisitor {
+    variants: Vec<(Path, TokenStream)>,
+}
+
+impl VariantVisitor for EnumVariantVisitor {
+    fn visit(&mut self, ident: Path, fields: &Fields, value: Option<TokenStream>, _: VariantAttributes) -> syn::Result<()> {
+        match fields {
+            Fields::Unit => {
+                self.variants.push((ident, value.unwrap())); // it is enum
+                Ok(())
+            }
+            _ => Err(syn::Error::new(fields.span(), "PacketEnum supports only variants without fields")),
+        }
+    }
+}
+
+pub fn enum_impl(args: &DeriveInput) -> syn::Result<TokenStream> {
+    let data_attributes: DataAttributes =
+        get_attributes(DATA_ATTRIBUTES, &args.attrs)?.try_into()?;
+    let ty = match (&args.data, &data_attributes.enum_type) {
+        (Data::Enum(_), Some(ty)) => ty,
+        _ => return Err(syn::Error::new(
+            Span::call_site(), "PacketEnum requires enum with enum type and optionally variant",
+        )),
+    };
+    let mut visitor = EnumVariantVisitor { variants: vec![] };
+    visit_derive_input(args, &mut visitor)?;
+    let protocol_crate = get_bird_protocol_crate();
+    let mut values = quote! {};
+    let mut to_value = quote! {};
+    let mut from_value = quote! {};
+    for (counter, (variant, value)) in visitor.variants.iter().enumerate() {
+        let value_ident = Ident::new(format!("__{}", counter).as_str(), value.span());
+        values = quote! {
+            #values
+            const #value_ident: #ty = #value as #ty;
+        };
+        to_value = quote! {
+            #to_value
+            #variant => #value_ident,
+        };
+        from_value = quote! {
+            #from_value
+            #value_ident => std::option::Option::Some(#variant),
+        };
+    }
+    let read = match data_attributes.enum_variant {
+        Some(ref variant) => quote! {
+            < #variant as #protocol_crate ::packet::PacketVariantReadable<'a, #ty >>::read_variant(read)
+        },
+        None => quote! { < #ty as #protocol_crate ::packet::PacketReadable<'a>>::read(read) },
+    };
+    let write = write_ts(&data_attributes.enum_variant, ty, &quote! { value });
+    let DeriveInput { ident, generics, .. } = args;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #protocol_crate ::packet::PacketEnum for #ident #ty_generics #where_clause {
+            type Value = #ty;
+
+            fn value(&self) -> #ty {
+                #values
+                match self {
+                    #to_value
+                }
+            }
+
+            fn from_value(value: #ty) -> std::option::Option<Self> {
+                #values
+                match value {
+                    #from_value
+                    _ => std::option::Option::None,
+                }
+            }
+
+            fn read_value<'a, R>(read: &mut R) -> Result<#ty, #protocol_crate ::packet::PacketReadableError>
+                where R: #protocol_crate ::packet::PacketRead<'a> {
+                #read
+            }
+
+            fn write_value<W>(value: &#ty, write: &mut W) -> Result<(), anyhow::Error>
+                where W: #protocol_crate ::packet::PacketWrite {
+                #write
+                Ok(())
+            }
+        }
+    })
+}
@@ -19,7 +19,7 @@ pub fn packet_impl(args: &DeriveInput) -> syn::Result<TokenStream> {
             pub const STATE: #protocol_crate ::packet::PacketState = #protocol_crate ::packet::PacketState:: #state;
         }
 
-        impl #impl_generics #protocol_crate ::packet::Packet for #ident #ty_generics #where_clause {
+        impl #impl_generics #protocol_crate ::packet::PacketScope for #ident #ty_generics #where_clause {
             fn bound() -> #protocol_crate ::packet::PacketBound {
                 Self::BOUND
             }
@@ -27,7 +27,9 @@ pub fn packet_impl(args: &DeriveInput) -> syn::Result<TokenStream> {
             fn state() -> #protocol_crate ::packet::PacketState {
                 Self::STATE
             }
+        }
 
+        impl #impl_generics #protocol_crate ::packet::Packet for #ident #ty_generics #where_clause {
             fn id() -> i32 {
                 Self::ID
             }
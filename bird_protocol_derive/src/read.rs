@@ -2,20 +2,25 @@ use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{Data, DeriveInput, Field, Fields, Path};
 use syn::spanned::Spanned;
-use crate::util::{add_trait_lifetime, DATA_ATTRIBUTES, DataAttributes, FieldAttributes, FieldVisitor, get_attributes, get_bird_protocol_crate, get_lifetimes, VariantAttributes, VariantVisitor, visit_derive_input, visit_fields};
+use crate::util::{add_trait_lifetime, DATA_ATTRIBUTES, DataAttributes, FieldAttributes, FieldVisitor, get_attributes, get_bird_protocol_crate, get_lifetimes, has_versioned_fields, VariantAttributes, VariantVisitor, visit_derive_input, visit_fields};
 
 pub struct ReadableVariantVisitor {
     pub data_attributes: DataAttributes,
     pub lifetime: TokenStream,
     pub variant_creators: Vec<(Option<TokenStream>, TokenStream)>,
+    /// Whether reads of fields should track path and offset of the failed field.
+    pub traced: bool,
 }
 
 pub struct ReadableFieldVisitor {
     raw_reads: Vec<TokenStream>,
     ordered_reads: Vec<(usize, TokenStream)>,
     values: Vec<TokenStream>,
+    /// Fields which are read before, so conditions of the presence can use them.
+    previous: Vec<(Ident, Ident)>,
     named: bool,
     lifetime: TokenStream,
+    traced: bool,
 }
 
 impl VariantVisitor for ReadableVariantVisitor {
@@ -28,7 +33,7 @@ impl VariantVisitor for ReadableVariantVisitor {
                     _ => false
                 };
                 let mut field_visitor = ReadableFieldVisitor::new(
-                    named, self.lifetime.clone(),
+                    named, self.lifetime.clone(), self.traced,
                 );
                 visit_fields(fields, &mut field_visitor)?;
                 let (reads, values) = field_visitor.into_pieces();
@@ -52,13 +57,15 @@ impl VariantVisitor for ReadableVariantVisitor {
 }
 
 impl ReadableFieldVisitor {
-    pub fn new(named: bool, lifetime: TokenStream) -> Self {
+    pub fn new(named: bool, lifetime: TokenStream, traced: bool) -> Self {
         Self {
             raw_reads: vec![],
             ordered_reads: vec![],
             values: vec![],
+            previous: vec![],
             named,
             lifetime,
+            traced,
         }
     }
 
@@ -78,8 +85,47 @@ impl FieldVisitor for ReadableFieldVisitor {
             format!("__{}", ident.to_string()).as_str(), ident.span(),
         );
         let Field { ty, .. } = field;
-        let value_read = read_statement(&quote! {#ty}, &attributes.variant, &self.lifetime)?;
+        let value_read = match attributes.present_if {
+            Some(ref present_if) => {
+                let (previous, previous_values): (Vec<_>, Vec<_>) = self.previous.iter().cloned().unzip();
+                quote! {{
+                    #( #[allow(unused_variables)] let #previous = &#previous_values; )*
+                    < #ty >::read_if(#present_if, read)
+                }}
+            }
+            None => read_statement(&quote! {#ty}, &attributes.variant, &self.lifetime)?,
+        };
+        let value_read = match self.traced {
+            true => {
+                let protocol_crate = get_bird_protocol_crate();
+                let name = match self.named {
+                    true => ident.to_string(),
+                    false => ident.to_string().trim_start_matches("__").to_string(),
+                };
+                let lifetime = &self.lifetime;
+                let traced_read = match (&attributes.present_if, &attributes.variant) {
+                    (None, None) => quote! {
+                        < #ty as #protocol_crate ::packet::PacketReadable< #lifetime >>::read_with_path(read)
+                    },
+                    _ => quote! {
+                        #value_read.map_err(|error| #protocol_crate ::packet::FieldReadError::new(error, __offset))
+                    },
+                };
+                quote! {{
+                    let __offset = #protocol_crate ::packet::PacketRead::offset(read);
+                    #traced_read.map_err(|error| error.in_field(#name, __offset))?
+                }}
+            }
+            false => quote! { #value_read? },
+        };
+        let value_read = match attributes.version_condition() {
+            Some(condition) => quote! {
+                if #condition { #value_read } else { std::default::Default::default() }
+            },
+            None => value_read,
+        };
         let read = quote! { let #value_ident = #value_read };
+        self.previous.push((ident.clone(), value_ident.clone()));
         match attributes.order {
             Some(index) => self.ordered_reads.push((index, read)),
             None => self.raw_reads.push(read)
@@ -97,10 +143,10 @@ pub fn read_statement(ty: &TokenStream, variant: &Option<TokenStream>, lifetime:
     Ok(match variant {
         Some(ref variant) => quote! {
             < #variant as #protocol_crate ::packet::PacketVariantReadable< #lifetime , #ty >>
-            ::read_variant(read)?
+            ::read_variant(read)
         },
         None => quote! {
-            < #ty as #protocol_crate ::packet::PacketReadable< #lifetime >>::read(read)?
+            < #ty as #protocol_crate ::packet::PacketReadable< #lifetime >>::read(read)
         }
     })
 }
@@ -126,10 +172,68 @@ pub fn read_impl(args: &DeriveInput) -> syn::Result<TokenStream> {
     if let Data::Union(_) = args.data {
         return Err(syn::Error::new(Span::call_site(), "union type is not supported"));
     }
+    let body = read_body(args, &lifetime, false)?;
+    let traced_body = read_body(args, &lifetime, true)?;
+    let protocol_crate = get_bird_protocol_crate();
+    let DeriveInput { ident, generics, .. } = args;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut cloned_generics = generics.clone();
+    let impl_generics = match add_lifetime {
+        true => {
+            add_trait_lifetime(&mut cloned_generics, quote! {'a});
+            cloned_generics.split_for_impl().0
+        }
+        false => impl_generics,
+    };
+    if has_versioned_fields(args) {
+        return Ok(quote! {
+            impl #impl_generics #protocol_crate ::packet_version::VersionedReadable< #lifetime > for #ident #ty_generics #where_clause {
+                fn read_versioned<R>(
+                    version: #protocol_crate ::packet_version::ProtocolVersion, read: &mut R,
+                ) -> Result<Self, #protocol_crate ::packet::PacketReadableError>
+                where R: #protocol_crate ::packet::PacketRead< #lifetime > {
+                    let __version = version.number();
+                    #body
+                }
+            }
+
+            impl #impl_generics #protocol_crate ::packet::PacketReadable< #lifetime > for #ident #ty_generics #where_clause {
+                fn read<R>(read: &mut R) -> Result<Self, #protocol_crate ::packet::PacketReadableError>
+                where R: #protocol_crate ::packet::PacketRead< #lifetime > {
+                    #protocol_crate ::packet_version::VersionedReadable::read_versioned(
+                        #protocol_crate ::packet_version::DEFAULT_VERSION, read,
+                    )
+                }
+
+                fn read_with_path<R>(read: &mut R) -> Result<Self, #protocol_crate ::packet::FieldReadError>
+                where R: #protocol_crate ::packet::PacketRead< #lifetime > {
+                    let __version = #protocol_crate ::packet_version::DEFAULT_VERSION.number();
+                    #traced_body
+                }
+            }
+        });
+    }
+    Ok(quote! {
+        impl #impl_generics #protocol_crate ::packet::PacketReadable< #lifetime > for #ident #ty_generics #where_clause {
+            fn read<R>(read: &mut R) -> Result<Self, #protocol_crate ::packet::PacketReadableError>
+            where R: #protocol_crate ::packet::PacketRead< #lifetime > {
+                #body
+            }
+
+            fn read_with_path<R>(read: &mut R) -> Result<Self, #protocol_crate ::packet::FieldReadError>
+            where R: #protocol_crate ::packet::PacketRead< #lifetime > {
+                #traced_body
+            }
+        }
+    })
+}
+
+fn read_body(args: &DeriveInput, lifetime: &TokenStream, traced: bool) -> syn::Result<TokenStream> {
     let mut variant_visitor = ReadableVariantVisitor {
-        data_attributes,
+        data_attributes: get_attributes(DATA_ATTRIBUTES, &args.attrs)?.try_into()?,
         lifetime: lifetime.clone(),
         variant_creators: vec![],
+        traced,
     };
     visit_derive_input(args, &mut variant_visitor)?;
     let protocol_crate = get_bird_protocol_crate();
@@ -139,21 +243,37 @@ pub fn read_impl(args: &DeriveInput) -> syn::Result<TokenStream> {
             quote! {std::result::Result::Ok({ #variants })}
         }
         Data::Enum(_) => {
-            if variant_visitor.data_attributes.enum_type.is_none() && variant_visitor.data_attributes.enum_variant.is_none() {
-                return Err(syn::Error::new(
-                    Span::call_site(),
-                    "You should provide enum type and variant to use PacketReadable macro",
-                ));
-            }
             let (ty, variant) = match variant_visitor.data_attributes.enum_type {
                 Some(ref enum_type) => (enum_type, &variant_visitor.data_attributes.enum_variant),
-                None => (variant_visitor.data_attributes.enum_variant.as_ref().unwrap(), &None)
+                None => return Err(syn::Error::new(
+                    Span::call_site(),
+                    "You should provide enum type and optionally variant to use PacketReadable macro",
+                ))
             };
             let value_read_ts = read_statement(
                 ty,
                 variant,
-                &lifetime,
+                lifetime,
             )?;
+            // Failed value of the enum is reported at its start
+            let (value_start, value_read_ts, bad_value) = match traced {
+                true => (
+                    quote! { let __value_offset = #protocol_crate ::packet::PacketRead::offset(read); },
+                    quote! {
+                        #value_read_ts.map_err(|error| #protocol_crate ::packet::FieldReadError::new(error, __value_offset))?
+                    },
+                    quote! {
+                        #protocol_crate ::packet::FieldReadError::new(
+                            #protocol_crate ::packet::PacketReadableError::BadEnumValue, __value_offset,
+                        )
+                    },
+                ),
+                false => (
+                    quote! {},
+                    quote! { #value_read_ts? },
+                    quote! { #protocol_crate ::packet::PacketReadableError::BadEnumValue },
+                ),
+            };
             let mut values = quote! {};
             let mut counter = 0usize;
             let mut result = quote! {};
@@ -173,34 +293,16 @@ pub fn read_impl(args: &DeriveInput) -> syn::Result<TokenStream> {
                 }
             }
             quote! {
+                #value_start
                 let __value = #value_read_ts;
                 #values
                 std::result::Result::Ok(match __value {
                     #result
-                    _ => return std::result::Result::Err(
-                        #protocol_crate ::packet::PacketReadableError::Any(anyhow::Error::msg("Bad value for enum"))
-                    )
+                    _ => return std::result::Result::Err(#bad_value)
                 })
             }
         }
         _ => unreachable!()
     };
-    let DeriveInput { ident, generics, .. } = args;
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    let mut cloned_generics = generics.clone();
-    let impl_generics = match add_lifetime {
-        true => {
-            add_trait_lifetime(&mut cloned_generics, quote! {'a});
-            cloned_generics.split_for_impl().0
-        }
-        false => impl_generics,
-    };
-    Ok(quote! {
-        impl #impl_generics #protocol_crate ::packet::PacketReadable< #lifetime > for #ident #ty_generics #where_clause {
-            fn read<R>(read: &mut R) -> Result<Self, #protocol_crate ::packet::PacketReadableError>
-            where R: #protocol_crate ::packet::PacketRead< #lifetime > {
-                #body
-            }
-        }
-    })
-}
\ No newline at end of file
+    Ok(body)
+}
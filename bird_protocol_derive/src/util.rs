@@ -9,7 +9,9 @@ use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Colon2;
 
-pub const FIELD_ATTRIBUTES: &[&str] = &["variant", "var", "order"];
+pub const FIELD_ATTRIBUTES: &[&str] = &["variant", "var", "order", "present_if"];
+/// Field attribute with `since` and `until` versions, which is parsed apart from others as it has both of them.
+pub const PROTOCOL_ATTRIBUTE: &str = "protocol";
 pub const DATA_ATTRIBUTES: &[&str] = &["lifetime", "enum_type", "enum_variant"];
 pub const VARIANT_ATTRIBUTES: &[&str] = &["value"];
 
@@ -17,6 +19,24 @@ pub const VARIANT_ATTRIBUTES: &[&str] = &["value"];
 pub struct FieldAttributes {
     pub order: Option<usize>,
     pub variant: Option<TokenStream>,
+    /// Condition of the presence, which can use fields before this one.
+    pub present_if: Option<TokenStream>,
+    /// First protocol version where the field exists, from `protocol` attribute.
+    pub since: Option<TokenStream>,
+    /// Last protocol version where the field exists, from `protocol` attribute.
+    pub until: Option<TokenStream>,
+}
+
+impl FieldAttributes {
+    /// Condition which is true if the field exists in `__version`, or none if it exists in all versions.
+    pub fn version_condition(&self) -> Option<TokenStream> {
+        let since = self.since.as_ref().map(|since| quote! { __version >= i32::from(#since) });
+        let until = self.until.as_ref().map(|until| quote! { __version <= i32::from(#until) });
+        match (since, until) {
+            (Some(since), Some(until)) => Some(quote! { #since && #until }),
+            (since, until) => since.or(until),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,7 +72,7 @@ pub fn visit_fields(fields: &Fields, visitor: &mut impl FieldVisitor) -> syn::Re
             visitor.visit(
                 field.ident.clone().unwrap(),
                 field,
-                get_attributes(FIELD_ATTRIBUTES, &field.attrs)?.try_into()?,
+                get_field_attributes(field)?,
             )?;
         },
         Fields::Unnamed(unnamed) => {
@@ -61,7 +81,7 @@ pub fn visit_fields(fields: &Fields, visitor: &mut impl FieldVisitor) -> syn::Re
                 visitor.visit(
                     Ident::new(format!("__{}", counter).as_str(), field.span()),
                     field,
-                    get_attributes(FIELD_ATTRIBUTES, &field.attrs)?.try_into()?,
+                    get_field_attributes(field)?,
                 )?;
                 if counter != usize::MAX {
                     counter += 1;
@@ -73,6 +93,33 @@ pub fn visit_fields(fields: &Fields, visitor: &mut impl FieldVisitor) -> syn::Re
     Ok(())
 }
 
+fn get_field_attributes(field: &Field) -> syn::Result<FieldAttributes> {
+    let mut attributes: FieldAttributes = get_attributes(FIELD_ATTRIBUTES, &field.attrs)?.try_into()?;
+    for attribute in field.attrs.iter().filter(|attribute| attribute.path.is_ident(PROTOCOL_ATTRIBUTE)) {
+        let mut range = attribute.parse_args_with(|input: ParseStream| collect_map_attribute(input))?;
+        attributes.since = range.remove("since").map(|expr| expr.to_token_stream());
+        attributes.until = range.remove("until").map(|expr| expr.to_token_stream());
+        if let Some(name) = range.keys().next() {
+            return Err(syn::Error::new(attribute.span(), format!("Unknown protocol bound {}, expected since or until", name)));
+        }
+        if attributes.since.is_none() && attributes.until.is_none() {
+            return Err(syn::Error::new(attribute.span(), "protocol attribute requires since or until"));
+        }
+    }
+    Ok(attributes)
+}
+
+/// Whether any field has `protocol` attribute, so the type is read and written with the protocol version.
+pub fn has_versioned_fields(derive_input: &DeriveInput) -> bool {
+    let fields: Vec<&Field> = match derive_input.data {
+        Data::Struct(ref data_struct) => data_struct.fields.iter().collect(),
+        Data::Enum(ref data_enum) => data_enum.variants.iter().flat_map(|variant| variant.fields.iter()).collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    fields.iter()
+        .any(|field| field.attrs.iter().any(|attribute| attribute.path.is_ident(PROTOCOL_ATTRIBUTE)))
+}
+
 pub fn visit_derive_input(derive_input: &DeriveInput, visitor: &mut impl VariantVisitor) -> syn::Result<()> {
     match derive_input.data {
         Data::Struct(ref data_struct) =>
@@ -182,6 +229,11 @@ impl TryFrom<HashMap<&str, Expr>> for FieldAttributes {
     type Error = syn::Error;
 
     fn try_from(value: HashMap<&str, Expr>) -> Result<Self, Self::Error> {
+        if let (Some(present_if), Some(_)) = (value.get("present_if"), value.get("variant").or(value.get("var"))) {
+            return Err(syn::Error::new(
+                present_if.span(), "present_if attribute can't be used with variant attribute",
+            ));
+        }
         Ok(FieldAttributes {
             order: match value.get("order") {
                 Some(expr) => Some(expr_to_usize(expr)?),
@@ -190,6 +242,10 @@ impl TryFrom<HashMap<&str, Expr>> for FieldAttributes {
             variant: value.get("variant")
                 .or(value.get("var"))
                 .map(|expr| expr.to_token_stream()),
+            present_if: value.get("present_if")
+                .map(|expr| expr.to_token_stream()),
+            since: None,
+            until: None,
         })
     }
 }
@@ -198,6 +254,11 @@ impl TryFrom<HashMap<&str, Expr>> for DataAttributes {
     type Error = syn::Error;
 
     fn try_from(value: HashMap<&str, Expr>) -> Result<Self, Self::Error> {
+        if let (Some(enum_variant), None) = (value.get("enum_variant"), value.get("enum_type")) {
+            return Err(syn::Error::new(
+                enum_variant.span(), "enum_variant attribute requires enum_type attribute",
+            ));
+        }
         Ok(DataAttributes {
             enum_type: value.get("enum_type")
                 .map(|expr| expr.to_token_stream()),
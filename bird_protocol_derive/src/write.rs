@@ -1,7 +1,7 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{DeriveInput, Field, Fields, Path};
-use crate::util::{DATA_ATTRIBUTES, DataAttributes, FieldAttributes, FieldVisitor, get_attributes, get_bird_protocol_crate, VariantAttributes, VariantVisitor, visit_derive_input, visit_fields};
+use crate::util::{DATA_ATTRIBUTES, DataAttributes, FieldAttributes, FieldVisitor, get_attributes, get_bird_protocol_crate, has_versioned_fields, VariantAttributes, VariantVisitor, visit_derive_input, visit_fields};
 
 pub struct WritableVariantVisitor {
     variants: TokenStream,
@@ -50,29 +50,18 @@ impl VariantVisitor for WritableVariantVisitor {
         let end_writes;
         match value {
             Some(value) => {
-                if variant.is_none() && ty.is_none() {
-                    end_writes = quote! { #(#writes)* };
-                } else if variant.is_some() && ty.is_some() {
-                    let ty = ty.as_ref().unwrap();
-                    let write_ts = write_ts(
-                        variant,
-                        ty,
-                        &quote! { &((#value) as #ty) },
-                    );
-                    end_writes = quote! {
-                        #write_ts
-                        #( #writes )*
-                    }
-                } else {
-                    let end_variant = variant.as_ref().or(ty.as_ref()).unwrap().clone();
-                    let write_ts = write_ts(
-                        &None,
-                        &end_variant,
-                        &quote! { &((#value) as #end_variant) },
-                    );
-                    end_writes = quote! {
-                        #write_ts
-                        #( #writes )*
+                match ty {
+                    None => end_writes = quote! { #(#writes)* },
+                    Some(ty) => {
+                        let write_ts = write_ts(
+                            variant,
+                            ty,
+                            &quote! { &((#value) as #ty) },
+                        );
+                        end_writes = quote! {
+                            #write_ts
+                            #( #writes )*
+                        }
                     }
                 }
             }
@@ -118,11 +107,18 @@ impl FieldVisitor for WritableFieldVisitor {
             ref #ident,
         };
         let Field { ty, .. } = field;
-        let write_ts = write_ts(
-            &attributes.variant,
-            &quote! { #ty },
-            &ident.to_token_stream(),
-        );
+        let write_ts = match attributes.present_if {
+            Some(ref present_if) => quote! { < #ty >::write_if(#ident, #present_if, write)?; },
+            None => write_ts(
+                &attributes.variant,
+                &quote! { #ty },
+                &ident.to_token_stream(),
+            ),
+        };
+        let write_ts = match attributes.version_condition() {
+            Some(condition) => quote! { if #condition { #write_ts } },
+            None => write_ts,
+        };
         match attributes.order {
             Some(order) => ordered_writes.push((order, write_ts)),
             None => raw_writes.push(write_ts),
@@ -131,7 +127,7 @@ impl FieldVisitor for WritableFieldVisitor {
     }
 }
 
-fn write_ts(variant: &Option<TokenStream>, ty: &TokenStream, value: &TokenStream) -> TokenStream {
+pub fn write_ts(variant: &Option<TokenStream>, ty: &TokenStream, value: &TokenStream) -> TokenStream {
     let protocol_crate = get_bird_protocol_crate();
     match variant {
         Some(ref variant) => quote! {
@@ -153,6 +149,30 @@ pub fn write_impl(args: &DeriveInput) -> syn::Result<TokenStream> {
     let DeriveInput { ident, generics, .. } = args;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let variants = visitor.get_variants();
+    if has_versioned_fields(args) {
+        return Ok(quote! {
+            impl #impl_generics #protocol_crate ::packet_version::VersionedWritable for #ident #ty_generics #where_clause {
+                fn write_versioned<W>(
+                    &self, version: #protocol_crate ::packet_version::ProtocolVersion, write: &mut W,
+                ) -> Result<(), anyhow::Error> where W: #protocol_crate ::packet::PacketWrite {
+                    let __version = version.number();
+                    match self {
+                        #variants
+                    }
+                    Ok(())
+                }
+            }
+
+            impl #impl_generics #protocol_crate ::packet::PacketWritable for #ident #ty_generics #where_clause {
+                fn write<W>(&self, write: &mut W) -> Result<(), anyhow::Error>
+                    where W: #protocol_crate ::packet::PacketWrite {
+                    #protocol_crate ::packet_version::VersionedWritable::write_versioned(
+                        self, #protocol_crate ::packet_version::DEFAULT_VERSION, write,
+                    )
+                }
+            }
+        });
+    }
     Ok(quote! {
         impl #impl_generics #protocol_crate ::packet::PacketWritable for #ident #ty_generics #where_clause {
             fn write<W>(&self, write: &mut W) -> Result<(), anyhow::Error>